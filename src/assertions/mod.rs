@@ -0,0 +1,148 @@
+use crate::json::JsonValue;
+use crate::response::Response;
+
+#[cfg(test)]
+mod tests;
+
+/// Fluent assertions on a [`Response`], for test suites built around this client.
+///
+/// Every assertion panics with a message describing the response when it fails, pointing
+/// at the line of the caller, and returns the response otherwise so assertions can be
+/// chained.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{assertions::ResponseAssertions, client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     Client::new()
+///         .get("https://example.com/users/42")?
+///         .send()?
+///         .assert_status(200)
+///         .assert_header("Content-Type", "application/json")
+///         .assert_json_path("user.id", 42)
+///         .assert_json_path("user.roles.0", "admin");
+///     Ok(())
+/// }
+/// ```
+pub trait ResponseAssertions {
+    /// Assert the response has the given status code.
+    /// # Arguments
+    /// `status`    Expected status code, e.g `200`.
+    fn assert_status(&self, status: usize) -> &Self;
+
+    /// Assert the response has a header with the given value, among the values of headers
+    /// sent more than once.
+    /// # Arguments
+    /// `name`  Name of the header, case-insensitive.
+    /// `value` Expected value of the header.
+    fn assert_header(&self, name: &str, value: &str) -> &Self;
+
+    /// Assert the body of the response is the given text.
+    /// # Arguments
+    /// `text`  Expected body, decoded as with [`Response::text`].
+    fn assert_text(&self, text: &str) -> &Self;
+
+    /// Assert the JSON body of the response holds the given value at the given path.
+    /// # Arguments
+    /// `path`      Keys of objects and indexes of arrays separated by dots, e.g `user.id` or
+    ///             `items.0.name`. An empty path is the whole body.
+    /// `expected`  Expected value, e.g `42`, `"admin"` or `JsonValue::Null`.
+    fn assert_json_path<T: Into<JsonValue>>(&self, path: &str, expected: T) -> &Self;
+}
+
+impl ResponseAssertions for Response {
+    #[track_caller]
+    fn assert_status(&self, status: usize) -> &Self {
+        let actual = self.status_code();
+        if actual != status {
+            fail(self, &format!("expected status {status}, got {actual}"));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_header(&self, name: &str, value: &str) -> &Self {
+        let values = self.header_values(name);
+        if !values.iter().any(|actual| actual == value) {
+            let message = if values.is_empty() {
+                format!("expected header {name}: {value}, but it is missing")
+            } else {
+                format!("expected header {name}: {value}, got {values:?}")
+            };
+            fail(self, &message);
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_text(&self, text: &str) -> &Self {
+        let actual = self.text();
+        if actual != text {
+            fail(self, &format!("expected body {text:?}, got {actual:?}"));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_json_path<T: Into<JsonValue>>(&self, path: &str, expected: T) -> &Self {
+        let expected = expected.into();
+        let json = match self.json() {
+            Ok(json) => json,
+            Err(e) => fail(self, &format!("expected a JSON body, {e}")),
+        };
+        match lookup(&json, path) {
+            Some(actual) if *actual == expected => (),
+            Some(actual) => fail(
+                self,
+                &format!("expected {expected} at JSON path `{path}`, got {actual}"),
+            ),
+            None => fail(
+                self,
+                &format!("expected {expected} at JSON path `{path}`, but it is missing"),
+            ),
+        }
+        self
+    }
+}
+
+/// The value at a path of keys and indexes separated by dots inside a JSON value.
+/// # Arguments
+/// `json`  Value to look into.
+/// `path`  Path of the value, e.g `user.id` or `items.0.name`, empty for `json` itself.
+pub(crate) fn lookup<'a>(json: &'a JsonValue, path: &str) -> Option<&'a JsonValue> {
+    if path.is_empty() {
+        return Some(json);
+    }
+    path.split('.')
+        .try_fold(json, |value, segment| match value {
+            JsonValue::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index)),
+            _ => value.get(segment),
+        })
+}
+
+/// Helper method, fail an assertion on a response, describing it after the message.
+#[track_caller]
+fn fail(response: &Response, message: &str) -> ! {
+    panic!(
+        "{message}\nresponse: {} {}\n{}",
+        response.status_code(),
+        response.url(),
+        excerpt(&response.text())
+    )
+}
+
+/// Longest part of a body shown in a failed assertion, in characters.
+const EXCERPT_LENGTH: usize = 1024;
+
+/// The start of a body shown in a failed assertion, cut to a readable length.
+/// # Arguments
+/// `body`  Body of the response as text.
+pub(crate) fn excerpt(body: &str) -> String {
+    match body.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_owned(),
+    }
+}
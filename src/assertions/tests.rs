@@ -0,0 +1,27 @@
+use crate::assertions::{excerpt, lookup};
+use crate::json::parser::JsonParser;
+use crate::json::JsonValue;
+
+#[test]
+fn json_paths_looked_up() {
+    let json =
+        JsonParser::parse_json(r#"{"user": {"id": 42, "roles": ["admin", "dev"], "team": null}}"#)
+            .unwrap();
+    assert_eq!(lookup(&json, "user.id"), Some(&JsonValue::from(42)));
+    assert_eq!(lookup(&json, "user.roles.1"), Some(&JsonValue::from("dev")));
+    assert_eq!(lookup(&json, "user.team"), Some(&JsonValue::Null));
+    assert_eq!(lookup(&json, ""), Some(&json));
+    assert_eq!(lookup(&json, "user.roles.2"), None);
+    assert_eq!(lookup(&json, "user.roles.first"), None);
+    assert_eq!(lookup(&json, "user.id.value"), None);
+    assert_eq!(lookup(&json, "account"), None);
+}
+
+#[test]
+fn long_bodies_cut() {
+    assert_eq!(excerpt("short"), "short");
+    let long = "é".repeat(2000);
+    let cut = excerpt(&long);
+    assert!(cut.ends_with("..."));
+    assert_eq!(cut.chars().count(), 1024 + 3);
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http_parse::HttpUrl;
+
+use crate::encoding::base64_encode;
+use crate::error::HttpError;
+use crate::hash::{hex, Md5, Sha256};
+
+mod store;
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "keyring")]
+pub use store::KeyringStore;
+pub use store::{CredentialStore, MemoryStore};
+
+/// An authentication challenge sent by a server in a `WWW-Authenticate`
+/// or `Proxy-Authenticate` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    scheme: String,
+    params: HashMap<String, String>,
+}
+
+impl Challenge {
+    /// Parse every challenge found in an authenticate header value.
+    ///
+    /// # Example:
+    /// ```
+    /// use http_client::auth::Challenge;
+    /// let challenges = Challenge::parse(r#"Basic realm="users", Digest realm="api", nonce="abc""#);
+    /// assert_eq!(challenges.len(), 2);
+    /// assert_eq!(challenges[1].scheme(), "Digest");
+    /// assert_eq!(challenges[1].param("nonce"), Some("abc"));
+    /// ```
+    pub fn parse(value: &str) -> Vec<Challenge> {
+        let is_token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+        let mut challenges: Vec<Challenge> = Vec::new();
+        let mut rest = value;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+            let token_end = rest.find(|c: char| !is_token(c)).unwrap_or(rest.len());
+            let token = &rest[..token_end];
+            if token.is_empty() {
+                break;
+            }
+            let after = rest[token_end..].trim_start();
+            match (after.strip_prefix('='), challenges.last_mut()) {
+                (Some(value), Some(challenge)) if !value.starts_with('=') => {
+                    let value = value.trim_start();
+                    let (value, remaining) = match value.strip_prefix('"') {
+                        Some(quoted) => Self::parse_quoted(quoted),
+                        None => {
+                            let end = value.find(',').unwrap_or(value.len());
+                            (value[..end].trim().to_owned(), &value[end..])
+                        }
+                    };
+                    challenge.params.insert(token.to_ascii_lowercase(), value);
+                    rest = remaining;
+                }
+                _ => {
+                    challenges.push(Challenge {
+                        scheme: token.to_owned(),
+                        params: HashMap::new(),
+                    });
+                    rest = after;
+                }
+            }
+        }
+        challenges
+    }
+
+    /// Helper method, read a quoted string whose opening quote was already consumed.
+    fn parse_quoted(text: &str) -> (String, &str) {
+        let mut value = String::new();
+        let mut chars = text.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                '"' => return (value, &text[index + 1..]),
+                other => value.push(other),
+            }
+        }
+        (value, "")
+    }
+
+    /// The authentication scheme, e.g `Basic` or `Digest`.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// A parameter of this challenge, names are case insensitive.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .get(&name.to_ascii_lowercase())
+            .map(|value| value.as_str())
+    }
+
+    /// The protection space this challenge applies to.
+    pub fn realm(&self) -> Option<&str> {
+        self.param("realm")
+    }
+}
+
+/// Credentials used to answer an authentication challenge.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credentials {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer(String),
+    Digest {
+        username: String,
+        password: String,
+    },
+}
+
+impl Credentials {
+    /// Credentials for HTTP Basic authentication.
+    pub fn basic(username: &str, password: Option<&str>) -> Credentials {
+        Credentials::Basic {
+            username: username.to_owned(),
+            password: password.map(|password| password.to_owned()),
+        }
+    }
+
+    /// A bearer token.
+    pub fn bearer(token: &str) -> Credentials {
+        Credentials::Bearer(token.to_owned())
+    }
+
+    /// Credentials for HTTP Digest authentication.
+    pub fn digest(username: &str, password: &str) -> Credentials {
+        Credentials::Digest {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+
+    /// The header value sending these credentials in response to the given challenges.
+    ///
+    /// # Arguments
+    /// `challenges`    Challenges sent by the server, Digest credentials require one.
+    /// `method`    Method of the request being authenticated.
+    /// `uri`   Target of the request being authenticated.
+    pub fn authorization(
+        &self,
+        challenges: &[Challenge],
+        method: &str,
+        uri: &str,
+    ) -> Result<String, HttpError> {
+        match self {
+            Credentials::Basic { username, password } => {
+                let credentials = format!("{username}:{}", password.as_deref().unwrap_or_default());
+                Ok(format!("Basic {}", base64_encode(credentials.as_bytes())))
+            }
+            Credentials::Bearer(token) => Ok(format!("Bearer {token}")),
+            Credentials::Digest { username, password } => {
+                let challenge = challenges
+                    .iter()
+                    .filter(|challenge| challenge.scheme.eq_ignore_ascii_case("digest"))
+                    .filter(|challenge| DigestAlgorithm::from(challenge).is_some())
+                    .max_by_key(|challenge| DigestAlgorithm::from(challenge))
+                    .ok_or_else(|| {
+                        HttpError::Other("Server did not offer a supported Digest challenge".into())
+                    })?;
+                Ok(Self::digest_authorization(
+                    challenge,
+                    username,
+                    password,
+                    method,
+                    uri,
+                    &cnonce(),
+                ))
+            }
+        }
+    }
+
+    /// Helper method, compute the Digest authorization (RFC 7616) for a challenge.
+    pub(crate) fn digest_authorization(
+        challenge: &Challenge,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        let algorithm_name = challenge.param("algorithm").unwrap_or("MD5");
+        let algorithm = DigestAlgorithm::from(challenge).unwrap_or(DigestAlgorithm::Md5);
+        let realm = challenge.realm().unwrap_or_default();
+        let nonce = challenge.param("nonce").unwrap_or_default();
+        let qop = challenge
+            .param("qop")
+            .filter(|qop| qop.split(',').any(|value| value.trim() == "auth"))
+            .map(|_| "auth");
+        let nc = "00000001";
+
+        let mut ha1 = algorithm.hash(&format!("{username}:{realm}:{password}"));
+        if algorithm_name.to_ascii_lowercase().ends_with("-sess") {
+            ha1 = algorithm.hash(&format!("{ha1}:{nonce}:{cnonce}"));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        let response = match qop {
+            Some(qop) => algorithm.hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:{qop}:{ha2}")),
+            None => algorithm.hash(&format!("{ha1}:{nonce}:{ha2}")),
+        };
+
+        let mut header = format!(
+            r#"Digest username="{username}", realm="{realm}", nonce="{nonce}", uri="{uri}", algorithm={algorithm_name}, response="{response}""#
+        );
+        if let Some(qop) = qop {
+            header.push_str(&format!(r#", qop={qop}, nc={nc}, cnonce="{cnonce}""#));
+        }
+        if let Some(opaque) = challenge.param("opaque") {
+            header.push_str(&format!(r#", opaque="{opaque}""#));
+        }
+        header
+    }
+}
+
+/// Hash algorithms supported for Digest authentication, weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn from(challenge: &Challenge) -> Option<DigestAlgorithm> {
+        let name = challenge.param("algorithm").unwrap_or("MD5");
+        match name.to_ascii_uppercase().trim_end_matches("-SESS") {
+            "MD5" => Some(DigestAlgorithm::Md5),
+            "SHA-256" => Some(DigestAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    fn hash(&self, data: &str) -> String {
+        match self {
+            DigestAlgorithm::Md5 => hex(&Md5::digest(data.as_bytes())),
+            DigestAlgorithm::Sha256 => hex(&Sha256::digest(data.as_bytes())),
+        }
+    }
+}
+
+/// Helper method, a random client nonce.
+fn cnonce() -> String {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    format!("{:016x}", hasher.finish())
+}
+
+/// Supplies credentials when a server (status 401) or proxy (status 407) asks for authentication.
+///
+/// The request is sent again with the returned credentials, once per status.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{auth::Credentials, client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::builder()
+///         .credential_provider(Credentials::digest("user", "secret"))
+///         .build();
+///     client.get("http://localhost:8080/private")?.send()?;
+///     Ok(())
+/// }
+/// ```
+pub trait CredentialProvider: Send + Sync {
+    /// Credentials for the given URL, or `None` to leave the challenge unanswered.
+    ///
+    /// # Arguments
+    /// `url`   URL of the request being authenticated.
+    /// `challenges`    Challenges sent with the response.
+    /// `proxy` Whether the challenge comes from a proxy.
+    fn credentials(
+        &self,
+        url: &HttpUrl,
+        challenges: &[Challenge],
+        proxy: bool,
+    ) -> Option<Credentials>;
+}
+
+impl CredentialProvider for Credentials {
+    fn credentials(&self, _: &HttpUrl, _: &[Challenge], _: bool) -> Option<Credentials> {
+        Some(self.clone())
+    }
+}
+
+impl<F> CredentialProvider for F
+where
+    F: Fn(&HttpUrl, &[Challenge], bool) -> Option<Credentials> + Send + Sync,
+{
+    fn credentials(
+        &self,
+        url: &HttpUrl,
+        challenges: &[Challenge],
+        proxy: bool,
+    ) -> Option<Credentials> {
+        self(url, challenges, proxy)
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::auth::Credentials;
+use crate::encoding::{base64_decode, base64_encode};
+use crate::error::HttpError;
+
+/// Persistent storage for credentials, keyed by host.
+///
+/// A store can be handed to [`crate::client::ClientBuilder::credential_store`] to answer
+/// authentication challenges with the credentials saved for the requested host.
+pub trait CredentialStore: Send + Sync {
+    /// The credentials saved for the given host, if any.
+    fn get(&self, host: &str) -> Option<Credentials>;
+
+    /// Save credentials for the given host, replacing any previous ones.
+    /// # Arguments
+    /// `host`  Host the credentials belong to, e.g `api.example.com`
+    /// `credentials`   Credentials to be saved.
+    fn set(&self, host: &str, credentials: &Credentials) -> Result<(), HttpError>;
+
+    /// Forget the credentials saved for the given host.
+    fn remove(&self, host: &str) -> Result<(), HttpError>;
+}
+
+/// A credential store kept in memory for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    credentials: Mutex<HashMap<String, Credentials>>,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for MemoryStore {
+    fn get(&self, host: &str) -> Option<Credentials> {
+        let credentials = self.credentials.lock().ok()?;
+        credentials.get(&host.to_ascii_lowercase()).cloned()
+    }
+
+    fn set(&self, host: &str, credentials: &Credentials) -> Result<(), HttpError> {
+        self.credentials
+            .lock()
+            .map_err(|_| HttpError::Other("Credential store poisoned".into()))?
+            .insert(host.to_ascii_lowercase(), credentials.clone());
+        Ok(())
+    }
+
+    fn remove(&self, host: &str) -> Result<(), HttpError> {
+        self.credentials
+            .lock()
+            .map_err(|_| HttpError::Other("Credential store poisoned".into()))?
+            .remove(&host.to_ascii_lowercase());
+        Ok(())
+    }
+}
+
+/// A credential store backed by the keyring of the operating system.
+///
+/// Credentials are saved in the macOS Keychain, the Windows Credential Manager or the
+/// Secret Service on Linux, one entry per host under the given service name.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{auth::{Credentials, CredentialStore, KeyringStore}, client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let store = KeyringStore::new("my-cli");
+///     store.set("api.example.com", &Credentials::bearer("token"))?;
+///     let client = Client::builder().credential_store(store).build();
+///     client.get("https://api.example.com/me")?.send()?;
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone)]
+pub struct KeyringStore {
+    service: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringStore {
+    /// Create a store saving entries under the given service name.
+    pub fn new(service: &str) -> Self {
+        KeyringStore {
+            service: service.to_owned(),
+        }
+    }
+
+    /// Helper method, the keyring entry of a host.
+    fn entry(&self, host: &str) -> Result<keyring::Entry, HttpError> {
+        keyring::Entry::new(&self.service, &host.to_ascii_lowercase())
+            .map_err(|e| HttpError::Other(e.to_string()))
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl CredentialStore for KeyringStore {
+    fn get(&self, host: &str) -> Option<Credentials> {
+        let secret = self.entry(host).ok()?.get_password().ok()?;
+        decode(&secret)
+    }
+
+    fn set(&self, host: &str, credentials: &Credentials) -> Result<(), HttpError> {
+        self.entry(host)?
+            .set_password(&encode(credentials))
+            .map_err(|e| HttpError::Other(e.to_string()))
+    }
+
+    fn remove(&self, host: &str) -> Result<(), HttpError> {
+        match self.entry(host)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(HttpError::Other(e.to_string())),
+        }
+    }
+}
+
+/// Serialize credentials into a single secret, e.g `basic:<user>:<password>` with base64 fields.
+pub(crate) fn encode(credentials: &Credentials) -> String {
+    let field = |value: &str| base64_encode(value.as_bytes());
+    match credentials {
+        Credentials::Basic { username, password } => match password {
+            Some(password) => format!("basic:{}:{}", field(username), field(password)),
+            None => format!("basic:{}", field(username)),
+        },
+        Credentials::Bearer(token) => format!("bearer:{}", field(token)),
+        Credentials::Digest { username, password } => {
+            format!("digest:{}:{}", field(username), field(password))
+        }
+    }
+}
+
+/// Parse credentials serialized with [`encode`].
+pub(crate) fn decode(secret: &str) -> Option<Credentials> {
+    let mut fields = secret.split(':');
+    let scheme = fields.next()?;
+    let fields = fields
+        .map(|field| String::from_utf8(base64_decode(field).ok()?).ok())
+        .collect::<Option<Vec<String>>>()?;
+    match (scheme, fields.as_slice()) {
+        ("basic", [username]) => Some(Credentials::basic(username, None)),
+        ("basic", [username, password]) => Some(Credentials::basic(username, Some(password))),
+        ("bearer", [token]) => Some(Credentials::bearer(token)),
+        ("digest", [username, password]) => Some(Credentials::digest(username, password)),
+        _ => None,
+    }
+}
@@ -0,0 +1,76 @@
+use crate::auth::store::{decode, encode};
+use crate::auth::{Challenge, CredentialStore, Credentials, MemoryStore};
+
+#[test]
+fn parse_challenge_list() {
+    let challenges = Challenge::parse(
+        r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41", Basic realm="say \"hi\"", Bearer"#,
+    );
+    assert_eq!(challenges.len(), 3);
+    assert_eq!(challenges[0].scheme(), "Digest");
+    assert_eq!(challenges[0].realm(), Some("testrealm@host.com"));
+    assert_eq!(challenges[0].param("QOP"), Some("auth,auth-int"));
+    assert_eq!(challenges[1].realm(), Some(r#"say "hi""#));
+    assert_eq!(challenges[2].scheme(), "Bearer");
+    assert!(Challenge::parse("").is_empty());
+}
+
+#[test]
+fn digest_response_rfc2617() {
+    let challenge = &Challenge::parse(
+        r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+    )[0];
+    let header = Credentials::digest_authorization(
+        challenge,
+        "Mufasa",
+        "Circle Of Life",
+        "GET",
+        "/dir/index.html",
+        "0a4f113b",
+    );
+    assert!(header.starts_with(r#"Digest username="Mufasa""#));
+    assert!(header.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+    assert!(header.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+}
+
+#[test]
+fn basic_and_bearer_authorization() {
+    let basic = Credentials::basic("Aladdin", Some("open sesame"));
+    assert_eq!(
+        basic.authorization(&[], "GET", "/").unwrap(),
+        "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+    );
+    let bearer = Credentials::bearer("token");
+    assert_eq!(
+        bearer.authorization(&[], "GET", "/").unwrap(),
+        "Bearer token"
+    );
+    assert!(Credentials::digest("user", "pass")
+        .authorization(&Challenge::parse("Basic realm=x"), "GET", "/")
+        .is_err());
+}
+
+#[test]
+fn memory_store() {
+    let store = MemoryStore::new();
+    assert!(store.get("example.com").is_none());
+    store
+        .set("Example.com", &Credentials::bearer("token"))
+        .unwrap();
+    assert_eq!(store.get("example.com"), Some(Credentials::bearer("token")));
+    store.remove("example.com").unwrap();
+    assert!(store.get("example.com").is_none());
+}
+
+#[test]
+fn stored_credentials_round_trip() {
+    for credentials in [
+        Credentials::basic("user", Some("pa:ss")),
+        Credentials::basic("user", None),
+        Credentials::bearer("token"),
+        Credentials::digest("Mufasa", "Circle Of Life"),
+    ] {
+        assert_eq!(decode(&encode(&credentials)), Some(credentials));
+    }
+    assert!(decode("unknown:dXNlcg==").is_none());
+}
@@ -0,0 +1,603 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::error::HttpError;
+use crate::hash::{Checksum, DigestVerifier, Sha256};
+
+#[cfg(test)]
+mod tests;
+
+/// Size of the chunks a body of unknown length is sent in.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Largest line of the chunked framing of a response body, e.g a chunk size with its extensions.
+const MAX_LINE_SIZE: u64 = 8 * 1024;
+
+/// Largest trailer section of a chunked response body.
+const MAX_TRAILERS_SIZE: u64 = 64 * 1024;
+
+const H_CONTENT_DIGEST: &str = "Content-Digest";
+
+/// A request body read from its source while the request is being sent.
+///
+/// Bodies of unknown length are sent with `Transfer-Encoding: chunked`. The body is written
+/// once, requests needing it again (e.g to answer an authentication challenge or follow a
+/// `307` redirect) are not retried.
+pub(crate) struct BodyStream {
+    reader: Box<dyn Read + Send>,
+    length: Option<u64>,
+    sent: bool,
+    /// Hashes the body to send its `Content-Digest` as a trailer, if wanted.
+    digest: Option<Sha256>,
+}
+
+impl BodyStream {
+    /// Create a body read from `reader`, `length` bytes long if known.
+    pub(crate) fn new<R: Read + Send + 'static>(reader: R, length: Option<u64>) -> BodyStream {
+        BodyStream {
+            reader: Box::new(reader),
+            length,
+            sent: false,
+            digest: None,
+        }
+    }
+
+    /// Send the `Content-Digest` of the body as a trailer, hashing it while it is written.
+    ///
+    /// Trailers need the chunked framing, so the body is sent chunked even when its length
+    /// is known.
+    pub(crate) fn send_digest(&mut self) {
+        self.length = None;
+        self.digest = Some(Sha256::new());
+    }
+
+    /// Whether the `Content-Digest` of the body is sent as a trailer.
+    pub(crate) fn sends_digest(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    /// Size of the body in bytes, if known.
+    pub(crate) fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    /// Whether the body was already written.
+    pub(crate) fn is_sent(&self) -> bool {
+        self.sent
+    }
+
+    /// Write the whole body to `writer`, reporting the bytes of the body written to `progress`.
+    pub(crate) fn write_to<W: Write>(
+        &mut self,
+        writer: &mut W,
+        progress: Option<ProgressFn>,
+    ) -> Result<(), HttpError> {
+        if self.sent {
+            return Err(HttpError::Other(
+                "A streamed request body can only be sent once".into(),
+            ));
+        }
+        self.sent = true;
+        let length = self.length;
+        let mut payload = ProgressWriter::new(writer, progress, length);
+        if length.is_some() {
+            std::io::copy(&mut self.reader, &mut payload)?;
+            return Ok(());
+        }
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = match self.reader.read(&mut buffer) {
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if read == 0 {
+                break;
+            }
+            if let Some(digest) = &mut self.digest {
+                digest.update(&buffer[..read]);
+            }
+            // Only the data counts as progress, not the chunk framing
+            write!(payload.inner, "{read:x}\r\n")?;
+            payload.write_all(&buffer[..read])?;
+            payload.inner.write_all(b"\r\n")?;
+        }
+        payload.inner.write_all(b"0\r\n")?;
+        if let Some(digest) = self.digest.take() {
+            let digest = Checksum::Sha256(digest.finalize());
+            write!(
+                payload.inner,
+                "{H_CONTENT_DIGEST}: {}\r\n",
+                digest.digest_header()
+            )?;
+        }
+        payload.inner.write_all(b"\r\n")?;
+        Ok(())
+    }
+}
+
+/// How the end of a response body is found, as described by RFC 9112 section 6.3.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Framing {
+    /// The response has no body, e.g it answers a `HEAD` request or its status is `204`.
+    Empty,
+    /// The body is exactly this many bytes long.
+    Sized(u64),
+    /// The body is sent with `Transfer-Encoding: chunked`.
+    Chunked,
+    /// The body ends when the server closes the connection.
+    Close,
+}
+
+impl Framing {
+    /// The framing of a response from the request it answers, its status and headers.
+    ///
+    /// Responses to `HEAD` requests, `1xx`, `204` and `304` responses and `2xx` responses to
+    /// `CONNECT` never have a body, whatever their headers say.
+    /// # Arguments
+    /// `method`    Method of the request, e.g `GET`.
+    /// `status`    Status code of the response.
+    /// `transfer_encoding` Value of the `Transfer-Encoding` header, if any.
+    /// `content_length`    Value of the `Content-Length` header, if any.
+    pub(crate) fn of(
+        method: &str,
+        status: usize,
+        transfer_encoding: Option<&str>,
+        content_length: Option<&str>,
+    ) -> Result<Framing, HttpError> {
+        let no_body = method.eq_ignore_ascii_case("HEAD")
+            || (method.eq_ignore_ascii_case("CONNECT") && (200..300).contains(&status))
+            || (100..200).contains(&status)
+            || status == 204
+            || status == 304;
+        if no_body {
+            return Ok(Framing::Empty);
+        }
+        if let Some(encoding) = transfer_encoding {
+            let chunked = encoding
+                .rsplit(',')
+                .next()
+                .is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked"));
+            return Ok(if chunked {
+                Framing::Chunked
+            } else {
+                Framing::Close
+            });
+        }
+        match content_length {
+            Some(length) => length
+                .trim()
+                .parse()
+                .map(Framing::Sized)
+                .map_err(|_| HttpError::Other(format!("Invalid Content-Length `{length}`"))),
+            None => Ok(Framing::Close),
+        }
+    }
+}
+
+/// A response body decoded while it is read from the connection.
+pub(crate) struct ResponseBody {
+    reader: BufReader<Box<dyn Read + Send>>,
+    framing: Framing,
+    /// Bytes left in the body, or in the current chunk of a chunked body.
+    remaining: u64,
+    done: bool,
+    /// Fields of the trailer section of a chunked body, once it is read.
+    trailers: Vec<(String, String)>,
+    /// Bytes of the body read so far.
+    received: u64,
+    /// Verifies the `Content-Digest` of the body, if wanted.
+    digest: Option<DigestVerifier>,
+}
+
+impl ResponseBody {
+    /// Create a body read from `reader`, positioned right after the response head.
+    pub(crate) fn new<R: Read + Send + 'static>(reader: R, framing: Framing) -> ResponseBody {
+        ResponseBody {
+            reader: BufReader::new(Box::new(reader)),
+            framing,
+            remaining: match framing {
+                Framing::Sized(length) => length,
+                _ => 0,
+            },
+            done: matches!(framing, Framing::Empty | Framing::Sized(0)),
+            trailers: Vec::new(),
+            received: 0,
+            digest: None,
+        }
+    }
+
+    /// Verify the body against its `Content-Digest` while it is read, the one of the response
+    /// head if any, otherwise the one of the trailers. Reading fails at the end of a body not
+    /// matching its digest.
+    /// # Arguments
+    /// `announced` Digest announced in the head of the response, if any.
+    pub(crate) fn verify_digest(&mut self, announced: Option<Checksum>) {
+        // Only chunked bodies have trailers
+        if self.framing != Framing::Empty
+            && (announced.is_some() || self.framing == Framing::Chunked)
+        {
+            self.digest = Some(DigestVerifier::new(announced));
+        }
+    }
+
+    /// The fields sent after a chunked body, e.g checksums, empty until the body is fully read.
+    pub(crate) fn trailers(&self) -> &[(String, String)] {
+        &self.trailers
+    }
+
+    /// Helper method, read a line of the chunked framing, without its line break, failing
+    /// when it is longer than `limit` so a misbehaving server cannot exhaust the memory.
+    fn read_line(&mut self, limit: u64) -> std::io::Result<String> {
+        let mut line = Vec::new();
+        let read = (&mut self.reader)
+            .take(limit + 1)
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Err(self.truncated());
+        }
+        if read as u64 > limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Chunked body framing line larger than {limit} bytes"),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&line).trim_end().to_owned())
+    }
+
+    /// Helper method, start the next chunk of a chunked body, returns `false` after the last.
+    fn next_chunk(&mut self) -> std::io::Result<bool> {
+        let line = self.read_line(MAX_LINE_SIZE)?;
+        // Chunk extensions are ignored
+        let size = line.split(';').next().unwrap_or_default().trim();
+        self.remaining = u64::from_str_radix(size, 16).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid chunk size `{line}`"),
+            )
+        })?;
+        if self.remaining > 0 {
+            return Ok(true);
+        }
+        // The trailer section ends with an empty line
+        let mut budget = MAX_TRAILERS_SIZE;
+        loop {
+            let line = self.read_line(budget)?;
+            budget = budget.saturating_sub(line.len() as u64 + 2);
+            if line.is_empty() {
+                return Ok(false);
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                self.trailers
+                    .push((name.trim().to_owned(), value.trim().to_owned()));
+            }
+        }
+    }
+
+    /// Helper method, the error of a connection closed before the end of the body, an
+    /// [`HttpError::IncompleteBody`] once converted.
+    fn truncated(&self) -> std::io::Error {
+        let length = match self.framing {
+            Framing::Sized(length) => Some(length),
+            _ => None,
+        };
+        std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            HttpError::IncompleteBody(self.received, length),
+        )
+    }
+
+    /// Helper method, read the next bytes of the body, decoding its framing.
+    fn read_content(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.framing == Framing::Close {
+            let read = self.reader.read(buf)?;
+            self.received += read as u64;
+            self.done = read == 0;
+            return Ok(read);
+        }
+        if self.remaining == 0 && !self.next_chunk()? {
+            self.done = true;
+            return Ok(0);
+        }
+        let wanted = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..wanted])?;
+        if read == 0 {
+            return Err(self.truncated());
+        }
+        self.received += read as u64;
+        self.remaining -= read as u64;
+        if self.remaining == 0 {
+            match self.framing {
+                Framing::Chunked => {
+                    // Every chunk ends with a line break
+                    self.read_line(MAX_LINE_SIZE)?;
+                }
+                _ => self.done = true,
+            }
+        }
+        Ok(read)
+    }
+}
+
+impl Read for ResponseBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.read_content(buf)?;
+        if let Some(digest) = &mut self.digest {
+            digest.update(&buf[..read]);
+        }
+        if self.done {
+            if let Some(digest) = self.digest.take() {
+                let late = self
+                    .trailers
+                    .iter()
+                    .filter(|(name, _)| name.eq_ignore_ascii_case(H_CONTENT_DIGEST))
+                    .find_map(|(_, value)| Checksum::from_digest_header(value));
+                digest
+                    .verify(late)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Where a [`ChunkedDecoder`] is in a chunked body.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum ChunkState {
+    /// Expecting the size line of the next chunk.
+    #[default]
+    Size,
+    /// Expecting this many more bytes of the current chunk.
+    Data(u64),
+    /// Expecting the line break ending the current chunk.
+    DataEnd,
+    /// Expecting the next trailer field, or the empty line ending the body.
+    Trailers,
+    /// The whole body was decoded.
+    Done,
+}
+
+/// Decodes a chunked body as its bytes are received, without waiting for the rest of it.
+///
+/// The state of the body is kept between calls so every byte is decoded once, e.g by
+/// requests driven from an event loop.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkedDecoder {
+    state: ChunkState,
+    data: Vec<u8>,
+    trailers: Vec<(String, String)>,
+    /// Bytes of the trailer section decoded so far.
+    trailers_size: u64,
+}
+
+impl ChunkedDecoder {
+    /// Decode the next bytes of the body, returns how many were used. The bytes of a line
+    /// not received in full are left for the next call.
+    ///
+    /// # Errors
+    /// When the framing is invalid, or a line of it larger than allowed.
+    pub(crate) fn decode(&mut self, input: &[u8]) -> Result<usize, HttpError> {
+        let mut used = 0;
+        loop {
+            let rest = &input[used..];
+            match self.state {
+                ChunkState::Done => return Ok(used),
+                ChunkState::Data(remaining) => {
+                    let wanted = rest
+                        .len()
+                        .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+                    if wanted == 0 {
+                        return Ok(used);
+                    }
+                    self.data.extend_from_slice(&rest[..wanted]);
+                    used += wanted;
+                    self.state = match remaining - wanted as u64 {
+                        0 => ChunkState::DataEnd,
+                        remaining => ChunkState::Data(remaining),
+                    };
+                }
+                _ => {
+                    let Some(end) = rest.iter().position(|&byte| byte == b'\n') else {
+                        if rest.len() as u64 > MAX_LINE_SIZE {
+                            return Err(HttpError::TooLarge(format!(
+                                "chunked body framing line larger than {MAX_LINE_SIZE} bytes"
+                            )));
+                        }
+                        return Ok(used);
+                    };
+                    let line = String::from_utf8_lossy(&rest[..end]).trim_end().to_owned();
+                    used += end + 1;
+                    self.next_line(&line)?;
+                }
+            }
+        }
+    }
+
+    /// Whether the whole body was decoded.
+    pub(crate) fn is_done(&self) -> bool {
+        self.state == ChunkState::Done
+    }
+
+    /// The bytes of the body decoded so far.
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the decoded body and its trailer fields.
+    pub(crate) fn take(&mut self) -> (Vec<u8>, Vec<(String, String)>) {
+        (
+            std::mem::take(&mut self.data),
+            std::mem::take(&mut self.trailers),
+        )
+    }
+
+    /// Helper method, handle a line of the chunked framing, without its line break.
+    fn next_line(&mut self, line: &str) -> Result<(), HttpError> {
+        self.state = match self.state {
+            ChunkState::Size => {
+                // Chunk extensions are ignored
+                let size = line.split(';').next().unwrap_or_default().trim();
+                match u64::from_str_radix(size, 16) {
+                    Ok(0) => ChunkState::Trailers,
+                    Ok(size) => ChunkState::Data(size),
+                    Err(_) => return Err(HttpError::Other(format!("Invalid chunk size `{line}`"))),
+                }
+            }
+            ChunkState::DataEnd => ChunkState::Size,
+            // The trailer section ends with an empty line
+            ChunkState::Trailers if line.is_empty() => ChunkState::Done,
+            ChunkState::Trailers => {
+                self.trailers_size += line.len() as u64 + 2;
+                if self.trailers_size > MAX_TRAILERS_SIZE {
+                    return Err(HttpError::TooLarge(format!(
+                        "trailer section larger than {MAX_TRAILERS_SIZE} bytes"
+                    )));
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    self.trailers
+                        .push((name.trim().to_owned(), value.trim().to_owned()));
+                }
+                ChunkState::Trailers
+            }
+            state => state,
+        };
+        Ok(())
+    }
+}
+
+/// A writer shared by the requests mirroring their responses to it.
+pub(crate) type SharedWriter = Arc<Mutex<dyn Write + Send>>;
+
+/// A reader or writer copying every byte read or written through it to another writer, as
+/// soon as it goes through.
+pub(crate) struct Tee<R> {
+    inner: R,
+    writer: SharedWriter,
+}
+
+impl<R> Tee<R> {
+    pub(crate) fn new(inner: R, writer: SharedWriter) -> Tee<R> {
+        Tee { inner, writer }
+    }
+}
+
+impl<R: Read> Read for Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| std::io::Error::other("Response mirror writer poisoned"))?;
+        writer.write_all(&buf[..read])?;
+        Ok(read)
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| std::io::Error::other("Mirror writer poisoned"))?;
+        writer.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A callback told how many bytes were transferred so far, and how many in total if known.
+pub(crate) type ProgressFn = Arc<Mutex<dyn FnMut(u64, Option<u64>) + Send>>;
+
+/// A writer reporting the bytes written through it after every write.
+pub(crate) struct ProgressWriter<W> {
+    inner: W,
+    callback: Option<ProgressFn>,
+    written: u64,
+    total: Option<u64>,
+}
+
+impl<W: Write> ProgressWriter<W> {
+    /// Create a writer reporting to `callback`, if any, out of `total` bytes if known.
+    pub(crate) fn new(inner: W, callback: Option<ProgressFn>, total: Option<u64>) -> Self {
+        ProgressWriter {
+            inner,
+            callback,
+            written: 0,
+            total,
+        }
+    }
+
+    /// The number of bytes written so far.
+    pub(crate) fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// The size reported as the total, if known.
+    pub(crate) fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Change the size reported as the total, e.g once the server announced it.
+    pub(crate) fn set_total(&mut self, total: Option<u64>) {
+        self.total = total;
+    }
+}
+
+impl<W: Write> Write for ProgressWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        if let Some(callback) = &self.callback {
+            report(callback, self.written, self.total);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A writer reporting its errors as [`HttpError::Destination`], telling failures of the
+/// destination of a download apart from failures of the connection.
+pub(crate) struct DestinationWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> DestinationWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        DestinationWriter { inner }
+    }
+
+    /// Helper method, wrap an error of the destination so it reaches the caller as is.
+    fn failed(error: std::io::Error) -> std::io::Error {
+        std::io::Error::new(error.kind(), HttpError::Destination(error.to_string()))
+    }
+}
+
+impl<W: Write> Write for DestinationWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf).map_err(Self::failed)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush().map_err(Self::failed)
+    }
+}
+
+/// Tell a progress callback how many bytes were transferred.
+pub(crate) fn report(callback: &ProgressFn, done: u64, total: Option<u64>) {
+    // A callback that panicked before is not called again
+    if let Ok(mut callback) = callback.lock() {
+        callback(done, total);
+    }
+}
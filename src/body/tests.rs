@@ -0,0 +1,281 @@
+use std::io::{Cursor, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::body::{
+    BodyStream, DestinationWriter, Framing, ProgressFn, ProgressWriter, ResponseBody, SharedWriter,
+    Tee,
+};
+use crate::error::HttpError;
+use crate::hash::{Checksum, Sha256};
+
+#[test]
+fn chunked_body() {
+    let mut body = BodyStream::new(Cursor::new(b"hello world".to_vec()), None);
+    let mut sent = Vec::new();
+    body.write_to(&mut sent, None).unwrap();
+    assert_eq!(sent, b"b\r\nhello world\r\n0\r\n\r\n");
+
+    let mut empty = BodyStream::new(std::io::empty(), None);
+    let mut sent = Vec::new();
+    empty.write_to(&mut sent, None).unwrap();
+    assert_eq!(sent, b"0\r\n\r\n");
+}
+
+#[test]
+fn sized_body() {
+    let mut body = BodyStream::new(Cursor::new(b"hello".to_vec()), Some(5));
+    let mut sent = Vec::new();
+    body.write_to(&mut sent, None).unwrap();
+    assert_eq!(sent, b"hello");
+    assert!(body.is_sent());
+}
+
+#[test]
+fn response_framing() {
+    assert_eq!(
+        Framing::of("HEAD", 200, None, Some("10")).unwrap(),
+        Framing::Empty
+    );
+    assert_eq!(
+        Framing::of("GET", 304, None, Some("10")).unwrap(),
+        Framing::Empty
+    );
+    assert_eq!(Framing::of("GET", 204, None, None).unwrap(), Framing::Empty);
+    assert_eq!(
+        Framing::of("GET", 200, Some("gzip, Chunked"), Some("10")).unwrap(),
+        Framing::Chunked
+    );
+    assert_eq!(
+        Framing::of("GET", 200, Some("gzip"), None).unwrap(),
+        Framing::Close
+    );
+    assert_eq!(
+        Framing::of("GET", 200, None, Some("10")).unwrap(),
+        Framing::Sized(10)
+    );
+    assert_eq!(Framing::of("GET", 200, None, None).unwrap(), Framing::Close);
+    assert!(Framing::of("GET", 200, None, Some("ten")).is_err());
+    assert_eq!(
+        Framing::of("CONNECT", 200, None, Some("10")).unwrap(),
+        Framing::Empty
+    );
+    assert_eq!(
+        Framing::of("CONNECT", 407, None, Some("10")).unwrap(),
+        Framing::Sized(10)
+    );
+    assert_eq!(
+        Framing::of("GET", 101, None, Some("10")).unwrap(),
+        Framing::Empty
+    );
+}
+
+#[test]
+fn response_bodies() {
+    let read = |data: &[u8], framing| {
+        let mut body = ResponseBody::new(Cursor::new(data.to_vec()), framing);
+        let mut result = Vec::new();
+        body.read_to_end(&mut result).map(|_| result)
+    };
+    let chunked = b"5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\nNEXT";
+    assert_eq!(read(chunked, Framing::Chunked).unwrap(), b"hello world");
+    assert_eq!(read(b"hello world", Framing::Sized(5)).unwrap(), b"hello");
+    assert_eq!(
+        read(b"hello world", Framing::Close).unwrap(),
+        b"hello world"
+    );
+    assert_eq!(read(b"hello", Framing::Empty).unwrap(), b"");
+    assert!(read(b"hello", Framing::Sized(10)).is_err());
+    assert!(read(b"5\r\nhel", Framing::Chunked).is_err());
+    assert!(read(b"x\r\n", Framing::Chunked).is_err());
+}
+
+#[test]
+fn truncated_bodies() {
+    let error = |data: &[u8], framing| {
+        let mut body = ResponseBody::new(Cursor::new(data.to_vec()), framing);
+        HttpError::from(body.read_to_end(&mut Vec::new()).unwrap_err())
+    };
+    assert!(matches!(
+        error(b"hello", Framing::Sized(10)),
+        HttpError::IncompleteBody(5, Some(10))
+    ));
+    assert!(matches!(
+        error(b"5\r\nhello\r\n3\r\nab", Framing::Chunked),
+        HttpError::IncompleteBody(7, None)
+    ));
+    assert!(matches!(
+        error(b"5\r\nhello\r\n", Framing::Chunked),
+        HttpError::IncompleteBody(5, None)
+    ));
+}
+
+#[test]
+fn chunked_body_read_incrementally() {
+    // The connection fails once the first chunk is consumed, its data is still handed over
+    let first = Cursor::new(b"5\r\nhello\r\n".to_vec());
+    let mut body = ResponseBody::new(first.chain(FailingReader), Framing::Chunked);
+    let mut buf = [0u8; 16];
+    assert_eq!(body.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert!(body.read(&mut buf).is_err());
+}
+
+#[test]
+fn chunked_body_line_limits() {
+    let long_size = format!("5;{}\r\nhello\r\n0\r\n\r\n", "x".repeat(10_000));
+    let mut body = ResponseBody::new(Cursor::new(long_size.into_bytes()), Framing::Chunked);
+    assert!(body.read_to_end(&mut Vec::new()).is_err());
+
+    let mut long_trailers = b"0\r\n".to_vec();
+    for _ in 0..10_000 {
+        long_trailers.extend_from_slice(b"x-padding: 0123456789\r\n");
+    }
+    long_trailers.extend_from_slice(b"\r\n");
+    let mut body = ResponseBody::new(Cursor::new(long_trailers), Framing::Chunked);
+    assert!(body.read_to_end(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn response_trailers() {
+    let chunked = b"5\r\nhello\r\n0\r\nDigest: sha-256=abc\r\ngrpc-status:0\r\n\r\n";
+    let mut body = ResponseBody::new(Cursor::new(chunked.to_vec()), Framing::Chunked);
+    let mut result = Vec::new();
+    body.read_exact(&mut [0u8; 2]).unwrap();
+    assert!(body.trailers().is_empty());
+    body.read_to_end(&mut result).unwrap();
+    assert_eq!(result, b"llo");
+    assert_eq!(
+        body.trailers(),
+        [("Digest", "sha-256=abc"), ("grpc-status", "0")]
+            .map(|(n, v)| (n.to_owned(), v.to_owned()))
+    );
+}
+
+#[test]
+fn tee_copies_read_bytes() {
+    let wire = b"HTTP/1.1 200 OK\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+    let mirror = Arc::new(Mutex::new(Vec::new()));
+    let writer: SharedWriter = mirror.clone();
+    let mut tee = Tee::new(Cursor::new(wire.to_vec()), writer);
+    let mut head = [0u8; 19];
+    tee.read_exact(&mut head).unwrap();
+    assert_eq!(*mirror.lock().unwrap(), head);
+    let mut body = ResponseBody::new(tee, Framing::Chunked);
+    let mut result = Vec::new();
+    body.read_to_end(&mut result).unwrap();
+    assert_eq!(result, b"hello");
+    assert_eq!(*mirror.lock().unwrap(), wire);
+}
+
+#[test]
+fn progress_reports_writes() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let seen = reports.clone();
+    let callback: ProgressFn = Arc::new(Mutex::new(move |done, total| {
+        seen.lock().unwrap().push((done, total));
+    }));
+    let mut written = Vec::new();
+    let mut writer = ProgressWriter::new(&mut written, Some(callback), Some(8));
+    writer.write_all(b"abc").unwrap();
+    writer.write_all(b"defgh").unwrap();
+    assert_eq!(written, b"abcdefgh");
+    assert_eq!(*reports.lock().unwrap(), [(3, Some(8)), (8, Some(8))]);
+}
+
+#[test]
+fn upload_progress_counts_data() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let seen = reports.clone();
+    let callback: ProgressFn = Arc::new(Mutex::new(move |done, total| {
+        seen.lock().unwrap().push((done, total));
+    }));
+    let mut body = BodyStream::new(Cursor::new(b"hello world".to_vec()), None);
+    let mut sent = Vec::new();
+    body.write_to(&mut sent, Some(callback)).unwrap();
+    assert_eq!(sent, b"b\r\nhello world\r\n0\r\n\r\n");
+    assert_eq!(*reports.lock().unwrap(), [(11, None)]);
+}
+
+/// A reader failing every read, like a dropped connection.
+struct FailingReader;
+
+impl Read for FailingReader {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+    }
+}
+
+#[test]
+fn content_digest_trailer() {
+    let mut body = BodyStream::new(Cursor::new(b"hello world".to_vec()), Some(11));
+    body.send_digest();
+    assert_eq!(body.length(), None);
+    let mut sent = Vec::new();
+    body.write_to(&mut sent, None).unwrap();
+    assert_eq!(
+        String::from_utf8(sent).unwrap(),
+        "b\r\nhello world\r\n0\r\n\
+         Content-Digest: sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:\r\n\r\n"
+    );
+}
+
+#[test]
+fn content_digest_verified() {
+    let digest = Checksum::Sha256(Sha256::digest(b"hello world"));
+    let read = |raw: &[u8], framing, announced| {
+        let mut body = ResponseBody::new(Cursor::new(raw.to_vec()), framing);
+        body.verify_digest(announced);
+        let mut data = Vec::new();
+        body.read_to_end(&mut data).map(|_| data)
+    };
+    assert!(read(b"hello world", Framing::Sized(11), Some(digest.clone())).is_ok());
+    assert!(read(b"hello there", Framing::Sized(11), Some(digest.clone())).is_err());
+    assert!(read(b"hello there", Framing::Sized(11), None).is_ok());
+
+    let chunked = |data: &str| {
+        format!(
+            "b\r\n{data}\r\n0\r\nContent-Digest: {}\r\n\r\n",
+            digest.digest_header()
+        )
+    };
+    assert!(read(chunked("hello world").as_bytes(), Framing::Chunked, None).is_ok());
+    assert!(read(chunked("hello there").as_bytes(), Framing::Chunked, None).is_err());
+}
+
+#[test]
+fn tee_copies_written_bytes() {
+    let mirror = Arc::new(Mutex::new(Vec::new()));
+    let writer: SharedWriter = mirror.clone();
+    let mut sent = Vec::new();
+    let mut body = BodyStream::new(Cursor::new(b"hello".to_vec()), None);
+    body.write_to(&mut Tee::new(&mut sent, writer), None)
+        .unwrap();
+    assert_eq!(sent, b"5\r\nhello\r\n0\r\n\r\n");
+    assert_eq!(*mirror.lock().unwrap(), sent);
+}
+
+#[test]
+fn destination_errors_told_apart() {
+    struct Full;
+    impl Write for Full {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::StorageFull,
+                "No space left on device",
+            ))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut writer = DestinationWriter::new(Full);
+    let error = writer.write_all(b"data").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::StorageFull);
+    assert!(matches!(
+        HttpError::from(error),
+        HttpError::Destination(message) if message == "No space left on device"
+    ));
+    let mut writer = DestinationWriter::new(Vec::new());
+    writer.write_all(b"data").unwrap();
+    assert_eq!(writer.inner, b"data");
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::HttpError;
+
+#[cfg(test)]
+mod tests;
+
+/// The failures of a host since its last success.
+#[derive(Debug, Default)]
+struct HostState {
+    failures: usize,
+    /// When the circuit opened, if it is open.
+    opened: Option<Instant>,
+    /// Whether a trial request is being sent after the cooldown.
+    trial: bool,
+}
+
+/// Fails requests fast to hosts that keep failing, so a dead backend is not hammered.
+///
+/// After `threshold` consecutive failures to a host, requests to it fail right away with
+/// [`HttpError::CircuitOpen`] for the cooldown period. A single request is then let through:
+/// its success closes the circuit, its failure opens it for another cooldown. Hosts are told
+/// apart by host name and port. Connection errors and `5xx` responses count as failures.
+///
+/// # Example:
+/// ``` no_run
+/// use std::{sync::Arc, time::Duration};
+/// use http_client::{breaker::CircuitBreaker, client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let breaker = Arc::new(CircuitBreaker::new(5, Duration::from_secs(30)));
+///     let client = Client::builder().circuit_breaker(breaker).build();
+///     for id in 0..1000 {
+///         match client.get(&format!("https://example.com/items/{id}"))?.send() {
+///             Err(HttpError::CircuitOpen(host)) => eprintln!("{host} is down, skipping {id}"),
+///             result => println!("{}", result?.status_code()),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: usize,
+    cooldown: Duration,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker opening after `threshold` consecutive failures for `cooldown`.
+    /// # Arguments
+    /// `threshold` Consecutive failures opening the circuit of a host, at least one.
+    /// `cooldown`  How long requests to the host fail fast.
+    pub fn new(threshold: usize, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold: threshold.max(1),
+            cooldown,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether requests to the given host currently fail fast.
+    /// # Arguments
+    /// `address`   Host and port, e.g `example.com:443`.
+    pub fn is_open(&self, address: &str) -> bool {
+        let Ok(hosts) = self.hosts.lock() else {
+            return false;
+        };
+        hosts
+            .get(&address.to_ascii_lowercase())
+            .and_then(|state| state.opened)
+            .is_some_and(|opened| opened.elapsed() < self.cooldown)
+    }
+
+    /// Close every circuit and forget every failure.
+    pub fn reset(&self) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            hosts.clear();
+        }
+    }
+
+    /// Let a request to the given host through, unless its circuit is open.
+    ///
+    /// # Errors
+    /// [`HttpError::CircuitOpen`] while the circuit is open, or while the trial request
+    /// after the cooldown is in flight.
+    pub(crate) fn allow(&self, address: &str) -> Result<(), HttpError> {
+        let Ok(mut hosts) = self.hosts.lock() else {
+            return Ok(());
+        };
+        let Some(state) = hosts.get_mut(&address.to_ascii_lowercase()) else {
+            return Ok(());
+        };
+        match state.opened {
+            None => Ok(()),
+            Some(opened) if opened.elapsed() < self.cooldown || state.trial => {
+                Err(HttpError::CircuitOpen(address.to_owned()))
+            }
+            Some(_) => {
+                state.trial = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Let another trial request through after one that failed on its own, e.g because of
+    /// an invalid header, telling nothing about the host.
+    pub(crate) fn release(&self, address: &str) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            if let Some(state) = hosts.get_mut(&address.to_ascii_lowercase()) {
+                state.trial = false;
+            }
+        }
+    }
+
+    /// Record the outcome of a request to the given host.
+    pub(crate) fn record(&self, address: &str, success: bool) {
+        let Ok(mut hosts) = self.hosts.lock() else {
+            return;
+        };
+        let address = address.to_ascii_lowercase();
+        if success {
+            hosts.remove(&address);
+            return;
+        }
+        let state = hosts.entry(address).or_default();
+        state.failures += 1;
+        state.trial = false;
+        if state.failures >= self.threshold {
+            state.opened = Some(Instant::now());
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use crate::breaker::CircuitBreaker;
+use crate::error::HttpError;
+
+#[test]
+fn opens_after_consecutive_failures() {
+    let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+    breaker.record("example.com:443", false);
+    assert!(breaker.allow("example.com:443").is_ok());
+    breaker.record("example.com:443", true);
+    breaker.record("example.com:443", false);
+    assert!(!breaker.is_open("example.com:443"));
+    breaker.record("Example.com:443", false);
+    assert!(breaker.is_open("example.com:443"));
+    assert!(matches!(
+        breaker.allow("example.com:443"),
+        Err(HttpError::CircuitOpen(_))
+    ));
+    assert!(breaker.allow("example.com:80").is_ok());
+    breaker.reset();
+    assert!(breaker.allow("example.com:443").is_ok());
+}
+
+#[test]
+fn single_trial_after_cooldown() {
+    let breaker = CircuitBreaker::new(1, Duration::ZERO);
+    breaker.record("example.com:443", false);
+    assert!(breaker.allow("example.com:443").is_ok());
+    // Only one request tries the host
+    assert!(breaker.allow("example.com:443").is_err());
+    breaker.record("example.com:443", false);
+    assert!(breaker.allow("example.com:443").is_ok());
+    assert!(breaker.allow("example.com:443").is_err());
+    breaker.release("example.com:443");
+    assert!(breaker.allow("example.com:443").is_ok());
+    breaker.record("example.com:443", true);
+    assert!(breaker.allow("example.com:443").is_ok());
+    assert!(breaker.allow("example.com:443").is_ok());
+}
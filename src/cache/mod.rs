@@ -0,0 +1,400 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::date::parse_http_date;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum size of the responses kept by a cache, unless configured otherwise.
+const DEFAULT_MAX_SIZE: usize = 32 * 1024 * 1024;
+
+/// Largest number of seconds in `Age` or `max-age`, larger values are clamped to it as
+/// RFC 9111 requires.
+const MAX_DELTA_SECONDS: u64 = 1 << 31;
+
+/// Status codes that can be cached without explicit freshness information, see RFC 9110.
+const HEURISTICALLY_CACHEABLE: [usize; 11] =
+    [200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// A response stored in the cache.
+#[derive(Debug, Clone)]
+struct Entry {
+    raw: Vec<u8>,
+    vary: Vec<(String, Option<String>)>,
+    stored_at: Instant,
+    initial_age: Duration,
+    lifetime: Duration,
+    no_cache: bool,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Entry {
+    /// How long ago the response was generated by the origin server.
+    fn age(&self) -> Duration {
+        self.initial_age.saturating_add(self.stored_at.elapsed())
+    }
+}
+
+/// Result of looking a request up in the cache.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Lookup {
+    /// Nothing usable is stored, the request must be sent.
+    Miss,
+    /// A fresh response, in wire format.
+    Fresh(Vec<u8>),
+    /// A stale response that must be revalidated with the given validators before it is used.
+    Stale {
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+/// An in-memory HTTP cache following RFC 9111.
+///
+/// Responses to `GET` requests are stored by URL and the request headers named in `Vary`.
+/// Fresh responses are served without contacting the server, stale ones are revalidated with
+/// `If-None-Match` or `If-Modified-Since` and served again when the server answers with
+/// `304 Not Modified`. Freshness follows `Cache-Control`, `Expires` and `Age`.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::builder().cache(true).build();
+///     client.get("https://example.com/api/items")?.send()?;
+///     // Served from the cache while the response is fresh
+///     client.get("https://example.com/api/items")?.send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HttpCache {
+    entries: Mutex<HashMap<String, Vec<Entry>>>,
+    order: Mutex<VecDeque<String>>,
+    max_size: usize,
+}
+
+impl Default for HttpCache {
+    fn default() -> Self {
+        HttpCache {
+            entries: Mutex::new(HashMap::new()),
+            order: Mutex::new(VecDeque::new()),
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+}
+
+impl HttpCache {
+    /// Create an empty cache holding up to 32 MiB of responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty cache holding up to `max_size` bytes of responses.
+    pub fn with_max_size(max_size: usize) -> Self {
+        HttpCache {
+            max_size,
+            ..Default::default()
+        }
+    }
+
+    /// Number of responses stored.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.values().map(Vec::len).sum())
+            .unwrap_or_default()
+    }
+
+    /// Whether no response is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove every stored response.
+    pub fn clear(&self) {
+        if let (Ok(mut entries), Ok(mut order)) = (self.entries.lock(), self.order.lock()) {
+            entries.clear();
+            order.clear();
+        }
+    }
+
+    /// Look up the response stored for a request.
+    ///
+    /// # Arguments
+    /// `key`   URL of the request.
+    /// `request_headers`   Name and value of every header of the request.
+    pub(crate) fn lookup(&self, key: &str, request_headers: &[(String, String)]) -> Lookup {
+        let request = Directives::parse(header(request_headers, "Cache-Control").as_deref());
+        if request.contains("no-store") {
+            return Lookup::Miss;
+        }
+        let Ok(entries) = self.entries.lock() else {
+            return Lookup::Miss;
+        };
+        let Some(entry) = entries
+            .get(key)
+            .and_then(|entries| entries.iter().find(|entry| entry.matches(request_headers)))
+        else {
+            return Lookup::Miss;
+        };
+        let age = entry.age();
+        let acceptable_age = request
+            .seconds("max-age")
+            .map_or(entry.lifetime, |max_age| max_age.min(entry.lifetime));
+        let fresh = !entry.no_cache && !request.contains("no-cache") && age < acceptable_age;
+        if fresh {
+            Lookup::Fresh(entry.raw.clone())
+        } else if entry.etag.is_some() || entry.last_modified.is_some() {
+            Lookup::Stale {
+                etag: entry.etag.clone(),
+                last_modified: entry.last_modified.clone(),
+            }
+        } else {
+            Lookup::Miss
+        }
+    }
+
+    /// Store the response to a `GET` request, if it can be cached.
+    ///
+    /// # Arguments
+    /// `key`   URL of the request.
+    /// `request_headers`   Name and value of every header of the request.
+    /// `status`    Status code of the response.
+    /// `response_header`   Value of a response header, by name.
+    /// `raw`   The response in wire format.
+    pub(crate) fn store<F>(
+        &self,
+        key: &str,
+        request_headers: &[(String, String)],
+        status: usize,
+        response_header: F,
+        raw: Vec<u8>,
+    ) where
+        F: Fn(&str) -> Option<String>,
+    {
+        let request = Directives::parse(header(request_headers, "Cache-Control").as_deref());
+        let response = Directives::parse(response_header("Cache-Control").as_deref());
+        // Partial responses are not combined, they are simply not stored
+        if request.contains("no-store")
+            || response.contains("no-store")
+            || status == 206
+            || header(request_headers, "Range").is_some()
+            || raw.len() > self.max_size
+        {
+            return;
+        }
+        let vary = response_header("Vary").unwrap_or_default();
+        if vary.split(',').any(|name| name.trim() == "*") {
+            return;
+        }
+        let explicit = explicit_lifetime(&response, &response_header);
+        let lifetime = match explicit {
+            Some(lifetime) => lifetime,
+            None if HEURISTICALLY_CACHEABLE.contains(&status) => {
+                heuristic_lifetime(&response_header)
+            }
+            None => return,
+        };
+        let entry = Entry {
+            raw,
+            vary: vary
+                .split(',')
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty())
+                .map(|name| (name.to_ascii_lowercase(), header(request_headers, name)))
+                .collect(),
+            stored_at: Instant::now(),
+            initial_age: initial_age(&response_header),
+            lifetime,
+            no_cache: response.contains("no-cache"),
+            etag: response_header("ETag"),
+            last_modified: response_header("Last-Modified"),
+        };
+        if entry.lifetime.is_zero() && entry.etag.is_none() && entry.last_modified.is_none() {
+            return;
+        }
+        self.insert(key, entry);
+    }
+
+    /// Update the stored response after the server confirmed it with `304 Not Modified`.
+    ///
+    /// Returns the stored response in wire format, or `None` if it is gone.
+    pub(crate) fn refresh<F>(
+        &self,
+        key: &str,
+        request_headers: &[(String, String)],
+        response_header: F,
+    ) -> Option<Vec<u8>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut entries = self.entries.lock().ok()?;
+        let entry = entries
+            .get_mut(key)?
+            .iter_mut()
+            .find(|entry| entry.matches(request_headers))?;
+        let response = Directives::parse(response_header("Cache-Control").as_deref());
+        if let Some(lifetime) = explicit_lifetime(&response, &response_header) {
+            entry.lifetime = lifetime;
+        }
+        entry.stored_at = Instant::now();
+        entry.initial_age = initial_age(&response_header);
+        if let Some(etag) = response_header("ETag") {
+            entry.etag = Some(etag);
+        }
+        Some(entry.raw.clone())
+    }
+
+    /// Remove the responses stored for a URL, after an unsafe request changed it.
+    pub(crate) fn invalidate(&self, key: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(key);
+        }
+    }
+
+    /// Helper method, store an entry, evicting the oldest ones when the cache is full.
+    fn insert(&self, key: &str, entry: Entry) {
+        let (Ok(mut entries), Ok(mut order)) = (self.entries.lock(), self.order.lock()) else {
+            return;
+        };
+        let stored = entries.entry(key.to_owned()).or_default();
+        stored.retain(|stored| stored.vary != entry.vary);
+        stored.push(entry);
+        order.retain(|stored| stored != key);
+        order.push_back(key.to_owned());
+
+        let size = |entries: &HashMap<String, Vec<Entry>>| {
+            entries
+                .values()
+                .flatten()
+                .map(|entry| entry.raw.len())
+                .sum::<usize>()
+        };
+        while size(&entries) > self.max_size {
+            let Some(oldest) = order.pop_front() else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+    }
+}
+
+impl Entry {
+    /// Whether the request headers selected by `Vary` match the ones of the stored request.
+    fn matches(&self, request_headers: &[(String, String)]) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| header(request_headers, name) == *value)
+    }
+}
+
+/// Whether a response with the given `Cache-Control` header may only be given to the request
+/// it answers, e.g because of `no-store` or `private`.
+pub(crate) fn is_private(cache_control: Option<&str>) -> bool {
+    let directives = Directives::parse(cache_control);
+    directives.contains("no-store") || directives.contains("private")
+}
+
+/// Directives of a `Cache-Control` header.
+struct Directives(HashMap<String, Option<String>>);
+
+impl Directives {
+    fn parse(value: Option<&str>) -> Directives {
+        Directives(
+            value
+                .unwrap_or_default()
+                .split(',')
+                .filter(|directive| !directive.trim().is_empty())
+                .map(|directive| match directive.split_once('=') {
+                    Some((name, value)) => (
+                        name.trim().to_ascii_lowercase(),
+                        Some(value.trim().trim_matches('"').to_owned()),
+                    ),
+                    None => (directive.trim().to_ascii_lowercase(), None),
+                })
+                .collect(),
+        )
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    fn seconds(&self, name: &str) -> Option<Duration> {
+        delta_seconds(self.0.get(name)?.as_deref()?)
+    }
+}
+
+/// Helper method, parse a number of seconds such as the value of `Age`, clamped to
+/// [`MAX_DELTA_SECONDS`].
+fn delta_seconds(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    // Digits only, so parsing can only fail because the value is too large
+    let seconds = value.parse::<u64>().unwrap_or(MAX_DELTA_SECONDS);
+    Some(Duration::from_secs(seconds.min(MAX_DELTA_SECONDS)))
+}
+
+/// Helper method, the value of a request header, by case-insensitive name.
+fn header(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+/// Helper method, the freshness lifetime given by `max-age` or `Expires`.
+fn explicit_lifetime<F>(directives: &Directives, response_header: &F) -> Option<Duration>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some(max_age) = directives.seconds("max-age") {
+        return Some(max_age);
+    }
+    let expires = response_header("Expires")?;
+    // An invalid date, e.g `0`, means already expired
+    let Some(expires) = parse_http_date(&expires) else {
+        return Some(Duration::ZERO);
+    };
+    let date = response_header("Date")
+        .and_then(|date| parse_http_date(&date))
+        .unwrap_or_else(SystemTime::now);
+    Some(expires.duration_since(date).unwrap_or_default())
+}
+
+/// Helper method, a freshness lifetime of 10% of the time since the response last changed.
+fn heuristic_lifetime<F>(response_header: &F) -> Duration
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let Some(last_modified) =
+        response_header("Last-Modified").and_then(|date| parse_http_date(&date))
+    else {
+        return Duration::ZERO;
+    };
+    let date = response_header("Date")
+        .and_then(|date| parse_http_date(&date))
+        .unwrap_or_else(SystemTime::now);
+    date.duration_since(last_modified).unwrap_or_default() / 10
+}
+
+/// Helper method, the age of a response when it was received.
+fn initial_age<F>(response_header: &F) -> Duration
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let age = response_header("Age")
+        .and_then(|age| delta_seconds(&age))
+        .unwrap_or_default();
+    let apparent_age = response_header("Date")
+        .and_then(|date| parse_http_date(&date))
+        .and_then(|date| SystemTime::now().duration_since(date).ok())
+        .unwrap_or_default();
+    age.max(apparent_age)
+}
@@ -0,0 +1,133 @@
+use crate::cache::{HttpCache, Lookup};
+
+fn response_headers(
+    headers: &'static [(&'static str, &'static str)],
+) -> impl Fn(&str) -> Option<String> {
+    move |name| {
+        headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+#[test]
+fn serve_fresh_responses() {
+    let cache = HttpCache::new();
+    let raw = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+    cache.store(
+        "http://a/",
+        &[],
+        200,
+        response_headers(&[("Cache-Control", "public, max-age=60")]),
+        raw.clone(),
+    );
+    assert_eq!(cache.lookup("http://a/", &[]), Lookup::Fresh(raw));
+    assert_eq!(cache.lookup("http://b/", &[]), Lookup::Miss);
+
+    let no_cache = [("Cache-Control".to_owned(), "no-cache".to_owned())];
+    assert_eq!(cache.lookup("http://a/", &no_cache), Lookup::Miss);
+
+    cache.invalidate("http://a/");
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn revalidate_stale_responses() {
+    let cache = HttpCache::new();
+    cache.store(
+        "http://a/",
+        &[],
+        200,
+        response_headers(&[("Cache-Control", "max-age=0"), ("ETag", "\"v1\"")]),
+        b"cached".to_vec(),
+    );
+    assert_eq!(
+        cache.lookup("http://a/", &[]),
+        Lookup::Stale {
+            etag: Some("\"v1\"".into()),
+            last_modified: None
+        }
+    );
+    let refreshed = cache.refresh(
+        "http://a/",
+        &[],
+        response_headers(&[("Cache-Control", "max-age=60")]),
+    );
+    assert_eq!(refreshed.as_deref(), Some(&b"cached"[..]));
+    assert_eq!(
+        cache.lookup("http://a/", &[]),
+        Lookup::Fresh(b"cached".to_vec())
+    );
+}
+
+#[test]
+fn respect_no_store_and_vary() {
+    let cache = HttpCache::new();
+    cache.store(
+        "http://a/",
+        &[],
+        200,
+        response_headers(&[("Cache-Control", "no-store, max-age=60")]),
+        b"secret".to_vec(),
+    );
+    cache.store(
+        "http://b/",
+        &[],
+        500,
+        response_headers(&[]),
+        b"error".to_vec(),
+    );
+    assert!(cache.is_empty());
+
+    let english = [("Accept-Language".to_owned(), "en".to_owned())];
+    let french = [("accept-language".to_owned(), "fr".to_owned())];
+    cache.store(
+        "http://a/",
+        &english,
+        200,
+        response_headers(&[("Cache-Control", "max-age=60"), ("Vary", "Accept-Language")]),
+        b"hello".to_vec(),
+    );
+    assert_eq!(
+        cache.lookup("http://a/", &english),
+        Lookup::Fresh(b"hello".to_vec())
+    );
+    assert_eq!(cache.lookup("http://a/", &french), Lookup::Miss);
+}
+
+#[test]
+fn evict_oldest_entries() {
+    let cache = HttpCache::with_max_size(10);
+    let headers = || response_headers(&[("Cache-Control", "max-age=60")]);
+    cache.store("http://a/", &[], 200, headers(), vec![0; 6]);
+    cache.store("http://b/", &[], 200, headers(), vec![0; 6]);
+    assert_eq!(cache.lookup("http://a/", &[]), Lookup::Miss);
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn huge_ages_clamped() {
+    let cache = HttpCache::new();
+    let raw = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+    cache.store(
+        "http://a/",
+        &[],
+        200,
+        response_headers(&[
+            ("Cache-Control", "max-age=99999999999999999999999"),
+            ("Age", "18446744073709551615"),
+        ]),
+        raw.clone(),
+    );
+    // Both are clamped to the same value, so the response is already stale
+    assert_eq!(cache.lookup("http://a/", &[]), Lookup::Miss);
+    cache.store(
+        "http://b/",
+        &[],
+        200,
+        response_headers(&[("Cache-Control", "max-age=99999999999999999999999")]),
+        raw.clone(),
+    );
+    assert_eq!(cache.lookup("http://b/", &[]), Lookup::Fresh(raw));
+}
@@ -0,0 +1,264 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::HttpError;
+
+#[cfg(test)]
+mod tests;
+
+/// How often a sleeping request checks whether it was cancelled.
+const SLEEP_SLICE: Duration = Duration::from_millis(20);
+
+/// The state shared by the copies of a token.
+#[derive(Debug, Default)]
+struct Shared {
+    cancelled: AtomicBool,
+    /// Copies of the sockets of the requests in flight, shut down on cancellation.
+    sockets: Mutex<Vec<(u64, TcpStream)>>,
+    next_id: AtomicU64,
+    /// Tokens cancelled along with this one, see [`CancelToken::child`].
+    children: Mutex<Vec<Weak<Shared>>>,
+}
+
+/// Aborts requests from another thread.
+///
+/// Requests given a token, see [`crate::client::ClientRequest::cancel_token`], fail with
+/// [`HttpError::Cancelled`] once it is cancelled. Their connections are shut down, so a
+/// request blocked reading or writing returns right away, and downloads are not resumed.
+/// Resolving the host and connecting run on a helper thread that is no longer waited for,
+/// and a TLS handshake in progress is aborted. Cancelling is final, every request given the
+/// token afterwards fails before being sent.
+///
+/// # Example:
+/// ``` no_run
+/// use std::{thread, time::Duration};
+/// use http_client::{cancel::CancelToken, client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let token = CancelToken::new();
+///     let canceller = token.clone();
+///     thread::spawn(move || {
+///         thread::sleep(Duration::from_secs(10));
+///         canceller.cancel();
+///     });
+///     let mut file = std::fs::File::create("large.iso")?;
+///     match Client::new()
+///         .get("https://example.com/large.iso")?
+///         .cancel_token(token)
+///         .download_to_file(&mut file)
+///     {
+///         Err(HttpError::Cancelled) => println!("Gave up after 10 seconds"),
+///         result => println!("{}", result?),
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    shared: Arc<Shared>,
+}
+
+impl CancelToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort every request given this token, or a copy of it.
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(sockets) = self.shared.sockets.lock() {
+            for (_, socket) in sockets.iter() {
+                // The connection may already be closed
+                let _ = socket.shutdown(Shutdown::Both);
+            }
+        }
+        let children = match self.shared.children.lock() {
+            Ok(mut children) => std::mem::take(&mut *children),
+            Err(_) => Vec::new(),
+        };
+        for shared in children.iter().filter_map(Weak::upgrade) {
+            CancelToken { shared }.cancel();
+        }
+    }
+
+    /// A new token cancelled along with this one, that can also be cancelled on its own
+    /// without affecting this one.
+    pub(crate) fn child(&self) -> CancelToken {
+        let child = CancelToken::new();
+        if let Ok(mut children) = self.shared.children.lock() {
+            children.retain(|child| child.strong_count() > 0);
+            children.push(Arc::downgrade(&child.shared));
+        }
+        // Checked once registered, a cancellation in between cancels the child itself
+        if self.is_cancelled() {
+            child.cancel();
+        }
+        child
+    }
+
+    /// Whether this token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail with [`HttpError::Cancelled`] when this token was cancelled.
+    pub(crate) fn check(&self) -> Result<(), HttpError> {
+        match self.is_cancelled() {
+            true => Err(HttpError::Cancelled),
+            false => Ok(()),
+        }
+    }
+
+    /// Shut the given socket down on cancellation until the returned guard is dropped.
+    ///
+    /// # Errors
+    /// When this token is already cancelled.
+    pub(crate) fn guard(&self, socket: &TcpStream) -> Result<Guard, HttpError> {
+        Ok(Guard {
+            token: self.clone(),
+            id: self.watch(socket)?,
+        })
+    }
+
+    /// Run `work` on its own thread and wait for its result, failing as soon as this token
+    /// is cancelled, for work that cannot be interrupted such as resolving a host name.
+    ///
+    /// The work is left to finish on its thread once cancelled, its result dropped.
+    pub(crate) fn wait_for<T, F>(&self, work: F) -> Result<T, HttpError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.check()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // Nobody receives the result once cancelled
+            let _ = sender.send(work());
+        });
+        loop {
+            match receiver.recv_timeout(SLEEP_SLICE) {
+                Ok(result) => return Ok(result),
+                Err(RecvTimeoutError::Timeout) => self.check()?,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(HttpError::Other("Cancellable work panicked".to_owned()))
+                }
+            }
+        }
+    }
+
+    /// Helper method, shut the given socket down on cancellation until the returned id is
+    /// forgotten.
+    fn watch(&self, socket: &TcpStream) -> Result<u64, HttpError> {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut sockets = self
+            .shared
+            .sockets
+            .lock()
+            .map_err(|e| HttpError::Other(e.to_string()))?;
+        sockets.push((id, socket.try_clone()?));
+        // Checked once registered, a cancellation in between shuts the socket down itself
+        if let Err(error) = self.check() {
+            sockets.retain(|(watched, _)| *watched != id);
+            return Err(error);
+        }
+        Ok(id)
+    }
+
+    /// Sleep for the given time, waking up early to fail when this token is cancelled.
+    pub(crate) fn sleep(&self, duration: Duration) -> Result<(), HttpError> {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.check()?;
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return Ok(());
+            };
+            thread::sleep(remaining.min(SLEEP_SLICE));
+        }
+    }
+
+    /// Helper method, stop shutting down the socket registered under `id`.
+    fn forget(&self, id: u64) {
+        if let Ok(mut sockets) = self.shared.sockets.lock() {
+            sockets.retain(|(watched, _)| *watched != id);
+        }
+    }
+}
+
+/// A socket shut down when its token is cancelled, until the guard is dropped.
+pub(crate) struct Guard {
+    token: CancelToken,
+    id: u64,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.token.forget(self.id);
+    }
+}
+
+/// A connection failing with [`HttpError::Cancelled`] once its token is cancelled.
+pub(crate) struct Cancellable<S> {
+    stream: S,
+    guard: Option<Guard>,
+}
+
+impl<S: AsRef<TcpStream>> Cancellable<S> {
+    /// Wrap a connection, shutting its socket down on cancellation.
+    ///
+    /// # Errors
+    /// When the token is already cancelled.
+    pub(crate) fn new(stream: S, token: Option<&CancelToken>) -> Result<Self, HttpError> {
+        let guard = match token {
+            Some(token) => Some(token.guard(stream.as_ref())?),
+            None => None,
+        };
+        Ok(Cancellable { stream, guard })
+    }
+}
+
+impl<S> Cancellable<S> {
+    /// Helper method, fail with [`HttpError::Cancelled`] once the token was cancelled.
+    fn check(&self) -> std::io::Result<()> {
+        match &self.guard {
+            // Not `Interrupted`, which readers retry
+            Some(guard) if guard.token.is_cancelled() => {
+                Err(std::io::Error::other(HttpError::Cancelled))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Helper method, the error to report when the connection failed, the socket may have
+    /// been shut down by a cancellation.
+    fn error(&self, error: std::io::Error) -> std::io::Error {
+        self.check().err().unwrap_or(error)
+    }
+}
+
+impl<S: Read> Read for Cancellable<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.check()?;
+        let read = self.stream.read(buf).map_err(|e| self.error(e))?;
+        // A socket shut down reads as closed
+        if read == 0 {
+            self.check()?;
+        }
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Cancellable<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.check()?;
+        self.stream.write(buf).map_err(|e| self.error(e))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush().map_err(|e| self.error(e))
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cancel::{CancelToken, Cancellable};
+use crate::client::Client;
+use crate::connection::Connection;
+use crate::error::HttpError;
+use crate::test_server::{TestResponse, TestServer};
+
+/// Both ends of a connection over the loopback interface.
+fn socket_pair() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (client, server)
+}
+
+#[test]
+fn cancel_blocked_read() {
+    let (client, _server) = socket_pair();
+    let token = CancelToken::new();
+    let mut connection = Cancellable::new(Connection::Plain(client), Some(&token)).unwrap();
+    let canceller = token.clone();
+    let cancelling = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        canceller.cancel();
+    });
+    let error = connection.read(&mut [0u8; 16]).unwrap_err();
+    assert!(matches!(HttpError::from(error), HttpError::Cancelled));
+    assert!(token.is_cancelled());
+    cancelling.join().unwrap();
+}
+
+#[test]
+fn cancelled_before_sending() {
+    let (client, _server) = socket_pair();
+    let token = CancelToken::new();
+    token.cancel();
+    assert!(matches!(token.check(), Err(HttpError::Cancelled)));
+    assert!(token.sleep(Duration::from_secs(60)).is_err());
+    let connection = Connection::Plain(client);
+    assert!(matches!(
+        Cancellable::new(connection, Some(&token)),
+        Err(HttpError::Cancelled)
+    ));
+}
+
+#[test]
+fn child_tokens() {
+    let parent = CancelToken::new();
+    let first = parent.child();
+    let second = parent.child();
+    first.cancel();
+    assert!(first.is_cancelled());
+    assert!(!parent.is_cancelled() && !second.is_cancelled());
+
+    let (client, _server) = socket_pair();
+    let mut connection = Cancellable::new(Connection::Plain(client), Some(&second)).unwrap();
+    parent.cancel();
+    assert!(second.is_cancelled());
+    let error = connection.read(&mut [0u8; 16]).unwrap_err();
+    assert!(matches!(HttpError::from(error), HttpError::Cancelled));
+    assert!(parent.child().is_cancelled());
+}
+
+#[test]
+fn hedged_attempts_cancelled_separately() {
+    let server = TestServer::start().unwrap();
+    let slow = TestResponse::text(200, "slow").delay(Duration::from_secs(2));
+    server.route("GET", "/item", slow);
+    server.route("GET", "/item", TestResponse::text(200, "fast"));
+    let client = Client::builder()
+        .no_proxy()
+        .hedge(Duration::from_millis(50))
+        .build();
+    let token = CancelToken::new();
+    let started = Instant::now();
+    let response = client
+        .get(&server.url("/item"))
+        .unwrap()
+        .cancel_token(token.clone())
+        .send()
+        .unwrap();
+    assert_eq!(response.text(), "fast");
+    assert!(started.elapsed() < Duration::from_secs(1));
+    // Cancelling the slow attempt leaves the token of the request alone
+    assert!(!token.is_cancelled());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn hedged_request_cancelled() {
+    let server = TestServer::start().unwrap();
+    let slow = TestResponse::text(200, "slow").delay(Duration::from_secs(2));
+    server.route("GET", "/item", slow);
+    let client = Client::builder()
+        .no_proxy()
+        .hedge(Duration::from_millis(20))
+        .build();
+    let token = CancelToken::new();
+    let canceller = token.clone();
+    let cancelling = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        canceller.cancel();
+    });
+    let started = Instant::now();
+    let result = client
+        .get(&server.url("/item"))
+        .unwrap()
+        .cancel_token(token)
+        .send();
+    assert!(matches!(result, Err(HttpError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    cancelling.join().unwrap();
+}
+
+#[test]
+fn cancelled_before_connecting() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let token = CancelToken::new();
+    token.cancel();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let result = Client::builder()
+        .no_proxy()
+        .build()
+        .get(&url)
+        .unwrap()
+        .cancel_token(token)
+        .send();
+    assert!(matches!(result, Err(HttpError::Cancelled)));
+    assert!(listener.accept().is_err());
+}
+
+#[test]
+fn cancel_tls_handshake() {
+    // Accepts the connection but never answers the handshake
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}/", listener.local_addr().unwrap());
+    let token = CancelToken::new();
+    let canceller = token.clone();
+    let cancelling = thread::spawn(move || {
+        let _connection = listener.accept().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        canceller.cancel();
+        thread::sleep(Duration::from_millis(200));
+    });
+    let started = Instant::now();
+    let result = Client::builder()
+        .no_proxy()
+        .build()
+        .get(&url)
+        .unwrap()
+        .cancel_token(token)
+        .send();
+    assert!(matches!(result, Err(HttpError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    cancelling.join().unwrap();
+}
+
+#[test]
+fn cancel_blocking_work() {
+    let token = CancelToken::new();
+    assert_eq!(token.wait_for(|| 42).unwrap(), 42);
+    let canceller = token.clone();
+    let cancelling = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        canceller.cancel();
+    });
+    let started = Instant::now();
+    let result = token.wait_for(|| thread::sleep(Duration::from_secs(5)));
+    assert!(matches!(result, Err(HttpError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    cancelling.join().unwrap();
+}
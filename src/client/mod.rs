@@ -0,0 +1,3668 @@
+use http_parse::*;
+use std::cmp::min;
+use std::collections::VecDeque;
+
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use std::{fmt::Display, io::Write};
+
+use crate::auth::{Challenge, CredentialProvider, CredentialStore, Credentials};
+use crate::body::{
+    self, BodyStream, DestinationWriter, Framing, ProgressFn, ProgressWriter, ResponseBody,
+    SharedWriter, Tee,
+};
+use crate::breaker::CircuitBreaker;
+use crate::cache::{self, HttpCache, Lookup};
+use crate::cancel::{CancelToken, Cancellable};
+use crate::coalesce::Coalescer;
+use crate::config::{Config, Hook, WireDump};
+use crate::connection::{self, Bounded, Connection};
+use crate::cookie::CookieJar;
+use crate::date::format_http_date;
+use crate::download::DownloadReport;
+use crate::duplex::{self, DuplexReader, DuplexWriter};
+#[cfg(feature = "serde")]
+use crate::encoding::to_pairs;
+use crate::encoding::{form_urlencode, percent_encode};
+use crate::error::HttpError;
+use crate::extensions::Extensions;
+use crate::fault::{FaultInjection, Faulty};
+use crate::har::HarReplay;
+use crate::hash::{Checksum, ChecksumWriter, Sha256};
+use crate::hsts::HstsStore;
+use crate::json::parser::JsonParser;
+use crate::json::{JsonObj, JsonValue};
+#[cfg(feature = "log")]
+use crate::logging;
+use crate::metrics::{Counted, MetricsSnapshot};
+use crate::mime::{is_token, url_filename, Mime};
+use crate::multipart::{self, Form};
+use crate::oauth::OAuth2;
+use crate::poll::PendingResponse;
+use crate::proxy::Proxy;
+use crate::redirect::{self, Redirect, RedirectCache};
+use crate::response::Response;
+use crate::retry::{self, RetryPolicy};
+use crate::tls::{CallbackVerification, RevocationPolicy, VerifyFn};
+#[cfg(feature = "tracing")]
+use crate::trace_context;
+use crate::trace_context::TraceParent;
+use crate::vcr::Cassette;
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) const LIB_USER_AGENT: &str = "HTTP Lib / 0.1.0 WD Client";
+/// Name of the files saved from URLs that do not provide one, as `wget` does.
+const DEFAULT_FILENAME: &str = "index.html";
+const MIME_JSON: &str = "application/json";
+pub(crate) const H_AUTHORIZATION: &str = "Authorization";
+pub(crate) const H_PROXY_AUTHORIZATION: &str = "Proxy-Authorization";
+pub(crate) const H_WWW_AUTHENTICATE: &str = "WWW-Authenticate";
+pub(crate) const H_PROXY_AUTHENTICATE: &str = "Proxy-Authenticate";
+pub(crate) const H_LOCATION: &str = "Location";
+pub(crate) const H_ACCEPT: &str = "Accept";
+pub(crate) const H_IF_NONE_MATCH: &str = "If-None-Match";
+pub(crate) const H_IF_MODIFIED_SINCE: &str = "If-Modified-Since";
+pub(crate) const H_STRICT_TRANSPORT_SECURITY: &str = "Strict-Transport-Security";
+pub(crate) const H_COOKIE: &str = "Cookie";
+pub(crate) const H_SET_COOKIE: &str = "Set-Cookie";
+pub(crate) const H_REPR_DIGEST: &str = "Repr-Digest";
+pub(crate) const H_CONTENT_DIGEST: &str = "Content-Digest";
+const H_TRAILER: &str = "Trailer";
+const H_RETRY_AFTER: &str = "Retry-After";
+const H_IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+const H_TRACEPARENT: &str = "traceparent";
+pub(crate) const H_REQUEST_ID: &str = "X-Request-Id";
+pub(crate) const H_CONTENT_MD5: &str = "Content-MD5";
+const H_ETAG: &str = "ETag";
+const H_LAST_MODIFIED: &str = "Last-Modified";
+const H_CACHE_CONTROL: &str = "Cache-Control";
+
+pub struct Body;
+pub struct NoBody;
+
+/// A HTTP Client.
+///
+/// # Example 1:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::new();
+///     client
+///         .post("localhost:8080/login_action")?
+///         .form_data("email", "test@mail.com")
+///         .form_data("password", "password")
+///         .send()?;
+///     Ok(())
+/// }
+/// ```
+/// # Example 2:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let mut file = std::fs::File::create("video.mp4")?;
+///     let client = Client::new();
+///     client
+///         .get("localhost:8080/Video.mp4")?
+///         .download_to_file(&mut file)?;
+///     Ok(())
+/// }
+/// ```
+///
+#[derive(Debug, Default, Clone)]
+pub struct Client {
+    config: Arc<Config>,
+}
+
+impl Client {
+    /// Create a new Client
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder to configure a new Client
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// The metrics of the requests sent by this client and its clones so far: requests by
+    /// status class, bytes sent and received, connections and latencies.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::new();
+    ///     client.get("https://example.com")?.send()?;
+    ///     println!("{} bytes received", client.metrics().bytes_received());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.config.metrics.snapshot()
+    }
+
+    /// Forget the permanent redirects remembered by this client.
+    pub fn clear_redirect_cache(&self) {
+        if let Some(cache) = &self.config.redirect_cache {
+            cache.clear();
+        }
+    }
+
+    /// Send a request built with [`ClientRequest::build`] to the given URL.
+    ///
+    /// The request is not consumed, the same request can be sent again.
+    /// # Arguments
+    /// `request`   Request being sent, its `Host` header and target follow `url`.
+    /// `url`       URL the request is sent to.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError, HttpUrl};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::new();
+    ///     let url = HttpUrl::try_from("https://example.com/status")
+    ///         .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+    ///     let request = client.get("https://example.com/status")?.build()?;
+    ///     for _ in 0..3 {
+    ///         client.execute(&request, &url)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn execute(&self, request: &HttpRequest, url: &HttpUrl) -> Result<Response, HttpError> {
+        let mut request = ClientRequest::<Body>::retarget(&self.config, url, request);
+        request.put_header(H_HOST, url.host());
+        ClientRequest::<Body>::send_request(&self.config, url, &mut Outgoing::new(request))
+    }
+
+    /// Send many requests concurrently, at most `parallelism` at once, and return their
+    /// results in the order of the requests.
+    ///
+    /// Every request is sent with its own settings, as with [`ClientRequest::send`]. A failed
+    /// request does not stop the others.
+    /// # Arguments
+    /// `requests`      Requests to send, e.g from [`Client::get`].
+    /// `parallelism`   Maximum number of requests in flight, at least one.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::new();
+    ///     let requests = (1..=100)
+    ///         .map(|id| client.get(&format!("https://example.com/items/{id}")))
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///     for result in client.send_all(requests, 8) {
+    ///         println!("{}", result?.status_code());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn send_all<T, I>(
+        &self,
+        requests: I,
+        parallelism: usize,
+    ) -> Vec<Result<Response, HttpError>>
+    where
+        T: Send,
+        I: IntoIterator<Item = ClientRequest<T>>,
+    {
+        run_parallel(requests, parallelism, |_, request| request.send())
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(HttpError::Other("Request was not sent".to_owned())))
+            })
+            .collect()
+    }
+
+    /// Creates a new POST request to the given URL
+    pub fn post(&self, url: &str) -> Result<ClientRequest<Body>, HttpError> {
+        let url = self.url(url)?;
+        ClientRequest::new(self.config.clone(), url, HttpMethod::Post)
+    }
+    /// Creates a new GET request to the given URL
+    pub fn get(&self, url: &str) -> Result<ClientRequest<NoBody>, HttpError> {
+        let url = self.url(url)?;
+        ClientRequest::new(self.config.clone(), url, HttpMethod::Get)
+    }
+    /// Creates a new HEAD request to the given URL
+    pub fn head(&self, url: &str) -> Result<ClientRequest<NoBody>, HttpError> {
+        let url = self.url(url)?;
+        ClientRequest::new(self.config.clone(), url, HttpMethod::Head)
+    }
+    /// Creates a new PUT request to the given URL
+    pub fn put(&self, url: &str) -> Result<ClientRequest<Body>, HttpError> {
+        let url = self.url(url)?;
+        ClientRequest::new(self.config.clone(), url, HttpMethod::Put)
+    }
+    /// Creates a new CONNECT request to the given URL
+    pub fn connect(&self, url: &str) -> Result<ClientRequest<NoBody>, HttpError> {
+        let url = self.url(url)?;
+        ClientRequest::new(self.config.clone(), url, HttpMethod::Connect)
+    }
+    /// Creates a new TRACE request to the given URL
+    pub fn trace(&self, url: &str) -> Result<ClientRequest<NoBody>, HttpError> {
+        let url = self.url(url)?;
+        ClientRequest::new(self.config.clone(), url, HttpMethod::Trace)
+    }
+    /// Creates a new PATCH request to the given URL
+    pub fn patch(&self, url: &str) -> Result<ClientRequest<Body>, HttpError> {
+        self.custom("PATCH", url)
+    }
+    /// Creates a new OPTIONS request to the given URL
+    pub fn options(&self, url: &str) -> Result<ClientRequest<NoBody>, HttpError> {
+        self.custom("OPTIONS", url)
+    }
+    /// Creates a new DELETE request to the given URL
+    pub fn delete(&self, url: &str) -> Result<ClientRequest<Body>, HttpError> {
+        self.custom("DELETE", url)
+    }
+
+    /// Creates a new request using any method, e.g WebDAV's `PROPFIND` or `MKCOL`.
+    ///
+    /// # Arguments
+    /// `method`    Name of the method, sent as given on the request line.
+    /// `url`       URL the request is sent to.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::new();
+    ///     client
+    ///         .request("PROPFIND", "https://example.com/files/")?
+    ///         .header("Depth", 1)
+    ///         .send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn request(&self, method: &str, url: &str) -> Result<ClientRequest<Body>, HttpError> {
+        self.custom(method, url)
+    }
+
+    /// Helper method, creates a request for a method given by name.
+    fn custom<T>(&self, method: &str, url: &str) -> Result<ClientRequest<T>, HttpError> {
+        // A method is a token, RFC 9110 section 9.1
+        if !is_token(method) {
+            return Err(HttpError::Other(format!("Invalid method `{method}`")));
+        }
+        let url = self.url(url)?;
+        let known = match method {
+            "GET" => Some(HttpMethod::Get),
+            "POST" => Some(HttpMethod::Post),
+            "HEAD" => Some(HttpMethod::Head),
+            "PUT" => Some(HttpMethod::Put),
+            "CONNECT" => Some(HttpMethod::Connect),
+            "TRACE" => Some(HttpMethod::Trace),
+            _ => None,
+        };
+        let mut request = ClientRequest::new(
+            self.config.clone(),
+            url,
+            known.clone().unwrap_or(HttpMethod::Post),
+        )?;
+        if known.is_none() {
+            request.request.method = Some(method.to_owned());
+        }
+        Ok(request)
+    }
+
+    /// Helper method, parse the URL of a new request.
+    ///
+    /// URLs without a scheme are resolved against the base URL of the client, if it has one.
+    fn url(&self, url: &str) -> Result<HttpUrl, HttpError> {
+        let resolved = match &self.config.base_url {
+            Some(base) if !url.contains("://") => {
+                // The base URL was validated when the client was built
+                let base = HttpUrl::try_from(base.as_str())
+                    .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+                redirect::resolve_location(
+                    base.scheme(),
+                    &base.address().to_string(),
+                    base.path(),
+                    url,
+                )
+            }
+            _ => url.to_owned(),
+        };
+        HttpUrl::try_from(resolved.as_str()).map_err(|e| HttpError::InvalidUrl(e.to_string()))
+    }
+}
+
+/// A builder used to configure a [`Client`].
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, proxy::Proxy};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::builder()
+///         .proxy(Proxy::all("http://proxy.local:3128")?)
+///         .build();
+///     client.get("http://example.com/")?.send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    config: Config,
+}
+
+impl ClientBuilder {
+    /// Send requests through the given proxy.
+    /// # Arguments
+    /// `proxy` Proxy settings to be used.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.config.proxy = Some(proxy);
+        self
+    }
+
+    /// Send requests through the proxy configured by the system, if any, see [`Proxy::system`].
+    ///
+    /// Requests connect to servers directly unless a proxy is set.
+    pub fn system_proxy(mut self) -> Self {
+        self.config.proxy = Proxy::system();
+        self
+    }
+
+    /// Connect to every server directly, the default.
+    pub fn no_proxy(mut self) -> Self {
+        self.config.proxy = None;
+        self
+    }
+
+    /// Connect to the given IP address whenever a request targets `host:port`.
+    ///
+    /// The request keeps its `Host` header and TLS server name, only the address
+    /// the connection is made to changes.
+    ///
+    /// # Arguments
+    /// `host`  Host and port being overridden, e.g `example.com:443`
+    /// `ip`    Address to connect to instead, e.g `10.0.0.5`
+    pub fn resolve(mut self, host: &str, ip: &str) -> Self {
+        let ip = ip.trim_start_matches('[').trim_end_matches(']');
+        self.config
+            .resolve
+            .insert(host.to_ascii_lowercase(), ip.to_owned());
+        self
+    }
+
+    /// Resolve the URL of every request without a scheme against the given base URL.
+    ///
+    /// With a base URL of `https://api.example.com/v1/`, `client.get("users/1")` requests
+    /// `https://api.example.com/v1/users/1` and `client.get("/status")` requests
+    /// `https://api.example.com/status`.
+    /// # Arguments
+    /// `url`   Absolute URL requests are resolved against.
+    ///
+    /// # Errors
+    /// When `url` is not a valid URL.
+    pub fn base_url(mut self, url: &str) -> Result<Self, HttpError> {
+        HttpUrl::try_from(url).map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+        self.config.base_url = Some(url.to_owned());
+        Ok(self)
+    }
+
+    /// Append a percent-encoded query parameter to the URL of every request of this client.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::builder()
+    ///         .default_query("api_key", "secret")
+    ///         .default_query("format", "json")
+    ///         .build();
+    ///     // Sent to `https://example.com/weather?city=Paris&api_key=secret&format=json`
+    ///     client.get("https://example.com/weather?city=Paris")?.send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    /// # Arguments
+    /// `key`   Name of the parameter.
+    /// `value` Value of the parameter.
+    pub fn default_query(mut self, key: &str, value: impl Display) -> Self {
+        self.config
+            .default_query
+            .push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    /// Send the given `User-Agent` header instead of the one of this library.
+    /// # Arguments
+    /// `user_agent`    Value of the header.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Send requests without a `User-Agent` header, unless one is set on the request.
+    pub fn no_user_agent(mut self) -> Self {
+        self.config.user_agent = None;
+        self
+    }
+
+    /// Add a header to every request of this client.
+    ///
+    /// Headers set on a request replace the default ones with the same name.
+    /// # Arguments
+    /// `key`   Header name being added.
+    /// `value` The value of header being added.
+    pub fn default_header(mut self, key: &str, value: impl Display) -> Self {
+        self.config
+            .default_headers
+            .push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    /// Fail connection attempts that do not complete within the given duration.
+    /// # Arguments
+    /// `timeout`   Maximum duration of each connection attempt.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail requests when the server sends nothing for the given duration.
+    /// # Arguments
+    /// `timeout`   Maximum duration of a single read from the connection.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail requests whose response head, the status line and headers, is larger than `size`.
+    ///
+    /// Response heads are limited to 64 KiB by default.
+    /// # Arguments
+    /// `size`  Largest response head accepted, in bytes.
+    pub fn max_header_size(mut self, size: usize) -> Self {
+        self.config.max_header_size = size;
+        self
+    }
+
+    /// Fail requests whose response body is larger than `size`, instead of buffering it.
+    ///
+    /// Bodies are not limited by default. The limit does not apply to bodies read from
+    /// [`ClientRequest::send_streaming`] responses, which are never buffered.
+    /// # Arguments
+    /// `size`  Largest response body accepted, in bytes.
+    pub fn max_body_size(mut self, size: u64) -> Self {
+        self.config.max_body_size = Some(size);
+        self
+    }
+
+    /// Download resources of a known size in blocks of `size` bytes, one request per block.
+    ///
+    /// Blocks are 1 MB by default. Larger blocks need fewer round trips, smaller ones less
+    /// memory.
+    /// # Arguments
+    /// `size`  Number of bytes requested at once with a `Range` header.
+    pub fn download_block_size(mut self, size: usize) -> Self {
+        self.config.download_block_size = size;
+        self
+    }
+
+    /// Whether resources of a known size are downloaded in blocks using `Range` requests, see
+    /// [`ClientBuilder::download_block_size`]. Enabled by default, when disabled every
+    /// download is a single request.
+    /// # Arguments
+    /// `enabled`   Whether to download in blocks.
+    pub fn ranged_downloads(mut self, enabled: bool) -> Self {
+        self.config.ranged_downloads = enabled;
+        self
+    }
+
+    /// Resume downloads interrupted by a network error from the bytes already written, up to
+    /// `retries` times in a row without progress. `3` by default, `0` fails downloads on the
+    /// first error.
+    /// # Arguments
+    /// `retries`   Attempts made after a failure, before giving up.
+    pub fn download_retries(mut self, retries: usize) -> Self {
+        self.config.download_retries = retries;
+        self
+    }
+
+    /// Send the SHA-256 `Content-Digest` (RFC 9530) of every request body so the server can
+    /// detect corrupted uploads.
+    ///
+    /// A body held in memory is hashed before it is sent. A streamed body is hashed while
+    /// it is written and its digest sent as a trailer, so it is sent with
+    /// `Transfer-Encoding: chunked` even when its length is known.
+    /// # Arguments
+    /// `enabled`   Whether to send digests.
+    pub fn content_digest(mut self, enabled: bool) -> Self {
+        self.config.content_digest = enabled;
+        self
+    }
+
+    /// Fail requests when the server accepts no data for the given duration.
+    /// # Arguments
+    /// `timeout`   Maximum duration of a single write to the connection.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.config.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail requests with [`HttpError::DeadlineExceeded`] when they take longer than the
+    /// given duration in total.
+    ///
+    /// The time counts from the moment a request is first sent and covers every retry,
+    /// redirect and block of a ranged download, as well as reading the body of a streamed
+    /// response. Connection, read and write timeouts are shortened to the time left, and a
+    /// retry that would only start after the deadline is not attempted.
+    /// # Arguments
+    /// `deadline`  Maximum duration of a whole request.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.config.deadline = Some(deadline);
+        self
+    }
+
+    /// Authenticate every request of this client using HTTP Basic authentication.
+    ///
+    /// Requests can override it with their own `Authorization` header or opt out with
+    /// [`ClientRequest::no_auth`].
+    /// # Arguments
+    /// `username`  User name sent to the server.
+    /// `password`  Optional password sent with the user name.
+    pub fn basic_auth(self, username: &str, password: Option<&str>) -> Self {
+        let credentials = Credentials::basic(username, password);
+        // Basic credentials never depend on a challenge
+        let authorization = credentials.authorization(&[], "", "").unwrap_or_default();
+        self.authorization(&authorization)
+    }
+
+    /// Authenticate every request of this client with the given bearer token.
+    /// # Arguments
+    /// `token` Token sent as `Authorization: Bearer <token>`.
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.config.authorization = Some(format!("Bearer {token}"));
+        self
+    }
+
+    /// Send the given `Authorization` header with every request of this client, for schemes
+    /// without dedicated support.
+    /// # Arguments
+    /// `value` Value of the header, e.g `ApiKey 0123456789`.
+    pub fn authorization(mut self, value: &str) -> Self {
+        self.config.authorization = Some(value.to_owned());
+        self
+    }
+
+    /// Authenticate every request of this client with OAuth2 bearer tokens.
+    ///
+    /// Tokens are refreshed automatically when they expire or are rejected with status 401.
+    /// They are only sent to the origin a request was first sent to, not to the hosts it is
+    /// redirected to.
+    /// # Arguments
+    /// `oauth` Token source, see [`OAuth2`].
+    pub fn oauth2(mut self, oauth: OAuth2) -> Self {
+        self.config.oauth = Some(Arc::new(oauth));
+        self
+    }
+
+    /// Follow at most `limit` redirects per request, `0` disables following redirects.
+    ///
+    /// At most 10 redirects are followed by default. `301`, `302` and `303` redirects turn
+    /// `POST` requests into `GET` requests without a body, `307` and `308` redirects send the
+    /// same method and body again.
+    /// # Arguments
+    /// `limit` Maximum number of redirects followed.
+    pub fn redirect_limit(mut self, limit: usize) -> Self {
+        self.config.redirect_limit = limit;
+        self
+    }
+
+    /// Send failed requests again according to the given policy, see [`RetryPolicy`].
+    ///
+    /// Requests are not retried by default. Every hop of a redirected request is retried on
+    /// its own, as is each block of a ranged download.
+    /// # Arguments
+    /// `policy`    When and how often requests are sent again.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = Some(policy);
+        self
+    }
+
+    /// Send a random `Idempotency-Key` header with `POST` and `PATCH` requests, so servers
+    /// supporting it process a request only once however often it is sent.
+    ///
+    /// The key is kept when the request is retried, and requests carrying one are retried
+    /// like idempotent requests, see [`ClientBuilder::retry`]. A key set by the caller is
+    /// sent as it is.
+    /// # Arguments
+    /// `enabled`   Whether keys are generated.
+    pub fn idempotency_keys(mut self, enabled: bool) -> Self {
+        self.config.idempotency_keys = enabled;
+        self
+    }
+
+    /// Send a random `X-Request-Id` header with every request, a UUID, so the requests can be
+    /// found in the logs of the server.
+    ///
+    /// The identifier is kept when the request is retried or redirected. It is available from
+    /// [`Response::request_id`], logged with the `log` feature, recorded in the span of the
+    /// request with the `tracing` feature, and appended to the message of errors. An
+    /// identifier set by the caller is sent and reported as it is.
+    /// # Arguments
+    /// `enabled`   Whether identifiers are generated.
+    pub fn request_ids(mut self, enabled: bool) -> Self {
+        self.config.request_id = enabled.then(|| {
+            let generate: Arc<dyn Fn() -> String + Send + Sync> = Arc::new(retry::uuid_v4);
+            Hook(generate)
+        });
+        self
+    }
+
+    /// Send an `X-Request-Id` header made by `generate` with every request, see
+    /// [`ClientBuilder::request_ids`].
+    /// # Arguments
+    /// `generate`  Makes a new identifier, e.g from a counter or the identifier of the
+    /// operation being served.
+    pub fn request_id_generator<F>(mut self, generate: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.config.request_id = Some(Hook(Arc::new(generate)));
+        self
+    }
+
+    /// Send a W3C Trace Context `traceparent` header with every request, starting a new
+    /// trace unless the request continues one, see [`ClientRequest::trace_parent`].
+    ///
+    /// With the `tracing` feature the trace identifier is recorded in the span of the
+    /// request. A `traceparent` header set by the caller is sent as it is.
+    /// # Arguments
+    /// `enabled`   Whether the header is generated.
+    pub fn trace_context(mut self, enabled: bool) -> Self {
+        self.config.trace_context = enabled;
+        self
+    }
+
+    /// Copy the exact bytes written to and read from the connection of every request to
+    /// `sent` and `received`, to diagnose protocol issues without capturing packets.
+    ///
+    /// The bytes are copied as HTTP sees them, before TLS encrypts them and after it decrypts
+    /// them, as soon as they go through: heads, bodies with their chunk framing, redirects,
+    /// retries and interim responses included. Proxy tunnel setup is not copied, nor are
+    /// responses served from the cache, nor requests sent with
+    /// [`ClientRequest::send_nonblocking`] or [`ClientRequest::send_duplex`]. Credentials are
+    /// copied as they are sent.
+    /// # Arguments
+    /// `sent`      Destination of the bytes written, e.g a file.
+    /// `received`  Destination of the bytes read.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::builder()
+    ///         .wire_dump(std::fs::File::create("sent.bin")?, std::io::stderr())
+    ///         .build();
+    ///     client.get("https://example.com")?.send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn wire_dump<S, R>(mut self, sent: S, received: R) -> Self
+    where
+        S: Write + Send + 'static,
+        R: Write + Send + 'static,
+    {
+        self.config.wire_dump = Some(WireDump {
+            sent: Hook(Arc::new(Mutex::new(sent))),
+            received: Hook(Arc::new(Mutex::new(received))),
+        });
+        self
+    }
+
+    /// Answer requests with the responses recorded in a HAR file instead of sending them,
+    /// e.g for deterministic integration tests or offline demos, see [`HarReplay`].
+    ///
+    /// Redirects, retries, cookies and authentication are handled as for responses received
+    /// from the network. Requests matching no recorded entry fail. Requests sent with
+    /// [`ClientRequest::send_nonblocking`] or [`ClientRequest::send_duplex`] are not replayed.
+    /// # Arguments
+    /// `replay`    The recorded entries, shared by the clients replaying them.
+    pub fn har_replay(mut self, replay: Arc<HarReplay>) -> Self {
+        self.config.har_replay = Some(replay);
+        self
+    }
+
+    /// Record the requests of this client to a cassette on the first run and replay them
+    /// from it on the next runs, see [`Cassette`].
+    ///
+    /// A replay set with [`ClientBuilder::har_replay`] takes precedence, nothing is recorded
+    /// then.
+    /// # Arguments
+    /// `cassette`  The cassette, shared by the clients using it.
+    pub fn cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.config.cassette = Some(cassette);
+        self
+    }
+
+    /// Inject faults such as latency or resets into every connection, to test how an
+    /// application copes with a bad network, see [`FaultInjection`]. Faults apply once the
+    /// head of a request was sent.
+    /// # Arguments
+    /// `faults`    The faults injected.
+    pub fn fault_injection(mut self, faults: FaultInjection) -> Self {
+        self.config.faults = Some(Arc::new(faults));
+        self
+    }
+
+    /// Fail requests fast to hosts that keep failing, see [`CircuitBreaker`].
+    ///
+    /// The breaker can be shared by several clients so they agree on which hosts are down.
+    /// # Arguments
+    /// `breaker`   Tracks the failures of every host.
+    pub fn circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.config.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Hedge `GET` and `HEAD` requests: send a copy of a request when no response arrived
+    /// within `threshold`, and keep whichever response comes first.
+    ///
+    /// The slower attempt is cancelled, its connection closed, once the other one answered.
+    /// Requests streaming a body are never hedged. For [`ClientRequest::send_streaming`] the
+    /// threshold covers the head of the response, otherwise the whole response.
+    /// # Arguments
+    /// `threshold` Time after which the copy is sent.
+    pub fn hedge(mut self, threshold: Duration) -> Self {
+        self.config.hedge_after = Some(threshold);
+        self
+    }
+
+    /// Cache responses in memory following RFC 9111, see [`HttpCache`].
+    /// # Arguments
+    /// `enabled`   Whether this client keeps a cache.
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.config.cache = enabled.then(|| Arc::new(HttpCache::new()));
+        self
+    }
+
+    /// Coalesce identical `GET` requests sent at the same time, by several threads, into a
+    /// single request whose response they all receive.
+    ///
+    /// Requests are identical when they have the same URL and headers, apart from the
+    /// `X-Request-Id` and `traceparent` headers generated for every request. Requests
+    /// reporting their progress, copying their responses, streaming them or given a
+    /// [`CancelToken`] are always sent on their own. When the shared request fails, or its
+    /// response is marked `no-store` or `private`, every request is sent on its own.
+    /// # Arguments
+    /// `enabled`   Whether identical requests are coalesced.
+    pub fn coalesce(mut self, enabled: bool) -> Self {
+        self.config.coalescer = enabled.then(|| Arc::new(Coalescer::new()));
+        self
+    }
+
+    /// Cache responses in the given cache, which can be shared with other clients.
+    /// # Arguments
+    /// `cache` Cache responses are stored in and served from.
+    pub fn http_cache(mut self, cache: Arc<HttpCache>) -> Self {
+        self.config.cache = Some(cache);
+        self
+    }
+
+    /// Remember hosts sending `Strict-Transport-Security` and send later plain `http://`
+    /// requests to them over HTTPS, see [`HstsStore`].
+    /// # Arguments
+    /// `enabled`   Whether this client keeps track of HSTS hosts.
+    pub fn hsts(mut self, enabled: bool) -> Self {
+        self.config.hsts = enabled.then(|| Arc::new(HstsStore::new()));
+        self
+    }
+
+    /// Keep track of HSTS hosts in the given store, which can be shared or preloaded.
+    /// # Arguments
+    /// `store` Store of the hosts requiring HTTPS.
+    pub fn hsts_store(mut self, store: Arc<HstsStore>) -> Self {
+        self.config.hsts = Some(store);
+        self
+    }
+
+    /// Refuse to send requests over plain HTTP, including redirects to `http://` URLs.
+    ///
+    /// Plain URLs to hosts known to require HTTPS are still upgraded when HSTS is enabled.
+    /// # Arguments
+    /// `enabled`   Whether only HTTPS is allowed.
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.config.https_only = enabled;
+        self
+    }
+
+    /// Remember permanent redirects (`301` and `308`) for `ttl`, sending later requests to the
+    /// redirected URLs straight to their new location. See [`RedirectCache`].
+    /// # Arguments
+    /// `ttl`   How long a redirect is remembered.
+    pub fn permanent_redirect_cache(mut self, ttl: Duration) -> Self {
+        self.config.redirect_cache = Some(Arc::new(RedirectCache::new(ttl)));
+        self
+    }
+
+    /// Store cookies set by responses and send them back with later requests.
+    /// # Arguments
+    /// `enabled`   Whether this client keeps a cookie jar.
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        self.config.cookie_jar = enabled.then(|| Arc::new(CookieJar::new()));
+        self
+    }
+
+    /// Keep cookies in the given jar, which can be shared with other clients or inspected.
+    /// # Arguments
+    /// `jar`   Jar cookies are stored in and read from.
+    pub fn cookie_jar(mut self, jar: Arc<CookieJar>) -> Self {
+        self.config.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Answer authentication challenges (status 401 and 407) using the given provider.
+    ///
+    /// When a challenge is received the provider is asked for credentials and the request
+    /// is sent again with them. Basic, Bearer and Digest credentials are supported. Only
+    /// the challenges of proxies and of the origin a request was first sent to are answered,
+    /// not those of the hosts it is redirected to.
+    ///
+    /// # Arguments
+    /// `provider`  Source of credentials, e.g a fixed set of [`Credentials`].
+    pub fn credential_provider<P>(mut self, provider: P) -> Self
+    where
+        P: CredentialProvider + 'static,
+    {
+        self.config.credential_provider = Some(Hook(Arc::new(provider)));
+        self
+    }
+
+    /// Answer authentication challenges from servers with the credentials saved for their host.
+    ///
+    /// Proxy challenges are left unanswered, configure the proxy credentials on the [`Proxy`].
+    /// # Arguments
+    /// `store` Store the credentials are looked up in, see [`CredentialStore`].
+    pub fn credential_store<S>(self, store: S) -> Self
+    where
+        S: CredentialStore + 'static,
+    {
+        self.credential_provider(
+            move |url: &HttpUrl, _: &[Challenge], proxy: bool| {
+                if proxy {
+                    None
+                } else {
+                    store.get(url.host())
+                }
+            },
+        )
+    }
+
+    /// **DANGER:** Skip verification of server certificates.
+    ///
+    /// Any certificate is accepted for any host, including expired and self-signed ones, which
+    /// leaves connections open to man-in-the-middle attacks. Only meant for local development
+    /// against servers using self-signed certificates. HTTPS requests fail when a
+    /// [`RevocationPolicy`] other than `Ignore` is set as well.
+    ///
+    /// # Arguments
+    /// `accept`    Whether invalid certificates are accepted.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.config.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// Log TLS session secrets to the file named by the `SSLKEYLOGFILE` environment variable.
+    ///
+    /// Disabled by default. Anyone able to read that file can decrypt the captured traffic,
+    /// so this is only meant for debugging with tools like Wireshark.
+    ///
+    /// # Arguments
+    /// `enabled`   Whether session secrets are logged.
+    pub fn tls_key_log(mut self, enabled: bool) -> Self {
+        self.config.tls_key_log = enabled;
+        self
+    }
+
+    /// Fail connections whose TLS handshake does not complete within the given time.
+    /// # Arguments
+    /// `timeout`   Maximum duration of the handshake.
+    pub fn tls_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.config.tls_handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Use a different TLS server name (SNI) when connecting to the given host.
+    ///
+    /// The certificate presented by the server is validated against `name` instead of
+    /// the host of the request, the `Host` header is not changed.
+    ///
+    /// # Arguments
+    /// `host`  Host of the requests being affected, e.g `10.0.0.5`
+    /// `name`  Server name sent and validated instead, e.g `api.example.com`
+    pub fn tls_server_name(mut self, host: &str, name: &str) -> Self {
+        self.config
+            .tls_server_names
+            .insert(host.to_ascii_lowercase(), name.to_owned());
+        self
+    }
+
+    /// Verify server certificates with the given rustls verifier instead of the bundled roots.
+    ///
+    /// The verifier also decides on revocation, HTTPS requests fail when a
+    /// [`RevocationPolicy`] other than `Ignore` is set as well.
+    /// # Arguments
+    /// `verifier`  Verifier deciding which certificates are trusted.
+    pub fn tls_certificate_verifier(
+        mut self,
+        verifier: Arc<dyn rustls::client::danger::ServerCertVerifier>,
+    ) -> Self {
+        self.config.tls_verifier = Some(verifier);
+        self
+    }
+
+    /// Decide which server certificates are trusted using the given function.
+    ///
+    /// The function receives the DER encoded certificate chain sent by the server, starting with
+    /// the server's own certificate, and the server name. Returning `false` aborts the connection.
+    /// Useful for trust-on-first-use schemes or short lived internal certificates.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let pinned: Vec<u8> = std::fs::read("server.der")?;
+    ///     let client = Client::builder()
+    ///         .tls_verify_fn(move |chain, _host| chain.first() == Some(&pinned.as_slice()))
+    ///         .build();
+    ///     client.get("https://internal.local/")?.send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tls_verify_fn<F>(self, verify: F) -> Self
+    where
+        F: Fn(&[&[u8]], &str) -> bool + Send + Sync + 'static,
+    {
+        let callback: Arc<VerifyFn> = Arc::new(verify);
+        self.tls_certificate_verifier(Arc::new(CallbackVerification::new(callback)))
+    }
+
+    /// Send GET and HEAD requests as TLS 1.3 early data (0-RTT) when resuming a TLS session.
+    ///
+    /// Saves a round trip on repeated connections to the same server. Early data can be replayed
+    /// by an attacker, which is why only idempotent requests use it. Requests rejected as early
+    /// data are sent again normally once the handshake completes.
+    ///
+    /// # Arguments
+    /// `enabled`   Whether early data is used.
+    pub fn tls_early_data(mut self, enabled: bool) -> Self {
+        self.config.tls_early_data = enabled;
+        self
+    }
+
+    /// Check server certificates against the given certificate revocation list (CRL).
+    ///
+    /// CRLs are only consulted when a [`RevocationPolicy`] other than `Ignore` is set.
+    ///
+    /// # Arguments
+    /// `der`   DER encoded certificate revocation list.
+    pub fn tls_crl(mut self, der: Vec<u8>) -> Self {
+        self.config.tls_crls.push(der.into());
+        self
+    }
+
+    /// Set how certificate revocation is checked against the configured CRLs.
+    ///
+    /// With `SoftFail` only certificates listed as revoked are rejected, with `HardFail`
+    /// certificates not covered by any CRL and expired CRLs are rejected as well. `HardFail`
+    /// without any CRL fails every HTTPS request rather than accepting every certificate.
+    /// A policy other than `Ignore` cannot be combined with a custom certificate verifier or
+    /// with accepting invalid certificates, every HTTPS request fails then.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError, tls::RevocationPolicy};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let client = Client::builder()
+    ///         .tls_crl(std::fs::read("intermediate.crl")?)
+    ///         .tls_revocation_policy(RevocationPolicy::HardFail)
+    ///         .build();
+    ///     client.get("https://internal.local/")?.send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tls_revocation_policy(mut self, policy: RevocationPolicy) -> Self {
+        self.config.tls_revocation = policy;
+        self
+    }
+
+    /// Create a Client using this builder's configuration.
+    pub fn build(self) -> Client {
+        Client {
+            config: Arc::new(self.config),
+        }
+    }
+}
+
+pub(crate) enum SecurityType {
+    Tls,
+    None,
+}
+
+/// A request on its way to the server, the head and the body streamed after it.
+struct Outgoing {
+    head: HttpRequest,
+    /// Method sent on the request line when `head` cannot represent it.
+    method: Option<String>,
+    body: Option<BodyStream>,
+    /// Lowercase names of the headers set by the caller, never replaced by the client.
+    explicit: Vec<String>,
+    /// Whether response bodies are left on the connection to be read while streaming.
+    streaming: bool,
+    /// The body of the last response received while streaming.
+    response_body: Option<ResponseBody>,
+    /// Head of the last response received, as sent by the server.
+    response_head: Vec<u8>,
+    /// Address the last response was received from, unless it came from the cache.
+    peer_addr: Option<SocketAddr>,
+    /// Trailer fields of the last response whose body was read in full.
+    response_trailers: Vec<(String, String)>,
+    /// Receives a copy of every response as read from the connection.
+    tee: Option<SharedWriter>,
+    /// Told about the progress of downloads.
+    download_progress: Option<ProgressFn>,
+    /// Told about the progress of the request body.
+    upload_progress: Option<ProgressFn>,
+    /// Digest downloads must match.
+    checksum: Option<Checksum>,
+    /// Whether downloads must match the digest announced by the server, if any.
+    verify_digest: bool,
+    /// Other locations of the resource, tried in order when downloading it fails.
+    mirrors: Vec<HttpUrl>,
+    /// Aborts the request from another thread.
+    cancel: Option<CancelToken>,
+    /// When the request fails, set when it is first sent.
+    deadline: Option<Instant>,
+    /// URL the request was first sent to, the credentials of the client are only sent to
+    /// its origin.
+    origin: Option<HttpUrl>,
+}
+
+impl Outgoing {
+    fn new(head: HttpRequest) -> Self {
+        Outgoing {
+            head,
+            method: None,
+            body: None,
+            explicit: Vec::new(),
+            streaming: false,
+            response_body: None,
+            response_head: Vec::new(),
+            peer_addr: None,
+            response_trailers: Vec::new(),
+            tee: None,
+            download_progress: None,
+            upload_progress: None,
+            checksum: None,
+            verify_digest: false,
+            mirrors: Vec::new(),
+            cancel: None,
+            deadline: None,
+            origin: None,
+        }
+    }
+
+    /// Whether the credentials of the client can be sent to the given URL, the request
+    /// being first sent to the same origin.
+    fn trusts(&self, url: &HttpUrl) -> bool {
+        self.origin
+            .as_ref()
+            .is_none_or(|origin| redirect::same_origin(origin, url))
+    }
+
+    /// Whether the caller set the header with the given name.
+    fn is_explicit(&self, name: &str) -> bool {
+        self.explicit
+            .iter()
+            .any(|explicit| explicit.eq_ignore_ascii_case(name))
+    }
+
+    /// The key identifying identical requests to the given URL, for requests that can share
+    /// their response with others.
+    fn coalescing_key(&self, url: &HttpUrl) -> Option<String> {
+        let private = self.streaming
+            || self.body.is_some()
+            || self.tee.is_some()
+            || self.download_progress.is_some()
+            || self.upload_progress.is_some()
+            || self.cancel.is_some();
+        if private || self.method.is_some() || *self.head.method() != HttpMethod::Get {
+            return None;
+        }
+        let mut key = url.to_string();
+        // Headers generated for every request would tell identical requests apart
+        let generated = [H_REQUEST_ID, H_TRACEPARENT];
+        for header in self.head.headers().filter(|header| {
+            !generated
+                .iter()
+                .any(|name| header.name().eq_ignore_ascii_case(name))
+        }) {
+            // save to unwrap, a str can always turn into String
+            key.push_str(&format!(
+                "\n{}: {}",
+                header.name(),
+                header.value::<String>().unwrap()
+            ));
+        }
+        Some(key)
+    }
+
+    /// Whether the request has a header with the given name.
+    fn has_header(&self, name: &str) -> bool {
+        self.head
+            .headers()
+            .any(|header| header.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The value of the first header with the given name, if any.
+    fn header_value(&self, name: &str) -> Option<String> {
+        self.head
+            .headers()
+            .find(|header| header.name().eq_ignore_ascii_case(name))
+            .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+    }
+
+    /// The header fields of the request.
+    fn header_pairs(&self) -> Vec<(String, String)> {
+        self.head
+            .headers()
+            .map(|header| (header.name().to_owned(), header.value::<String>().unwrap())) // save to unwrap, a str can always turn into String
+            .collect()
+    }
+
+    /// Set a header, unless the caller set it.
+    fn put_default(&mut self, name: &str, value: impl Display) {
+        if !self.is_explicit(name) {
+            self.head.put_header(name, value);
+        }
+    }
+
+    /// The name of the method sent on the request line.
+    fn method_name(&self) -> String {
+        match &self.method {
+            Some(method) => method.clone(),
+            None => method_name(self.head.method()),
+        }
+    }
+
+    /// Whether a streamed body was already sent and cannot be sent again.
+    fn body_sent(&self) -> bool {
+        self.body.as_ref().is_some_and(BodyStream::is_sent)
+    }
+
+    /// The request head and buffered data in wire format.
+    fn to_bytes(&self) -> Vec<u8> {
+        let bytes = self.head.into_bytes();
+        let Some(method) = &self.method else {
+            return bytes;
+        };
+        let start = bytes.iter().position(|b| *b == b' ').unwrap_or(0);
+        let mut request = method.as_bytes().to_vec();
+        request.extend_from_slice(&bytes[start..]);
+        request
+    }
+}
+
+pub struct ClientRequest<T> {
+    config: Arc<Config>,
+    url: HttpUrl,
+    request: Outgoing,
+    extensions: Extensions,
+    _d: PhantomData<T>,
+}
+
+impl ClientRequest<Body> {
+    /// Send this request and write its body while its response is read, for protocols
+    /// where the server answers as the body is received.
+    ///
+    /// The head of the request is sent right away, its body with `Transfer-Encoding:
+    /// chunked` through the returned [`DuplexWriter`], each write in its own chunk. The
+    /// response is read through the returned [`DuplexReader`], typically on another thread.
+    /// Redirects, retries and authentication challenges are not handled. Plain URLs are
+    /// upgraded to HTTPS or refused like other requests, see [`ClientBuilder::https_only`].
+    ///
+    /// # Errors
+    /// When the connection cannot be opened, the URL is refused, or the request already has
+    /// a body.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use std::io::{Read, Write};
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let (mut writer, reader) = Client::new()
+    ///         .post("https://example.com/echo")?
+    ///         .send_duplex()?;
+    ///     let replies = std::thread::spawn(move || -> Result<String, HttpError> {
+    ///         let mut response = reader.response()?;
+    ///         let mut text = String::new();
+    ///         response.read_to_string(&mut text)?;
+    ///         Ok(text)
+    ///     });
+    ///     for line in ["ping\n", "ping\n"] {
+    ///         writer.write_all(line.as_bytes())?;
+    ///     }
+    ///     writer.finish()?;
+    ///     println!("{}", replies.join().unwrap()?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn send_duplex(mut self) -> Result<(DuplexWriter, DuplexReader), HttpError> {
+        if self.request.body.is_some() || !self.request.head.data().is_empty() {
+            return Err(HttpError::Other(
+                "The body of a duplex request is written through its DuplexWriter".to_owned(),
+            ));
+        }
+        let mut head = Self::without_header(
+            &self.config,
+            &self.url,
+            &self.request.head,
+            H_CONTENT_LENGTH,
+        );
+        head.put_header(H_TRANSFER_ENCODING, "chunked");
+        self.request.head = head;
+        if let Some(url) = Self::secure_url(&self.config, &self.url)? {
+            self.url = url;
+        }
+        let connection = Connection::send(
+            &self.config,
+            &self.url,
+            &self.request.to_bytes(),
+            false,
+            self.request.cancel.as_ref(),
+        )?;
+        duplex::split(
+            connection,
+            self.url.to_string(),
+            self.request.method_name(),
+            self.config.max_header_size,
+            self.config.read_timeout,
+        )
+    }
+
+    /// Add a data to the body of the request
+    /// # Arguments
+    /// `data`  data to be added
+    pub fn body(mut self, data: &[u8]) -> Self {
+        self.request.head.add_data(data);
+        self
+    }
+    /// Add a URL-Encoded data to the body of the request.
+    ///
+    /// Both the name and the value are encoded, so they may contain any character.
+    /// # Arguments
+    /// `name`  ID for the encoded entry being added
+    /// `value` The value of entry being added.
+    pub fn form_data(mut self, name: &str, value: impl Display) -> Self {
+        self.request
+            .put_default(H_CONTENT_TYPE, MINE_URLENCODED_FORM);
+        let name = form_urlencode(name);
+        let value = form_urlencode(&value.to_string());
+        if self.request.head.data().is_empty() {
+            self.request
+                .head
+                .add_data(format!("{name}={value}").as_bytes());
+        } else {
+            self.request
+                .head
+                .add_data(format!("&{name}={value}").as_bytes());
+        }
+        self
+    }
+
+    /// Send a serializable struct or map as a URL-encoded form body.
+    ///
+    /// Sequences repeat their name, `None` values are skipped.
+    /// # Arguments
+    /// `form`  Struct or map with plain values, e.g `HashMap<&str, &str>`.
+    ///
+    /// # Errors
+    /// When the value is not a struct or map, or contains nested structs or maps.
+    #[cfg(feature = "serde")]
+    pub fn form<S: serde::Serialize + ?Sized>(mut self, form: &S) -> Result<Self, HttpError> {
+        for (name, value) in to_pairs(form).map_err(HttpError::Other)? {
+            self = self.form_data(&name, value);
+        }
+        Ok(self)
+    }
+
+    /// Stream the body of this request from a reader while the request is sent.
+    ///
+    /// When the length is unknown the body is sent with `Transfer-Encoding: chunked`, e.g to
+    /// upload generated data or the standard input.
+    /// # Arguments
+    /// `reader`    Source of the body.
+    /// `length`    Number of bytes `reader` provides, if known.
+    pub fn body_reader<R>(mut self, reader: R, length: Option<u64>) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        match length {
+            Some(length) => self.request.head.put_header(H_CONTENT_LENGTH, length),
+            None => self.request.head.put_header(H_TRANSFER_ENCODING, "chunked"),
+        }
+        self.request.body = Some(BodyStream::new(reader, length));
+        self
+    }
+
+    /// Stream the file at `path` as the body of this request, e.g to upload it with a `PUT`.
+    ///
+    /// The file is read while the request is sent, never loaded in memory. The
+    /// `Content-Length` is the size of the file, and unless set by the caller the
+    /// `Content-Type` is guessed from its extension.
+    /// # Arguments
+    /// `path`  Path of the file being uploaded.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     Client::new()
+    ///         .put("https://example.com/uploads/report.pdf")?
+    ///         .put_file("report.pdf")?
+    ///         .send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn put_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HttpError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        self.request
+            .put_default(H_CONTENT_TYPE, multipart::mime_type(path));
+        // A file growing during the upload must not send more than announced
+        Ok(self.body_reader(file.take(length), Some(length)))
+    }
+
+    /// Send a `multipart/form-data` body, e.g to upload files.
+    ///
+    /// File parts are streamed from disk while the request is sent.
+    /// # Arguments
+    /// `form`  The form being sent, see [`Form`].
+    pub fn multipart(mut self, form: Form) -> Self {
+        self.request
+            .head
+            .put_header(H_CONTENT_TYPE, form.content_type());
+        let body = form.into_stream();
+        match body.length() {
+            Some(length) => self.request.head.put_header(H_CONTENT_LENGTH, length),
+            None => self.request.head.put_header(H_TRANSFER_ENCODING, "chunked"),
+        }
+        self.request.body = Some(body);
+        self
+    }
+
+    /// Send the given JSON value as the body, with `Content-Type: application/json`.
+    /// # Arguments
+    /// `json`  The value being sent.
+    #[cfg(not(feature = "serde"))]
+    pub fn json(mut self, json: &JsonValue) -> Result<Self, HttpError> {
+        self.request.put_default(H_CONTENT_TYPE, MIME_JSON);
+        self.request.head.add_data(json.to_string().as_bytes());
+        Ok(self)
+    }
+
+    /// Serialize the given value as the JSON body, with `Content-Type: application/json`.
+    ///
+    /// # Errors
+    /// When the value cannot be represented as JSON, e.g a map with non-string keys.
+    /// # Arguments
+    /// `json`  The value being sent, a [`JsonValue`] or any `serde::Serialize` type.
+    #[cfg(feature = "serde")]
+    pub fn json<S: serde::Serialize + ?Sized>(mut self, json: &S) -> Result<Self, HttpError> {
+        let data = serde_json::to_vec(json).map_err(|e| HttpError::Other(e.to_string()))?;
+        self.request.put_default(H_CONTENT_TYPE, MIME_JSON);
+        self.request.head.add_data(&data);
+        Ok(self)
+    }
+
+    /// Add a JSON payload to this request.
+    ///
+    /// # Arguments
+    /// `json`  the JSON object being added to the request's body.
+    pub fn json_body(mut self, json: &JsonObj) -> Self {
+        self.request.head.add_data(json.to_string().as_bytes());
+        self
+    }
+}
+impl<T> ClientRequest<T> {
+    /// Create a new ClientRequest
+    /// # Argument
+    /// `config`    Settings of the client creating the request.
+    /// `url`   URL being added
+    /// `method`    HTTP Method used for creating the request.
+    ///
+    /// # Errors
+    /// When the default query parameters of the client make the URL invalid.
+    pub(crate) fn new(
+        config: Arc<Config>,
+        url: HttpUrl,
+        method: HttpMethod,
+    ) -> Result<ClientRequest<T>, HttpError> {
+        let mut inner = HttpRequest::builder()
+            .method(method)
+            .path(&Self::request_target(&config, &url))
+            .header(H_HOST, url.host())
+            .build();
+        if let Some(user_agent) = &config.user_agent {
+            inner.put_header(H_USER_AGENT, user_agent);
+        }
+        for (key, value) in &config.default_headers {
+            inner.put_header(key, value);
+        }
+        if let Some(authorization) = &config.authorization {
+            inner.put_header(H_AUTHORIZATION, authorization);
+        }
+        if let Some(authorization) = connection::forwarding_proxy(&config, &url)
+            .and_then(|proxy| proxy.authorization.as_ref())
+        {
+            inner.put_header(H_PROXY_AUTHORIZATION, authorization);
+        }
+        let defaults = config.default_query.clone();
+        let request = ClientRequest {
+            request: Outgoing::new(inner),
+            config,
+            url,
+            extensions: Extensions::new(),
+            _d: PhantomData,
+        };
+        if defaults.is_empty() {
+            Ok(request)
+        } else {
+            request.query_pairs(defaults)
+        }
+    }
+
+    /// Append a percent-encoded query parameter to the URL of this request.
+    /// # Arguments
+    /// `key`   Name of the parameter.
+    /// `value` Value of the parameter.
+    ///
+    /// # Errors
+    /// When the URL with the parameter cannot be parsed.
+    pub fn query(self, key: &str, value: impl Display) -> Result<Self, HttpError> {
+        self.query_pairs([(key, value)])
+    }
+
+    /// Append percent-encoded query parameters to the URL of this request.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     // Sent to `https://example.com/search?q=rust%20%26%20http&page=2`
+    ///     Client::new()
+    ///         .get("https://example.com/search")?
+    ///         .query_pairs([("q", "rust & http"), ("page", "2")])?
+    ///         .send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    /// # Arguments
+    /// `pairs` Name and value of every parameter.
+    ///
+    /// # Errors
+    /// When the URL with the parameters cannot be parsed.
+    pub fn query_pairs<I, K, V>(mut self, pairs: I) -> Result<Self, HttpError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Display,
+    {
+        let url = self.url.to_string();
+        let (mut url, fragment) = match url.split_once('#') {
+            Some((url, fragment)) => (url.to_owned(), Some(fragment.to_owned())),
+            None => (url, None),
+        };
+        for (key, value) in pairs {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&percent_encode(key.as_ref()));
+            url.push('=');
+            url.push_str(&percent_encode(&value.to_string()));
+        }
+        if let Some(fragment) = fragment {
+            url.push('#');
+            url.push_str(&fragment);
+        }
+        let url =
+            HttpUrl::try_from(url.as_str()).map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+        self.request.head = Self::retarget(&self.config, &url, &self.request.head);
+        self.url = url;
+        Ok(self)
+    }
+
+    /// Append the fields of a serializable struct or map as percent-encoded query parameters.
+    ///
+    /// Sequences repeat their name, `None` values are skipped.
+    /// # Arguments
+    /// `query` Struct or map with plain values, e.g `HashMap<&str, u32>`.
+    ///
+    /// # Errors
+    /// When the value is not a struct or map, or contains nested structs or maps.
+    #[cfg(feature = "serde")]
+    pub fn query_struct<S: serde::Serialize + ?Sized>(self, query: &S) -> Result<Self, HttpError> {
+        let pairs = to_pairs(query).map_err(HttpError::Other)?;
+        self.query_pairs(pairs)
+    }
+
+    /// Add a a header to this request, replacing the value already set, if any.
+    ///
+    /// Use [`ClientRequest::add_header`] to send several values. Headers set this way are never
+    /// replaced by the client, e.g by its cookies, OAuth2 tokens or cache validators.
+    /// # Arguments
+    /// `key`   Header name being added
+    /// `value` The value of header being added.
+    pub fn header(mut self, key: &str, value: impl Display) -> Self {
+        self.request.head.put_header(key, value);
+        if !self.request.is_explicit(key) {
+            self.request.explicit.push(key.to_ascii_lowercase());
+        }
+        self
+    }
+
+    /// Add another value to a header of this request, keeping the values already set.
+    ///
+    /// Values are combined into a comma separated list, `Cookie` values are separated with `;`.
+    /// # Arguments
+    /// `key`   Header name being added
+    /// `value` The value being appended.
+    pub fn add_header(self, key: &str, value: impl Display) -> Self {
+        let existing = self
+            .request
+            .head
+            .headers()
+            .find(|header| header.name().eq_ignore_ascii_case(key))
+            .map(|header| header.value::<String>().unwrap()); // save to unwrap, a str can always turn into String
+        match existing {
+            Some(existing) if key.eq_ignore_ascii_case(H_COOKIE) => {
+                self.header(key, format!("{existing}; {value}"))
+            }
+            Some(existing) => self.header(key, format!("{existing}, {value}")),
+            None => self.header(key, value),
+        }
+    }
+
+    /// Remove a header from this request, including the ones set by the client.
+    /// # Arguments
+    /// `key`   Name of the header being removed.
+    pub fn remove_header(mut self, key: &str) -> Self {
+        self.request.head = Self::without_header(&self.config, &self.url, &self.request.head, key);
+        self.request
+            .explicit
+            .retain(|explicit| !explicit.eq_ignore_ascii_case(key));
+        self
+    }
+
+    /// Set the media type of the body of this request.
+    /// # Arguments
+    /// `mime`  Media type sent as `Content-Type`.
+    pub fn content_type(self, mime: Mime) -> Self {
+        self.header(H_CONTENT_TYPE, mime)
+    }
+
+    /// Ask for a response of the given media type, call it again to accept several.
+    /// # Arguments
+    /// `mime`  Media type added to the `Accept` header, e.g [`Mime::JSON`].
+    pub fn accept(self, mime: Mime) -> Self {
+        self.add_header(H_ACCEPT, mime)
+    }
+
+    /// Ask for part of the resource only, e.g `0..1024` for its first kilobyte.
+    /// # Arguments
+    /// `range` Byte offsets being requested.
+    ///
+    /// # Errors
+    /// When the range is empty.
+    pub fn range<R: RangeBounds<u64>>(self, range: R) -> Result<Self, HttpError> {
+        match Self::byte_bounds(&range)? {
+            (start, None) => Ok(self.header(H_RANGE, format!("bytes={start}-"))),
+            (start, Some(end)) => Ok(self.header(H_RANGE, format!("bytes={start}-{end}"))),
+        }
+    }
+
+    /// Helper method, the first and last byte, if bounded, of a range of bytes.
+    ///
+    /// # Errors
+    /// When the range is empty.
+    fn byte_bounds<R: RangeBounds<u64>>(range: &R) -> Result<(u64, Option<u64>), HttpError> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => Some(*start),
+            Bound::Excluded(start) => start.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => Some(Some(*end)),
+            Bound::Excluded(end) => end.checked_sub(1).map(Some),
+            Bound::Unbounded => Some(None),
+        };
+        match (start, end) {
+            (Some(start), Some(None)) => Ok((start, None)),
+            (Some(start), Some(Some(end))) if start <= end => Ok((start, Some(end))),
+            _ => Err(HttpError::Other("Empty byte range requested".to_owned())),
+        }
+    }
+
+    /// Only get the resource when it no longer matches the given entity tag, call it again to
+    /// send several.
+    /// # Arguments
+    /// `etag`  Entity tag, e.g a previous `ETag` header value, quoted when needed.
+    pub fn if_none_match(self, etag: &str) -> Self {
+        if etag == "*" || etag.ends_with('"') {
+            self.add_header(H_IF_NONE_MATCH, etag)
+        } else {
+            self.add_header(H_IF_NONE_MATCH, format!("\"{etag}\""))
+        }
+    }
+
+    /// Only get the resource when it changed after the given time.
+    /// # Arguments
+    /// `time`  Time sent as `If-Modified-Since`.
+    pub fn if_modified_since(self, time: SystemTime) -> Self {
+        self.header(H_IF_MODIFIED_SINCE, format_http_date(time))
+    }
+
+    /// Authenticate this request using HTTP Basic authentication.
+    /// # Arguments
+    /// `username`  User name sent to the server.
+    /// `password`  Optional password sent with the user name.
+    pub fn basic_auth(self, username: &str, password: Option<&str>) -> Self {
+        let credentials = Credentials::basic(username, password);
+        // Basic credentials never depend on a challenge
+        let authorization = credentials.authorization(&[], "", "").unwrap_or_default();
+        self.header(H_AUTHORIZATION, authorization)
+    }
+
+    /// Authenticate this request with the given bearer token.
+    /// # Arguments
+    /// `token` Token sent as `Authorization: Bearer <token>`.
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header(H_AUTHORIZATION, format!("Bearer {token}"))
+    }
+
+    /// Send this request without the credentials configured on the client.
+    ///
+    /// The default `Authorization` header is removed and authentication challenges are not
+    /// answered, nor are OAuth2 tokens sent.
+    pub fn no_auth(self) -> Self {
+        self.configure(|config| {
+            config.authorization = None;
+            config.oauth = None;
+            config.credential_provider = None;
+        })
+        .remove_header(H_AUTHORIZATION)
+    }
+
+    /// Give up connecting for this request after the given duration, instead of using the
+    /// timeout of the client.
+    /// # Arguments
+    /// `timeout`   Maximum duration of each connection attempt.
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        self.configure(|config| config.connect_timeout = Some(timeout))
+    }
+
+    /// Wait at most the given duration for data from the server, instead of using the
+    /// timeout of the client.
+    /// # Arguments
+    /// `timeout`   Maximum duration of a single read from the connection.
+    pub fn read_timeout(self, timeout: Duration) -> Self {
+        self.configure(|config| config.read_timeout = Some(timeout))
+    }
+
+    /// Wait at most the given duration for the server to accept data, instead of using the
+    /// timeout of the client.
+    /// # Arguments
+    /// `timeout`   Maximum duration of a single write to the connection.
+    pub fn write_timeout(self, timeout: Duration) -> Self {
+        self.configure(|config| config.write_timeout = Some(timeout))
+    }
+
+    /// Fail this request when it takes longer than the given duration in total, instead of
+    /// using the deadline of the client, see [`ClientBuilder::deadline`].
+    /// # Arguments
+    /// `deadline`  Maximum duration of the whole request.
+    pub fn deadline(self, deadline: Duration) -> Self {
+        self.configure(|config| config.deadline = Some(deadline))
+    }
+
+    /// Fail this request if its response body is larger than `size`, instead of using the
+    /// limit of the client.
+    /// # Arguments
+    /// `size`  Largest response body accepted, in bytes.
+    pub fn max_body_size(self, size: u64) -> Self {
+        self.configure(|config| config.max_body_size = Some(size))
+    }
+
+    /// Copy every response to `writer` exactly as it is received, head and body, while it is
+    /// parsed as usual.
+    ///
+    /// Every response received for this request is copied, including redirects and
+    /// authentication challenges. Bytes are written as they are read, so the body of a
+    /// streamed response is copied while it is read. Responses served from the cache are not
+    /// received and are not copied.
+    /// # Arguments
+    /// `writer`    Destination of the copy, e.g a file.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let archive = std::fs::File::create("response.http")?;
+    ///     let response = Client::new()
+    ///         .get("https://example.com")?
+    ///         .tee(archive)
+    ///         .send()?;
+    ///     println!("{}", response.status_code());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tee<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.request.tee = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+
+    /// Download this resource in blocks of `size` bytes, instead of using the block size of
+    /// the client.
+    /// # Arguments
+    /// `size`  Number of bytes requested at once with a `Range` header.
+    pub fn download_block_size(self, size: usize) -> Self {
+        self.configure(|config| config.download_block_size = size)
+    }
+
+    /// Whether this resource is downloaded in blocks using `Range` requests, instead of
+    /// using the setting of the client.
+    /// # Arguments
+    /// `enabled`   Whether to download in blocks.
+    pub fn ranged_downloads(self, enabled: bool) -> Self {
+        self.configure(|config| config.ranged_downloads = enabled)
+    }
+
+    /// Resume this download up to `retries` times in a row when interrupted by a network
+    /// error, instead of using the setting of the client.
+    /// # Arguments
+    /// `retries`   Attempts made after a failure, before giving up.
+    pub fn download_retries(self, retries: usize) -> Self {
+        self.configure(|config| config.download_retries = retries)
+    }
+
+    /// Whether the `Content-Digest` of the body of this request is sent, instead of using the
+    /// setting of the client, see [`ClientBuilder::content_digest`].
+    /// # Arguments
+    /// `enabled`   Whether to send the digest.
+    pub fn content_digest(self, enabled: bool) -> Self {
+        self.configure(|config| config.content_digest = enabled)
+    }
+
+    /// Retry this request according to the given policy, instead of the one of the client.
+    /// # Arguments
+    /// `policy`    When and how often the request is sent again.
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        self.configure(|config| config.retry = Some(policy))
+    }
+
+    /// Never retry this request, even when the client has a retry policy.
+    pub fn no_retry(self) -> Self {
+        self.configure(|config| config.retry = None)
+    }
+
+    /// Whether a random `Idempotency-Key` is sent with this request, instead of using the
+    /// setting of the client, see [`ClientBuilder::idempotency_keys`].
+    /// # Arguments
+    /// `enabled`   Whether a key is generated.
+    pub fn idempotency_key(self, enabled: bool) -> Self {
+        self.configure(|config| config.idempotency_keys = enabled)
+    }
+
+    /// Send this request as part of the trace of `parent`, e.g the request being handled by
+    /// a server, with a `traceparent` header identifying a new operation of that trace.
+    /// # Arguments
+    /// `parent`    Context of the calling operation.
+    pub fn trace_parent(self, parent: &TraceParent) -> Self {
+        self.header(H_TRACEPARENT, parent.child())
+    }
+
+    /// Hedge this request after the given time, instead of using the setting of the client,
+    /// see [`ClientBuilder::hedge`].
+    /// # Arguments
+    /// `threshold` Time after which a copy of the request is sent, `None` to never hedge it.
+    pub fn hedge(self, threshold: Option<Duration>) -> Self {
+        self.configure(|config| config.hedge_after = threshold)
+    }
+
+    /// Follow at most `limit` redirects for this request, `0` disables following redirects.
+    /// # Arguments
+    /// `limit` Maximum number of redirects followed.
+    pub fn redirect_limit(self, limit: usize) -> Self {
+        self.configure(|config| config.redirect_limit = limit)
+    }
+
+    /// Send this request through the given proxy instead of the one of the client.
+    /// # Arguments
+    /// `proxy` Proxy settings to be used.
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        self.use_proxy(Some(proxy))
+    }
+
+    /// Connect directly to the server for this request, ignoring the proxy of the client.
+    pub fn no_proxy(self) -> Self {
+        self.use_proxy(None)
+    }
+
+    /// The URL this request is sent to.
+    pub fn url(&self) -> &HttpUrl {
+        &self.url
+    }
+
+    /// The name of the method of this request, e.g `GET`.
+    pub fn method(&self) -> String {
+        self.request.method_name()
+    }
+
+    /// The headers of this request.
+    ///
+    /// Headers added while sending, such as cookies or answers to authentication challenges,
+    /// are not included.
+    pub fn headers(&self) -> impl Iterator<Item = &HttpHeader> + '_ {
+        self.request.head.headers()
+    }
+
+    /// The body of this request, `None` when it is streamed by a reader.
+    pub fn body_bytes(&self) -> Option<&[u8]> {
+        match self.request.body {
+            Some(_) => None,
+            None => Some(self.request.head.data()),
+        }
+    }
+
+    /// Attach a value to this request, replacing the value of the same type if any.
+    ///
+    /// The value is available from the response, see [`Extensions`].
+    /// # Arguments
+    /// `value` Value being attached.
+    pub fn extension<V: Clone + Send + Sync + 'static>(mut self, value: V) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// The values attached to this request.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// The values attached to this request, for modification.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// Copy this request, e.g to send it again when it failed.
+    ///
+    /// Returns `None` when the request streams its body, a stream can only be sent once.
+    pub fn try_clone(&self) -> Option<Self> {
+        if self.request.body.is_some() {
+            return None;
+        }
+        let url = HttpUrl::try_from(self.url.to_string().as_str()).ok()?;
+        let mut request = Outgoing::new(Self::retarget(&self.config, &url, &self.request.head));
+        request.method = self.request.method.clone();
+        request.explicit = self.request.explicit.clone();
+        request.tee = self.request.tee.clone();
+        request.download_progress = self.request.download_progress.clone();
+        request.upload_progress = self.request.upload_progress.clone();
+        request.checksum = self.request.checksum.clone();
+        request.verify_digest = self.request.verify_digest;
+        request.cancel = self.request.cancel.clone();
+        request.mirrors = self
+            .request
+            .mirrors
+            .iter()
+            .filter_map(|mirror| HttpUrl::try_from(mirror.to_string().as_str()).ok())
+            .collect();
+        Some(ClientRequest {
+            config: self.config.clone(),
+            url,
+            request,
+            extensions: self.extensions.clone(),
+            _d: PhantomData,
+        })
+    }
+
+    /// Build this request without sending it, so it can be inspected, signed or sent
+    /// several times with [`Client::execute`].
+    ///
+    /// # Errors
+    /// When the request uses a method or streamed body that a [`HttpRequest`] cannot hold.
+    pub fn build(self) -> Result<HttpRequest, HttpError> {
+        if let Some(method) = &self.request.method {
+            return Err(HttpError::Other(format!(
+                "`{method}` requests cannot be built, send them instead"
+            )));
+        }
+        if self.request.body.is_some() {
+            return Err(HttpError::Other(
+                "Requests with a streamed body cannot be built, send them instead".to_owned(),
+            ));
+        }
+        Ok(self.request.head)
+    }
+
+    /// Send this request to the given given URL.
+    pub fn send(mut self) -> Result<Response, HttpError> {
+        if let Some(threshold) = self.hedging() {
+            return self.send_hedged(threshold);
+        }
+        let mut response = Self::send_request(&self.config, &self.url, &mut self.request)?;
+        *response.extensions_mut() = self.extensions;
+        Ok(response)
+    }
+
+    /// Send this request and return as soon as the head of the response is received.
+    ///
+    /// The body is not buffered, it is decoded while it is read from the connection through
+    /// the [`Read`] implementation of the response, so responses of any size can be handled
+    /// in bounded memory. Responses are never answered from the cache.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let mut response = Client::new()
+    ///         .get("https://example.com/large.iso")?
+    ///         .send_streaming()?;
+    ///     let mut file = std::fs::File::create("large.iso")?;
+    ///     let copied = response.copy_to(&mut file)?;
+    ///     println!("Downloaded {copied} bytes");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn send_streaming(mut self) -> Result<Response, HttpError> {
+        if let Some(threshold) = self.hedging() {
+            self.request.streaming = true;
+            return self.send_hedged(threshold);
+        }
+        let mut response = self.send_streamed()?;
+        *response.extensions_mut() = self.extensions;
+        Ok(response)
+    }
+
+    /// Start sending this request without blocking, to drive it from an event loop, see
+    /// [`PendingResponse`].
+    ///
+    /// This call still blocks while the host is resolved and the connection opened, only
+    /// the TLS handshake, the request and the response are driven without blocking. Plain
+    /// URLs are upgraded to HTTPS or refused like other requests, see
+    /// [`ClientBuilder::https_only`].
+    ///
+    /// # Errors
+    /// When the connection cannot be opened, the URL is refused, or the request streams its
+    /// body or goes through a proxy.
+    pub fn send_nonblocking(self) -> Result<PendingResponse, HttpError> {
+        if self.request.body.is_some() {
+            return Err(HttpError::Other(
+                "Requests with a streamed body cannot be sent without blocking".to_owned(),
+            ));
+        }
+        let url = Self::secure_url(&self.config, &self.url)?;
+        PendingResponse::start(
+            &self.config,
+            url.as_ref().unwrap_or(&self.url),
+            self.request.method_name(),
+            self.request.to_bytes(),
+        )
+    }
+
+    /// Download the resource from the given mirrors, in order, when downloading it from the URL
+    /// of this request fails.
+    ///
+    /// A mirror taking over part way through continues from the bytes already written,
+    /// with a `Range` request when ranged downloads are enabled. Credentials set on this
+    /// request are not sent to mirrors on other hosts.
+    /// # Arguments
+    /// `urls`  Other locations of the same resource.
+    ///
+    /// # Errors
+    /// When one of the URLs is invalid.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let iso = Client::new()
+    ///         .get("https://example.com/release.iso")?
+    ///         .mirrors(["https://mirror1.example.org/release.iso", "https://mirror2.example.net/release.iso"])?
+    ///         .download()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn mirrors<I, S>(mut self, urls: I) -> Result<Self, HttpError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for url in urls {
+            let url = HttpUrl::try_from(url.as_ref())
+                .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+            self.request.mirrors.push(url);
+        }
+        Ok(self)
+    }
+
+    /// Download part of the URL resource and return its bytes, e.g `0..1024` for its first
+    /// kilobyte or `1024..` for everything after it.
+    ///
+    /// The `Content-Range` of the response must match the range requested. Fewer bytes are
+    /// returned when the range goes past the end of the resource. A server ignoring the
+    /// `Range` header sends the whole resource, which the range is taken from.
+    /// # Arguments
+    /// `range` Byte offsets being requested.
+    ///
+    /// # Errors
+    /// When the range is empty, [`HttpError::RangeNotSatisfiable`] when it starts after the
+    /// end of the resource, and [`HttpError::BadResponse`] when the server sends other bytes.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let header = Client::new()
+    ///         .get("https://example.com/video.mp4")?
+    ///         .download_range(0..64)?;
+    ///     println!("{} header bytes", header.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn download_range<R: RangeBounds<u64>>(self, range: R) -> Result<Vec<u8>, HttpError> {
+        let (start, end) = Self::byte_bounds(&range)?;
+        let response = self.range(range)?.send()?;
+        let status = response.status_code();
+        match status {
+            StatusCode::PARTIAL_CONTENT => {
+                let mut parts = response.byte_ranges()?;
+                if parts.len() != 1 {
+                    return Err(HttpError::BadResponse(
+                        status,
+                        format!("Expected a single range, got {}", parts.len()),
+                    ));
+                }
+                let part = parts.remove(0);
+                let length = part
+                    .end()
+                    .checked_sub(part.start())
+                    .and_then(|length| length.checked_add(1));
+                // The range only ends early at the end of the resource
+                let ends_resource = part
+                    .total()
+                    .is_none_or(|total| part.end().checked_add(1) == Some(total));
+                let valid_end = match end {
+                    Some(end) => part.end() == end || (part.end() < end && ends_resource),
+                    None => ends_resource,
+                };
+                if part.start() != start || !valid_end || Some(part.data().len() as u64) != length {
+                    return Err(HttpError::BadResponse(
+                        status,
+                        format!(
+                            "Requested bytes {start}-{}, got bytes {}-{} in {} bytes",
+                            end.map(|end| end.to_string()).unwrap_or_default(),
+                            part.start(),
+                            part.end(),
+                            part.data().len()
+                        ),
+                    ));
+                }
+                Ok(part.into_data())
+            }
+            416 => Err(HttpError::RangeNotSatisfiable(Self::unsatisfied_size(
+                &response,
+            ))),
+            // The server ignored the range
+            StatusCode::OK => {
+                let data = response.data();
+                let length = data.len() as u64;
+                if start >= length {
+                    return Err(HttpError::RangeNotSatisfiable(Some(length)));
+                }
+                let end = end.map_or(length, |end| min(end + 1, length));
+                Ok(data[start as usize..end as usize].to_vec())
+            }
+            _ => Err(HttpError::BadResponse(
+                status,
+                response.status_msg().to_owned(),
+            )),
+        }
+    }
+
+    /// Download the URL resource and return it's bytes.
+    pub fn download(self) -> Result<Vec<u8>, HttpError> {
+        let mut data = Vec::new();
+        self.download_to_file(&mut data)?;
+        Ok(data)
+    }
+
+    /// Download the URL resource and store the resource bytes, returning statistics about
+    /// the transfer.
+    ///
+    /// The body is written to `writer` while it is received. With ranged downloads, the
+    /// default, the resource is requested in blocks, see [`ClientBuilder::download_block_size`],
+    /// its size being learned from the response to the first block. Servers ignoring the
+    /// `Range` header simply send the whole resource at once, and a server ignoring it part
+    /// way through has the bytes already written skipped from the whole resource. A download
+    /// interrupted by a network error resumes from the bytes already written, see
+    /// [`ClientBuilder::download_retries`], and otherwise goes on from the next mirror, see
+    /// [`ClientRequest::mirrors`].
+    /// # Arguments
+    /// `writer`    Destination for bytes sent by the remote server.
+    pub fn download_to_file<V>(mut self, writer: &mut V) -> Result<DownloadReport, HttpError>
+    where
+        V: Write,
+    {
+        let started = Instant::now();
+        let progress = self.request.download_progress.clone();
+        let writer = DestinationWriter::new(writer);
+        let mut writer = ChecksumWriter::new(ProgressWriter::new(writer, progress, None), None);
+        let mut report = DownloadReport::default();
+        let mut retries = 0;
+        let mut written = 0;
+        while let Err(error) = self.download_rest(&mut writer, &mut report) {
+            // Only failures without progress count towards the limit
+            if writer.get_ref().written() > written {
+                written = writer.get_ref().written();
+                retries = 0;
+            }
+            if retry::is_transient(&error) && retries < self.config.download_retries {
+                retries += 1;
+                report.retries += 1;
+                continue;
+            }
+            // A mirror takes over from the bytes already written
+            let aborted = matches!(error, HttpError::Cancelled | HttpError::DeadlineExceeded);
+            if aborted || !self.next_mirror() {
+                return Err(error);
+            }
+            retries = 0;
+            report.retries += 1;
+        }
+        report.bytes = writer.get_ref().written();
+        writer.finish()?;
+        report.elapsed = started.elapsed();
+        Ok(report)
+    }
+
+    /// Helper method, download the part of the resource `writer` does not hold yet.
+    ///
+    /// The size of the resource and the digest to verify are learned from the first response.
+    fn download_rest<W: Write>(
+        &mut self,
+        writer: &mut ChecksumWriter<ProgressWriter<W>>,
+        report: &mut DownloadReport,
+    ) -> Result<(), HttpError> {
+        let block_size = self.config.download_block_size.max(1) as u64;
+        // A range set by the caller is downloaded as it is
+        let ranged = self.config.ranged_downloads && !self.request.is_explicit(H_RANGE);
+        loop {
+            let offset = writer.get_ref().written();
+            let total = writer.get_ref().total();
+            if offset > 0 && total.is_some_and(|total| offset >= total) {
+                return Ok(());
+            }
+            if ranged {
+                let end =
+                    total.map_or(offset + block_size, |total| min(total, offset + block_size));
+                self.request
+                    .head
+                    .put_header(H_RANGE, format!("bytes={offset}-{}", end - 1));
+            }
+            report.requests += 1;
+            let mut response = self.send_streamed()?;
+            let status = response.status_code();
+            if offset == 0 && (200..300).contains(&status) {
+                let expected = self.expected_checksum(Self::announced_digest(&response));
+                writer.expect(expected);
+            }
+            match status {
+                StatusCode::PARTIAL_CONTENT if ranged => {
+                    let content = Self::partial_content(&mut response)?;
+                    if content.start != offset {
+                        return Err(HttpError::BadResponse(
+                            status,
+                            format!(
+                                "Expected content from byte {offset}, got it from byte {}",
+                                content.start
+                            ),
+                        ));
+                    }
+                    if offset == 0 {
+                        writer.get_mut().set_total(content.total);
+                    }
+                    let copied = match content.data {
+                        Some(data) => {
+                            writer.write_all(&data)?;
+                            data.len() as u64
+                        }
+                        None => response.copy_to(writer)?,
+                    };
+                    let done = match writer.get_ref().total() {
+                        Some(total) => offset + copied >= total,
+                        // Without a known size the last block is the first one to come short
+                        None => copied < block_size,
+                    };
+                    if done {
+                        return Ok(());
+                    }
+                }
+                416 if ranged => {
+                    let size = Self::unsatisfied_size(&response);
+                    // An empty resource has no byte to satisfy the first block with
+                    if offset == 0 && size == Some(0) {
+                        writer.expect(self.expected_checksum(None));
+                        if let Some(progress) = &self.request.download_progress {
+                            body::report(progress, 0, Some(0));
+                        }
+                        return Ok(());
+                    }
+                    return Err(HttpError::RangeNotSatisfiable(size));
+                }
+                // The whole resource, from a server ignoring ranges the bytes already written
+                // come first
+                200..=299 => {
+                    if offset == 0 {
+                        writer.get_mut().set_total(response.content_length());
+                    }
+                    Self::skip_downloaded(&mut response, offset)?;
+                    response.copy_to(writer)?;
+                    return Ok(());
+                }
+                _ => {
+                    return Err(HttpError::BadResponse(
+                        status,
+                        response.status_msg().to_owned(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Helper method, send the following requests to the next mirror, returns `false` when
+    /// there is none left.
+    fn next_mirror(&mut self) -> bool {
+        if self.request.mirrors.is_empty() {
+            return false;
+        }
+        let mirror = self.request.mirrors.remove(0);
+        // Credentials are only sent to another host when configured for the client
+        Self::redirect_request(&self.config, &self.url, &mirror, &mut self.request, 307);
+        self.url = mirror;
+        true
+    }
+
+    /// Download the URL resource into the file at `path`, which only appears once the
+    /// download succeeds, returning statistics about the transfer.
+    ///
+    /// The data is written to `path` with a `.part` extension added, renamed to `path` when
+    /// complete and deleted when the download fails, so an incomplete file never takes the
+    /// place of the resource. An existing file at `path` is replaced.
+    /// # Arguments
+    /// `path`  Destination of the resource.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     Client::new()
+    ///         .get("https://example.com/video.mp4")?
+    ///         .download_to_path("video.mp4")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn download_to_path<P: AsRef<Path>>(self, path: P) -> Result<DownloadReport, HttpError> {
+        write_atomically(path.as_ref(), |file| self.download_to_file(file))
+    }
+
+    /// Download the URL resource into a directory, named after the file name suggested by
+    /// the server or the last segment of the URL, and return the path of the file.
+    ///
+    /// The name given by `Content-Disposition` is preferred over the one of the URL, the URL
+    /// after following redirects, and `index.html` is used when neither provides one. Any
+    /// directory in the name is ignored, so the file is always created in `dir`. An existing
+    /// file with the same name is replaced once the download succeeds, see
+    /// [`ClientRequest::download_to_path`].
+    /// # Arguments
+    /// `dir`   Directory the file is saved to.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let path = Client::new()
+    ///         .get("https://example.com/download?id=42")?
+    ///         .save_to_dir("downloads")?;
+    ///     println!("Saved to {}", path.display());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn save_to_dir<P: AsRef<Path>>(self, dir: P) -> Result<PathBuf, HttpError> {
+        let dir = dir.as_ref();
+        self.save_named(|name| dir.join(name))
+    }
+
+    /// Download the URL resource into the file at the path given by `path_for` for the name
+    /// suggested by the server or the URL, see [`ClientRequest::save_to_dir`].
+    pub(crate) fn save_named<F>(mut self, path_for: F) -> Result<PathBuf, HttpError>
+    where
+        F: FnOnce(String) -> PathBuf,
+    {
+        let mut response = self.send_streamed()?;
+        if !(200..300).contains(&response.status_code()) {
+            return Err(HttpError::BadResponse(
+                response.status_code(),
+                response.status_msg().to_owned(),
+            ));
+        }
+        let name = response
+            .filename()
+            .or_else(|| {
+                let url = HttpUrl::try_from(response.url()).ok()?;
+                url_filename(url.path())
+            })
+            .unwrap_or_else(|| DEFAULT_FILENAME.to_owned());
+        let path = path_for(name);
+        self.save_response(&mut response, &path)?;
+        Ok(path)
+    }
+
+    /// Download the URL resource into the file at `path` only when it changed since the
+    /// last download, returns whether the file was written.
+    ///
+    /// The `ETag` and `Last-Modified` headers of the response are kept in a file next to
+    /// `path`, with a `.validators` extension added. When both files exist they are sent back
+    /// as `If-None-Match` and `If-Modified-Since`, and a `304 Not Modified` response leaves
+    /// the file untouched. Otherwise the file is written as by
+    /// [`ClientRequest::download_to_path`].
+    /// # Arguments
+    /// `path`  Destination of the resource.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let changed = Client::new()
+    ///         .get("https://example.com/feed.xml")?
+    ///         .download_if_changed("feed.xml")?;
+    ///     if changed {
+    ///         println!("New feed downloaded");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn download_if_changed<P: AsRef<Path>>(mut self, path: P) -> Result<bool, HttpError> {
+        let path = path.as_ref();
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(".validators");
+        let sidecar = PathBuf::from(sidecar);
+        // Validators of a file that was deleted since are not used
+        if path.exists() {
+            let validators = std::fs::read_to_string(&sidecar).unwrap_or_default();
+            for line in validators.lines() {
+                match line.split_once(':') {
+                    Some((H_ETAG, etag)) => self = self.header(H_IF_NONE_MATCH, etag.trim()),
+                    Some((H_LAST_MODIFIED, date)) => {
+                        self = self.header(H_IF_MODIFIED_SINCE, date.trim())
+                    }
+                    _ => (),
+                }
+            }
+        }
+        let mut response = self.send_streamed()?;
+        match response.status_code() {
+            304 => return Ok(false),
+            200..=299 => (),
+            status => {
+                return Err(HttpError::BadResponse(
+                    status,
+                    response.status_msg().to_owned(),
+                ))
+            }
+        }
+        let validators = [H_ETAG, H_LAST_MODIFIED]
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header_value(name)?;
+                Some(format!("{name}: {value}\n"))
+            })
+            .collect::<String>();
+        self.save_response(&mut response, path)?;
+        if validators.is_empty() {
+            // Stale validators would make the next download skip a changed resource
+            match std::fs::remove_file(&sidecar) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => (),
+            }
+        } else {
+            std::fs::write(&sidecar, validators)?;
+        }
+        Ok(true)
+    }
+
+    /// Fail [`ClientRequest::download`] and [`ClientRequest::download_to_file`] when the
+    /// downloaded bytes do not match `checksum`.
+    ///
+    /// The digest is computed while the data is written, the destination of
+    /// [`ClientRequest::download_to_file`] holds the data even when it does not match.
+    /// # Arguments
+    /// `checksum`  Expected digest, e.g published next to the file.
+    pub fn checksum(mut self, checksum: Checksum) -> Self {
+        self.request.checksum = Some(checksum);
+        self
+    }
+
+    /// Fail when a response does not match the digest announced by the server.
+    ///
+    /// The body of every response is verified against its `Content-Digest` (RFC 9530) while
+    /// it is read, whether it is sent in the head or the trailers of the response. Reading a
+    /// streamed response fails at the end of a body not matching it.
+    ///
+    /// [`ClientRequest::download`] and [`ClientRequest::download_to_file`] also verify the
+    /// whole resource, against the SHA-256 or MD5 digest of a `Repr-Digest` or
+    /// `Content-Digest` header or the `Content-MD5` header, only `Repr-Digest` when the
+    /// resource is downloaded in blocks. Responses without one are not verified, a checksum
+    /// given to [`ClientRequest::checksum`] takes precedence.
+    pub fn verify_digest(mut self) -> Self {
+        self.request.verify_digest = true;
+        self
+    }
+
+    /// Abort this request when the given token is cancelled, failing with
+    /// [`HttpError::Cancelled`], see [`CancelToken`].
+    ///
+    /// Covers the whole request, redirects, retries and every block of a download included,
+    /// as well as reading the body of a streamed response.
+    /// # Arguments
+    /// `token` Cancelled from another thread to abort the request.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.request.cancel = Some(token);
+        self
+    }
+
+    /// Helper method, write the body of a streamed response into the file at `path`, through
+    /// a `.part` file, verifying its checksum and reporting progress.
+    fn save_response(&self, response: &mut Response, path: &Path) -> Result<(), HttpError> {
+        let expected = self.expected_checksum(Self::announced_digest(response));
+        let progress = self.request.download_progress.clone();
+        let total = response.content_length();
+        write_atomically(path, |file| {
+            let file = DestinationWriter::new(file);
+            let mut writer =
+                ChecksumWriter::new(ProgressWriter::new(file, progress, total), expected);
+            response.copy_to(&mut writer)?;
+            writer.finish()?;
+            Ok(())
+        })
+    }
+
+    /// Helper method, discard the first `downloaded` bytes of a whole resource sent in response
+    /// to a range request, as they were already received.
+    fn skip_downloaded(response: &mut Response, downloaded: u64) -> Result<(), HttpError> {
+        let skipped = std::io::copy(
+            &mut response.by_ref().take(downloaded),
+            &mut std::io::sink(),
+        )?;
+        if skipped < downloaded {
+            return Err(HttpError::BadResponse(
+                response.status_code(),
+                format!("Expected a resource of at least {downloaded} bytes, got {skipped} bytes"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Helper method, the time after which a copy of this request is sent, when it is hedged.
+    fn hedging(&self) -> Option<Duration> {
+        self.config.hedge_after.filter(|_| {
+            self.request.body.is_none()
+                && matches!(self.request.method_name().as_str(), "GET" | "HEAD")
+        })
+    }
+
+    /// Helper method, send this request and a copy of it once `threshold` passed, returning
+    /// the first response.
+    fn send_hedged(self, threshold: Duration) -> Result<Response, HttpError> {
+        let backup = self.try_clone().map(|mut copy| {
+            copy.request.streaming = self.request.streaming;
+            (copy.url, copy.request)
+        });
+        let ClientRequest {
+            config,
+            url,
+            request,
+            extensions,
+            ..
+        } = self;
+        let mut response = race_attempts(&config, (url, request), backup, threshold)?;
+        *response.extensions_mut() = extensions;
+        Ok(response)
+    }
+
+    /// Helper method, send this request, leaving the body of the response on the connection.
+    ///
+    /// Following requests go straight to the URL a redirect led to.
+    fn send_streamed(&mut self) -> Result<Response, HttpError> {
+        self.request.streaming = true;
+        let response = Self::send_request(&self.config, &self.url, &mut self.request)?;
+        if !response.redirects().is_empty() {
+            if let Ok(url) = HttpUrl::try_from(response.url()) {
+                self.url = url;
+            }
+        }
+        Ok(match self.request.response_body.take() {
+            Some(body) => response.streamed(body),
+            None => response,
+        })
+    }
+
+    /// Helper method, the digest of the whole resource announced in the headers of a response.
+    fn announced_digest(response: &Response) -> Option<Checksum> {
+        let repr_digest = response
+            .header_values(H_REPR_DIGEST)
+            .iter()
+            .find_map(|value| Checksum::from_digest_header(value));
+        // The digests of the content only cover the range sent by a `206` response
+        if response.status_code() == StatusCode::PARTIAL_CONTENT {
+            return repr_digest;
+        }
+        repr_digest
+            .or_else(|| {
+                response
+                    .header_values(H_CONTENT_DIGEST)
+                    .iter()
+                    .find_map(|value| Checksum::from_digest_header(value))
+            })
+            .or_else(|| {
+                response
+                    .header_values(H_CONTENT_MD5)
+                    .iter()
+                    .find_map(|value| Checksum::from_content_md5(value))
+            })
+    }
+
+    /// Helper method, the first byte and size of the resource, if known, of the content of a
+    /// `206` response. A `multipart/byteranges` body is read in memory and its parts joined,
+    /// they must follow each other.
+    fn partial_content(response: &mut Response) -> Result<PartialContent, HttpError> {
+        let status = response.status_code();
+        let Some(boundary) = response.byteranges_boundary() else {
+            let (start, _, total) = response
+                .header_value(H_CONTENT_RANGE)
+                .and_then(|value| multipart::parse_content_range(&value))
+                .ok_or_else(|| {
+                    HttpError::BadResponse(
+                        status,
+                        format!("Missing or invalid `{H_CONTENT_RANGE}` header"),
+                    )
+                })?;
+            return Ok(PartialContent {
+                start,
+                total,
+                data: None,
+            });
+        };
+        let mut body = Vec::new();
+        response.read_to_end(&mut body)?;
+        let mut parts = multipart::parse_byteranges(&body, &boundary)?;
+        parts.sort_by_key(|part| part.start());
+        let first = parts.first().ok_or_else(|| {
+            HttpError::BadResponse(status, "Empty multipart/byteranges body".to_owned())
+        })?;
+        let (start, total) = (first.start(), first.total());
+        let mut data = Vec::new();
+        for part in parts {
+            let next = start + data.len() as u64;
+            if part.start() > next {
+                return Err(HttpError::BadResponse(
+                    status,
+                    format!("Missing content from byte {next} to byte {}", part.start()),
+                ));
+            }
+            // Overlapping parts repeat bytes already joined
+            if part.end() < next {
+                continue;
+            }
+            data.extend_from_slice(&part.data()[(next - part.start()) as usize..]);
+        }
+        Ok(PartialContent {
+            start,
+            total,
+            data: Some(data),
+        })
+    }
+
+    /// Helper method, the size of the resource sent by a `416` response, e.g `bytes */1234`.
+    fn unsatisfied_size(response: &Response) -> Option<u64> {
+        multipart::parse_unsatisfied_range(&response.header_value(H_CONTENT_RANGE)?)
+    }
+
+    /// Helper method, the checksum a download must match, given the one the server announced.
+    fn expected_checksum(&self, announced: Option<Checksum>) -> Option<Checksum> {
+        self.request
+            .checksum
+            .clone()
+            .or_else(|| announced.filter(|_| self.request.verify_digest))
+    }
+
+    /// Report the progress of [`ClientRequest::download`] and [`ClientRequest::download_to_file`].
+    ///
+    /// `callback` is called with the number of bytes written so far and the size of the
+    /// resource, when the server reports it, every time data is written.
+    /// # Arguments
+    /// `callback`  Called with the bytes downloaded and the total, e.g to draw a progress bar.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let mut file = std::fs::File::create("video.mp4")?;
+    ///     Client::new()
+    ///         .get("https://example.com/video.mp4")?
+    ///         .on_progress(|downloaded, total| match total {
+    ///             Some(total) => println!("{}%", downloaded * 100 / total.max(1)),
+    ///             None => println!("{downloaded} bytes"),
+    ///         })
+    ///         .download_to_file(&mut file)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        self.request.download_progress = Some(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    /// Report the progress of sending the request body.
+    ///
+    /// `callback` is called with the number of bytes of the body sent so far and the size of
+    /// the body, when known. Streamed bodies, e.g from [`ClientRequest::body_reader`] or
+    /// [`ClientRequest::multipart`], are reported as they are sent, bodies held in memory once
+    /// they are written. A body sent again, e.g after a `307` redirect, is reported again.
+    /// # Arguments
+    /// `callback`  Called with the bytes uploaded and the total.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let file = std::fs::File::open("backup.tar")?;
+    ///     let length = file.metadata()?.len();
+    ///     Client::new()
+    ///         .put("https://example.com/backup.tar")?
+    ///         .body_reader(file, Some(length))
+    ///         .on_upload_progress(|sent, total| println!("{sent} of {total:?} bytes sent"))
+    ///         .send()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_upload_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        self.request.upload_progress = Some(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    /// Creates a JSON object from the payload of the response.
+    ///
+    /// # Errors
+    /// On connection errors, or when the data cannot be parse properly
+    pub fn json_response(mut self) -> Result<JsonValue, HttpError> {
+        let res = Self::send_request(&self.config, &self.url, &mut self.request)?;
+        if res.status_code() != StatusCode::OK {
+            Err(HttpError::BadResponse(
+                res.status_code(),
+                res.status_msg().to_owned(),
+            ))
+        } else {
+            let json = JsonParser::parse_json(&String::from_utf8_lossy(res.data()))
+                .map_err(|e| HttpError::Other(e))?;
+            Ok(json)
+        }
+    }
+
+    /// Helper method, send a request in a span of its own when tracing is enabled.
+    fn send_request(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<Response, HttpError> {
+        if config.trace_context && !req.has_header(H_TRACEPARENT) {
+            req.head.put_header(H_TRACEPARENT, TraceParent::new());
+        }
+        if let Some(generate) = config
+            .request_id
+            .as_ref()
+            .filter(|_| !req.has_header(H_REQUEST_ID))
+        {
+            req.head.put_header(H_REQUEST_ID, (generate.0)());
+        }
+        let request_id = req.header_value(H_REQUEST_ID);
+        #[cfg(feature = "tracing")]
+        let result = {
+            let method = req.method_name();
+            let trace_id = req
+                .header_value(H_TRACEPARENT)
+                .and_then(|header| TraceParent::parse(&header))
+                .map(|parent| parent.trace_id().to_owned());
+            trace_context::in_span(
+                &method,
+                url.host(),
+                url.path(),
+                trace_id.as_deref(),
+                request_id.as_deref(),
+                || Self::send_followed(config, url, req),
+            )
+        };
+        #[cfg(not(feature = "tracing"))]
+        let result = Self::send_followed(config, url, req);
+        match request_id {
+            Some(id) => result
+                .map_err(|error| error.with_request_id(&id))
+                .map(|response| response.with_request_id(Some(id))),
+            None => result,
+        }
+    }
+
+    /// Helper method, send a request and follow the redirects it receives.
+    fn send_followed(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<Response, HttpError> {
+        // Kept by the following requests of a download
+        if req.deadline.is_none() {
+            req.deadline = config.deadline.map(|deadline| Instant::now() + deadline);
+        }
+        // Generated once, every attempt sends the same key
+        if config.idempotency_keys
+            && matches!(req.method_name().as_str(), "POST" | "PATCH")
+            && !req.has_header(H_IDEMPOTENCY_KEY)
+        {
+            req.head
+                .put_header(H_IDEMPOTENCY_KEY, retry::idempotency_key());
+        }
+        let mut current = Self::secure_url(config, url)?;
+        if let Some(upgraded) = &current {
+            // An internal redirect, keeping the method and body
+            Self::redirect_request(config, url, upgraded, req, 307);
+        }
+        if req.origin.is_none() {
+            let first = current.as_ref().unwrap_or(url);
+            req.origin = HttpUrl::try_from(first.to_string().as_str()).ok();
+        }
+        let mut redirects = Vec::new();
+        // Skip the round trips to URLs known to be permanently redirected
+        let mut response = loop {
+            let target = current.as_ref().unwrap_or(url);
+            let cached = config
+                .redirect_cache
+                .as_ref()
+                .filter(|_| redirects.len() < config.redirect_limit)
+                .and_then(|cache| cache.get(&target.to_string()));
+            let Some((status, location)) = cached else {
+                break Self::send_retried(config, target, req)?;
+            };
+            let next = HttpUrl::try_from(location.as_str())
+                .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+            let next = Self::secure_url(config, &next)?.unwrap_or(next);
+            redirects.push(Redirect::new(target.to_string(), status));
+            Self::redirect_request(config, target, &next, req, status);
+            current = Some(next);
+        };
+        loop {
+            let base = current.as_ref().unwrap_or(url);
+            let status = response.status_code();
+            let to_get = redirect::changes_to_get(status, &req.method_name());
+            let location = match response.header(H_LOCATION) {
+                // A streamed body cannot be sent again to the new location
+                Some(_) if !to_get && req.body_sent() => break,
+                Some(location) if redirect::is_redirect(status) && config.redirect_limit > 0 => {
+                    location.value::<String>().unwrap() // save to unwrap, a str can always turn into String
+                }
+                _ => break,
+            };
+            if redirects.len() >= config.redirect_limit {
+                return Err(HttpError::BadResponse(
+                    status,
+                    format!("Too many redirects, gave up after {}", redirects.len()),
+                ));
+            }
+            let next = redirect::resolve_location(
+                base.scheme(),
+                &base.address().to_string(),
+                base.path(),
+                &location,
+            );
+            let next = HttpUrl::try_from(next.as_str())
+                .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+            let next = Self::secure_url(config, &next)?.unwrap_or(next);
+            if let (Some(cache), 301 | 308) = (&config.redirect_cache, status) {
+                cache.insert(&base.to_string(), status, &next.to_string());
+            }
+            redirects.push(Redirect::new(base.to_string(), status));
+            Self::redirect_request(config, base, &next, req, status);
+            response = Self::send_retried(config, &next, req)?;
+            current = Some(next);
+        }
+        let url = current.as_ref().unwrap_or(url).to_string();
+        let head = std::mem::take(&mut req.response_head);
+        Ok(Response::new(response, head, url, redirects)
+            .with_peer_addr(req.peer_addr)
+            .with_trailers(std::mem::take(&mut req.response_trailers)))
+    }
+
+    /// Helper method, change the settings used for this request only.
+    fn configure<F: FnOnce(&mut Config)>(mut self, change: F) -> Self {
+        change(Arc::make_mut(&mut self.config));
+        self
+    }
+
+    /// Helper method, send this request through another proxy.
+    ///
+    /// The credentials of the previous proxy are never sent to the new one.
+    fn use_proxy(mut self, proxy: Option<Proxy>) -> Self {
+        self = self.configure(|config| config.proxy = proxy);
+        let mut request = Self::without_header(
+            &self.config,
+            &self.url,
+            &self.request.head,
+            H_PROXY_AUTHORIZATION,
+        );
+        if let Some(authorization) = connection::forwarding_proxy(&self.config, &self.url)
+            .and_then(|proxy| proxy.authorization.as_ref())
+        {
+            request.put_header(H_PROXY_AUTHORIZATION, authorization);
+        }
+        self.request.head = request;
+        self
+    }
+
+    /// Helper method, a copy of a request without the header with the given name.
+    fn without_header(
+        config: &Config,
+        url: &HttpUrl,
+        req: &HttpRequest,
+        name: &str,
+    ) -> HttpRequest {
+        let mut request = HttpRequest::builder()
+            .method(req.method().clone())
+            .path(&Self::request_target(config, url))
+            .build();
+        for header in req.headers() {
+            if !header.name().eq_ignore_ascii_case(name) {
+                request.put_header(header.name(), header.value::<String>().unwrap());
+                // save to unwrap, a str can always turn into String
+            }
+        }
+        request.add_data(req.data());
+        request
+    }
+
+    /// Helper method, a copy of a request sent to another URL on the same host.
+    ///
+    /// The `Host` header of the request is kept, the one of `url` is only used when the
+    /// request has none.
+    fn retarget(config: &Config, url: &HttpUrl, req: &HttpRequest) -> HttpRequest {
+        let mut request = HttpRequest::builder()
+            .method(req.method().clone())
+            .path(&Self::request_target(config, url))
+            .header(H_HOST, url.host())
+            .build();
+        for header in req.headers() {
+            request.put_header(header.name(), header.value::<String>().unwrap());
+            // save to unwrap, a str can always turn into String
+        }
+        request.add_data(req.data());
+        request
+    }
+
+    /// Helper method, the HTTPS URL a plain `url` must be sent to instead, if any.
+    ///
+    /// Plain URLs to hosts known to require HTTPS are upgraded, other plain URLs are refused
+    /// when the client only allows HTTPS.
+    fn secure_url(config: &Config, url: &HttpUrl) -> Result<Option<HttpUrl>, HttpError> {
+        if url.scheme().eq_ignore_ascii_case("https") {
+            return Ok(None);
+        }
+        if config
+            .hsts
+            .as_ref()
+            .is_some_and(|hsts| hsts.is_known(url.host()))
+        {
+            let address = url.address().to_string();
+            let address = address.strip_suffix(":80").unwrap_or(&address);
+            let secure = HttpUrl::try_from(format!("https://{address}{}", url.path()).as_str())
+                .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+            return Ok(Some(secure));
+        }
+        if config.https_only {
+            return Err(HttpError::InvalidUrl(format!(
+                "{url} refused, the client only allows HTTPS"
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Helper method, turn a request into the one sent to `next` when `previous` redirected
+    /// to it with `status`.
+    ///
+    /// Credentials are only kept when the redirect stays on the same origin.
+    fn redirect_request(
+        config: &Config,
+        previous: &HttpUrl,
+        next: &HttpUrl,
+        req: &mut Outgoing,
+        status: usize,
+    ) {
+        let to_get = redirect::changes_to_get(status, &req.method_name());
+        let method = if to_get {
+            HttpMethod::Get
+        } else {
+            req.head.method().clone()
+        };
+        let same_origin = redirect::same_origin(previous, next);
+        let mut request = HttpRequest::builder()
+            .method(method)
+            .path(&Self::request_target(config, next))
+            .header(H_HOST, next.host())
+            .build();
+        for header in req.head.headers() {
+            let name = header.name();
+            let skipped = [H_HOST, H_COOKIE, H_PROXY_AUTHORIZATION]
+                .iter()
+                .any(|skipped| name.eq_ignore_ascii_case(skipped))
+                || (!same_origin && name.eq_ignore_ascii_case(H_AUTHORIZATION))
+                || (to_get
+                    && redirect::BODY_HEADERS
+                        .iter()
+                        .any(|body_header| name.eq_ignore_ascii_case(body_header)));
+            if !skipped {
+                request.put_header(name, header.value::<String>().unwrap()); // save to unwrap, a str can always turn into String
+            }
+        }
+        if let Some(authorization) = connection::forwarding_proxy(config, next)
+            .and_then(|proxy| proxy.authorization.as_ref())
+        {
+            request.put_header(H_PROXY_AUTHORIZATION, authorization);
+        }
+        if to_get {
+            req.method = None;
+            req.body = None;
+        } else {
+            request.add_data(req.head.data());
+        }
+        req.head = request;
+    }
+
+    /// Helper method, send a request to a single URL, sending it again after a failure as
+    /// far as the retry policy allows.
+    fn send_retried(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<HttpResponse, HttpError> {
+        let policy = config.retry.as_ref().filter(|policy| {
+            policy.allows(&req.method_name()) || req.has_header(H_IDEMPOTENCY_KEY)
+        });
+        let Some(policy) = policy else {
+            return Self::send_guarded(config, url, req);
+        };
+        let mut retry = 0;
+        loop {
+            let result = Self::send_guarded(config, url, req);
+            // A streamed body cannot be sent again
+            let again = retry + 1 < policy.attempts()
+                && !req.body_sent()
+                && match &result {
+                    Ok(response) => policy.retries_status(response.status_code()),
+                    Err(error) => policy.retries_error(error),
+                };
+            if !again {
+                return result;
+            }
+            let delay = match &result {
+                Ok(response) => policy.response_delay(
+                    retry,
+                    response.status_code(),
+                    response
+                        .header(H_RETRY_AFTER)
+                        .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+                        .as_deref(),
+                ),
+                Err(_) => policy.delay(retry),
+            };
+            // The last result is better than failing without trying again
+            if req
+                .deadline
+                .is_some_and(|deadline| Instant::now() + delay >= deadline)
+            {
+                return result;
+            }
+            match &req.cancel {
+                Some(token) => token.sleep(delay)?,
+                None => std::thread::sleep(delay),
+            }
+            retry += 1;
+        }
+    }
+
+    /// Helper method, send a request unless the circuit breaker fails it, recording its outcome.
+    fn send_guarded(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<HttpResponse, HttpError> {
+        let Some(breaker) = &config.circuit_breaker else {
+            return Self::send_authenticated(config, url, req);
+        };
+        let address = url.address().to_string();
+        breaker.allow(&address)?;
+        let result = Self::send_authenticated(config, url, req);
+        match &result {
+            Ok(response) => breaker.record(&address, response.status_code() < 500),
+            Err(error) if retry::is_transient(error) => breaker.record(&address, false),
+            // Failures of the request itself say nothing about the host
+            Err(_) => breaker.release(&address),
+        }
+        result
+    }
+
+    /// Helper method, send a request answering the authentication challenges it receives.
+    fn send_authenticated(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<HttpResponse, HttpError> {
+        let oauth = config
+            .oauth
+            .as_ref()
+            .filter(|_| !req.is_explicit(H_AUTHORIZATION) && req.trusts(url));
+        let mut response = match oauth {
+            Some(oauth) => {
+                let token = oauth.token()?;
+                req.head
+                    .put_header(H_AUTHORIZATION, format!("Bearer {token}"));
+                let response = Self::send_once(config, url, req)?;
+                if response.status_code() == 401 && !req.body_sent() {
+                    let token = oauth.token_after_rejection(&token)?;
+                    req.head
+                        .put_header(H_AUTHORIZATION, format!("Bearer {token}"));
+                    Self::send_once(config, url, req)?
+                } else {
+                    response
+                }
+            }
+            None => Self::send_once(config, url, req)?,
+        };
+        let Some(Hook(provider)) = &config.credential_provider else {
+            return Ok(response);
+        };
+        let mut answered = Vec::new();
+        loop {
+            let status = response.status_code();
+            let (challenge_header, authorization_header) = match status {
+                401 => (H_WWW_AUTHENTICATE, H_AUTHORIZATION),
+                407 => (H_PROXY_AUTHENTICATE, H_PROXY_AUTHORIZATION),
+                _ => return Ok(response),
+            };
+            // Proxy credentials go to the proxy, wherever the request was redirected
+            if answered.contains(&status)
+                || req.body_sent()
+                || req.is_explicit(authorization_header)
+                || (status == 401 && !req.trusts(url))
+            {
+                return Ok(response);
+            }
+            answered.push(status);
+            let challenges = response
+                .header(challenge_header)
+                .map(|header| Challenge::parse(&header.value::<String>().unwrap())) // save to unwrap, a str can always turn into String
+                .unwrap_or_default();
+            let Some(credentials) = provider.credentials(url, &challenges, status == 407) else {
+                return Ok(response);
+            };
+            let authorization = credentials.authorization(
+                &challenges,
+                &req.method_name(),
+                &Self::request_target(config, url),
+            )?;
+            req.head.put_header(authorization_header, authorization);
+            response = Self::send_once(config, url, req)?;
+        }
+    }
+
+    /// Helper method, send a request for the given URL once
+    fn send_once(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<HttpResponse, HttpError> {
+        let jar = config.cookie_jar.as_deref();
+        if let Some(cookies) = jar.and_then(|jar| jar.cookie_header(url)) {
+            req.put_default(H_COOKIE, cookies);
+        }
+        let response = match config.cache.as_deref().filter(|_| !req.streaming) {
+            Some(cache) => Self::send_cached(cache, config, url, req)?,
+            None => Self::exchange_shared(config, url, req, false)?.0,
+        };
+        if let Some(jar) = jar {
+            for header in connection::head_values(&req.response_head, H_SET_COOKIE) {
+                jar.set_cookie(&header, url);
+            }
+        }
+        if let (Some(hsts), Some(header)) =
+            (&config.hsts, response.header(H_STRICT_TRANSPORT_SECURITY))
+        {
+            // Only honored when received over a secure connection
+            if url.scheme().eq_ignore_ascii_case("https") {
+                hsts.record(url.host(), &header.value::<String>().unwrap()); // save to unwrap, a str can always turn into String
+            }
+        }
+        Ok(response)
+    }
+
+    /// Helper method, answer a request from the cache when possible and store its response.
+    fn send_cached(
+        cache: &HttpCache,
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<HttpResponse, HttpError> {
+        let key = url.to_string();
+        if *req.head.method() != HttpMethod::Get {
+            if !matches!(
+                req.head.method(),
+                HttpMethod::Head | HttpMethod::Trace | HttpMethod::Connect
+            ) {
+                cache.invalidate(&key);
+            }
+            return Ok(Self::exchange(config, url, req, false)?.0);
+        }
+        let request_headers = req
+            .head
+            .headers()
+            .map(|header| (header.name().to_owned(), header.value::<String>().unwrap())) // save to unwrap, a str can always turn into String
+            .collect::<Vec<(String, String)>>();
+        let revalidating = match cache.lookup(&key, &request_headers) {
+            Lookup::Fresh(raw) => {
+                req.peer_addr = None;
+                req.response_trailers.clear();
+                req.response_head = connection::head_of(&raw).to_vec();
+                return Ok(HttpParser::from_reader(&mut Cursor::new(raw)).response()?);
+            }
+            // Conditions set by the caller are sent as they are
+            Lookup::Stale { .. }
+                if req.is_explicit(H_IF_NONE_MATCH) || req.is_explicit(H_IF_MODIFIED_SINCE) =>
+            {
+                false
+            }
+            Lookup::Stale {
+                etag,
+                last_modified,
+            } => {
+                if let Some(etag) = etag {
+                    req.head.put_header(H_IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = last_modified {
+                    req.head.put_header(H_IF_MODIFIED_SINCE, last_modified);
+                }
+                true
+            }
+            Lookup::Miss => false,
+        };
+        let (response, raw) = Self::exchange_shared(config, url, req, true)?;
+        let response_header = |name: &str| {
+            response
+                .header(name)
+                .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+        };
+        if revalidating && response.status_code() == 304 {
+            if let Some(raw) = cache.refresh(&key, &request_headers, response_header) {
+                req.response_head = connection::head_of(&raw).to_vec();
+                return Ok(HttpParser::from_reader(&mut Cursor::new(raw)).response()?);
+            }
+        }
+        cache.store(
+            &key,
+            &request_headers,
+            response.status_code(),
+            response_header,
+            raw,
+        );
+        Ok(response)
+    }
+
+    /// Helper method, send a request and read its response, sharing it with identical
+    /// requests sent at the same time when coalescing is enabled.
+    ///
+    /// When `record` is set the response is also returned in wire format.
+    fn exchange_shared(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+        record: bool,
+    ) -> Result<(HttpResponse, Vec<u8>), HttpError> {
+        let (Some(coalescer), Some(key)) = (&config.coalescer, req.coalescing_key(url)) else {
+            return Self::exchange(config, url, req, record);
+        };
+        let deadline = req.deadline;
+        let mut sent = None;
+        let (raw, shared) = coalescer.run(&key, deadline, || {
+            let (response, raw) = Self::exchange(config, url, req, true)?;
+            let cache_control = response
+                .header(H_CACHE_CONTROL)
+                .map(|header| header.value::<String>().unwrap()); // save to unwrap, a str can always turn into String
+            let shareable = !cache::is_private(cache_control.as_deref());
+            sent = Some(response);
+            Ok((raw, shareable))
+        })?;
+        let response = match sent {
+            Some(response) if !shared => response,
+            _ => {
+                req.peer_addr = None;
+                req.response_trailers.clear();
+                req.response_head = connection::head_of(&raw).to_vec();
+                HttpParser::from_reader(&mut Cursor::new(&raw)).response()?
+            }
+        };
+        Ok((response, if record { raw } else { Vec::new() }))
+    }
+
+    /// Helper method, send a request and read its response, counting it in the metrics of
+    /// the client.
+    ///
+    /// When `record` is set the response is also returned in wire format.
+    fn exchange(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+        record: bool,
+    ) -> Result<(HttpResponse, Vec<u8>), HttpError> {
+        let result = Self::exchange_once(config, url, req, record);
+        config.metrics.record_request(result.is_err());
+        result
+    }
+
+    /// Helper method, send a request and read its response.
+    ///
+    /// When `record` is set the response is also returned in wire format.
+    fn exchange_once(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+        record: bool,
+    ) -> Result<(HttpResponse, Vec<u8>), HttpError> {
+        if config.content_digest {
+            Self::put_content_digest(config, url, req);
+        }
+        if let Some(token) = &req.cancel {
+            token.check()?;
+        }
+        let bounded;
+        let config = match req.deadline {
+            Some(deadline) => {
+                bounded = config.bounded(connection::time_left(deadline)?);
+                &bounded
+            }
+            None => config,
+        };
+        let started = Instant::now();
+        #[cfg(feature = "log")]
+        logging::request(
+            &req.method_name(),
+            &url.to_string(),
+            req.head
+                .headers()
+                .map(|header| (header.name(), header.value::<String>().unwrap())), // save to unwrap, a str can always turn into String
+        );
+        let recorded = config.cassette.as_deref().and_then(Cassette::replay);
+        let recorder = config
+            .cassette
+            .as_deref()
+            .filter(|cassette| cassette.is_recording() && config.har_replay.is_none());
+        let connection = match config.har_replay.as_deref().or(recorded) {
+            Some(replay) => Self::replay(replay, url, req)?,
+            None => Self::transmit(config, url, req)?,
+        };
+        let mut connection: Box<dyn Read + Send> = match &req.tee {
+            Some(writer) => Box::new(Tee::new(connection, writer.clone())),
+            None => connection,
+        };
+        // The head is kept to give access to headers sent more than once, e.g `Set-Cookie`.
+        // Interim responses such as `100 Continue` or `103 Early Hints` are skipped.
+        let response = loop {
+            req.response_head = connection::read_head(&mut connection, config.max_header_size)?;
+            let response = HttpParser::from_reader(&mut Cursor::new(&req.response_head))
+                .response_head_only()?;
+            let status = response.status_code();
+            if !(100..200).contains(&status) || status == 101 {
+                break response;
+            }
+        };
+        config
+            .metrics
+            .record_response(response.status_code(), started.elapsed());
+        #[cfg(feature = "log")]
+        logging::response(&url.to_string(), &req.response_head, started.elapsed());
+        let header = |name: &str| {
+            response
+                .header(name)
+                .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+        };
+        let framing = Framing::of(
+            &req.method_name(),
+            response.status_code(),
+            header(H_TRANSFER_ENCODING).as_deref(),
+            header(H_CONTENT_LENGTH).as_deref(),
+        )?;
+        let mut body = ResponseBody::new(connection, framing);
+        if req.verify_digest {
+            let announced = connection::head_values(&req.response_head, H_CONTENT_DIGEST)
+                .iter()
+                .find_map(|value| Checksum::from_digest_header(value));
+            body.verify_digest(announced);
+        }
+        // Streamed responses are read in full to be recorded
+        if req.streaming && recorder.is_none() {
+            req.response_body = Some(body);
+            return Ok((response, Vec::new()));
+        }
+        let mut data = Vec::new();
+        match config.max_body_size {
+            Some(limit) => {
+                let too_large =
+                    || HttpError::TooLarge(format!("response body exceeds {limit} bytes"));
+                if matches!(framing, Framing::Sized(length) if length > limit) {
+                    return Err(too_large());
+                }
+                (&mut body)
+                    .take(limit.saturating_add(1))
+                    .read_to_end(&mut data)?;
+                if data.len() as u64 > limit {
+                    return Err(too_large());
+                }
+            }
+            None => {
+                body.read_to_end(&mut data)?;
+            }
+        }
+        req.response_trailers = body.trailers().to_vec();
+        if let Some(cassette) = recorder {
+            cassette.record(
+                &req.method_name(),
+                &url.to_string(),
+                &req.header_pairs(),
+                &req.response_head,
+                &data,
+                started.elapsed(),
+            )?;
+            if req.streaming {
+                let length = data.len() as u64;
+                req.response_body =
+                    Some(ResponseBody::new(Cursor::new(data), Framing::Sized(length)));
+                return Ok((response, Vec::new()));
+            }
+        }
+
+        let message = connection::decoded_message(&req.response_head, framing, &data);
+        let response = match framing {
+            Framing::Empty => response,
+            _ => HttpParser::from_reader(&mut Cursor::new(&message)).response()?,
+        };
+        Ok((response, if record { message } else { Vec::new() }))
+    }
+
+    /// Helper method, send a request over a new connection and return the connection to
+    /// read its response from.
+    fn transmit(
+        config: &Config,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<Box<dyn Read + Send>, HttpError> {
+        let replayable = matches!(req.head.method(), HttpMethod::Get | HttpMethod::Head);
+        let request = req.to_bytes();
+        let cancel = req.cancel.as_ref();
+        let connection =
+            Connection::send(config, url, &request, replayable, cancel).map_err(|error| {
+                match req.deadline.map(connection::time_left) {
+                    // Timeouts were shortened to the time left
+                    Some(Err(exceeded)) => exceeded,
+                    _ => error,
+                }
+            })?;
+        if let Some(dump) = &config.wire_dump {
+            dump.record_sent(&request)?;
+        }
+        config.metrics.record_connection();
+        config.metrics.record_sent(request.len());
+        req.peer_addr = connection.peer_addr();
+        let connection = Bounded::new(connection, req.deadline, config);
+        let connection = Faulty::new(connection, config.faults.clone());
+        let connection = Cancellable::new(connection, req.cancel.as_ref())?;
+        let mut connection = Counted::new(connection, config.metrics.clone());
+        match (&mut req.body, &req.upload_progress) {
+            (Some(body), progress) => match &config.wire_dump {
+                Some(dump) => body.write_to(
+                    &mut Tee::new(&mut connection, dump.sent.0.clone()),
+                    progress.clone(),
+                )?,
+                None => body.write_to(&mut connection, progress.clone())?,
+            },
+            // A body held in memory is written along with the head
+            (None, Some(progress)) if !req.head.data().is_empty() => {
+                let length = req.head.data().len() as u64;
+                body::report(progress, length, Some(length));
+            }
+            _ => (),
+        }
+        Ok(match &config.wire_dump {
+            Some(dump) => Box::new(Tee::new(connection, dump.received.0.clone())),
+            None => Box::new(connection),
+        })
+    }
+
+    /// Helper method, answer a request with the response recorded for it, without sending it.
+    fn replay(
+        replay: &HarReplay,
+        url: &HttpUrl,
+        req: &mut Outgoing,
+    ) -> Result<Box<dyn Read + Send>, HttpError> {
+        let response = replay.respond(&req.method_name(), &url.to_string(), &req.header_pairs())?;
+        req.peer_addr = None;
+        match (&mut req.body, &req.upload_progress) {
+            (Some(body), progress) => body.write_to(&mut std::io::sink(), progress.clone())?,
+            (None, Some(progress)) if !req.head.data().is_empty() => {
+                let length = req.head.data().len() as u64;
+                body::report(progress, length, Some(length));
+            }
+            _ => (),
+        }
+        Ok(Box::new(Cursor::new(response)))
+    }
+
+    /// Helper method, announce the `Content-Digest` of the request body, in the head for a
+    /// body held in memory or as a trailer for a streamed body.
+    fn put_content_digest(config: &Config, url: &HttpUrl, req: &mut Outgoing) {
+        match &mut req.body {
+            Some(body) if !body.is_sent() && !body.sends_digest() => {
+                body.send_digest();
+                // Trailers need the chunked framing
+                req.head = Self::without_header(config, url, &req.head, H_CONTENT_LENGTH);
+                req.head.put_header(H_TRANSFER_ENCODING, "chunked");
+                req.head.put_header(H_TRAILER, H_CONTENT_DIGEST);
+            }
+            Some(_) => (),
+            None if !req.head.data().is_empty() => {
+                let digest = Checksum::Sha256(Sha256::digest(req.head.data()));
+                req.put_default(H_CONTENT_DIGEST, digest.digest_header());
+            }
+            None => (),
+        }
+    }
+
+    /// Helper method, the request target for the given URL.
+    /// Requests forwarded by a plain HTTP proxy must use the absolute URL.
+    fn request_target(config: &Config, url: &HttpUrl) -> String {
+        if connection::forwarding_proxy(config, url).is_some() {
+            url.to_string()
+        } else {
+            url.path().to_owned()
+        }
+    }
+}
+
+/// The content of a `206` response to a download block.
+struct PartialContent {
+    /// Offset of the first byte of the content in the resource.
+    start: u64,
+    /// Size of the resource, if announced.
+    total: Option<u64>,
+    /// The content of a `multipart/byteranges` body, joined in memory, otherwise the body is
+    /// read from the response.
+    data: Option<Vec<u8>>,
+}
+
+/// Write a file through a `.part` file renamed to `path` on success and deleted on failure.
+fn write_atomically<F, T>(path: &Path, write: F) -> Result<T, HttpError>
+where
+    F: FnOnce(&mut File) -> Result<T, HttpError>,
+{
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let part = PathBuf::from(part);
+    let failed = |error: std::io::Error| HttpError::Destination(error.to_string());
+    let result = File::create(&part)
+        .map_err(failed)
+        .and_then(|mut file| {
+            let written = write(&mut file)?;
+            file.sync_all().map_err(failed)?;
+            Ok(written)
+        })
+        .and_then(|written| {
+            std::fs::rename(&part, path).map_err(failed)?;
+            Ok(written)
+        });
+    if result.is_err() {
+        // The partial file is useless, failing to delete it must not hide the cause
+        let _ = std::fs::remove_file(&part);
+    }
+    result
+}
+
+/// Run `work` on every item on at most `workers` threads at once, and return the results in
+/// the order of the items, `None` for an item whose result could not be kept.
+/// # Arguments
+/// `items`     Items to work on.
+/// `workers`   Maximum number of threads, at least one.
+/// `work`      Called with the index of an item and the item.
+pub(crate) fn run_parallel<T, R, I, F>(items: I, workers: usize, work: F) -> Vec<Option<R>>
+where
+    T: Send,
+    R: Send,
+    I: IntoIterator<Item = T>,
+    F: Fn(usize, T) -> R + Sync,
+{
+    let pending = items.into_iter().enumerate().collect::<VecDeque<_>>();
+    let count = pending.len();
+    let pending = Mutex::new(pending);
+    let results = Mutex::new((0..count).map(|_| None).collect::<Vec<_>>());
+    let workers = workers.clamp(1, count.max(1));
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                // The lock is released before working
+                let next = pending
+                    .lock()
+                    .ok()
+                    .and_then(|mut pending| pending.pop_front());
+                let Some((index, item)) = next else {
+                    return;
+                };
+                let result = work(index, item);
+                if let Ok(mut results) = results.lock() {
+                    results[index] = Some(result);
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap_or_default()
+}
+
+/// Send a request on its own thread, then its backup once `threshold` passed without an
+/// answer, returning the first response, or the last error when both attempts fail.
+///
+/// Each attempt is given its own cancel token, cancelled along with the token of the request
+/// if any. The losing attempt is cancelled once the other one answered, closing its connection.
+fn race_attempts(
+    config: &Arc<Config>,
+    primary: (HttpUrl, Outgoing),
+    backup: Option<(HttpUrl, Outgoing)>,
+    threshold: Duration,
+) -> Result<Response, HttpError> {
+    let (sender, receiver) = mpsc::channel();
+    let mut tokens = Vec::new();
+    let mut attempt = |(url, mut request): (HttpUrl, Outgoing)| {
+        let token = request
+            .cancel
+            .as_ref()
+            .map_or_else(CancelToken::new, CancelToken::child);
+        request.cancel = Some(token.clone());
+        let index = tokens.len();
+        tokens.push(token);
+        let config = config.clone();
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let result = ClientRequest::<NoBody>::send_request(&config, &url, &mut request).map(
+                |response| match request.response_body.take() {
+                    Some(body) => response.streamed(body),
+                    None => response,
+                },
+            );
+            // The other attempt already answered when nobody receives this one
+            let _ = sender.send((index, result));
+        });
+    };
+    let not_sent = || HttpError::Other("Request was not sent".to_owned());
+    attempt(primary);
+    if let Ok((_, result)) = receiver.recv_timeout(threshold) {
+        return result;
+    }
+    let Some(backup) = backup else {
+        return receiver.recv().map_err(|_| not_sent())?.1;
+    };
+    attempt(backup);
+    // Wait for the second answer only when the first one is an error
+    let (winner, result) = match receiver.recv() {
+        Ok((_, Err(_))) => receiver.recv().map_err(|_| not_sent())?,
+        answer => answer.map_err(|_| not_sent())?,
+    };
+    for (index, token) in tokens.iter().enumerate() {
+        if index != winner {
+            token.cancel();
+        }
+    }
+    result
+}
+
+/// The name of a HTTP method as sent on the request line.
+pub(crate) fn method_name(method: &HttpMethod) -> String {
+    format!("{method:?}").to_ascii_uppercase()
+}
@@ -0,0 +1,562 @@
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use crate::client::{Client, ClientRequest, NoBody, LIB_USER_AGENT};
+use crate::error::HttpError;
+use crate::proxy::Proxy;
+use crate::test_server::{TestResponse, TestServer};
+use crate::HttpUrl;
+
+#[test]
+fn bearer_tokens() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "ok"));
+    let client = Client::builder().no_proxy().bearer_auth("client").build();
+    client.get(&server.url("/")).unwrap().send().unwrap();
+    client
+        .get(&server.url("/"))
+        .unwrap()
+        .bearer_auth("request")
+        .send()
+        .unwrap();
+    Client::builder()
+        .no_proxy()
+        .build()
+        .get(&server.url("/"))
+        .unwrap()
+        .send()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("Authorization"), Some("Bearer client"));
+    // The token of the request replaces the one of the client
+    assert_eq!(requests[1].header("Authorization"), Some("Bearer request"));
+    assert_eq!(requests[2].header("Authorization"), None);
+}
+
+#[test]
+fn base_url() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/v1/users/1", TestResponse::text(200, "user"));
+    server.route("GET", "/status", TestResponse::text(200, "up"));
+    let client = Client::builder()
+        .no_proxy()
+        .base_url(&server.url("/v1/users/"))
+        .unwrap()
+        .build();
+    let body = |path: &str| client.get(path).unwrap().send().unwrap().text();
+    assert_eq!(body("1"), "user");
+    assert_eq!(body("/status"), "up");
+    // Absolute URLs are used as given
+    assert_eq!(body(&server.url("/status")), "up");
+
+    assert!(matches!(
+        Client::builder().base_url("not a url"),
+        Err(HttpError::InvalidUrl(_))
+    ));
+}
+
+#[test]
+fn default_headers() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "ok"));
+    let client = Client::builder()
+        .no_proxy()
+        .user_agent("agent/1.0")
+        .default_header("X-Tenant", "blue")
+        .default_header("Accept", "text/plain")
+        .build();
+    client.get(&server.url("/")).unwrap().send().unwrap();
+    client
+        .get(&server.url("/"))
+        .unwrap()
+        .header("Accept", "application/json")
+        .send()
+        .unwrap();
+    Client::builder()
+        .no_proxy()
+        .build()
+        .get(&server.url("/"))
+        .unwrap()
+        .send()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("User-Agent"), Some("agent/1.0"));
+    assert_eq!(requests[0].header("X-Tenant"), Some("blue"));
+    assert_eq!(requests[0].header("Accept"), Some("text/plain"));
+    // Headers of the request replace the default ones
+    assert_eq!(requests[1].header("Accept"), Some("application/json"));
+    assert_eq!(requests[1].header("X-Tenant"), Some("blue"));
+    assert_eq!(requests[2].header("User-Agent"), Some(LIB_USER_AGENT));
+    assert_eq!(requests[2].header("X-Tenant"), None);
+}
+
+#[test]
+fn read_timeout() {
+    let server = TestServer::start().unwrap();
+    let slow = TestResponse::text(200, "slow").delay(Duration::from_secs(2));
+    server.route("GET", "/slow", slow);
+    let client = Client::builder()
+        .no_proxy()
+        .read_timeout(Duration::from_millis(100))
+        .build();
+    let started = Instant::now();
+    assert!(client.get(&server.url("/slow")).unwrap().send().is_err());
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn request_overrides() {
+    let server = TestServer::start().unwrap();
+    let slow = TestResponse::text(200, "slow").delay(Duration::from_millis(300));
+    server.route("GET", "/slow", slow);
+    server.route(
+        "GET",
+        "/old",
+        TestResponse::new(302).header("Location", "/new"),
+    );
+    server.route("GET", "/new", TestResponse::text(200, "new"));
+    let client = Client::builder()
+        .no_proxy()
+        .read_timeout(Duration::from_millis(100))
+        .build();
+
+    let response = client
+        .get(&server.url("/slow"))
+        .unwrap()
+        .read_timeout(Duration::from_secs(5))
+        .send()
+        .unwrap();
+    assert_eq!(response.text(), "slow");
+    // The client keeps its own settings
+    assert!(client.get(&server.url("/slow")).unwrap().send().is_err());
+
+    let response = client
+        .get(&server.url("/old"))
+        .unwrap()
+        .redirect_limit(0)
+        .send()
+        .unwrap();
+    assert_eq!(response.status_code(), 302);
+    let response = client.get(&server.url("/old")).unwrap().send().unwrap();
+    assert_eq!(response.text(), "new");
+}
+
+#[test]
+fn built_requests_executed() {
+    let server = TestServer::start().unwrap();
+    server.route("POST", "/items", TestResponse::text(201, "created"));
+    let client = Client::builder().no_proxy().build();
+    let url = HttpUrl::try_from(server.url("/items").as_str()).unwrap();
+    let request = client
+        .post(&server.url("/items"))
+        .unwrap()
+        .header("X-Signature", "abc")
+        .body(b"item")
+        .build()
+        .unwrap();
+    for _ in 0..2 {
+        let response = client.execute(&request, &url).unwrap();
+        assert_eq!(response.status_code(), 201);
+    }
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for received in requests {
+        assert_eq!(received.header("X-Signature"), Some("abc"));
+        assert_eq!(received.body, b"item");
+    }
+
+    // Neither custom methods nor streamed bodies fit in a `HttpRequest`
+    let custom = client.request("PROPFIND", &server.url("/")).unwrap();
+    assert!(matches!(custom.build(), Err(HttpError::Other(_))));
+    let streamed = client
+        .post(&server.url("/items"))
+        .unwrap()
+        .body_reader(Cursor::new(b"item".to_vec()), Some(4));
+    assert!(matches!(streamed.build(), Err(HttpError::Other(_))));
+}
+
+#[test]
+fn requests_cloned() {
+    let server = TestServer::start().unwrap();
+    server.route("PUT", "/items/1", TestResponse::new(204));
+    let client = Client::builder().no_proxy().build();
+    let request = client
+        .put(&server.url("/items/1"))
+        .unwrap()
+        .header("X-Attempt", "same")
+        .body(b"item");
+    let copy = request.try_clone().unwrap();
+    request.send().unwrap();
+    copy.send().unwrap();
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0], requests[1]);
+
+    // A stream can only be sent once
+    let streamed = client
+        .put(&server.url("/items/1"))
+        .unwrap()
+        .body_reader(Cursor::new(b"item".to_vec()), Some(4));
+    assert!(streamed.try_clone().is_none());
+}
+
+#[test]
+fn headers_added_and_removed() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "ok"));
+    let client = Client::builder()
+        .no_proxy()
+        .default_header("X-Tenant", "blue")
+        .build();
+    client
+        .get(&server.url("/"))
+        .unwrap()
+        .add_header("Accept", "text/html")
+        .add_header("Accept", "text/plain")
+        .add_header("Cookie", "a=1")
+        .add_header("Cookie", "b=2")
+        .header("X-Replaced", "first")
+        .header("X-Replaced", "second")
+        .remove_header("X-Tenant")
+        .remove_header("user-agent")
+        .send()
+        .unwrap();
+
+    let received = &server.requests()[0];
+    assert_eq!(received.header("Accept"), Some("text/html, text/plain"));
+    assert_eq!(received.header("Cookie"), Some("a=1; b=2"));
+    assert_eq!(received.header("X-Replaced"), Some("second"));
+    // Headers set by the client can be removed too
+    assert_eq!(received.header("X-Tenant"), None);
+    assert_eq!(received.header("User-Agent"), None);
+}
+
+#[test]
+fn requests_inspected() {
+    let client = Client::builder().no_proxy().build();
+    let request = client
+        .post("http://example.com/items")
+        .unwrap()
+        .query("page", 2)
+        .unwrap()
+        .header("X-Trace", "on")
+        .body(b"item");
+    assert_eq!(request.url().to_string(), "http://example.com/items?page=2");
+    assert_eq!(request.method(), "POST");
+    assert!(request
+        .headers()
+        .any(|header| header.name() == "X-Trace" && header.value::<String>().unwrap() == "on"));
+    assert_eq!(request.body_bytes(), Some(&b"item"[..]));
+
+    let custom = client.request("PROPFIND", "http://example.com/").unwrap();
+    assert_eq!(custom.method(), "PROPFIND");
+    let streamed = client
+        .post("http://example.com/items")
+        .unwrap()
+        .body_reader(Cursor::new(b"item".to_vec()), None);
+    assert_eq!(streamed.body_bytes(), None);
+}
+
+#[test]
+fn default_query() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/weather", TestResponse::text(200, "sunny"));
+    let client = Client::builder()
+        .no_proxy()
+        .default_query("api_key", "s3cret key")
+        .default_query("format", "json")
+        .build();
+    client
+        .get(&server.url("/weather?city=Paris"))
+        .unwrap()
+        .send()
+        .unwrap();
+    client.get(&server.url("/weather")).unwrap().send().unwrap();
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].target,
+        "/weather?city=Paris&api_key=s3cret%20key&format=json"
+    );
+    assert_eq!(
+        requests[1].target,
+        "/weather?api_key=s3cret%20key&format=json"
+    );
+}
+
+#[test]
+fn default_authentication() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "ok"));
+    let basic = Client::builder()
+        .no_proxy()
+        .basic_auth("user", Some("pass"))
+        .build();
+    basic.get(&server.url("/")).unwrap().send().unwrap();
+    basic
+        .get(&server.url("/"))
+        .unwrap()
+        .header("Authorization", "ApiKey 42")
+        .send()
+        .unwrap();
+    basic
+        .get(&server.url("/"))
+        .unwrap()
+        .no_auth()
+        .send()
+        .unwrap();
+    Client::builder()
+        .no_proxy()
+        .authorization("ApiKey 0123")
+        .build()
+        .get(&server.url("/"))
+        .unwrap()
+        .send()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].header("Authorization"),
+        Some("Basic dXNlcjpwYXNz")
+    );
+    assert_eq!(requests[1].header("Authorization"), Some("ApiKey 42"));
+    assert_eq!(requests[2].header("Authorization"), None);
+    assert_eq!(requests[3].header("Authorization"), Some("ApiKey 0123"));
+}
+
+#[test]
+fn caller_headers_kept() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "ok"));
+    server.route("POST", "/", TestResponse::text(200, "ok"));
+    let login = TestResponse::new(200).header("Set-Cookie", "session=1");
+    server.route("GET", "/login", login);
+    let client = Client::builder()
+        .no_proxy()
+        .no_user_agent()
+        .cookie_store(true)
+        .build();
+    client.get(&server.url("/login")).unwrap().send().unwrap();
+    client
+        .get(&server.url("/"))
+        .unwrap()
+        .header("User-Agent", "caller/2.0")
+        .header("Cookie", "mine=2")
+        .send()
+        .unwrap();
+    client
+        .post(&server.url("/"))
+        .unwrap()
+        .header(
+            "Content-Type",
+            "application/x-www-form-urlencoded; charset=latin1",
+        )
+        .form_data("name", "value")
+        .send()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("User-Agent"), None);
+    assert_eq!(requests[1].header("User-Agent"), Some("caller/2.0"));
+    // The cookies of the jar do not replace the ones of the caller
+    assert_eq!(requests[1].header("Cookie"), Some("mine=2"));
+    assert_eq!(
+        requests[2].header("Content-Type"),
+        Some("application/x-www-form-urlencoded; charset=latin1")
+    );
+}
+
+#[test]
+fn response_size_limits() {
+    let server = TestServer::start().unwrap();
+    let body = "x".repeat(100);
+    server.route("GET", "/body", TestResponse::text(200, &body));
+    let large_head = TestResponse::text(200, "ok").header("X-Padding", &"p".repeat(200));
+    server.route("GET", "/head", large_head);
+    let client = Client::builder()
+        .no_proxy()
+        .max_header_size(150)
+        .max_body_size(10)
+        .build();
+
+    let result = client.get(&server.url("/body")).unwrap().send();
+    assert!(matches!(result.err(), Some(HttpError::TooLarge(_))));
+    let result = client.get(&server.url("/head")).unwrap().send();
+    assert!(matches!(result.err(), Some(HttpError::TooLarge(_))));
+
+    // A body of the size of the limit is accepted
+    let response = client
+        .get(&server.url("/body"))
+        .unwrap()
+        .max_body_size(100)
+        .send()
+        .unwrap();
+    assert_eq!(response.text(), body);
+    // Streamed bodies are never buffered, nor limited
+    let mut response = client
+        .get(&server.url("/body"))
+        .unwrap()
+        .send_streaming()
+        .unwrap();
+    let mut streamed = Vec::new();
+    response.copy_to(&mut streamed).unwrap();
+    assert_eq!(streamed.len(), 100);
+}
+
+#[test]
+fn files_uploaded() {
+    let server = TestServer::start().unwrap();
+    server.route("PUT", "/upload", TestResponse::new(201));
+    let dir = std::env::temp_dir().join(format!("put-file-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("data.json");
+    std::fs::write(&path, "{\"id\": 1}").unwrap();
+    let client = Client::builder().no_proxy().build();
+    client
+        .put(&server.url("/upload"))
+        .unwrap()
+        .put_file(&path)
+        .unwrap()
+        .send()
+        .unwrap();
+    client
+        .put(&server.url("/upload"))
+        .unwrap()
+        .header("Content-Type", "application/vnd.api+json")
+        .put_file(&path)
+        .unwrap()
+        .send()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].body, b"{\"id\": 1}");
+    assert_eq!(requests[0].header("Content-Length"), Some("9"));
+    assert_eq!(requests[0].header("Content-Type"), Some("application/json"));
+    // A type set by the caller is kept
+    assert_eq!(
+        requests[1].header("Content-Type"),
+        Some("application/vnd.api+json")
+    );
+
+    let missing = client
+        .put(&server.url("/upload"))
+        .unwrap()
+        .put_file(dir.join("missing.json"));
+    assert!(missing.is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn requests_sent_concurrently() {
+    let server = TestServer::start().unwrap();
+    for id in 0..4 {
+        let item = TestResponse::text(200, &id.to_string()).delay(Duration::from_millis(200));
+        server.route("GET", &format!("/items/{id}"), item);
+    }
+    let client = Client::builder().no_proxy().build();
+    let mut requests = (0..4)
+        .map(|id| client.get(&server.url(&format!("/items/{id}"))).unwrap())
+        .collect::<Vec<_>>();
+    requests.push(client.get(&server.url("/missing")).unwrap());
+
+    let started = Instant::now();
+    let results = client.send_all(requests, 2);
+    let elapsed = started.elapsed();
+    let bodies = results[..4]
+        .iter()
+        .map(|result| result.as_ref().unwrap().text())
+        .collect::<Vec<_>>();
+    // Results come in the order of the requests
+    assert_eq!(bodies, ["0", "1", "2", "3"]);
+    assert_eq!(results[4].as_ref().unwrap().status_code(), 404);
+    // Two requests at a time
+    assert!(elapsed >= Duration::from_millis(400));
+    assert!(elapsed < Duration::from_millis(800));
+
+    assert!(client
+        .send_all(Vec::<ClientRequest<NoBody>>::new(), 0)
+        .is_empty());
+    let single = client.get(&server.url("/items/0")).unwrap();
+    assert_eq!(
+        client.send_all([single], 0)[0].as_ref().unwrap().text(),
+        "0"
+    );
+}
+
+#[test]
+fn system_proxy_opt_in() {
+    assert_eq!(Client::new().config.proxy, None);
+    let client = Client::builder().system_proxy().build();
+    assert_eq!(client.config.proxy, Proxy::system());
+}
+
+#[test]
+fn ranges_checked() {
+    let server = TestServer::start().unwrap();
+    server.route(
+        "GET",
+        "/ok",
+        TestResponse::text(206, "ello").header("Content-Range", "bytes 1-4/5"),
+    );
+    // The length of the largest range does not fit in a u64
+    server.route(
+        "GET",
+        "/huge",
+        TestResponse::text(206, "hello").header("Content-Range", "bytes 0-18446744073709551615/*"),
+    );
+    let client = Client::builder().no_proxy().build();
+    let data = client.get(&server.url("/ok")).unwrap().download_range(1..5);
+    assert_eq!(data.unwrap(), b"ello");
+    assert!(matches!(
+        client
+            .get(&server.url("/huge"))
+            .unwrap()
+            .download_range(0..),
+        Err(HttpError::BadResponse(206, _))
+    ));
+}
+
+#[test]
+fn identical_requests_coalesced() {
+    let server = TestServer::start().unwrap();
+    let delay = Duration::from_millis(300);
+    server.route("GET", "/shared", TestResponse::text(200, "ok").delay(delay));
+    server.route(
+        "GET",
+        "/private",
+        TestResponse::text(200, "mine")
+            .header("Cache-Control", "private")
+            .delay(delay),
+    );
+    // Request ids and trace contexts differ for every request
+    let client = Client::builder()
+        .no_proxy()
+        .coalesce(true)
+        .request_ids(true)
+        .trace_context(true)
+        .build();
+    let send_together = |path: &str| {
+        let barrier = std::sync::Barrier::new(3);
+        std::thread::scope(|scope| {
+            for _ in 0..3 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    client.get(&server.url(path)).unwrap().send().unwrap();
+                });
+            }
+        });
+    };
+    send_together("/shared");
+    send_together("/private");
+    let requests = server.requests();
+    let sent = |path: &str| {
+        requests
+            .iter()
+            .filter(|request| request.path() == path)
+            .count()
+    };
+    assert_eq!(sent("/shared"), 1);
+    assert_eq!(sent("/private"), 3);
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+use crate::error::HttpError;
+
+#[cfg(test)]
+mod tests;
+
+/// The state of a request being sent for several callers.
+#[derive(Debug, Default)]
+struct Flight {
+    /// The response in wire format once received, `None` inside when the request failed.
+    outcome: Mutex<Option<Option<Arc<Vec<u8>>>>>,
+    landed: Condvar,
+}
+
+/// Shares a single request between the callers sending identical requests at the same
+/// time, see [`crate::client::ClientBuilder::coalesce`].
+///
+/// The first caller sends the request, the others wait for its response and receive a
+/// copy of it. When the request fails or its response may not be shared every waiting caller
+/// sends its own.
+#[derive(Debug, Default)]
+pub(crate) struct Coalescer {
+    flights: Mutex<HashMap<String, Arc<Flight>>>,
+}
+
+impl Coalescer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the response to the request identified by `key` in wire format, from `send`
+    /// or from the same request sent by another caller, whether it was shared.
+    /// # Arguments
+    /// `key`       Identifies identical requests, e.g their URL and headers.
+    /// `deadline`  Time after which waiting for another caller fails.
+    /// `send`      Sends the request and returns its response in wire format, and whether
+    ///             it may be shared.
+    ///
+    /// # Errors
+    /// The errors of `send`, or [`HttpError::DeadlineExceeded`] when the deadline passed
+    /// while waiting.
+    pub(crate) fn run<F>(
+        &self,
+        key: &str,
+        deadline: Option<Instant>,
+        send: F,
+    ) -> Result<(Vec<u8>, bool), HttpError>
+    where
+        F: FnOnce() -> Result<(Vec<u8>, bool), HttpError>,
+    {
+        let (flight, leader) = {
+            let mut flights = self
+                .flights
+                .lock()
+                .map_err(|e| HttpError::Other(e.to_string()))?;
+            match flights.get(key) {
+                Some(flight) => (flight.clone(), false),
+                None => {
+                    let flight = Arc::new(Flight::default());
+                    flights.insert(key.to_owned(), flight.clone());
+                    (flight, true)
+                }
+            }
+        };
+        if !leader {
+            return match Self::wait(&flight, deadline)? {
+                Some(raw) => Ok((raw.to_vec(), true)),
+                // The error or private response went to the caller that sent the request
+                None => send().map(|(raw, _)| (raw, false)),
+            };
+        }
+        let mut landing = Landing {
+            coalescer: self,
+            key,
+            flight: &flight,
+            shared: None,
+        };
+        let result = send();
+        landing.shared = match &result {
+            Ok((raw, true)) => Some(Arc::new(raw.clone())),
+            _ => None,
+        };
+        drop(landing);
+        result.map(|(raw, _)| (raw, false))
+    }
+
+    /// Helper method, wait for the outcome of a request sent by another caller.
+    fn wait(flight: &Flight, deadline: Option<Instant>) -> Result<Option<Arc<Vec<u8>>>, HttpError> {
+        let mut outcome = flight
+            .outcome
+            .lock()
+            .map_err(|e| HttpError::Other(e.to_string()))?;
+        loop {
+            if let Some(outcome) = outcome.as_ref() {
+                return Ok(outcome.clone());
+            }
+            outcome = match deadline {
+                Some(deadline) => {
+                    let left = crate::connection::time_left(deadline)?;
+                    flight
+                        .landed
+                        .wait_timeout(outcome, left)
+                        .map_err(|e| HttpError::Other(e.to_string()))?
+                        .0
+                }
+                None => flight
+                    .landed
+                    .wait(outcome)
+                    .map_err(|e| HttpError::Other(e.to_string()))?,
+            };
+        }
+    }
+}
+
+/// Publishes the outcome of a request to the callers waiting for it when dropped, so they
+/// are woken up even if sending it panicked.
+struct Landing<'a> {
+    coalescer: &'a Coalescer,
+    key: &'a str,
+    flight: &'a Flight,
+    shared: Option<Arc<Vec<u8>>>,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut flights) = self.coalescer.flights.lock() {
+            flights.remove(self.key);
+        }
+        if let Ok(mut outcome) = self.flight.outcome.lock() {
+            *outcome = Some(self.shared.take());
+        }
+        self.flight.landed.notify_all();
+    }
+}
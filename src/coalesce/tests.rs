@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+use std::time::Duration;
+
+use crate::coalesce::Coalescer;
+use crate::error::HttpError;
+
+#[test]
+fn identical_requests_sent_once() {
+    let coalescer = Coalescer::new();
+    let sent = AtomicUsize::new(0);
+    let barrier = Barrier::new(4);
+    let results = thread::scope(|scope| {
+        let handles = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    coalescer.run("GET /a", None, || {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                        // Long enough for the other callers to join
+                        thread::sleep(Duration::from_millis(200));
+                        Ok((b"HTTP/1.1 200 OK\r\n\r\n".to_vec(), true))
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(sent.load(Ordering::SeqCst), 1);
+    assert_eq!(results.iter().filter(|(_, shared)| !shared).count(), 1);
+    assert!(results
+        .iter()
+        .all(|(raw, _)| raw == b"HTTP/1.1 200 OK\r\n\r\n"));
+}
+
+#[test]
+fn failures_not_shared() {
+    let coalescer = Coalescer::new();
+    let result = coalescer.run("GET /a", None, || {
+        Err(HttpError::ConnectionError("refused".to_owned()))
+    });
+    assert!(result.is_err());
+    // Nothing is left over for the next caller
+    let (raw, shared) = coalescer
+        .run("GET /a", None, || Ok((b"raw".to_vec(), true)))
+        .unwrap();
+    assert_eq!(raw, b"raw");
+    assert!(!shared);
+}
+
+#[test]
+fn private_responses_not_shared() {
+    let coalescer = Coalescer::new();
+    let sent = AtomicUsize::new(0);
+    let barrier = Barrier::new(3);
+    thread::scope(|scope| {
+        let handles = (0..3)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    coalescer.run("GET /me", None, || {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(200));
+                        Ok((b"HTTP/1.1 200 OK\r\n\r\n".to_vec(), false))
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert!(!handle.join().unwrap().unwrap().1);
+        }
+    });
+    // Every caller sent its own request
+    assert_eq!(sent.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rustls::client::danger::ServerCertVerifier;
+use rustls::client::WebPkiServerVerifier;
+#[cfg(feature = "native-roots")]
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::CertificateRevocationListDer;
+use rustls::ClientConfig;
+use rustls::RootCertStore;
+
+use crate::auth::CredentialProvider;
+use crate::breaker::CircuitBreaker;
+use crate::cache::HttpCache;
+use crate::client::LIB_USER_AGENT;
+use crate::coalesce::Coalescer;
+use crate::cookie::CookieJar;
+use crate::error::HttpError;
+use crate::fault::FaultInjection;
+use crate::har::HarReplay;
+use crate::hsts::HstsStore;
+use crate::metrics::Metrics;
+use crate::oauth::OAuth2;
+use crate::proxy::Proxy;
+use crate::redirect::RedirectCache;
+use crate::retry::RetryPolicy;
+use crate::tls::{NoCertificateVerification, RevocationPolicy};
+use crate::vcr::Cassette;
+
+#[cfg(test)]
+mod tests;
+
+/// Redirects followed before giving up, unless configured otherwise.
+const DEFAULT_REDIRECT_LIMIT: usize = 10;
+
+/// Bytes requested at once by ranged downloads, unless configured otherwise.
+const DEFAULT_BLOCK_SIZE: usize = 1_000_000;
+
+/// Attempts made to resume a download after it failed without progress, unless configured
+/// otherwise.
+const DEFAULT_DOWNLOAD_RETRIES: usize = 3;
+
+/// Largest response head accepted, unless configured otherwise.
+const DEFAULT_MAX_HEADER_SIZE: usize = 64 * 1024;
+
+#[cfg(feature = "native-roots")]
+static NATIVE_ROOTS: OnceLock<Vec<CertificateDer<'static>>> = OnceLock::new();
+
+/// A user provided extension stored in a configuration.
+pub(crate) struct Hook<T: ?Sized>(pub(crate) Arc<T>);
+
+impl<T: ?Sized> Clone for Hook<T> {
+    fn clone(&self) -> Self {
+        Hook(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> Debug for Hook<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Hook({})", std::any::type_name::<T>())
+    }
+}
+
+/// Sinks receiving the bytes exchanged with servers, see
+/// [`crate::client::ClientBuilder::wire_dump`].
+#[derive(Debug, Clone)]
+pub(crate) struct WireDump {
+    pub(crate) sent: Hook<Mutex<dyn Write + Send>>,
+    pub(crate) received: Hook<Mutex<dyn Write + Send>>,
+}
+
+impl WireDump {
+    /// Copy bytes written to the connection other than through [`crate::body::Tee`].
+    pub(crate) fn record_sent(&self, bytes: &[u8]) -> Result<(), HttpError> {
+        self.sent
+            .0
+            .lock()
+            .map_err(|e| HttpError::Other(e.to_string()))?
+            .write_all(bytes)?;
+        Ok(())
+    }
+}
+
+/// Settings shared by a client and every request it creates.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub(crate) proxy: Option<Proxy>,
+    pub(crate) resolve: HashMap<String, String>,
+    pub(crate) base_url: Option<String>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) default_headers: Vec<(String, String)>,
+    pub(crate) default_query: Vec<(String, String)>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) write_timeout: Option<Duration>,
+    pub(crate) max_header_size: usize,
+    pub(crate) max_body_size: Option<u64>,
+    pub(crate) download_block_size: usize,
+    pub(crate) ranged_downloads: bool,
+    pub(crate) download_retries: usize,
+    pub(crate) content_digest: bool,
+    pub(crate) authorization: Option<String>,
+    pub(crate) credential_provider: Option<Hook<dyn CredentialProvider>>,
+    pub(crate) oauth: Option<Arc<OAuth2>>,
+    pub(crate) cookie_jar: Option<Arc<CookieJar>>,
+    pub(crate) cache: Option<Arc<HttpCache>>,
+    pub(crate) coalescer: Option<Arc<Coalescer>>,
+    pub(crate) hsts: Option<Arc<HstsStore>>,
+    pub(crate) https_only: bool,
+    pub(crate) redirect_limit: usize,
+    pub(crate) retry: Option<RetryPolicy>,
+    pub(crate) circuit_breaker: Option<Arc<CircuitBreaker>>,
+    pub(crate) hedge_after: Option<Duration>,
+    pub(crate) idempotency_keys: bool,
+    pub(crate) trace_context: bool,
+    pub(crate) request_id: Option<Hook<dyn Fn() -> String + Send + Sync>>,
+    pub(crate) wire_dump: Option<WireDump>,
+    pub(crate) har_replay: Option<Arc<HarReplay>>,
+    pub(crate) cassette: Option<Arc<Cassette>>,
+    pub(crate) faults: Option<Arc<FaultInjection>>,
+    pub(crate) metrics: Arc<Metrics>,
+    pub(crate) deadline: Option<Duration>,
+    pub(crate) redirect_cache: Option<Arc<RedirectCache>>,
+    pub(crate) danger_accept_invalid_certs: bool,
+    pub(crate) tls_key_log: bool,
+    pub(crate) tls_handshake_timeout: Option<Duration>,
+    pub(crate) tls_server_names: HashMap<String, String>,
+    pub(crate) tls_verifier: Option<Arc<dyn ServerCertVerifier>>,
+    pub(crate) tls_early_data: bool,
+    pub(crate) tls_crls: Vec<CertificateRevocationListDer<'static>>,
+    pub(crate) tls_revocation: RevocationPolicy,
+    /// Built TLS settings, shared by the copies made for single requests and attempts.
+    tls: Arc<OnceLock<Arc<ClientConfig>>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            proxy: None,
+            resolve: HashMap::new(),
+            base_url: None,
+            user_agent: Some(LIB_USER_AGENT.to_owned()),
+            default_headers: Vec::new(),
+            default_query: Vec::new(),
+            connect_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_body_size: None,
+            download_block_size: DEFAULT_BLOCK_SIZE,
+            ranged_downloads: true,
+            download_retries: DEFAULT_DOWNLOAD_RETRIES,
+            content_digest: false,
+            authorization: None,
+            credential_provider: None,
+            oauth: None,
+            cookie_jar: None,
+            cache: None,
+            coalescer: None,
+            hsts: None,
+            https_only: false,
+            redirect_limit: DEFAULT_REDIRECT_LIMIT,
+            retry: None,
+            circuit_breaker: None,
+            hedge_after: None,
+            idempotency_keys: false,
+            trace_context: false,
+            request_id: None,
+            wire_dump: None,
+            har_replay: None,
+            cassette: None,
+            faults: None,
+            metrics: Arc::new(Metrics::default()),
+            deadline: None,
+            redirect_cache: None,
+            danger_accept_invalid_certs: false,
+            tls_key_log: false,
+            tls_handshake_timeout: None,
+            tls_server_names: HashMap::new(),
+            tls_verifier: None,
+            tls_early_data: false,
+            tls_crls: Vec::new(),
+            tls_revocation: RevocationPolicy::Ignore,
+            tls: Arc::new(OnceLock::new()),
+        }
+    }
+}
+
+impl Config {
+    /// A copy of these settings whose timeouts end within `left`, e.g the time left before
+    /// the deadline of a request.
+    ///
+    /// The copy shares the TLS settings, they are only built once.
+    pub(crate) fn bounded(&self, left: Duration) -> Config {
+        let bound = |timeout: Option<Duration>| Some(timeout.map_or(left, |t| t.min(left)));
+        let mut config = self.clone();
+        config.connect_timeout = bound(self.connect_timeout);
+        config.read_timeout = bound(self.read_timeout);
+        config.write_timeout = bound(self.write_timeout);
+        config.tls_handshake_timeout = bound(self.tls_handshake_timeout);
+        config
+    }
+
+    /// The address to connect to for the given `host:port`, honoring resolve overrides.
+    pub(crate) fn resolved(&self, address: String) -> String {
+        match self.resolve.get(&address.to_ascii_lowercase()) {
+            Some(ip) => {
+                let port = address.rsplit_once(':').map_or("", |(_, port)| port);
+                if ip.contains(':') {
+                    format!("[{ip}]:{port}")
+                } else {
+                    format!("{ip}:{port}")
+                }
+            }
+            None => address,
+        }
+    }
+
+    /// The name used for SNI and certificate validation when connecting to the given host.
+    pub(crate) fn tls_server_name<'a>(&'a self, host: &'a str) -> &'a str {
+        self.tls_server_names
+            .get(&host.to_ascii_lowercase())
+            .map_or(host, |name| name.as_str())
+    }
+
+    /// TLS settings of this client, built the first time they are needed.
+    ///
+    /// Copies of these settings share them, the fields they are built from are only set
+    /// by the [`crate::client::ClientBuilder`].
+    pub(crate) fn tls_settings(&self) -> Result<Arc<ClientConfig>, HttpError> {
+        if let Some(config) = self.tls.get() {
+            return Ok(config.clone());
+        }
+        let config = self.build_tls_settings()?;
+        Ok(self.tls.get_or_init(|| config).clone())
+    }
+
+    /// Helper method, build the TLS settings described by this configuration.
+    fn build_tls_settings(&self) -> Result<Arc<ClientConfig>, HttpError> {
+        let builder = rustls::ClientConfig::builder();
+        // A verifier deciding trust on its own would skip the revocation checks asked for
+        let custom = self.danger_accept_invalid_certs || self.tls_verifier.is_some();
+        if custom && self.tls_revocation != RevocationPolicy::Ignore {
+            return Err(HttpError::ConnectionError(
+                "A revocation policy cannot be combined with a custom certificate verifier or \
+                 accepting invalid certificates"
+                    .to_owned(),
+            ));
+        }
+        let verifier = if self.danger_accept_invalid_certs {
+            Some(Arc::new(NoCertificateVerification::new()) as Arc<dyn ServerCertVerifier>)
+        } else if self.tls_verifier.is_some() {
+            self.tls_verifier.clone()
+        } else if self.tls_revocation != RevocationPolicy::Ignore {
+            // Without CRLs the verifier checks nothing, every certificate would pass
+            if self.tls_revocation == RevocationPolicy::HardFail && self.tls_crls.is_empty() {
+                return Err(HttpError::ConnectionError(
+                    "The HardFail revocation policy needs at least one CRL".to_owned(),
+                ));
+            }
+            let mut verifier = WebPkiServerVerifier::builder(Arc::new(Self::root_store()))
+                .with_crls(self.tls_crls.iter().cloned());
+            verifier = match self.tls_revocation {
+                RevocationPolicy::SoftFail => verifier.allow_unknown_revocation_status(),
+                _ => verifier.enforce_revocation_expiration(),
+            };
+            let verifier = verifier
+                .build()
+                .map_err(|e| HttpError::ConnectionError(e.to_string()))?;
+            Some(verifier as Arc<dyn ServerCertVerifier>)
+        } else {
+            None
+        };
+        let mut config = if let Some(verifier) = verifier {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(verifier)
+                .with_no_client_auth()
+        } else {
+            builder
+                .with_root_certificates(Self::root_store())
+                .with_no_client_auth()
+        };
+        config.enable_early_data = self.tls_early_data;
+        if self.tls_key_log {
+            config.key_log = Arc::new(rustls::KeyLogFile::new());
+        }
+        Ok(Arc::new(config))
+    }
+
+    /// Helper method, the certificates trusted by default.
+    ///
+    /// With the `native-roots` feature the certificates from the operating system store are
+    /// trusted as well, they are loaded once per process.
+    fn root_store() -> RootCertStore {
+        #[allow(unused_mut)]
+        let mut root_store = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+        };
+        #[cfg(feature = "native-roots")]
+        root_store.add_parsable_certificates(
+            NATIVE_ROOTS
+                .get_or_init(|| rustls_native_certs::load_native_certs().certs)
+                .iter()
+                .cloned(),
+        );
+        root_store
+    }
+}
@@ -0,0 +1,91 @@
+use crate::client::Client;
+use crate::config::Config;
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn resolve_overrides() {
+    let mut config = Config::default();
+    config
+        .resolve
+        .insert("example.com:443".into(), "10.0.0.5".into());
+    config
+        .resolve
+        .insert("v6.example.com:80".into(), "2001:db8::1".into());
+    assert_eq!(config.resolved("EXAMPLE.com:443".into()), "10.0.0.5:443");
+    assert_eq!(
+        config.resolved("v6.example.com:80".into()),
+        "[2001:db8::1]:80"
+    );
+    // Other ports of the host are not overridden
+    assert_eq!(config.resolved("example.com:80".into()), "example.com:80");
+}
+
+#[test]
+fn resolved_host_keeps_its_name() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "overridden"));
+    let host = format!("api.example.test:{}", server.address().port());
+    let response = Client::builder()
+        .no_proxy()
+        .resolve(&host, "[127.0.0.1]")
+        .build()
+        .get(&format!("http://{host}/"))
+        .unwrap()
+        .send()
+        .unwrap();
+    assert_eq!(response.text(), "overridden");
+    assert_eq!(
+        server.requests()[0].header("Host"),
+        Some("api.example.test")
+    );
+}
+
+#[test]
+fn key_log_opt_in() {
+    let mut config = Config::default();
+    assert!(!config
+        .tls_settings()
+        .unwrap()
+        .key_log
+        .will_log("CLIENT_RANDOM"));
+    config = Config::default();
+    config.tls_key_log = true;
+    assert!(config
+        .tls_settings()
+        .unwrap()
+        .key_log
+        .will_log("CLIENT_RANDOM"));
+}
+
+#[test]
+fn root_certificates() {
+    let roots = Config::root_store();
+    assert!(roots.len() >= webpki_roots::TLS_SERVER_ROOTS.len());
+    // Loaded from the operating system once, on first use
+    #[cfg(feature = "native-roots")]
+    assert!(crate::config::NATIVE_ROOTS.get().is_some());
+}
+
+#[test]
+fn tls_server_names() {
+    let mut config = Config::default();
+    config
+        .tls_server_names
+        .insert("10.0.0.5".into(), "api.example.com".into());
+    assert_eq!(config.tls_server_name("10.0.0.5"), "api.example.com");
+    // Other hosts keep their own name
+    assert_eq!(config.tls_server_name("10.0.0.6"), "10.0.0.6");
+}
+
+#[test]
+fn early_data_opt_in() {
+    let early_data = |enabled| {
+        let config = Config {
+            tls_early_data: enabled,
+            ..Config::default()
+        };
+        config.tls_settings().unwrap().enable_early_data
+    };
+    assert!(!early_data(false));
+    assert!(early_data(true));
+}
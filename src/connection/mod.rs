@@ -0,0 +1,506 @@
+use std::io::{Cursor, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use http_parse::{HttpParser, HttpUrl};
+use rustls::pki_types::ServerName;
+use rustls::{ClientConnection, StreamOwned};
+
+use crate::auth::Challenge;
+use crate::body::Framing;
+use crate::cancel::CancelToken;
+use crate::client::{H_PROXY_AUTHENTICATE, H_PROXY_AUTHORIZATION};
+use crate::config::{Config, Hook};
+use crate::error::HttpError;
+use crate::proxy::ProxyServer;
+
+#[cfg(test)]
+mod tests;
+
+/// Delay before starting the next connection attempt, as recommended by RFC 8305.
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// An open connection to a remote server, either plain or secured with TLS.
+///
+/// Connections are never pooled: each request opens its own and it is closed once the
+/// response is read, so a request is never written to a connection the server already closed.
+pub(crate) enum Connection {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Connection {
+    /// The address of the remote end of the connection, i.e the proxy when one is used.
+    pub(crate) fn peer_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Connection::Plain(socket) => socket.peer_addr(),
+            Connection::Tls(stream) => stream.get_ref().peer_addr(),
+        }
+        .ok()
+    }
+
+    /// Open a connection to the given URL and write a request to it.
+    ///
+    /// When a proxy is configured for the URL the connection is made to the proxy instead,
+    /// HTTPS connections are tunneled through it using `CONNECT`.
+    ///
+    /// When TLS early data is enabled and a session is being resumed, `replayable` requests are
+    /// sent as TLS 1.3 early data. If the server rejects the early data the request is written
+    /// again once the handshake completes.
+    ///
+    /// Cancelling `cancel` aborts resolving the host, connecting, the TLS handshake and
+    /// writing the request.
+    pub(crate) fn send(
+        config: &Config,
+        url: &HttpUrl,
+        request: &[u8],
+        replayable: bool,
+        cancel: Option<&CancelToken>,
+    ) -> Result<Connection, HttpError> {
+        let early_data = (replayable && config.tls_early_data).then_some(request);
+        let send = || {
+            let (mut connection, accepted) = Self::establish(config, url, early_data, cancel)?;
+            if !accepted {
+                let _guard = cancel
+                    .map(|token| token.guard(connection.as_ref()))
+                    .transpose()?;
+                connection.write_all(request)?;
+            }
+            Ok(connection)
+        };
+        // A connection shut down by a cancellation fails with whatever error came first
+        send().map_err(|error| match cancel {
+            Some(token) if token.is_cancelled() => HttpError::Cancelled,
+            _ => error,
+        })
+    }
+
+    /// Helper method, open a connection and report whether the early data was accepted.
+    fn establish(
+        config: &Config,
+        url: &HttpUrl,
+        early_data: Option<&[u8]>,
+        cancel: Option<&CancelToken>,
+    ) -> Result<(Connection, bool), HttpError> {
+        let secure = url.scheme().eq_ignore_ascii_case("https");
+        let proxy = config
+            .proxy
+            .as_ref()
+            .and_then(|proxy| proxy.server_for(url));
+        let mut socket = match proxy {
+            Some(proxy) if secure => Self::tunnel(config, proxy, url, cancel)?,
+            Some(proxy) => Self::connect(config, config.resolved(proxy.address()), cancel)?,
+            None => Self::connect(config, config.resolved(url.address().to_string()), cancel)?,
+        };
+        if !secure {
+            return Ok((Connection::Plain(socket), false));
+        }
+        let _guard = cancel.map(|token| token.guard(&socket)).transpose()?;
+        let name = config.tls_server_name(url.host()).to_owned();
+        let server_name =
+            ServerName::try_from(name).map_err(|_e| HttpError::InvalidUrl(url.to_string()))?;
+        let mut connection = ClientConnection::new(config.tls_settings()?, server_name)
+            .map_err(|e| HttpError::ConnectionError(e.to_string()))?;
+
+        let mut early_data_sent = false;
+        if let (Some(data), Some(mut early)) = (early_data, connection.early_data()) {
+            if early.bytes_left() >= data.len() {
+                early.write_all(data)?;
+                early_data_sent = true;
+            }
+        }
+        if early_data_sent || config.tls_handshake_timeout.is_some() {
+            Self::handshake(config, &mut connection, &mut socket)?;
+        }
+        let accepted = early_data_sent && connection.is_early_data_accepted();
+        Ok((
+            Connection::Tls(Box::new(StreamOwned::new(connection, socket))),
+            accepted,
+        ))
+    }
+
+    /// Helper method, complete the TLS handshake, failing if it takes longer than `timeout`.
+    fn handshake(
+        config: &Config,
+        connection: &mut ClientConnection,
+        socket: &mut TcpStream,
+    ) -> Result<(), HttpError> {
+        let Some(timeout) = config.tls_handshake_timeout else {
+            while connection.is_handshaking() {
+                connection
+                    .complete_io(socket)
+                    .map_err(|e| HttpError::ConnectionError(e.to_string()))?;
+            }
+            return Ok(());
+        };
+        let deadline = Instant::now() + timeout;
+        let timed_out = || HttpError::ConnectionError("TLS handshake timed out".to_owned());
+        while connection.is_handshaking() {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| !remaining.is_zero())
+                .ok_or_else(timed_out)?;
+            socket.set_read_timeout(Some(remaining))?;
+            socket.set_write_timeout(Some(remaining))?;
+            match connection.complete_io(socket) {
+                Ok(_) => (),
+                Err(e)
+                    if e.kind() == std::io::ErrorKind::WouldBlock
+                        || e.kind() == std::io::ErrorKind::TimedOut =>
+                {
+                    return Err(timed_out())
+                }
+                Err(e) => return Err(HttpError::ConnectionError(e.to_string())),
+            }
+        }
+        socket.set_read_timeout(config.read_timeout)?;
+        socket.set_write_timeout(config.write_timeout)?;
+        Ok(())
+    }
+
+    /// Helper method, connect to a host and apply the read and write timeouts of the client.
+    ///
+    /// With a cancel token the host is resolved and connected to on a helper thread, which
+    /// is no longer waited for once the token is cancelled.
+    pub(crate) fn connect(
+        config: &Config,
+        address: String,
+        cancel: Option<&CancelToken>,
+    ) -> Result<TcpStream, HttpError> {
+        let timeout = config.connect_timeout;
+        let socket = match cancel {
+            Some(token) => token.wait_for(move || Self::connect_any(timeout, address))??,
+            None => Self::connect_any(timeout, address)?,
+        };
+        socket.set_read_timeout(config.read_timeout)?;
+        socket.set_write_timeout(config.write_timeout)?;
+        Ok(socket)
+    }
+
+    /// Helper method, connect to the first reachable address of a host.
+    ///
+    /// Addresses are tried in the order described by RFC 8305 (Happy Eyeballs): IPv6 and IPv4
+    /// addresses are interleaved and a new attempt is started whenever the previous one fails
+    /// or does not complete within [`CONNECTION_ATTEMPT_DELAY`]. The first attempt to succeed wins.
+    fn connect_any<A: ToSocketAddrs>(
+        timeout: Option<Duration>,
+        address: A,
+    ) -> Result<TcpStream, HttpError> {
+        let addresses = interleave(address.to_socket_addrs()?.collect());
+        if let [address] = addresses[..] {
+            return Ok(connect_to(address, timeout)?);
+        }
+        if addresses.is_empty() {
+            return Ok(TcpStream::connect(&addresses[..])?);
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut pending = 0;
+        let mut last_error = None;
+        for address in addresses {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                // The receiver is gone once another attempt won, the stream is simply dropped.
+                let _ = sender.send(connect_to(address, timeout));
+            });
+            pending += 1;
+            match receiver.recv_timeout(CONNECTION_ATTEMPT_DELAY) {
+                Ok(Ok(stream)) => return Ok(stream),
+                Ok(Err(e)) => {
+                    pending -= 1;
+                    last_error = Some(e);
+                }
+                Err(_) => (),
+            }
+        }
+        while pending > 0 {
+            match receiver.recv() {
+                Ok(Ok(stream)) => return Ok(stream),
+                Ok(Err(e)) => {
+                    pending -= 1;
+                    last_error = Some(e);
+                }
+                Err(_) => break,
+            }
+        }
+        Err(last_error
+            .map(HttpError::from)
+            .unwrap_or_else(|| HttpError::ConnectionError("No address could be reached".into())))
+    }
+
+    /// Helper method, ask a proxy to open a tunnel to the given URL.
+    ///
+    /// A `407` response is answered once with the credential provider of the client,
+    /// if there is one, using a new connection to the proxy.
+    fn tunnel(
+        config: &Config,
+        proxy: &ProxyServer,
+        url: &HttpUrl,
+        cancel: Option<&CancelToken>,
+    ) -> Result<TcpStream, HttpError> {
+        let address = url.address().to_string();
+        let mut authorization = proxy.authorization.clone();
+        let mut answered = false;
+        loop {
+            let mut socket = Self::connect(config, config.resolved(proxy.address()), cancel)?;
+            let _guard = cancel.map(|token| token.guard(&socket)).transpose()?;
+            let mut request = format!("CONNECT {address} HTTP/1.1\r\nHost: {address}\r\n");
+            if let Some(authorization) = &authorization {
+                request.push_str(&format!("{H_PROXY_AUTHORIZATION}: {authorization}\r\n"));
+            }
+            request.push_str("\r\n");
+            socket.write_all(request.as_bytes())?;
+
+            let head = read_head(&mut socket, config.max_header_size)?;
+            let response = HttpParser::from_reader(&mut Cursor::new(head)).response_head_only()?;
+            match response.status_code() {
+                200..=299 => return Ok(socket),
+                407 if !answered => {
+                    answered = true;
+                    let unanswered =
+                        || HttpError::BadResponse(407, response.status_msg().to_owned());
+                    let Some(Hook(provider)) = &config.credential_provider else {
+                        return Err(unanswered());
+                    };
+                    let challenges = response
+                        .header(H_PROXY_AUTHENTICATE)
+                        .map(|header| Challenge::parse(&header.value::<String>().unwrap())) // save to unwrap, a str can always turn into String
+                        .unwrap_or_default();
+                    let credentials = provider
+                        .credentials(url, &challenges, true)
+                        .ok_or_else(unanswered)?;
+                    authorization =
+                        Some(credentials.authorization(&challenges, "CONNECT", &address)?);
+                }
+                status => {
+                    return Err(HttpError::ConnectionError(format!(
+                        "Proxy refused tunnel to `{address}`: {status} {}",
+                        response.status_msg()
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// The proxy forwarding plain HTTP requests to the given URL, if any.
+///
+/// Requests forwarded by a proxy must use the absolute URL as request target.
+pub(crate) fn forwarding_proxy<'a>(config: &'a Config, url: &HttpUrl) -> Option<&'a ProxyServer> {
+    if url.scheme().eq_ignore_ascii_case("https") {
+        return None;
+    }
+    config
+        .proxy
+        .as_ref()
+        .and_then(|proxy| proxy.server_for(url))
+}
+/// Connect to a single address, giving up after `timeout` if there is one.
+fn connect_to(address: SocketAddr, timeout: Option<Duration>) -> std::io::Result<TcpStream> {
+    match timeout {
+        Some(timeout) => TcpStream::connect_timeout(&address, timeout),
+        None => TcpStream::connect(address),
+    }
+}
+
+/// Order resolved addresses alternating between IPv6 and IPv4, starting with IPv6.
+fn interleave(addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let (ipv6, ipv4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addresses.into_iter().partition(|address| address.is_ipv6());
+    let mut ipv6 = ipv6.into_iter();
+    let mut ipv4 = ipv4.into_iter();
+    let mut result = Vec::new();
+    loop {
+        match (ipv6.next(), ipv4.next()) {
+            (None, None) => break,
+            (first, second) => result.extend(first.into_iter().chain(second)),
+        }
+    }
+    result
+}
+
+/// Read a response head, up to and including the empty line that ends it.
+///
+/// Bytes are read one at a time so that nothing past the head is consumed.
+/// # Arguments
+/// `reader`    Connection positioned at the start of a response.
+/// `max_size`  Largest head accepted, in bytes.
+pub(crate) fn read_head<R: Read>(reader: &mut R, max_size: usize) -> Result<Vec<u8>, HttpError> {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= max_size {
+            return Err(HttpError::TooLarge(format!(
+                "response head exceeds {max_size} bytes"
+            )));
+        }
+        if reader.read(&mut byte)? == 0 {
+            return Err(HttpError::ConnectionError(
+                "Connection closed while reading response head".to_owned(),
+            ));
+        }
+        head.push(byte[0]);
+    }
+    Ok(head)
+}
+
+/// A response in wire format with its body already decoded, handed to the parser again,
+/// a chunked body as a single chunk.
+pub(crate) fn decoded_message(head: &[u8], framing: Framing, data: &[u8]) -> Vec<u8> {
+    let mut message = head.to_vec();
+    match framing {
+        Framing::Chunked if !data.is_empty() => {
+            message.extend_from_slice(format!("{:x}\r\n", data.len()).as_bytes());
+            message.extend_from_slice(data);
+            message.extend_from_slice(b"\r\n0\r\n\r\n");
+        }
+        Framing::Chunked => message.extend_from_slice(b"0\r\n\r\n"),
+        _ => message.extend_from_slice(data),
+    }
+    message
+}
+
+impl AsRef<TcpStream> for Connection {
+    fn as_ref(&self) -> &TcpStream {
+        match self {
+            Connection::Plain(socket) => socket,
+            Connection::Tls(stream) => stream.get_ref(),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            Connection::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            Connection::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            Connection::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// The head of a response in wire format, up to and including the empty line that ends it.
+pub(crate) fn head_of(raw: &[u8]) -> &[u8] {
+    raw.windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map_or(raw, |end| &raw[..end + 4])
+}
+
+/// Every value of the header with the given name in a response head, in order.
+///
+/// Unlike [`http_parse::HttpResponse::header`] this returns headers sent more than once,
+/// such as `Set-Cookie`.
+pub(crate) fn head_values(head: &[u8], name: &str) -> Vec<String> {
+    String::from_utf8_lossy(head)
+        .split("\r\n")
+        // Skip the status line
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(header, _)| header.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_owned())
+        .collect()
+}
+
+/// A connection failing with [`HttpError::DeadlineExceeded`] once its deadline passed, each
+/// read and write given no more than the time left.
+pub(crate) struct Bounded<S> {
+    stream: S,
+    deadline: Option<Instant>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+}
+
+impl<S: AsRef<TcpStream>> Bounded<S> {
+    /// Wrap a connection opened with the given settings, left alone without a deadline.
+    pub(crate) fn new(stream: S, deadline: Option<Instant>, config: &Config) -> Self {
+        Bounded {
+            stream,
+            deadline,
+            read_timeout: config.read_timeout,
+            write_timeout: config.write_timeout,
+        }
+    }
+
+    /// Helper method, the timeout for the next operation, the time left when it is shorter
+    /// than `timeout`.
+    fn timeout(&self, timeout: Option<Duration>) -> std::io::Result<Option<Duration>> {
+        let Some(deadline) = self.deadline else {
+            return Ok(timeout);
+        };
+        let left = time_left(deadline).map_err(std::io::Error::other)?;
+        Ok(Some(timeout.map_or(left, |timeout| timeout.min(left))))
+    }
+
+    /// Helper method, the error to report, [`HttpError::DeadlineExceeded`] when an operation
+    /// timed out because of the deadline.
+    fn error(&self, error: std::io::Error) -> std::io::Error {
+        let timed_out = matches!(
+            error.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        );
+        match self.deadline {
+            Some(deadline) if timed_out => time_left(deadline)
+                .err()
+                .map_or(error, std::io::Error::other),
+            _ => error,
+        }
+    }
+}
+
+impl<S: AsRef<TcpStream> + Read> Read for Bounded<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.deadline.is_some() {
+            let timeout = self.timeout(self.read_timeout)?;
+            self.stream.as_ref().set_read_timeout(timeout)?;
+        }
+        self.stream.read(buf).map_err(|e| self.error(e))
+    }
+}
+
+impl<S: AsRef<TcpStream> + Write> Write for Bounded<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.deadline.is_some() {
+            let timeout = self.timeout(self.write_timeout)?;
+            self.stream.as_ref().set_write_timeout(timeout)?;
+        }
+        self.stream.write(buf).map_err(|e| self.error(e))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush().map_err(|e| self.error(e))
+    }
+}
+
+impl<S: AsRef<TcpStream>> AsRef<TcpStream> for Bounded<S> {
+    fn as_ref(&self) -> &TcpStream {
+        self.stream.as_ref()
+    }
+}
+
+/// The time left before a deadline.
+///
+/// # Errors
+/// [`HttpError::DeadlineExceeded`] once the deadline passed.
+pub(crate) fn time_left(deadline: Instant) -> Result<Duration, HttpError> {
+    deadline
+        .checked_duration_since(Instant::now())
+        .filter(|left| !left.is_zero())
+        .ok_or(HttpError::DeadlineExceeded)
+}
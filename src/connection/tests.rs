@@ -0,0 +1,85 @@
+use std::net::{SocketAddr, TcpListener};
+use std::time::{Duration, Instant};
+
+use crate::client::Client;
+use crate::connection::{head_values, interleave, Connection};
+use crate::error::HttpError;
+
+/// An address of the loopback interface nothing listens on.
+fn closed_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[test]
+fn addresses_interleaved() {
+    let address = |address: &str| address.parse::<SocketAddr>().unwrap();
+    let addresses = vec![
+        address("10.0.0.1:80"),
+        address("10.0.0.2:80"),
+        address("10.0.0.3:80"),
+        address("[2001:db8::1]:80"),
+        address("[2001:db8::2]:80"),
+    ];
+    assert_eq!(
+        interleave(addresses),
+        [
+            address("[2001:db8::1]:80"),
+            address("10.0.0.1:80"),
+            address("[2001:db8::2]:80"),
+            address("10.0.0.2:80"),
+            address("10.0.0.3:80"),
+        ]
+    );
+    assert!(interleave(Vec::new()).is_empty());
+}
+
+#[test]
+fn first_reachable_address_wins() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let reachable = listener.local_addr().unwrap();
+    let addresses = [closed_address(), reachable];
+    let socket = Connection::connect_any(None, &addresses[..]).unwrap();
+    assert_eq!(socket.peer_addr().unwrap(), reachable);
+
+    let unreachable = [closed_address(), closed_address()];
+    assert!(Connection::connect_any(None, &unreachable[..]).is_err());
+    let none: [SocketAddr; 0] = [];
+    assert!(Connection::connect_any(None, &none[..]).is_err());
+}
+
+#[test]
+fn tls_handshake_timeout() {
+    // Connections are queued by the OS but the handshake is never answered
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}/", listener.local_addr().unwrap());
+    let client = Client::builder()
+        .no_proxy()
+        .tls_handshake_timeout(Duration::from_millis(100))
+        .build();
+    let start = Instant::now();
+    match client.get(&url).unwrap().send().err() {
+        Some(HttpError::ConnectionError(message)) => {
+            assert_eq!(message, "TLS handshake timed out")
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn repeated_header_values() {
+    let head = b"HTTP/1.1 200 OK\r\n\
+        Set-Cookie: a=1\r\n\
+        Content-Type: text/plain\r\n\
+        set-cookie: b=2; Path=/\r\n\
+        SET-COOKIE:c=3\r\n\r\n";
+    assert_eq!(
+        head_values(head, "Set-Cookie"),
+        ["a=1", "b=2; Path=/", "c=3"]
+    );
+    assert_eq!(head_values(head, "Content-Type"), ["text/plain"]);
+    assert!(head_values(head, "Location").is_empty());
+}
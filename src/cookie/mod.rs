@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http_parse::HttpUrl;
+
+use crate::date::parse_http_date;
+
+#[cfg(test)]
+mod tests;
+
+/// Latest expiry of a cookie, `9999-12-31T23:59:59Z` after the Unix epoch.
+const LATEST_EXPIRY: Duration = Duration::from_secs(253_402_300_799);
+
+/// A cookie received in a `Set-Cookie` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    path: String,
+    expires: Option<SystemTime>,
+    secure: bool,
+    http_only: bool,
+    host_only: bool,
+}
+
+impl Cookie {
+    /// Parse a `Set-Cookie` value received from `host` in response to a request for `request_path`.
+    ///
+    /// Returns `None` when the cookie is malformed or must be rejected, e.g when its `Domain`
+    /// does not cover `host` or is a public suffix.
+    pub(crate) fn parse(header: &str, host: &str, request_path: &str) -> Option<Cookie> {
+        let host = host.to_ascii_lowercase();
+        let mut attributes = header.split(';');
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let mut cookie = Cookie {
+            name: name.to_owned(),
+            value: value.trim().trim_matches('"').to_owned(),
+            domain: host.clone(),
+            path: default_path(request_path),
+            expires: None,
+            secure: false,
+            http_only: false,
+            host_only: true,
+        };
+
+        let mut max_age = None;
+        let mut expires = None;
+        for attribute in attributes {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if is_public_suffix(&domain) {
+                        // A public suffix can only be set by that exact host, as a host only cookie
+                        if domain != host {
+                            return None;
+                        }
+                    } else if !domain_match(&host, &domain) {
+                        return None;
+                    } else {
+                        cookie.domain = domain;
+                        cookie.host_only = false;
+                    }
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_owned(),
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "expires" => expires = parse_http_date(value),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => (),
+            }
+        }
+        cookie.expires = match max_age {
+            Some(seconds) if seconds <= 0 => Some(UNIX_EPOCH),
+            // Larger ages, possibly too large to represent, expire as late as any cookie can
+            Some(seconds) => Some(
+                SystemTime::now()
+                    .checked_add(Duration::from_secs(seconds as u64))
+                    .map_or(UNIX_EPOCH + LATEST_EXPIRY, |expires| {
+                        expires.min(UNIX_EPOCH + LATEST_EXPIRY)
+                    }),
+            ),
+            None => expires,
+        };
+        Some(cookie)
+    }
+
+    /// Name of this cookie.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Value of this cookie.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Domain this cookie is sent to.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Path prefix this cookie is sent to.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// When this cookie expires, `None` for session cookies.
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    /// Whether this cookie is only sent over HTTPS.
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Whether this cookie is hidden from scripts.
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    /// Whether this cookie expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| expires <= SystemTime::now())
+    }
+
+    /// Whether this cookie must be sent with a request to the given host and path.
+    pub(crate) fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let host = host.to_ascii_lowercase();
+        let host_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+        host_matches && path_match(path, &self.path) && (secure || !self.secure)
+    }
+}
+
+/// Cookies stored across requests.
+///
+/// Cookies set by responses are sent back with later requests to matching URLs.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::builder().cookie_store(true).build();
+///     client
+///         .post("https://example.com/login")?
+///         .form_data("user", "me")
+///         .send()?;
+///     client.get("https://example.com/profile")?.send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Mutex<Vec<Cookie>>,
+}
+
+impl CookieJar {
+    /// Create an empty jar.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the cookie set by a `Set-Cookie` header received from the given URL.
+    pub fn set_cookie(&self, header: &str, url: &HttpUrl) {
+        self.store(header, url.host(), url.path());
+    }
+
+    /// The `Cookie` header value to be sent with a request to the given URL, if any.
+    pub fn cookie_header(&self, url: &HttpUrl) -> Option<String> {
+        let secure = url.scheme().eq_ignore_ascii_case("https");
+        self.header_for(url.host(), url.path(), secure)
+    }
+
+    /// Every cookie currently stored.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.cookies
+            .lock()
+            .map(|cookies| cookies.clone())
+            .unwrap_or_default()
+    }
+
+    /// Remove every cookie.
+    pub fn clear(&self) {
+        if let Ok(mut cookies) = self.cookies.lock() {
+            cookies.clear();
+        }
+    }
+
+    /// Helper method, store a cookie received from the given host.
+    pub(crate) fn store(&self, header: &str, host: &str, request_path: &str) {
+        let Some(cookie) = Cookie::parse(header, host, request_path) else {
+            return;
+        };
+        let Ok(mut cookies) = self.cookies.lock() else {
+            return;
+        };
+        cookies.retain(|stored| {
+            (stored.name != cookie.name
+                || stored.domain != cookie.domain
+                || stored.path != cookie.path)
+                && !stored.is_expired()
+        });
+        if !cookie.is_expired() {
+            cookies.push(cookie);
+        }
+    }
+
+    /// Helper method, the `Cookie` header value for the given host and path.
+    pub(crate) fn header_for(&self, host: &str, path: &str, secure: bool) -> Option<String> {
+        let cookies = self.cookies.lock().ok()?;
+        let mut matching = cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired() && cookie.matches(host, path, secure))
+            .collect::<Vec<&Cookie>>();
+        if matching.is_empty() {
+            return None;
+        }
+        // Cookies with longer paths are listed first
+        matching.sort_by_key(|cookie| Reverse(cookie.path.len()));
+        Some(
+            matching
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<String>>()
+                .join("; "),
+        )
+    }
+}
+
+/// Whether cookies can be set for the given domain only by that exact host.
+///
+/// With the `psl` feature the Public Suffix List is consulted, so that e.g `evil.github.io` cannot
+/// set cookies for `github.io`. Otherwise only top level domains are treated as public suffixes.
+#[cfg(feature = "psl")]
+fn is_public_suffix(domain: &str) -> bool {
+    psl::suffix_str(domain) == Some(domain)
+}
+
+/// Whether cookies can be set for the given domain only by that exact host.
+///
+/// With the `psl` feature the Public Suffix List is consulted, so that e.g `evil.github.io` cannot
+/// set cookies for `github.io`. Otherwise only top level domains are treated as public suffixes.
+#[cfg(not(feature = "psl"))]
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.')
+}
+
+/// Whether `host` is `domain` or one of its subdomains, as defined by RFC 6265.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<std::net::IpAddr>().is_err())
+}
+
+/// Whether `path` is covered by the cookie path `cookie_path`, as defined by RFC 6265.
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The default cookie path for a request path, its directory.
+fn default_path(request_path: &str) -> String {
+    let path = request_path.split(['?', '#']).next().unwrap_or_default();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(index) => path[..index].to_owned(),
+    }
+}
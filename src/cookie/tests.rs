@@ -0,0 +1,74 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cookie::{Cookie, CookieJar};
+
+#[test]
+fn parse_set_cookie() {
+    let cookie = Cookie::parse(
+        "id=a3fWa; Domain=.Example.com; Path=/docs; Secure; HttpOnly",
+        "www.example.com",
+        "/index.html",
+    )
+    .expect("Cookie should be accepted");
+    assert_eq!(cookie.name(), "id");
+    assert_eq!(cookie.value(), "a3fWa");
+    assert_eq!(cookie.domain(), "example.com");
+    assert_eq!(cookie.path(), "/docs");
+    assert!(cookie.secure() && cookie.http_only());
+
+    let cookie = Cookie::parse("lang=en", "example.com", "/docs/page").unwrap();
+    assert_eq!(cookie.path(), "/docs");
+    assert!(cookie.matches("example.com", "/docs/other", false));
+    assert!(!cookie.matches("www.example.com", "/docs", false));
+    assert!(!cookie.matches("example.com", "/documents", false));
+}
+
+#[test]
+fn max_age_expiry() {
+    let cookie = Cookie::parse("id=1; Max-Age=60", "example.com", "/").unwrap();
+    let expires = cookie.expires().unwrap();
+    assert!(expires > SystemTime::now() + Duration::from_secs(50));
+    let cookie = Cookie::parse("id=1; Max-Age=0", "example.com", "/").unwrap();
+    assert_eq!(cookie.expires(), Some(UNIX_EPOCH));
+    // Huge ages are clamped instead of overflowing
+    let cookie = Cookie::parse("id=1; Max-Age=9223372036854775807", "example.com", "/").unwrap();
+    assert_eq!(
+        cookie.expires(),
+        Some(UNIX_EPOCH + Duration::from_secs(253_402_300_799))
+    );
+}
+
+#[test]
+fn reject_foreign_domains() {
+    assert!(Cookie::parse("id=1; Domain=example.org", "example.com", "/").is_none());
+    assert!(Cookie::parse("id=1; Domain=ample.com", "example.com", "/").is_none());
+    assert!(Cookie::parse("id=1; Domain=com", "example.com", "/").is_none());
+    // A public suffix is accepted as a host only cookie from that exact host
+    let cookie = Cookie::parse("id=1; Domain=localhost", "localhost", "/").unwrap();
+    assert!(!cookie.matches("sub.localhost", "/", false));
+}
+
+#[test]
+fn jar_sends_matching_cookies() {
+    let jar = CookieJar::new();
+    jar.store("a=1; Path=/", "example.com", "/");
+    jar.store("b=2; Path=/api", "example.com", "/");
+    jar.store("c=3; Secure", "example.com", "/");
+    jar.store("d=4", "other.com", "/");
+    assert_eq!(
+        jar.header_for("example.com", "/api/users", false)
+            .as_deref(),
+        Some("b=2; a=1")
+    );
+    assert_eq!(
+        jar.header_for("example.com", "/", true).as_deref(),
+        Some("a=1; c=3")
+    );
+
+    jar.store("a=5", "example.com", "/");
+    jar.store("b=2; Path=/api; Max-Age=0", "example.com", "/");
+    assert_eq!(
+        jar.header_for("example.com", "/api", false).as_deref(),
+        Some("a=5")
+    );
+}
@@ -0,0 +1,131 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+mod tests;
+
+/// Parse an HTTP date such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub(crate) fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let tokens = value
+        .split([' ', '-', ',', ':'])
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<&str>>();
+    // Skip the week day, if present
+    let tokens = match tokens.first()?.parse::<u64>() {
+        Ok(_) => &tokens[..],
+        Err(_) => &tokens[1..],
+    };
+    let [day, month, year, hour, minute, second, ..] = tokens else {
+        return None;
+    };
+    let month = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ]
+    .iter()
+    .position(|name| month.to_ascii_lowercase().starts_with(name))? as i64
+        + 1;
+    let mut year = year.parse::<i64>().ok()?;
+    if year < 100 {
+        year += if year < 70 { 2000 } else { 1900 };
+    }
+    let day = day.parse::<i64>().ok()?;
+    let (hour, minute, second) = (
+        hour.parse::<i64>().ok()?,
+        minute.parse::<i64>().ok()?,
+        second.parse::<i64>().ok()?,
+    );
+    // Out of range values are invalid, and could overflow the timestamp
+    if year > 9999 || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let seconds = hour * 3600 + minute * 60 + second;
+    let timestamp = days_from_civil(year, month, day) * 86_400 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).ok()?))
+}
+
+/// Format a time as an HTTP date such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+///
+/// Times before the Unix epoch are formatted as the epoch.
+pub(crate) fn format_http_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs()) as i64;
+    let days = seconds.div_euclid(86_400);
+    let seconds = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    // 1970-01-01 was a Thursday
+    let week_day = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"][days.rem_euclid(7) as usize];
+    let month = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ][month as usize - 1];
+    format!(
+        "{week_day}, {day:02} {month} {year} {:02}:{:02}:{:02} GMT",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Format a time as an ISO 8601 basic timestamp such as `19941106T084937Z`, as used to sign
+/// requests.
+///
+/// Times before the Unix epoch are formatted as the epoch.
+pub(crate) fn format_iso8601_basic(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs()) as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let seconds = seconds.rem_euclid(86_400);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Format a time as an ISO 8601 extended timestamp with milliseconds such as
+/// `1994-11-06T08:49:37.000Z`, as used by HAR files.
+///
+/// Times before the Unix epoch are formatted as the epoch.
+pub(crate) fn format_iso8601(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = elapsed.as_secs() as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let seconds = seconds.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+/// The date the given number of days after the Unix epoch, the inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since the Unix epoch of the given date, in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
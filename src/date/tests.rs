@@ -0,0 +1,56 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::date::{format_http_date, format_iso8601, format_iso8601_basic, parse_http_date};
+
+#[test]
+fn http_dates() {
+    let expected = UNIX_EPOCH + Duration::from_secs(784_111_777);
+    assert_eq!(
+        parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"),
+        Some(expected)
+    );
+    assert_eq!(parse_http_date("not a date"), None);
+    assert_eq!(
+        parse_http_date("Sun, 06 Nov 99999999999 08:49:37 GMT"),
+        None
+    );
+    assert_eq!(
+        parse_http_date("Sun, 99999999999 Nov 1994 08:49:37 GMT"),
+        None
+    );
+    assert_eq!(
+        parse_http_date("Sun, 06 Nov 1994 99999999999:49:37 GMT"),
+        None
+    );
+}
+
+#[test]
+fn format_dates() {
+    let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+    assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(
+        format_http_date(UNIX_EPOCH),
+        "Thu, 01 Jan 1970 00:00:00 GMT"
+    );
+    let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+    assert_eq!(format_http_date(leap_day), "Tue, 29 Feb 2000 00:00:00 GMT");
+    assert_eq!(parse_http_date(&format_http_date(leap_day)), Some(leap_day));
+}
+
+#[test]
+fn format_basic_timestamps() {
+    let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+    assert_eq!(format_iso8601_basic(time), "19941106T084937Z");
+    assert_eq!(format_iso8601_basic(UNIX_EPOCH), "19700101T000000Z");
+}
+
+#[test]
+fn format_extended_timestamps() {
+    let time = UNIX_EPOCH + Duration::from_millis(784_111_777_042);
+    assert_eq!(format_iso8601(time), "1994-11-06T08:49:37.042Z");
+    assert_eq!(format_iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+}
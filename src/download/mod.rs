@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::body::{self, ProgressFn};
+use crate::client::{self, Client};
+use crate::error::HttpError;
+use crate::retry::RetryPolicy;
+
+#[cfg(test)]
+mod tests;
+
+/// Files downloaded at the same time, unless configured otherwise.
+const DEFAULT_WORKERS: usize = 4;
+
+/// Attempts made to download a file after the first one failed, unless configured otherwise.
+const DEFAULT_RETRIES: usize = 2;
+
+/// Statistics of a finished download, e.g to log how a transfer went.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let mut file = std::fs::File::create("video.mp4")?;
+///     let report = Client::new()
+///         .get("https://example.com/video.mp4")?
+///         .download_to_file(&mut file)?;
+///     println!("{report}");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DownloadReport {
+    pub(crate) bytes: u64,
+    pub(crate) elapsed: Duration,
+    pub(crate) requests: usize,
+    pub(crate) retries: usize,
+}
+
+impl DownloadReport {
+    /// The number of bytes written to the destination.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// How long the download took, including retries.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The average number of bytes received per second.
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            0.0 => 0.0,
+            seconds => self.bytes as f64 / seconds,
+        }
+    }
+
+    /// The number of requests sent, one per block of a ranged download.
+    pub fn requests(&self) -> usize {
+        self.requests
+    }
+
+    /// The number of times the download was resumed after a failure, from the same
+    /// location or from a mirror.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+}
+
+impl Display for DownloadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} bytes in {:.2}s ({:.0} B/s), {} requests, {} retries",
+            self.bytes,
+            self.elapsed.as_secs_f64(),
+            self.throughput(),
+            self.requests,
+            self.retries
+        )
+    }
+}
+
+/// The bytes written and the size of every file being downloaded.
+type FileProgress = Arc<Mutex<Vec<(u64, Option<u64>)>>>;
+
+/// The paths files are saved to, with the index of the URL saved to each.
+type Destinations = Mutex<HashMap<PathBuf, usize>>;
+
+/// Downloads many files to a directory, several at a time.
+///
+/// Each file is saved as by [`crate::client::ClientRequest::save_to_dir`], named after the
+/// name suggested by the server or the URL. Files that would get the same name are told
+/// apart with a number, e.g `report (1).pdf`. A file failing because of a network error or a
+/// `429` or `5xx` response is downloaded again from the start after a growing delay, as with
+/// [`RetryPolicy`]. A file failing for good does not stop the others.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, download::ParallelDownloader};
+/// fn main() {
+///     let urls = ["https://example.com/a.zip", "https://example.com/b.zip"];
+///     let results = ParallelDownloader::new(Client::new())
+///         .workers(8)
+///         .on_progress(|done, total| println!("{done}/{} bytes", total.unwrap_or_default()))
+///         .download(&urls, "downloads");
+///     for (url, result) in urls.iter().zip(results) {
+///         match result {
+///             Ok(path) => println!("{url} saved to {}", path.display()),
+///             Err(e) => eprintln!("{url} failed: {e}"),
+///         }
+///     }
+/// }
+/// ```
+pub struct ParallelDownloader {
+    client: Client,
+    workers: usize,
+    retry: RetryPolicy,
+    progress: Option<ProgressFn>,
+}
+
+impl ParallelDownloader {
+    /// Create a downloader sending its requests with the given client.
+    pub fn new(client: Client) -> Self {
+        ParallelDownloader {
+            client,
+            workers: DEFAULT_WORKERS,
+            retry: RetryPolicy::new().max_attempts(DEFAULT_RETRIES + 1),
+            progress: None,
+        }
+    }
+
+    /// Download up to the given number of files at the same time.
+    /// # Arguments
+    /// `workers`   Files downloaded at once, at least one.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Try downloading a failed file again, up to the given number of times.
+    /// # Arguments
+    /// `retries`   Attempts made after the first one failed.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retry = self.retry.max_attempts(retries.saturating_add(1));
+        self
+    }
+
+    /// Wait `base` before downloading a failed file again, doubling the delay after every
+    /// attempt up to `cap`.
+    /// # Arguments
+    /// `base`  Delay before the first retry.
+    /// `cap`   Longest delay between two attempts.
+    pub fn backoff(mut self, base: Duration, cap: Duration) -> Self {
+        self.retry = self.retry.backoff(base, cap);
+        self
+    }
+
+    /// Report the progress of downloads.
+    ///
+    /// `callback` is called with the number of bytes written so far for all the files, and
+    /// their total size once the size of every file is known.
+    /// # Arguments
+    /// `callback`  Called with the bytes downloaded and their total, e.g to draw a progress bar.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        self.progress = Some(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    /// Download the given URLs into `dir` and return the path of every file, or the error
+    /// that failed it, in the order of the URLs.
+    /// # Arguments
+    /// `urls`  Resources to download.
+    /// `dir`   Directory the files are saved to.
+    pub fn download<P: AsRef<Path>>(
+        &self,
+        urls: &[&str],
+        dir: P,
+    ) -> Vec<Result<PathBuf, HttpError>> {
+        let dir = dir.as_ref();
+        let files = Arc::new(Mutex::new(vec![(0, None); urls.len()]));
+        let destinations = Mutex::new(HashMap::new());
+        client::run_parallel(urls, self.workers, |index, url| {
+            self.fetch(url, dir, index, &files, &destinations)
+        })
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|| Err(HttpError::Other("File was not downloaded".to_owned())))
+        })
+        .collect()
+    }
+
+    /// Helper method, download a file, trying again when it fails.
+    ///
+    /// `files` holds the bytes written and the size of every file, its entry `index` is
+    /// updated as the file is downloaded.
+    fn fetch(
+        &self,
+        url: &str,
+        dir: &Path,
+        index: usize,
+        files: &FileProgress,
+        destinations: &Destinations,
+    ) -> Result<PathBuf, HttpError> {
+        let mut retry = 0;
+        loop {
+            let mut request = self.client.get(url)?;
+            if let Some(progress) = self.progress.clone() {
+                let files = files.clone();
+                request = request.on_progress(move |done, total| {
+                    let Ok(mut files) = files.lock() else {
+                        return;
+                    };
+                    files[index] = (done, total);
+                    let (done, total) = aggregate(&files);
+                    // The callback is not called with the lock held
+                    drop(files);
+                    body::report(&progress, done, total);
+                });
+            }
+            let result = request.save_named(|name| destination(dir, &name, index, destinations));
+            let again = retry + 1 < self.retry.attempts()
+                && match &result {
+                    Ok(_) => false,
+                    Err(HttpError::BadResponse(status, _)) => self.retry.retries_status(*status),
+                    Err(error) => self.retry.retries_error(error),
+                };
+            if !again {
+                return result;
+            }
+            std::thread::sleep(self.retry.delay(retry));
+            retry += 1;
+        }
+    }
+}
+
+/// The path a file named `name` is saved to in `dir`, numbered when another file of the same
+/// download already took that name, e.g `report (1).pdf`.
+/// # Arguments
+/// `dir`   Directory the files are saved to.
+/// `name`  Name suggested for the file.
+/// `index` Index of the URL of the file, keeping its path when it is downloaded again.
+/// `destinations`  Paths taken so far.
+pub(crate) fn destination(
+    dir: &Path,
+    name: &str,
+    index: usize,
+    destinations: &Destinations,
+) -> PathBuf {
+    let Ok(mut destinations) = destinations.lock() else {
+        return dir.join(name);
+    };
+    let name = Path::new(name);
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = name
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut number = 0;
+    loop {
+        let path = match number {
+            0 => dir.join(name),
+            _ => dir.join(format!("{stem} ({number}){extension}")),
+        };
+        match destinations.get(&path) {
+            Some(taken) if *taken != index => number += 1,
+            _ => {
+                destinations.insert(path.clone(), index);
+                return path;
+            }
+        }
+    }
+}
+
+/// The bytes written for all the files and their total size, known once every size is.
+pub(crate) fn aggregate(files: &[(u64, Option<u64>)]) -> (u64, Option<u64>) {
+    let done = files.iter().map(|(done, _)| done).sum();
+    let total = files.iter().map(|(_, total)| *total).sum();
+    (done, total)
+}
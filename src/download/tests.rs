@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::client::Client;
+use crate::download::{aggregate, destination, DownloadReport, ParallelDownloader};
+use crate::error::HttpError;
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn download_statistics() {
+    let report = DownloadReport {
+        bytes: 3_000_000,
+        elapsed: Duration::from_millis(1500),
+        requests: 3,
+        retries: 1,
+    };
+    assert_eq!(report.throughput(), 2_000_000.0);
+    assert_eq!(
+        report.to_string(),
+        "3000000 bytes in 1.50s (2000000 B/s), 3 requests, 1 retries"
+    );
+    assert_eq!(DownloadReport::default().throughput(), 0.0);
+}
+
+#[test]
+fn aggregate_progress() {
+    assert_eq!(
+        aggregate(&[(10, Some(100)), (5, Some(50))]),
+        (15, Some(150))
+    );
+    assert_eq!(aggregate(&[(10, Some(100)), (0, None)]), (10, None));
+    assert_eq!(aggregate(&[]), (0, Some(0)));
+}
+
+#[test]
+fn destination_failures_not_retried() {
+    struct Closed;
+    impl Write for Closed {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("file closed"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/file", TestResponse::text(200, "content"));
+    let client = Client::builder()
+        .no_proxy()
+        .ranged_downloads(false)
+        .download_retries(3)
+        .build();
+    let error = client
+        .get(&server.url("/file"))
+        .unwrap()
+        .download_to_file(&mut Closed)
+        .unwrap_err();
+    assert!(matches!(error, HttpError::Destination(_)));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn destinations_numbered() {
+    let dir = Path::new("downloads");
+    let destinations = Mutex::new(HashMap::new());
+    let path = |name, index| destination(dir, name, index, &destinations);
+    assert_eq!(path("report.pdf", 0), dir.join("report.pdf"));
+    assert_eq!(path("report.pdf", 1), dir.join("report (1).pdf"));
+    assert_eq!(path("report.pdf", 2), dir.join("report (2).pdf"));
+    // A file downloaded again keeps its path
+    assert_eq!(path("report.pdf", 1), dir.join("report (1).pdf"));
+    assert_eq!(path("README", 3), dir.join("README"));
+    assert_eq!(path("README", 4), dir.join("README (1)"));
+}
+
+#[test]
+fn parallel_downloads() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/a/data.txt", TestResponse::text(200, "first"));
+    server.route("GET", "/b/data.txt", TestResponse::text(200, "second"));
+    server.route("GET", "/missing.txt", TestResponse::new(404));
+    server.route("GET", "/flaky.txt", TestResponse::new(503));
+    server.route("GET", "/flaky.txt", TestResponse::text(200, "flaky"));
+    let dir = std::env::temp_dir().join(format!("parallel-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let urls =
+        ["/a/data.txt", "/b/data.txt", "/missing.txt", "/flaky.txt"].map(|path| server.url(path));
+    let urls = urls.iter().map(String::as_str).collect::<Vec<_>>();
+    let results = ParallelDownloader::new(Client::builder().no_proxy().build())
+        .workers(2)
+        .backoff(Duration::from_millis(1), Duration::from_millis(1))
+        .download(&urls, &dir);
+    let mut saved = [&results[0], &results[1]]
+        .map(|result| std::fs::read_to_string(result.as_ref().unwrap()).unwrap());
+    saved.sort();
+    assert_eq!(saved, ["first", "second"]);
+    assert_ne!(results[0].as_ref().unwrap(), results[1].as_ref().unwrap());
+    assert!(matches!(results[2], Err(HttpError::BadResponse(404, _))));
+    assert_eq!(
+        std::fs::read_to_string(results[3].as_ref().unwrap()).unwrap(),
+        "flaky"
+    );
+    let requests = server.requests();
+    let sent = |path: &str| {
+        requests
+            .iter()
+            .filter(|request| request.path() == path)
+            .count()
+    };
+    // Not found for good, while the unavailable server is asked again
+    assert_eq!(sent("/missing.txt"), 1);
+    assert_eq!(sent("/flaky.txt"), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn downloads_written_atomically() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/file", TestResponse::text(200, "new"));
+    server.route("GET", "/missing", TestResponse::new(404));
+    let dir = std::env::temp_dir().join(format!("atomic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("file.txt");
+    let part = dir.join("file.txt.part");
+    std::fs::write(&path, "old").unwrap();
+    let client = Client::builder().no_proxy().build();
+
+    let failed = client
+        .get(&server.url("/missing"))
+        .unwrap()
+        .download_to_path(&path);
+    assert!(matches!(failed, Err(HttpError::BadResponse(404, _))));
+    // The previous file is left as it was
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+    assert!(!part.exists());
+
+    let report = client
+        .get(&server.url("/file"))
+        .unwrap()
+        .download_to_path(&path)
+        .unwrap();
+    assert_eq!(report.bytes, 3);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+    assert!(!part.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// A `206 Partial Content` response with bytes `start..end` of `resource`.
+fn partial(resource: &str, start: usize, end: usize) -> TestResponse {
+    let range = format!("bytes {start}-{}/{}", end - 1, resource.len());
+    TestResponse::new(206)
+        .header("Content-Range", &range)
+        .body(&resource.as_bytes()[start..end])
+}
+
+#[test]
+fn downloaded_in_blocks() {
+    let resource = "abcdefghij";
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/blocks", partial(resource, 0, 4));
+    server.route("GET", "/blocks", partial(resource, 4, 8));
+    server.route("GET", "/blocks", partial(resource, 8, 10));
+    server.route("GET", "/single", partial(resource, 0, 10));
+    server.route("GET", "/whole", TestResponse::text(200, resource));
+    let client = Client::builder().no_proxy().download_block_size(4).build();
+
+    let data = client
+        .get(&server.url("/blocks"))
+        .unwrap()
+        .download()
+        .unwrap();
+    assert_eq!(data, resource.as_bytes());
+    let data = client
+        .get(&server.url("/single"))
+        .unwrap()
+        .download_block_size(16)
+        .download()
+        .unwrap();
+    assert_eq!(data, resource.as_bytes());
+    let data = client
+        .get(&server.url("/whole"))
+        .unwrap()
+        .ranged_downloads(false)
+        .download()
+        .unwrap();
+    assert_eq!(data, resource.as_bytes());
+
+    let requests = server.requests();
+    let ranges = requests
+        .iter()
+        .map(|request| request.header("Range"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ranges,
+        [
+            Some("bytes=0-3"),
+            Some("bytes=4-7"),
+            Some("bytes=8-9"),
+            Some("bytes=0-15"),
+            None
+        ]
+    );
+}
+
+#[test]
+fn ranges_ignored() {
+    let resource = "abcdefghij";
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/ignored", TestResponse::text(200, resource));
+    // Ranges stop being honoured after the first block
+    server.route("GET", "/midway", partial(resource, 0, 4));
+    server.route("GET", "/midway", TestResponse::text(200, resource));
+    server.route("GET", "/misplaced", partial(resource, 0, 4));
+    server.route("GET", "/misplaced", partial(resource, 2, 6));
+    let client = Client::builder().no_proxy().download_block_size(4).build();
+    let download = |path| client.get(&server.url(path)).unwrap().download();
+
+    assert_eq!(download("/ignored").unwrap(), resource.as_bytes());
+    assert_eq!(download("/midway").unwrap(), resource.as_bytes());
+    assert!(matches!(
+        download("/misplaced"),
+        Err(HttpError::BadResponse(206, _))
+    ));
+}
+
+#[test]
+fn mirrors_take_over() {
+    let resource = "abcdefghij";
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/primary", partial(resource, 0, 4));
+    server.route("GET", "/primary", TestResponse::new(404));
+    server.route("GET", "/mirror", partial(resource, 4, 8));
+    server.route("GET", "/mirror", partial(resource, 8, 10));
+    server.route("GET", "/gone", TestResponse::new(410));
+    let client = Client::builder().no_proxy().download_block_size(4).build();
+
+    let data = client
+        .get(&server.url("/primary"))
+        .unwrap()
+        .mirrors([server.url("/gone"), server.url("/mirror")])
+        .unwrap()
+        .download()
+        .unwrap();
+    assert_eq!(data, resource.as_bytes());
+    // The mirror resumes from the bytes already downloaded
+    let requests = server.requests();
+    let mirrored = requests
+        .iter()
+        .filter(|request| request.path() == "/mirror")
+        .map(|request| request.header("Range"))
+        .collect::<Vec<_>>();
+    assert_eq!(mirrored, [Some("bytes=4-7"), Some("bytes=8-9")]);
+
+    // The error of the last mirror is returned when every one fails
+    let failed = client
+        .get(&server.url("/gone"))
+        .unwrap()
+        .mirrors([server.url("/missing")])
+        .unwrap()
+        .download();
+    assert!(matches!(failed, Err(HttpError::BadResponse(404, _))));
+    let invalid = client
+        .get(&server.url("/primary"))
+        .unwrap()
+        .mirrors(["not a url"]);
+    assert!(matches!(invalid.err(), Some(HttpError::InvalidUrl(_))));
+}
+
+#[test]
+fn unchanged_downloads_skipped() {
+    let server = TestServer::start().unwrap();
+    let feed = TestResponse::text(200, "feed")
+        .header("ETag", "\"v1\"")
+        .header("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT");
+    server.route("GET", "/feed", feed);
+    server.route("GET", "/feed", TestResponse::new(304));
+    server.route("GET", "/feed", TestResponse::text(200, "feed"));
+    server.route("GET", "/feed", TestResponse::new(500));
+    let dir = std::env::temp_dir().join(format!("if-changed-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("feed.xml");
+    let sidecar = dir.join("feed.xml.validators");
+    let client = Client::builder().no_proxy().build();
+    let download = || {
+        client
+            .get(&server.url("/feed"))
+            .unwrap()
+            .download_if_changed(&path)
+    };
+
+    assert!(download().unwrap());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "feed");
+    assert!(sidecar.exists());
+    assert!(!download().unwrap());
+    // Validators of a deleted file are not sent
+    std::fs::remove_file(&path).unwrap();
+    assert!(download().unwrap());
+    assert!(!sidecar.exists());
+    assert!(matches!(download(), Err(HttpError::BadResponse(500, _))));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "feed");
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("If-None-Match"), None);
+    assert_eq!(requests[1].header("If-None-Match"), Some("\"v1\""));
+    assert_eq!(
+        requests[1].header("If-Modified-Since"),
+        Some("Wed, 21 Oct 2015 07:28:00 GMT")
+    );
+    assert_eq!(requests[2].header("If-None-Match"), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,245 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http_parse::{HttpParser, H_CONTENT_LENGTH, H_TRANSFER_ENCODING};
+
+use crate::body::{Framing, ResponseBody};
+use crate::connection::{self, Connection};
+use crate::error::HttpError;
+use crate::response::Response;
+
+#[cfg(test)]
+mod tests;
+
+/// How long the response half of a TLS connection holds it while waiting for data, so the
+/// request half can write in between.
+const TLS_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// One direction of a connection shared by the two halves of a duplex exchange.
+enum Half {
+    /// A handle to the socket of a plain connection, each half reading or writing on its own.
+    Plain(TcpStream),
+    /// A TLS connection, taken in turns by the halves.
+    Tls {
+        connection: Arc<Mutex<Connection>>,
+        read_timeout: Option<Duration>,
+    },
+}
+
+impl Half {
+    /// Split an open connection into the halves writing the request and reading the
+    /// response.
+    fn split(
+        connection: Connection,
+        read_timeout: Option<Duration>,
+    ) -> Result<(Half, Half), HttpError> {
+        match connection {
+            Connection::Plain(socket) => {
+                Ok((Half::Plain(socket.try_clone()?), Half::Plain(socket)))
+            }
+            tls @ Connection::Tls(_) => {
+                let connection = Arc::new(Mutex::new(tls));
+                Ok((
+                    Half::Tls {
+                        connection: connection.clone(),
+                        read_timeout,
+                    },
+                    Half::Tls {
+                        connection,
+                        read_timeout,
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Helper method, the shared TLS connection.
+    fn lock(
+        connection: &Mutex<Connection>,
+    ) -> std::io::Result<std::sync::MutexGuard<'_, Connection>> {
+        connection
+            .lock()
+            .map_err(|e| std::io::Error::other(e.to_string()))
+    }
+}
+
+impl Read for Half {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let (connection, read_timeout) = match self {
+            Half::Plain(socket) => return socket.read(buf),
+            Half::Tls {
+                connection,
+                read_timeout,
+            } => (connection, *read_timeout),
+        };
+        let started = Instant::now();
+        loop {
+            {
+                let mut connection = Self::lock(connection)?;
+                connection
+                    .as_ref()
+                    .set_read_timeout(Some(TLS_POLL_INTERVAL))?;
+                match connection.read(buf) {
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+                    result => return result,
+                }
+            }
+            // The lock is released, letting the request half write
+            if read_timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                return Err(std::io::Error::from(ErrorKind::TimedOut));
+            }
+            std::thread::yield_now();
+        }
+    }
+}
+
+impl Write for Half {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Half::Plain(socket) => socket.write(buf),
+            Half::Tls { connection, .. } => Self::lock(connection)?.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Half::Plain(socket) => socket.flush(),
+            Half::Tls { connection, .. } => Self::lock(connection)?.flush(),
+        }
+    }
+}
+
+/// Writes the body of a duplex request while its response is read, see
+/// [`crate::client::ClientRequest::send_duplex`].
+///
+/// Every write is sent right away as a chunk of the body. The body ends with
+/// [`DuplexWriter::finish`], or when the writer is dropped.
+pub struct DuplexWriter {
+    half: Half,
+    finished: bool,
+}
+
+impl DuplexWriter {
+    /// End the body of the request.
+    ///
+    /// # Errors
+    /// When the connection fails.
+    pub fn finish(mut self) -> Result<(), HttpError> {
+        self.end()
+    }
+
+    /// Helper method, write the last chunk once.
+    fn end(&mut self) -> Result<(), HttpError> {
+        if !self.finished {
+            self.finished = true;
+            self.half.write_all(b"0\r\n\r\n")?;
+            self.half.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for DuplexWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.finished {
+            return Err(std::io::Error::other(
+                "The request body is already finished",
+            ));
+        }
+        // An empty chunk would end the body
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut chunk = format!("{:x}\r\n", buf.len()).into_bytes();
+        chunk.extend_from_slice(buf);
+        chunk.extend_from_slice(b"\r\n");
+        self.half.write_all(&chunk)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.half.flush()
+    }
+}
+
+impl Drop for DuplexWriter {
+    fn drop(&mut self) {
+        // Errors are reported to the reader of the response
+        let _ = self.end();
+    }
+}
+
+/// Reads the response of a duplex request while its body is written, see
+/// [`crate::client::ClientRequest::send_duplex`].
+pub struct DuplexReader {
+    half: Half,
+    url: String,
+    method: String,
+    max_header_size: usize,
+}
+
+impl DuplexReader {
+    /// Wait for the head of the response and return it, its body read from the connection
+    /// through the [`Read`] implementation of the response as it arrives.
+    ///
+    /// Interim responses such as `100 Continue` are skipped.
+    ///
+    /// # Errors
+    /// When the connection fails or the response is invalid.
+    pub fn response(mut self) -> Result<Response, HttpError> {
+        let (response, head) = loop {
+            let head = connection::read_head(&mut self.half, self.max_header_size)?;
+            let response =
+                HttpParser::from_reader(&mut std::io::Cursor::new(&head)).response_head_only()?;
+            let status = response.status_code();
+            if !(100..200).contains(&status) || status == 101 {
+                break (response, head);
+            }
+        };
+        let header = |name: &str| {
+            response
+                .header(name)
+                .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+        };
+        let framing = Framing::of(
+            &self.method,
+            response.status_code(),
+            header(H_TRANSFER_ENCODING).as_deref(),
+            header(H_CONTENT_LENGTH).as_deref(),
+        )?;
+        let body = ResponseBody::new(self.half, framing);
+        Ok(Response::new(response, head, self.url, Vec::new()).streamed(body))
+    }
+}
+
+/// Split a connection the head of a chunked request was written to into the writer of its
+/// body and the reader of its response.
+/// # Arguments
+/// `connection`    Connection the request head was sent on.
+/// `url`           URL of the request.
+/// `method`        Method of the request, e.g `POST`.
+/// `max_header_size`   Largest response head accepted, in bytes.
+/// `read_timeout`  Maximum duration of a single read of the response.
+pub(crate) fn split(
+    connection: Connection,
+    url: String,
+    method: String,
+    max_header_size: usize,
+    read_timeout: Option<Duration>,
+) -> Result<(DuplexWriter, DuplexReader), HttpError> {
+    let (writer, reader) = Half::split(connection, read_timeout)?;
+    Ok((
+        DuplexWriter {
+            half: writer,
+            finished: false,
+        },
+        DuplexReader {
+            half: reader,
+            url,
+            method,
+            max_header_size,
+        },
+    ))
+}
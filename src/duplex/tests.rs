@@ -0,0 +1,54 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use crate::client::Client;
+use crate::connection::Connection;
+use crate::duplex::split;
+use crate::error::HttpError;
+
+#[test]
+fn body_written_while_response_read() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    let (mut writer, mut reader) = split(
+        Connection::Plain(client),
+        "http://localhost/echo".to_owned(),
+        "POST".to_owned(),
+        8192,
+        None,
+    )
+    .unwrap();
+
+    // The server answers the first chunk before the body ends
+    let echo = thread::spawn(move || {
+        let mut chunk = [0u8; 10];
+        server.read_exact(&mut chunk).unwrap();
+        server.write_all(&chunk).unwrap();
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).unwrap();
+        rest
+    });
+    writer.write_all(b"hello").unwrap();
+    writer.write_all(b"").unwrap();
+    let mut answer = [0u8; 10];
+    reader.half.read_exact(&mut answer).unwrap();
+    assert_eq!(&answer, b"5\r\nhello\r\n");
+
+    writer.write_all(b"bye").unwrap();
+    writer.finish().unwrap();
+    // The server sees the end of the body once the reader closes its end
+    drop(reader);
+    assert_eq!(echo.join().unwrap(), b"3\r\nbye\r\n0\r\n\r\n");
+}
+
+#[test]
+fn plain_urls_refused() {
+    let client = Client::builder().https_only(true).build();
+    let result = client
+        .post("http://127.0.0.1:9/echo")
+        .unwrap()
+        .send_duplex();
+    assert!(matches!(result, Err(HttpError::InvalidUrl(_))));
+}
@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod tests;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode the given bytes using standard Base64 with padding.
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let triple = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (triple >> (18 - 6 * index)) & 0x3F;
+                result.push(BASE64_ALPHABET[sextet as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+/// Decode standard Base64 text, padding is optional.
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim().trim_end_matches('=');
+    let mut result = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = match BASE64_ALPHABET.iter().position(|&a| a == c) {
+            Some(value) => value as u32,
+            None => return Err(format!("Invalid Base64 character `{}`", c as char)),
+        };
+        buffer = buffer << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(result)
+}
+
+/// Percent-encode text for use in a URL component, as described by RFC 3986.
+///
+/// Every byte except the unreserved characters (`A-Z a-z 0-9 - . _ ~`) is encoded.
+pub fn percent_encode(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            result.push(byte as char);
+        } else {
+            result.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    result
+}
+
+/// Encode text as a name or value of an `application/x-www-form-urlencoded` body.
+///
+/// Spaces become `+` and every byte except `A-Z a-z 0-9 * - . _` is percent-encoded.
+pub fn form_urlencode(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b' ' => result.push('+'),
+            byte if byte.is_ascii_alphanumeric() || b"*-._".contains(&byte) => {
+                result.push(byte as char)
+            }
+            byte => result.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    result
+}
+
+/// Flatten a serializable struct or map into name and value pairs, for forms and query strings.
+///
+/// Pairs are sorted by name. Sequences repeat their name, e.g `tag=a&tag=b`, and `None`
+/// values are skipped.
+///
+/// # Errors
+/// When the value is not a struct or map, or contains nested structs or maps.
+#[cfg(feature = "serde")]
+pub(crate) fn to_pairs<S>(value: &S) -> Result<Vec<(String, String)>, String>
+where
+    S: serde::Serialize + ?Sized,
+{
+    use serde_json::Value;
+
+    let Value::Object(fields) = serde_json::to_value(value).map_err(|e| e.to_string())? else {
+        return Err("Only structs and maps can be encoded as name and value pairs".to_owned());
+    };
+    let mut pairs = Vec::new();
+    for (name, value) in fields {
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::Null => continue,
+                Value::String(value) => value,
+                Value::Bool(_) | Value::Number(_) => value.to_string(),
+                Value::Array(_) | Value::Object(_) => {
+                    return Err(format!("`{name}` is nested and cannot be encoded"))
+                }
+            };
+            pairs.push((name.clone(), value));
+        }
+    }
+    Ok(pairs)
+}
+
+/// Decode percent-encoded text, `%` not followed by two hexadecimal digits is kept as is.
+pub fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let decoded = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(byte) => {
+                result.push(byte);
+                index += 3;
+            }
+            None => {
+                result.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Characters of windows-1252 for the bytes `0x80` to `0x9F`, the other bytes match Latin-1.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Decode text in the given charset, e.g the `charset` parameter of a `Content-Type`.
+///
+/// A byte order mark takes precedence over the charset. UTF-8, UTF-16, US-ASCII, ISO-8859-1
+/// and windows-1252 are supported, with the `encoding` feature every charset of the WHATWG
+/// Encoding Standard is. Other charsets and invalid sequences are decoded as UTF-8,
+/// replacing what cannot be decoded with `U+FFFD`.
+pub(crate) fn decode_text(data: &[u8], charset: Option<&str>) -> String {
+    #[cfg(feature = "encoding")]
+    if let Some(encoding) =
+        charset.and_then(|charset| encoding_rs::Encoding::for_label(charset.trim().as_bytes()))
+    {
+        // Sniffs the byte order mark as well
+        return encoding.decode(data).0.into_owned();
+    }
+    if let Some(data) = data.strip_prefix(b"\xEF\xBB\xBF") {
+        return String::from_utf8_lossy(data).into_owned();
+    }
+    if let Some(data) = data.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(data, u16::from_le_bytes);
+    }
+    if let Some(data) = data.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(data, u16::from_be_bytes);
+    }
+    let charset = charset.unwrap_or("utf-8").trim().to_ascii_lowercase();
+    match charset.as_str() {
+        "utf-16le" => decode_utf16(data, u16::from_le_bytes),
+        "utf-16be" | "utf-16" => decode_utf16(data, u16::from_be_bytes),
+        // Browsers decode ASCII and Latin-1 as windows-1252 as well
+        "us-ascii" | "ascii" | "iso-8859-1" | "latin1" | "l1" | "windows-1252" | "cp1252" => data
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9F => WINDOWS_1252[byte as usize - 0x80],
+                byte => byte as char,
+            })
+            .collect(),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+/// Helper method, decode UTF-16 text using the given byte order.
+fn decode_utf16(data: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units = data.chunks(2).map(|pair| match pair {
+        [first, second] => unit([*first, *second]),
+        // A truncated unit cannot be decoded
+        _ => 0xFFFD,
+    });
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
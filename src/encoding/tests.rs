@@ -0,0 +1,108 @@
+use crate::client::Client;
+use crate::encoding::{
+    base64_decode, base64_encode, decode_text, form_urlencode, percent_decode, percent_encode,
+};
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn base64_round_trip() {
+    let cases = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("Aladdin:open sesame", "QWxhZGRpbjpvcGVuIHNlc2FtZQ=="),
+    ];
+    for (plain, encoded) in cases {
+        assert_eq!(base64_encode(plain.as_bytes()), encoded);
+        assert_eq!(base64_decode(encoded).unwrap(), plain.as_bytes());
+    }
+    assert!(base64_decode("Zm9v!").is_err());
+}
+
+#[test]
+fn percent_encoding() {
+    assert_eq!(percent_decode("caf%C3%A9%2"), "café%2".as_bytes());
+    assert_eq!(percent_decode("%zz+%41"), b"%zz+A");
+    assert_eq!(percent_encode("abc-._~123"), "abc-._~123");
+    assert_eq!(percent_encode("a b&c=d/e?"), "a%20b%26c%3Dd%2Fe%3F");
+    assert_eq!(percent_encode("café"), "caf%C3%A9");
+}
+
+#[test]
+fn form_encoding() {
+    assert_eq!(form_urlencode("a b&c=d"), "a+b%26c%3Dd");
+    assert_eq!(form_urlencode("x*y-z._~"), "x*y-z._%7E");
+    assert_eq!(form_urlencode("naïve+1"), "na%C3%AFve%2B1");
+}
+
+#[test]
+fn text_decoding() {
+    assert_eq!(decode_text("café".as_bytes(), None), "café");
+    assert_eq!(decode_text(b"caf\xE9", Some("ISO-8859-1")), "café");
+    assert_eq!(
+        decode_text(b"\x93quoted\x94 \x80", Some("windows-1252")),
+        "“quoted” €"
+    );
+    assert_eq!(decode_text(b"caf\xE9", Some("utf-8")), "caf\u{FFFD}");
+    assert_eq!(decode_text(b"c\x00a\x00", Some("utf-16le")), "ca");
+    assert_eq!(decode_text(b"\xFE\xFF\x00c\x00a", Some("latin1")), "ca");
+    assert_eq!(
+        decode_text(b"\xEF\xBB\xBFcaf\xC3\xA9", Some("latin1")),
+        "café"
+    );
+    assert_eq!(decode_text(b"ok", Some("koi8-r")), "ok");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn legacy_text_decoding() {
+    assert_eq!(decode_text(b"\x82\xA0\x82\xA2", Some("Shift_JIS")), "あい");
+    assert_eq!(decode_text(b"\xC1\xD2", Some("koi8-r")), "ар");
+    assert_eq!(decode_text(b"\xEF\xBB\xBFok", Some("shift_jis")), "ok");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized_pairs() {
+    use std::collections::HashMap;
+
+    use crate::encoding::to_pairs;
+
+    let mut fields = HashMap::new();
+    fields.insert("name", vec![Some("a b")]);
+    fields.insert("tag", vec![Some("x"), None, Some("y")]);
+    assert_eq!(
+        to_pairs(&fields).unwrap(),
+        [("name", "a b"), ("tag", "x"), ("tag", "y")].map(|(n, v)| (n.to_owned(), v.to_owned()))
+    );
+    assert!(to_pairs(&[1, 2]).is_err());
+    let mut nested = HashMap::new();
+    nested.insert("inner", HashMap::from([("a", 1)]));
+    assert!(to_pairs(&nested).is_err());
+}
+
+#[test]
+fn query_parameters_encoded() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/search", TestResponse::new(204));
+    let client = Client::builder()
+        .no_proxy()
+        .default_query("lang", "en")
+        .build();
+    client
+        .get(&server.url("/search"))
+        .unwrap()
+        .query_pairs([("q", "rust & http"), ("page", "2")])
+        .unwrap()
+        .query("tag", "a/b")
+        .unwrap()
+        .send()
+        .unwrap();
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].target,
+        "/search?lang=en&q=rust%20%26%20http&page=2&tag=a%2Fb"
+    );
+}
@@ -8,6 +8,44 @@ pub enum HttpError {
     InvalidUrl(String),
     Other(String),
     ConnectionError(String),
+    /// The response head or body is larger than the configured limit.
+    TooLarge(String),
+    /// The server answered `416 Range Not Satisfiable`, with the size of the resource if known.
+    RangeNotSatisfiable(Option<u64>),
+    /// The connection closed before the end of the response body, with the bytes of the body
+    /// received and its announced length if known.
+    IncompleteBody(u64, Option<u64>),
+    /// Requests to the given host fail fast after it failed repeatedly, see
+    /// [`crate::breaker::CircuitBreaker`].
+    CircuitOpen(String),
+    /// The request was aborted through its [`crate::cancel::CancelToken`].
+    Cancelled,
+    /// The deadline of the request passed, see [`crate::client::ClientBuilder::deadline`].
+    DeadlineExceeded,
+    /// Writing a downloaded resource to its destination failed, e.g because the disk is full.
+    Destination(String),
+    /// Content received does not match its expected checksum or announced digest.
+    ChecksumMismatch(String),
+}
+
+impl HttpError {
+    /// This error with the identifier of the request that failed appended to its message,
+    /// for errors that carry one.
+    pub(crate) fn with_request_id(self, id: &str) -> HttpError {
+        match self {
+            HttpError::BadResponse(status, message) => {
+                HttpError::BadResponse(status, format!("{message} (request id {id})"))
+            }
+            HttpError::Other(message) => HttpError::Other(format!("{message} (request id {id})")),
+            HttpError::ConnectionError(message) => {
+                HttpError::ConnectionError(format!("{message} (request id {id})"))
+            }
+            HttpError::TooLarge(message) => {
+                HttpError::TooLarge(format!("{message} (request id {id})"))
+            }
+            error => error,
+        }
+    }
 }
 
 impl core::error::Error for HttpError {}
@@ -19,6 +57,26 @@ impl Display for HttpError {
             HttpError::Other(e) => write!(f, "{e}"),
             HttpError::InvalidUrl(http_url) => write!(f, "Invalid Url: `{http_url}`"),
             HttpError::ConnectionError(e) => write!(f, "Connection error: `{e}`"),
+            HttpError::TooLarge(e) => write!(f, "Response too large: {e}"),
+            HttpError::RangeNotSatisfiable(Some(size)) => {
+                write!(f, "Range not satisfiable for a resource of {size} bytes")
+            }
+            HttpError::RangeNotSatisfiable(None) => write!(f, "Range not satisfiable"),
+            HttpError::IncompleteBody(received, Some(length)) => write!(
+                f,
+                "Connection closed after {received} of the {length} bytes of the response body"
+            ),
+            HttpError::CircuitOpen(host) => {
+                write!(f, "Circuit open for `{host}` after repeated failures")
+            }
+            HttpError::IncompleteBody(received, None) => write!(
+                f,
+                "Connection closed after {received} bytes, before the end of the response body"
+            ),
+            HttpError::Cancelled => write!(f, "Request cancelled"),
+            HttpError::DeadlineExceeded => write!(f, "Deadline exceeded"),
+            HttpError::Destination(e) => write!(f, "Failed to write the download: {e}"),
+            HttpError::ChecksumMismatch(e) => write!(f, "{e}"),
         }
     }
 }
@@ -37,6 +95,11 @@ impl From<HttpParseError> for HttpError {
 
 impl From<std::io::Error> for HttpError {
     fn from(value: std::io::Error) -> Self {
+        // Errors of the crate reach readers wrapped in I/O errors, e.g a truncated body
+        if value.get_ref().is_some_and(|inner| inner.is::<HttpError>()) {
+            // save to unwrap, the inner error was just checked
+            return *value.into_inner().unwrap().downcast::<HttpError>().unwrap();
+        }
         HttpParseError::from(value).into()
     }
 }
@@ -1,102 +1,33 @@
-use std::io::Read;
+use std::io::Write;
 
-use http_client::{client::Client, error::HttpError};
-use http_parse::{HttpUrl, StatusCode};
+use http_client::{
+    client::Client,
+    error::HttpError,
+    hls::{HlsDownloader, Playlist},
+};
 
-fn get_playlist_url_list(url: &str) -> Result<Vec<HttpUrl>, HttpError> {
-    let client = Client::new();
-    let res = client.get(url)?.send()?;
-    if res.status_code() != StatusCode::OK {
-        return Err(HttpError::BadResponse(
-            res.status_code(),
-            res.status_msg().to_owned(),
-        ));
-    }
-
-    // In case the url does not contain a full URL.
-    let url_base = match url.rfind("/") {
-        Some(index) => &url[0..index],
-        None => url,
-    };
-
-    let urls = String::from_utf8_lossy(res.data())
-        .lines()
-        .filter(|line| !line.starts_with("#") && !line.trim().is_empty())
-        .flat_map(|line| {
-            if line.starts_with("http") {
-                HttpUrl::parse(line)
-            } else {
-                HttpUrl::parse(&format!("{}/{}", url_base, line.trim()))
+fn download_stream(local: bool, url: &str, file: &str) -> Result<(), HttpError> {
+    let downloader = HlsDownloader::new(Client::new()).on_progress(|done, total| {
+        print!("\rDownloaded {done}/{} segments", total.unwrap_or_default());
+        let _ = std::io::stdout().flush();
+    });
+    let playlist = if local {
+        // Without the URL of the playlist its segments must have full URLs
+        match Playlist::parse(&std::fs::read_to_string(url)?, "")? {
+            Playlist::Media(playlist) => playlist,
+            Playlist::Master(_) => {
+                return Err(HttpError::Other(format!(
+                    "`{url}` lists variants, pass the playlist of one of them"
+                )))
             }
-        })
-        .collect();
-
-    Ok(urls)
-}
-
-fn read_playlist_url_list(file: &str) -> Result<Vec<HttpUrl>, HttpError> {
-    let mut file = std::fs::File::open(file)?;
-    let mut url_list = Vec::new();
-    let mut buf = String::new();
-    file.read_to_string(&mut buf)?;
-    for line in buf.lines() {
-        if line.starts_with("#") || line.trim().is_empty() {
-            continue;
-        }
-        if line.starts_with("http") {
-            let url = HttpUrl::parse(line).map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
-            url_list.push(url);
-        } else {
-            return Err(HttpError::InvalidUrl(format!("Invalid URL in file, cannot locate full resource. Missing protocol scheme. `{line}`")));
-        }
-    }
-    Ok(url_list)
-}
-fn download_video_list(out_dir: &str, list: &[HttpUrl]) -> Result<(), HttpError> {
-    let client = Client::new();
-    println!("Downloading videos into `{out_dir}...`");
-    for video in cpbar::ProgressBar::new(list.iter()).with_bounds() {
-        if let Some(file_name) = video.file() {
-            let mut writer = std::fs::File::create(&format!("{}/{}", out_dir, file_name))?;
-            client
-                .get(&video.to_string())?
-                .download_to_file(&mut writer)?;
-        } else {
-            println!("Could not download file from url: {}", video);
         }
-    }
-
-    Ok(())
-}
-
-fn consolidate_files(out_dir: &str, name: &str, urls: &[HttpUrl]) -> Result<(), HttpError> {
-    println!("Consolidating downloaded stream...");
-    let mut output_file = std::fs::File::create(format!("{out_dir}/{name}"))?;
-
-    for video in cpbar::ProgressBar::new(urls.iter()).with_bounds() {
-        if let Some(file) = video.file() {
-            let mut in_file = std::fs::File::open(format!("{out_dir}/{file}"))?;
-            std::io::copy(&mut in_file, &mut output_file)?;
-            std::fs::remove_file(format!("{out_dir}/{file}"))?;
-        }
-    }
-    Ok(())
-}
-
-fn download_stream(local: bool, url: &str, file: &str) -> Result<(), HttpError> {
-    let url_list = if local {
-        read_playlist_url_list(url)?
     } else {
-        get_playlist_url_list(url)?
+        downloader.playlist(url)?
     };
-    let folder_name = format!("temp_{file}");
-    match std::fs::create_dir(&folder_name) {
-        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return Err(e.into()),
-        _ => (),
-    }
-    println!("Creating folder: `{folder_name}`");
-    download_video_list(&folder_name, &url_list)?;
-    consolidate_files(&folder_name, file, &url_list)?;
+    println!("Downloading stream into `{file}`...");
+    let mut output_file = std::fs::File::create(file)?;
+    downloader.download_playlist(&playlist, &mut output_file)?;
+    println!();
     Ok(())
 }
 
@@ -0,0 +1,133 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Values of any type attached to a request, holding at most one value per type.
+///
+/// Extensions let code around the client, such as middleware or retry loops, carry data along
+/// with a request without encoding it in headers. The extensions of a request are handed to its
+/// [`crate::response::Response`].
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// #[derive(Clone)]
+/// struct Attempt(u32);
+///
+/// fn main() -> Result<(), HttpError> {
+///     let response = Client::new()
+///         .get("https://example.com/")?
+///         .extension(Attempt(1))
+///         .send()?;
+///     let attempt = response.extensions().get::<Attempt>().map_or(0, |attempt| attempt.0);
+///     println!("Answered on attempt {attempt}");
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Extension>>,
+}
+
+impl Extensions {
+    /// Create an empty set of extensions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach a value, returning the previous value of the same type if there was one.
+    /// # Arguments
+    /// `value` Value being attached.
+    pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.into_any().downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    /// The value of the given type, if one is attached.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// The value of the given type for modification, if one is attached.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// Detach the value of the given type and return it, if one is attached.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.into_any().downcast().ok())
+            .map(|value| *value)
+    }
+
+    /// Number of values attached.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether no value is attached.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Detach every value.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl Clone for Extensions {
+    fn clone(&self) -> Self {
+        Extensions {
+            map: self
+                .map
+                .iter()
+                .map(|(id, value)| (*id, (**value).clone_box()))
+                .collect(),
+        }
+    }
+}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+/// A value that can be stored in [`Extensions`].
+trait Extension: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn Extension>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Clone + Send + Sync + 'static> Extension for T {
+    fn clone_box(&self) -> Box<dyn Extension> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
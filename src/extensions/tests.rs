@@ -0,0 +1,27 @@
+use crate::extensions::Extensions;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Attempt(u32);
+
+#[test]
+fn typed_values() {
+    let mut extensions = Extensions::new();
+    assert!(extensions.is_empty());
+    assert_eq!(extensions.insert(Attempt(1)), None);
+    assert_eq!(extensions.insert("context"), None);
+    assert_eq!(extensions.insert(Attempt(2)), Some(Attempt(1)));
+    assert_eq!(extensions.len(), 2);
+
+    extensions.get_mut::<Attempt>().unwrap().0 += 1;
+    assert_eq!(extensions.get::<Attempt>(), Some(&Attempt(3)));
+    assert_eq!(extensions.get::<&str>(), Some(&"context"));
+    assert_eq!(extensions.get::<u32>(), None);
+
+    let copy = extensions.clone();
+    assert_eq!(extensions.remove::<Attempt>(), Some(Attempt(3)));
+    assert_eq!(extensions.get::<Attempt>(), None);
+    assert_eq!(copy.get::<Attempt>(), Some(&Attempt(3)));
+
+    extensions.clear();
+    assert!(extensions.is_empty());
+}
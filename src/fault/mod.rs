@@ -0,0 +1,185 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::retry::random_fraction;
+
+#[cfg(test)]
+mod tests;
+
+/// Faults injected into the connections of a client to test how an application copes with
+/// a bad network, see [`crate::client::ClientBuilder::fault_injection`].
+///
+/// Faults apply to the response and to request bodies streamed from a reader, after TLS, so
+/// retries, download resumption and timeouts can be exercised against any server. The head
+/// of a request and a body held in memory are written as the connection is opened, before
+/// any fault applies.
+///
+/// # Example:
+/// ``` no_run
+/// use std::time::Duration;
+/// use http_client::{client::Client, error::HttpError, fault::FaultInjection};
+/// fn main() -> Result<(), HttpError> {
+///     let faults = FaultInjection::new()
+///         .latency(Duration::from_millis(300))
+///         .throughput(64 * 1024)
+///         .resets(0.01);
+///     let client = Client::builder().fault_injection(faults).build();
+///     let mut file = std::fs::File::create("large.iso")?;
+///     // Resumed after every reset
+///     client.get("https://example.com/large.iso")?.download_to_file(&mut file)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultInjection {
+    latency: Option<Duration>,
+    throughput: Option<u64>,
+    reset_rate: f64,
+    truncate_rate: f64,
+}
+
+impl FaultInjection {
+    /// No faults, add them with the other methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait before the first byte of every response is read.
+    /// # Arguments
+    /// `latency`   Time waited.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Transfer at most `bytes_per_second` in each direction of every connection, request
+    /// heads and bodies held in memory excepted.
+    /// # Arguments
+    /// `bytes_per_second`  Throughput allowed, at least one byte per second.
+    pub fn throughput(mut self, bytes_per_second: u64) -> Self {
+        self.throughput = Some(bytes_per_second.max(1));
+        self
+    }
+
+    /// Reset connections at random: every read of the response, or write of a streamed
+    /// request body, fails with [`ErrorKind::ConnectionReset`] with the given probability.
+    /// # Arguments
+    /// `probability`   Chance of a reset per read or write, between `0.0` and `1.0`.
+    pub fn resets(mut self, probability: f64) -> Self {
+        self.reset_rate = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Close connections early at random: every read ends the response with the given
+    /// probability, truncating its body.
+    /// # Arguments
+    /// `probability`   Chance of the response ending per read, between `0.0` and `1.0`.
+    pub fn truncation(mut self, probability: f64) -> Self {
+        self.truncate_rate = probability.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// A connection suffering the faults of a [`FaultInjection`], if any.
+pub(crate) struct Faulty<S> {
+    stream: S,
+    faults: Option<Arc<FaultInjection>>,
+    started: Instant,
+    /// Bytes transferred in each direction, to limit the throughput.
+    read: u64,
+    written: u64,
+    /// Whether the response was truncated.
+    truncated: bool,
+}
+
+impl<S> Faulty<S> {
+    pub(crate) fn new(stream: S, faults: Option<Arc<FaultInjection>>) -> Self {
+        Faulty {
+            stream,
+            faults,
+            started: Instant::now(),
+            read: 0,
+            written: 0,
+            truncated: false,
+        }
+    }
+
+    /// Helper method, fail with a reset connection at random.
+    fn maybe_reset(faults: &FaultInjection) -> std::io::Result<()> {
+        if faults.reset_rate > 0.0 && random_fraction() < faults.reset_rate {
+            return Err(std::io::Error::new(
+                ErrorKind::ConnectionReset,
+                "Connection reset by fault injection",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Helper method, wait until the throughput allows more bytes after `transferred` and
+    /// return how many of the `wanted` bytes can be transferred.
+    fn throttle(&self, faults: &FaultInjection, transferred: u64, wanted: usize) -> usize {
+        let Some(rate) = faults.throughput else {
+            return wanted;
+        };
+        // Transfers of a tenth of a second keep the rate smooth
+        let allowed = (rate / 10).max(1).min(wanted as u64) as usize;
+        let due = Duration::from_secs_f64((transferred + allowed as u64) as f64 / rate as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(wait);
+        }
+        allowed
+    }
+}
+
+impl<S: Read> Read for Faulty<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(faults) = self.faults.clone() else {
+            return self.stream.read(buf);
+        };
+        if self.truncated {
+            return Ok(0);
+        }
+        if self.read == 0 {
+            if let Some(latency) = faults.latency {
+                thread::sleep(latency);
+                // The throughput is measured from the first byte
+                self.started = Instant::now();
+            }
+        }
+        Self::maybe_reset(&faults)?;
+        if faults.truncate_rate > 0.0 && random_fraction() < faults.truncate_rate {
+            self.truncated = true;
+            return Ok(0);
+        }
+        let allowed = self.throttle(&faults, self.read, buf.len());
+        let read = self.stream.read(&mut buf[..allowed])?;
+        self.read += read as u64;
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Faulty<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some(faults) = self.faults.clone() else {
+            return self.stream.write(buf);
+        };
+        Self::maybe_reset(&faults)?;
+        let allowed = self.throttle(&faults, self.written, buf.len());
+        let written = self.stream.write(&buf[..allowed])?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl<S: AsRef<TcpStream>> AsRef<TcpStream> for Faulty<S> {
+    fn as_ref(&self) -> &TcpStream {
+        self.stream.as_ref()
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::{Cursor, ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::client::Client;
+use crate::fault::{FaultInjection, Faulty};
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn no_faults_pass_through() {
+    let mut faulty = Faulty::new(Cursor::new(b"hello".to_vec()), None);
+    let mut read = Vec::new();
+    faulty.read_to_end(&mut read).unwrap();
+    assert_eq!(read, b"hello");
+}
+
+#[test]
+fn latency_and_throughput() {
+    let faults = FaultInjection::new()
+        .latency(Duration::from_millis(50))
+        .throughput(1000);
+    let started = Instant::now();
+    let mut faulty = Faulty::new(Cursor::new(vec![0u8; 200]), Some(Arc::new(faults)));
+    let mut read = Vec::new();
+    faulty.read_to_end(&mut read).unwrap();
+    assert_eq!(read.len(), 200);
+    // 50 ms of latency, then 200 bytes at 1000 bytes per second
+    assert!(started.elapsed() >= Duration::from_millis(240));
+}
+
+#[test]
+fn resets_and_truncation() {
+    let resets = Arc::new(FaultInjection::new().resets(1.0));
+    let mut faulty = Faulty::new(Cursor::new(b"hello".to_vec()), Some(resets.clone()));
+    let error = faulty.read(&mut [0u8; 5]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ConnectionReset);
+    let mut faulty = Faulty::new(Vec::new(), Some(resets));
+    let error = faulty.write(b"hello").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ConnectionReset);
+
+    let truncation = Arc::new(FaultInjection::new().truncation(1.0));
+    let mut faulty = Faulty::new(Cursor::new(b"hello".to_vec()), Some(truncation));
+    let mut read = Vec::new();
+    faulty.read_to_end(&mut read).unwrap();
+    assert!(read.is_empty());
+}
+
+#[test]
+fn request_head_sent_before_faults() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "unreachable"));
+    let client = Client::builder()
+        .no_proxy()
+        .fault_injection(FaultInjection::new().resets(1.0))
+        .build();
+    let result = client.get(&server.url("/")).unwrap().send();
+    assert!(result.is_err());
+    // Only the response was reset
+    let started = Instant::now();
+    while server.requests().is_empty() && started.elapsed() < Duration::from_secs(1) {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(server.requests().len(), 1);
+}
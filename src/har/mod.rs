@@ -0,0 +1,272 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::encoding::base64_decode;
+use crate::error::HttpError;
+use crate::json::parser::JsonParser;
+use crate::json::JsonValue;
+
+#[cfg(test)]
+mod tests;
+
+/// Response headers describing how the body was sent, replaced since a HAR file holds the
+/// body without its transfer framing.
+const FRAMING_HEADERS: [&str; 2] = ["content-length", "transfer-encoding"];
+
+/// Custom field of the content of an entry whose body is kept with its content coding, e.g
+/// compressed, as recorded by [`crate::vcr::Cassette`].
+pub(crate) const ENCODED_FIELD: &str = "_encoded";
+
+/// A request and its response recorded in a HAR file.
+#[derive(Debug, Clone)]
+struct Entry {
+    method: String,
+    /// The URL of the request, normalized.
+    url: String,
+    request_headers: Vec<(String, String)>,
+    /// The response in wire format.
+    response: Vec<u8>,
+}
+
+/// Serves the responses recorded in a HAR (HTTP Archive) file instead of sending requests,
+/// see [`crate::client::ClientBuilder::har_replay`].
+///
+/// A request is answered by the recorded entry with the same method and URL, and the same
+/// values for the headers given to [`HarReplay::match_header`]. When several entries match,
+/// they are served in the order they were recorded, the last one being served again once
+/// every entry was. A request matching no entry fails, nothing is sent to the network.
+///
+/// Responses are served in HTTP/1.1 with their decoded body, so `Content-Encoding` and
+/// `Transfer-Encoding` are dropped and `Content-Length` is set to the size of the body.
+///
+/// # Example:
+/// ``` no_run
+/// use std::sync::Arc;
+/// use http_client::{client::Client, error::HttpError, har::HarReplay};
+/// fn main() -> Result<(), HttpError> {
+///     let replay = HarReplay::from_file("tests/fixtures/checkout.har")?.match_header("Accept");
+///     let client = Client::builder().har_replay(Arc::new(replay)).build();
+///     let response = client.get("https://shop.example.com/cart")?.send()?;
+///     assert_eq!(response.status_code(), 200);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HarReplay {
+    entries: Vec<Entry>,
+    /// Lowercase names of the request headers that must match.
+    match_headers: Vec<String>,
+    /// Whether each entry was served.
+    served: Mutex<Vec<bool>>,
+}
+
+impl HarReplay {
+    /// Load the entries of a HAR file.
+    /// # Arguments
+    /// `path`  Location of the file.
+    ///
+    /// # Errors
+    /// When the file cannot be read or is not a valid HAR file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, HttpError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Load the entries of a HAR document.
+    /// # Arguments
+    /// `text`  The HAR document, a JSON object.
+    ///
+    /// # Errors
+    /// When the document is not a valid HAR document.
+    pub fn parse(text: &str) -> Result<Self, HttpError> {
+        let invalid = |reason: &str| HttpError::Other(format!("Invalid HAR document: {reason}"));
+        let har = JsonParser::parse_json(text).map_err(|e| invalid(&e))?;
+        let entries = har
+            .get("log")
+            .and_then(|log| log.get("entries"))
+            .and_then(JsonValue::array)
+            .ok_or_else(|| invalid("missing `log.entries`"))?
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                Entry::parse(entry).map_err(|reason| invalid(&format!("entry {index}: {reason}")))
+            })
+            .collect::<Result<Vec<Entry>, HttpError>>()?;
+        Ok(HarReplay {
+            served: Mutex::new(vec![false; entries.len()]),
+            entries,
+            match_headers: Vec::new(),
+        })
+    }
+
+    /// Only answer a request with entries whose request had the same value for the given
+    /// header, e.g `Accept` to tell apart JSON and HTML responses to the same URL.
+    /// # Arguments
+    /// `name`  Name of the header, case insensitive.
+    pub fn match_header(mut self, name: &str) -> Self {
+        self.match_headers.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// The number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no entry was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The recorded response to a request in wire format.
+    /// # Arguments
+    /// `method`    Method of the request, e.g `GET`.
+    /// `url`       URL of the request.
+    /// `headers`   Header fields of the request.
+    ///
+    /// # Errors
+    /// When no entry matches the request.
+    pub(crate) fn respond(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<Vec<u8>, HttpError> {
+        let url = normalize_url(url);
+        let header = |headers: &[(String, String)], name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim().to_owned())
+        };
+        let matching = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.method.eq_ignore_ascii_case(method) && entry.url == url)
+            .filter(|(_, entry)| {
+                self.match_headers
+                    .iter()
+                    .all(|name| header(&entry.request_headers, name) == header(headers, name))
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+        let mut served = self
+            .served
+            .lock()
+            .map_err(|e| HttpError::Other(e.to_string()))?;
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&index| !served[index])
+            .or(matching.last().copied())
+            .ok_or_else(|| {
+                HttpError::Other(format!("No recorded HAR entry matches {method} {url}"))
+            })?;
+        served[index] = true;
+        Ok(self.entries[index].response.clone())
+    }
+}
+
+impl Entry {
+    /// Helper method, read an entry of the `log.entries` array.
+    fn parse(entry: &JsonValue) -> Result<Entry, String> {
+        let string = |value: &JsonValue, key: &str| {
+            value
+                .get(key)
+                .and_then(JsonValue::string)
+                .cloned()
+                .ok_or_else(|| format!("missing `{key}`"))
+        };
+        let request = entry.get("request").ok_or("missing `request`")?;
+        let response = entry.get("response").ok_or("missing `response`")?;
+        let status = response
+            .get("status")
+            .and_then(JsonValue::integer)
+            .copied()
+            .filter(|status| (100..1000).contains(status))
+            .ok_or("missing or invalid `response.status`")?;
+        let reason = string(response, "statusText").unwrap_or_default();
+        let content = response.get("content");
+        let text = content
+            .and_then(|content| content.get("text"))
+            .and_then(JsonValue::string)
+            .map_or("", String::as_str);
+        // Bodies are decoded unless marked otherwise
+        let encoded = content
+            .and_then(|content| content.get(ENCODED_FIELD))
+            .and_then(JsonValue::boolean)
+            .is_some_and(|encoded| *encoded);
+        let body = match content
+            .and_then(|content| content.get("encoding"))
+            .and_then(JsonValue::string)
+        {
+            Some(encoding) if encoding.eq_ignore_ascii_case("base64") => base64_decode(text)?,
+            _ => text.as_bytes().to_vec(),
+        };
+
+        let mut wire = format!("HTTP/1.1 {status} {reason}\r\n").into_bytes();
+        for (name, value) in headers(response)? {
+            // HTTP/2 pseudo-headers, e.g `:status`, are not headers of an HTTP/1.1 response
+            let name_lowercase = name.to_ascii_lowercase();
+            if name.starts_with(':')
+                || FRAMING_HEADERS.contains(&name_lowercase.as_str())
+                || (name_lowercase == "content-encoding" && !encoded)
+            {
+                continue;
+            }
+            wire.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        wire.extend_from_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+        wire.extend_from_slice(&body);
+        Ok(Entry {
+            method: string(request, "method")?,
+            url: normalize_url(&string(request, "url")?),
+            request_headers: headers(request)?,
+            response: wire,
+        })
+    }
+}
+
+/// Helper method, the `headers` array of a request or response.
+fn headers(message: &JsonValue) -> Result<Vec<(String, String)>, String> {
+    let Some(headers) = message.get("headers").and_then(JsonValue::array) else {
+        return Ok(Vec::new());
+    };
+    headers
+        .iter()
+        .map(|header| {
+            let field = |key: &str| header.get(key).and_then(JsonValue::string).cloned();
+            field("name")
+                .zip(field("value"))
+                .ok_or_else(|| "header without `name` or `value`".to_owned())
+        })
+        .collect()
+}
+
+/// A URL in a form comparable with other URLs to the same resource: scheme and host in
+/// lowercase, without the default port or a fragment, with a path.
+pub(crate) fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split_once('#').map_or(url, |(url, _)| url);
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_owned();
+    };
+    let scheme = scheme.to_ascii_lowercase();
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, target) = rest.split_at(authority_end);
+    let mut authority = authority.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => Some(":80"),
+        "https" => Some(":443"),
+        _ => None,
+    };
+    if let Some(port) = default_port.filter(|port| authority.ends_with(port)) {
+        authority.truncate(authority.len() - port.len());
+    }
+    let target = if target.starts_with('/') {
+        target.to_owned()
+    } else {
+        format!("/{target}")
+    };
+    format!("{scheme}://{authority}{target}")
+}
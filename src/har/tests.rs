@@ -0,0 +1,110 @@
+use crate::har::{normalize_url, HarReplay};
+
+const HAR: &str = r#"{
+  "log": {
+    "version": "1.2",
+    "entries": [
+      {
+        "request": {
+          "method": "GET",
+          "url": "https://Example.com:443/items?page=1",
+          "headers": [{"name": "Accept", "value": "application/json"}]
+        },
+        "response": {
+          "status": 200,
+          "statusText": "OK",
+          "headers": [
+            {"name": "Content-Type", "value": "application/json"},
+            {"name": "Content-Encoding", "value": "gzip"},
+            {"name": "Content-Length", "value": "9"}
+          ],
+          "content": {"mimeType": "application/json", "text": "{\"id\": 1}"}
+        }
+      },
+      {
+        "request": {
+          "method": "GET",
+          "url": "https://example.com/items?page=1",
+          "headers": [{"name": "Accept", "value": "text/html"}]
+        },
+        "response": {
+          "status": 200,
+          "statusText": "OK",
+          "headers": [],
+          "content": {"text": "PGI+aGk8L2I+", "encoding": "base64"}
+        }
+      },
+      {
+        "request": {"method": "GET", "url": "https://example.com/items?page=1", "headers": []},
+        "response": {"status": 503, "statusText": "Service Unavailable", "headers": []}
+      }
+    ]
+  }
+}"#;
+
+#[test]
+fn entries_served_in_order() {
+    let replay = HarReplay::parse(HAR).unwrap();
+    assert_eq!(replay.len(), 3);
+    let url = "https://example.com/items?page=1";
+    let first = replay.respond("GET", url, &[]).unwrap();
+    assert_eq!(
+        first,
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 1}"
+    );
+    let second = replay.respond("get", url, &[]).unwrap();
+    assert_eq!(
+        second,
+        b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n<b>hi</b>"
+    );
+    // The last entry is served again once every entry was
+    for _ in 0..2 {
+        let last = replay.respond("GET", url, &[]).unwrap();
+        assert!(last.starts_with(b"HTTP/1.1 503 Service Unavailable\r\n"));
+    }
+    assert!(replay.respond("POST", url, &[]).is_err());
+    assert!(replay
+        .respond("GET", "https://example.com/items", &[])
+        .is_err());
+}
+
+#[test]
+fn entries_matched_on_headers() {
+    let replay = HarReplay::parse(HAR).unwrap().match_header("accept");
+    let accept = |value: &str| vec![("Accept".to_owned(), value.to_owned())];
+    let url = "https://example.com/items?page=1";
+    let html = replay.respond("GET", url, &accept("text/html")).unwrap();
+    assert!(html.ends_with(b"<b>hi</b>"));
+    let json = replay
+        .respond("GET", url, &accept("application/json"))
+        .unwrap();
+    assert!(json.ends_with(b"{\"id\": 1}"));
+    let none = replay.respond("GET", url, &[]).unwrap();
+    assert!(none.starts_with(b"HTTP/1.1 503"));
+    assert!(replay.respond("GET", url, &accept("image/png")).is_err());
+}
+
+#[test]
+fn invalid_documents_rejected() {
+    assert!(HarReplay::parse("{}").is_err());
+    assert!(HarReplay::parse(r#"{"log": {"entries": [{"request": {}}]}}"#).is_err());
+    assert!(HarReplay::parse(r#"{"log": {"entries": []}}"#)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn urls_normalized() {
+    assert_eq!(
+        normalize_url("HTTPS://Example.COM:443?q=A#top"),
+        "https://example.com/?q=A"
+    );
+    assert_eq!(
+        normalize_url("http://example.com:8080/Path"),
+        "http://example.com:8080/Path"
+    );
+    assert_eq!(
+        normalize_url("http://example.com:80"),
+        "http://example.com/"
+    );
+}
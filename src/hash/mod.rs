@@ -0,0 +1,308 @@
+use std::fmt::Display;
+use std::io::Write;
+
+use hmac::{Hmac, Mac};
+use sha2::Digest;
+
+use crate::encoding::{base64_decode, base64_encode};
+use crate::error::HttpError;
+use crate::structured::{self, BareItem, Member};
+
+#[cfg(test)]
+mod tests;
+
+/// Format bytes as lowercase hexadecimal text.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Incremental MD5 hasher (RFC 1321).
+///
+/// MD5 is broken for security purposes, it is only provided for protocols that still require it.
+#[derive(Debug, Clone, Default)]
+pub struct Md5(md5::Md5);
+
+impl Md5 {
+    /// Create a new hasher
+    pub fn new() -> Self {
+        Md5(md5::Md5::new())
+    }
+
+    /// Hash the given bytes at once.
+    pub fn digest(data: &[u8]) -> [u8; 16] {
+        md5::Md5::digest(data).into()
+    }
+
+    /// Add data to the hash.
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Finish hashing and return the 16 byte digest.
+    pub fn finalize(self) -> [u8; 16] {
+        self.0.finalize().into()
+    }
+}
+
+/// Incremental SHA-256 hasher (FIPS 180-4).
+#[derive(Debug, Clone, Default)]
+pub struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    /// Create a new hasher
+    pub fn new() -> Self {
+        Sha256(sha2::Sha256::new())
+    }
+
+    /// Hash the given bytes at once.
+    pub fn digest(data: &[u8]) -> [u8; 32] {
+        sha2::Sha256::digest(data).into()
+    }
+
+    /// Add data to the hash.
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Finish hashing and return the 32 byte digest.
+    pub fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+/// HMAC-SHA256 of `data` with the given key (RFC 2104), e.g to sign requests.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    // save to unwrap, HMAC accepts keys of any length
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// The expected digest of a download, verified while it is written.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, hash::Checksum};
+/// fn main() -> Result<(), HttpError> {
+///     let expected = Checksum::sha256("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")?;
+///     let mut file = std::fs::File::create("release.tar.gz")?;
+///     Client::new()
+///         .get("https://example.com/release.tar.gz")?
+///         .checksum(expected)
+///         .download_to_file(&mut file)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    Sha256([u8; 32]),
+    Md5([u8; 16]),
+}
+
+impl Checksum {
+    /// A SHA-256 digest given in hexadecimal, as published next to many downloads.
+    /// # Arguments
+    /// `digest`    The 64 hexadecimal digits of the digest.
+    pub fn sha256(digest: &str) -> Result<Checksum, HttpError> {
+        Ok(Checksum::Sha256(parse_hex(digest)?))
+    }
+
+    /// A MD5 digest given in hexadecimal, e.g the `ETag` S3 sends for objects uploaded in
+    /// a single part.
+    /// # Arguments
+    /// `digest`    The 32 hexadecimal digits of the digest, quotes are ignored.
+    pub fn md5(digest: &str) -> Result<Checksum, HttpError> {
+        Ok(Checksum::Md5(parse_hex(digest.trim_matches('"'))?))
+    }
+
+    /// The checksum announced by a `Repr-Digest` or `Content-Digest` header (RFC 9530),
+    /// SHA-256 is preferred over MD5, other algorithms are not supported.
+    pub(crate) fn from_digest_header(value: &str) -> Option<Checksum> {
+        let digests = structured::parse_dictionary(value).ok()?;
+        let digest = |algorithm: &str| {
+            digests.iter().find_map(|(key, member)| match member {
+                Member::Item(item) if key == algorithm => match item.value() {
+                    BareItem::ByteSequence(digest) => Some(digest.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+        };
+        let sha256 = digest("sha-256").and_then(|digest| digest.try_into().ok());
+        let md5 = digest("md5").and_then(|digest| digest.try_into().ok());
+        sha256
+            .map(Checksum::Sha256)
+            .or_else(|| md5.map(Checksum::Md5))
+    }
+
+    /// The checksum announced by a `Content-MD5` header (RFC 1864), base64 encoded.
+    pub(crate) fn from_content_md5(value: &str) -> Option<Checksum> {
+        let digest = base64_decode(value).ok()?;
+        Some(Checksum::Md5(digest.try_into().ok()?))
+    }
+
+    /// This checksum as the value of a `Content-Digest` or `Repr-Digest` header (RFC 9530).
+    pub(crate) fn digest_header(&self) -> String {
+        let algorithm = match self {
+            Checksum::Sha256(_) => "sha-256",
+            Checksum::Md5(_) => "md5",
+        };
+        format!("{algorithm}=:{}:", base64_encode(self.bytes()))
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Checksum::Sha256(digest) => digest,
+            Checksum::Md5(digest) => digest,
+        }
+    }
+}
+
+impl Display for Checksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Checksum::Sha256(_) => write!(f, "sha-256:{}", hex(self.bytes())),
+            Checksum::Md5(_) => write!(f, "md5:{}", hex(self.bytes())),
+        }
+    }
+}
+
+/// Helper method, parse a digest written in hexadecimal.
+fn parse_hex<const N: usize>(digest: &str) -> Result<[u8; N], HttpError> {
+    let invalid = || HttpError::Other(format!("Invalid digest `{digest}`"));
+    let digest = digest.trim();
+    if digest.len() != N * 2 || !digest.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; N];
+    for (byte, pair) in bytes.iter_mut().zip(digest.as_bytes().chunks(2)) {
+        // Only ASCII was accepted above
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+/// A writer hashing the bytes written through it, to be compared with a checksum at the end.
+pub(crate) struct ChecksumWriter<W> {
+    inner: W,
+    expected: Option<Checksum>,
+    sha256: Sha256,
+    md5: Md5,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    /// Create a writer verifying `expected`, if any.
+    pub(crate) fn new(inner: W, expected: Option<Checksum>) -> Self {
+        ChecksumWriter {
+            inner,
+            expected,
+            sha256: Sha256::new(),
+            md5: Md5::new(),
+        }
+    }
+
+    /// Verify `expected` instead, only before anything is written.
+    pub(crate) fn expect(&mut self, expected: Option<Checksum>) {
+        self.expected = expected;
+    }
+
+    /// The writer the bytes are written to.
+    pub(crate) fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The writer the bytes are written to, for modification.
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Compare the digest of the bytes written with the expected checksum.
+    ///
+    /// # Errors
+    /// When they differ.
+    pub(crate) fn finish(self) -> Result<W, HttpError> {
+        let actual = match &self.expected {
+            None => return Ok(self.inner),
+            Some(Checksum::Sha256(_)) => Checksum::Sha256(self.sha256.finalize()),
+            Some(Checksum::Md5(_)) => Checksum::Md5(self.md5.finalize()),
+        };
+        match self.expected {
+            Some(expected) if expected != actual => Err(HttpError::ChecksumMismatch(format!(
+                "Checksum mismatch, expected `{expected}` but got `{actual}`"
+            ))),
+            _ => Ok(self.inner),
+        }
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        match self.expected {
+            Some(Checksum::Sha256(_)) => self.sha256.update(&buf[..written]),
+            Some(Checksum::Md5(_)) => self.md5.update(&buf[..written]),
+            None => (),
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Verifies content against the `Content-Digest` announced for it (RFC 9530), hashing it
+/// while it is read.
+///
+/// The digest may only be known once the content was read, e.g sent in the trailers of a
+/// chunked body, both supported algorithms are computed until then.
+pub(crate) struct DigestVerifier {
+    expected: Option<Checksum>,
+    sha256: Sha256,
+    md5: Md5,
+}
+
+impl DigestVerifier {
+    /// Create a verifier of the `expected` digest, if already known.
+    pub(crate) fn new(expected: Option<Checksum>) -> Self {
+        DigestVerifier {
+            expected,
+            sha256: Sha256::new(),
+            md5: Md5::new(),
+        }
+    }
+
+    /// Hash the next bytes of the content.
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self.expected {
+            Some(Checksum::Sha256(_)) => self.sha256.update(data),
+            Some(Checksum::Md5(_)) => self.md5.update(data),
+            None => {
+                self.sha256.update(data);
+                self.md5.update(data);
+            }
+        }
+    }
+
+    /// Compare the digest of the content with the expected one, or with `late` when none was
+    /// known at first. Content without a digest is accepted.
+    ///
+    /// # Errors
+    /// When they differ.
+    pub(crate) fn verify(self, late: Option<Checksum>) -> Result<(), HttpError> {
+        let Some(expected) = self.expected.or(late) else {
+            return Ok(());
+        };
+        let actual = match expected {
+            Checksum::Sha256(_) => Checksum::Sha256(self.sha256.finalize()),
+            Checksum::Md5(_) => Checksum::Md5(self.md5.finalize()),
+        };
+        if actual != expected {
+            return Err(HttpError::ChecksumMismatch(format!(
+                "Content-Digest mismatch, expected `{expected}` but got `{actual}`"
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::Write;
+
+use crate::hash::{hex, hmac_sha256, Checksum, ChecksumWriter, Md5, Sha256};
+
+#[test]
+fn md5_known_values() {
+    assert_eq!(hex(&Md5::digest(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+    assert_eq!(
+        hex(&Md5::digest(b"The quick brown fox jumps over the lazy dog")),
+        "9e107d9d372bb6826bd81d3542a419d6"
+    );
+    let mut hasher = Md5::new();
+    for chunk in [b"12345678901234567890".as_slice(); 4] {
+        hasher.update(chunk);
+    }
+    assert_eq!(hex(&hasher.finalize()), "57edf4a22be3c955ac49da2e2107b67a");
+}
+
+#[test]
+fn sha256_known_values() {
+    assert_eq!(
+        hex(&Sha256::digest(b"")),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        hex(&Sha256::digest(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    let mut hasher = Sha256::new();
+    for _ in 0..1000 {
+        hasher.update(&[b'a'; 1000]);
+    }
+    assert_eq!(
+        hex(&hasher.finalize()),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn parse_checksums() {
+    let md5 = "d41d8cd98f00b204e9800998ecf8427e";
+    assert_eq!(
+        Checksum::md5(&format!("\"{md5}\"")).unwrap(),
+        Checksum::Md5(Md5::digest(b""))
+    );
+    assert!(Checksum::md5("d41d8cd98f00b204").is_err());
+    assert!(Checksum::sha256(md5).is_err());
+    assert_eq!(
+        Checksum::from_digest_header("md5=:1B2M2Y8AsgTpgAmY7PhCfg==:, sha-256=:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=:"),
+        Some(Checksum::Sha256(Sha256::digest(b"")))
+    );
+    assert_eq!(
+        Checksum::from_content_md5("1B2M2Y8AsgTpgAmY7PhCfg=="),
+        Some(Checksum::Md5(Md5::digest(b"")))
+    );
+    assert_eq!(Checksum::from_digest_header("sha-512=:AAAA:"), None);
+}
+
+#[test]
+fn verify_written_data() {
+    let expected = Checksum::Sha256(Sha256::digest(b"hello world"));
+    let mut writer = ChecksumWriter::new(Vec::new(), Some(expected.clone()));
+    writer.write_all(b"hello ").unwrap();
+    writer.write_all(b"world").unwrap();
+    assert_eq!(writer.finish().unwrap(), b"hello world");
+
+    let mut writer = ChecksumWriter::new(Vec::new(), Some(expected));
+    writer.write_all(b"hello").unwrap();
+    assert!(writer.finish().is_err());
+}
+
+#[test]
+fn hmac_sha256_vectors() {
+    // RFC 4231 test cases 2 and 6
+    assert_eq!(
+        hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
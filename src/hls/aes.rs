@@ -0,0 +1,40 @@
+use ::aes::cipher::{block_padding::Pkcs7, BlockDecrypt, BlockDecryptMut, KeyInit, KeyIvInit};
+
+use crate::error::HttpError;
+
+/// An AES-128 key (FIPS 197), for the segments of encrypted playlists.
+pub(crate) struct Aes128 {
+    key: [u8; 16],
+}
+
+impl Aes128 {
+    /// Use the given key.
+    pub(crate) fn new(key: &[u8; 16]) -> Aes128 {
+        Aes128 { key: *key }
+    }
+
+    /// Decrypt a single block.
+    pub(crate) fn decrypt_block(&self, block: &mut [u8; 16]) {
+        ::aes::Aes128::new(&self.key.into()).decrypt_block(block.into());
+    }
+
+    /// Decrypt data encrypted in CBC mode with PKCS#7 padding, as used by HLS `AES-128`.
+    ///
+    /// # Errors
+    /// When the data is not made of whole blocks or its padding is invalid, e.g with a wrong key.
+    pub(crate) fn decrypt_cbc(&self, iv: &[u8; 16], data: &[u8]) -> Result<Vec<u8>, HttpError> {
+        if data.is_empty() || !data.len().is_multiple_of(16) {
+            return Err(HttpError::Other(format!(
+                "Encrypted data of {} bytes is not made of 16 byte blocks",
+                data.len()
+            )));
+        }
+        cbc::Decryptor::<::aes::Aes128>::new(&self.key.into(), &(*iv).into())
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+            .map_err(|_| {
+                HttpError::Other(
+                    "Invalid padding in decrypted data, the key may be wrong".to_owned(),
+                )
+            })
+    }
+}
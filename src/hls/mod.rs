@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use http_parse::H_CONTENT_RANGE;
+
+use crate::body::{self, ProgressFn};
+use crate::client::{self, Client};
+use crate::error::HttpError;
+use crate::multipart;
+use crate::retry::RetryPolicy;
+
+mod aes;
+mod playlist;
+
+#[cfg(test)]
+mod tests;
+
+use self::aes::Aes128;
+pub use playlist::{
+    InitSection, Key, KeyMethod, MasterPlaylist, MediaPlaylist, Playlist, Segment, Variant,
+};
+
+/// Segments downloaded at the same time, unless configured otherwise.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Attempts made to download a segment after the first one failed, unless configured otherwise.
+const DEFAULT_RETRIES: usize = 2;
+
+/// Downloads HLS streams (RFC 8216), writing their segments one after the other.
+///
+/// A master playlist is resolved to the variant with the highest bandwidth, see
+/// [`HlsDownloader::max_bandwidth`]. Segments are downloaded several at a time and written
+/// in playing order. A segment failing because of a network error or a `429` or `5xx`
+/// response is downloaded again after a growing delay, as with [`RetryPolicy`]. Byte-range
+/// segments are requested with `Range` headers and `AES-128` encrypted segments are
+/// decrypted.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, hls::HlsDownloader};
+/// fn main() -> Result<(), HttpError> {
+///     let mut file = std::fs::File::create("stream.ts")?;
+///     HlsDownloader::new(Client::new())
+///         .concurrency(8)
+///         .on_progress(|done, total| println!("{done}/{} segments", total.unwrap_or_default()))
+///         .download("https://example.com/stream/master.m3u8", &mut file)?;
+///     Ok(())
+/// }
+/// ```
+pub struct HlsDownloader {
+    client: Client,
+    concurrency: usize,
+    retry: RetryPolicy,
+    max_bandwidth: Option<u64>,
+    progress: Option<ProgressFn>,
+}
+
+impl HlsDownloader {
+    /// Create a downloader sending its requests with the given client.
+    pub fn new(client: Client) -> Self {
+        HlsDownloader {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            retry: RetryPolicy::new().max_attempts(DEFAULT_RETRIES + 1),
+            max_bandwidth: None,
+            progress: None,
+        }
+    }
+
+    /// Download up to the given number of segments at the same time.
+    /// # Arguments
+    /// `segments`  Segments downloaded at once, at least one.
+    pub fn concurrency(mut self, segments: usize) -> Self {
+        self.concurrency = segments.max(1);
+        self
+    }
+
+    /// Try downloading a failed segment again, up to the given number of times.
+    /// # Arguments
+    /// `retries`   Attempts made after the first one failed.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retry = self.retry.max_attempts(retries.saturating_add(1));
+        self
+    }
+
+    /// Wait `base` before downloading a failed segment again, doubling the delay after every
+    /// attempt up to `cap`.
+    /// # Arguments
+    /// `base`  Delay before the first retry.
+    /// `cap`   Longest delay between two attempts.
+    pub fn backoff(mut self, base: Duration, cap: Duration) -> Self {
+        self.retry = self.retry.backoff(base, cap);
+        self
+    }
+
+    /// Pick the variant of a master playlist with the highest bandwidth not above the given one.
+    /// # Arguments
+    /// `bandwidth` Highest bandwidth accepted in bits per second.
+    pub fn max_bandwidth(mut self, bandwidth: u64) -> Self {
+        self.max_bandwidth = Some(bandwidth);
+        self
+    }
+
+    /// Report the progress of downloads.
+    ///
+    /// `callback` is called with the number of segments written so far and the number of
+    /// segments of the stream every time a segment is written.
+    /// # Arguments
+    /// `callback`  Called with the segments downloaded and their total, e.g to draw a progress bar.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        self.progress = Some(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    /// Fetch the media playlist at the given URL, or the one of the chosen variant when it is
+    /// a master playlist.
+    /// # Arguments
+    /// `url`   URL of the playlist.
+    pub fn playlist(&self, url: &str) -> Result<MediaPlaylist, HttpError> {
+        let mut url = url.to_owned();
+        // A master playlist leads to a media playlist
+        for _ in 0..2 {
+            let response = self.client.get(&url)?.send()?;
+            if !(200..300).contains(&response.status_code()) {
+                return Err(HttpError::BadResponse(
+                    response.status_code(),
+                    response.status_msg().to_owned(),
+                ));
+            }
+            match Playlist::parse(&response.text(), response.url())? {
+                Playlist::Media(playlist) => return Ok(playlist),
+                Playlist::Master(master) => {
+                    let variant = master.best_variant(self.max_bandwidth).ok_or_else(|| {
+                        HttpError::Other("Master playlist without variants".to_owned())
+                    })?;
+                    url = variant.uri().to_owned();
+                }
+            }
+        }
+        Err(HttpError::Other(format!(
+            "Expected a media playlist at `{url}`, got a master playlist"
+        )))
+    }
+
+    /// Download the stream of the playlist at the given URL into `writer`.
+    /// # Arguments
+    /// `url`       URL of a master or media playlist.
+    /// `writer`    Destination of the segments.
+    pub fn download<W: Write>(&self, url: &str, writer: &mut W) -> Result<(), HttpError> {
+        let playlist = self.playlist(url)?;
+        self.download_playlist(&playlist, writer)
+    }
+
+    /// Download the segments of a media playlist into `writer`, in playing order.
+    ///
+    /// The initialization section of the segments, if any, is written before the first
+    /// segment needing it.
+    /// # Arguments
+    /// `playlist`  Playlist being downloaded.
+    /// `writer`    Destination of the segments.
+    pub fn download_playlist<W: Write>(
+        &self,
+        playlist: &MediaPlaylist,
+        writer: &mut W,
+    ) -> Result<(), HttpError> {
+        let segments = playlist.segments();
+        let mut keys = HashMap::new();
+        for segment in segments {
+            self.fetch_key(segment, &mut keys)?;
+        }
+        let keys = &keys;
+        let failed = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let sender = sender;
+                client::run_parallel(segments, self.concurrency, |index, segment| {
+                    // Segments after a failure are not needed
+                    if !failed.load(Ordering::Relaxed) {
+                        let data = self.fetch_segment(segment, keys);
+                        failed.fetch_or(data.is_err(), Ordering::Relaxed);
+                        let _ = sender.send((index, data));
+                    }
+                });
+            });
+            let result = self.write_segments(segments, receiver, writer);
+            failed.fetch_or(result.is_err(), Ordering::Relaxed);
+            result
+        })
+    }
+
+    /// Helper method, write the segments received from the workers in playing order, a
+    /// segment being written as soon as the ones before it were.
+    fn write_segments<W: Write>(
+        &self,
+        segments: &[Segment],
+        receiver: mpsc::Receiver<(usize, Result<Vec<u8>, HttpError>)>,
+        writer: &mut W,
+    ) -> Result<(), HttpError> {
+        let total = segments.len() as u64;
+        let mut received = HashMap::new();
+        let mut init_section = None;
+        let mut next = 0;
+        for (index, data) in receiver {
+            received.insert(index, data);
+            while let Some(data) = received.remove(&next) {
+                let data = data?;
+                let segment = &segments[next];
+                if segment.init_section() != init_section {
+                    init_section = segment.init_section();
+                    if let Some(section) = init_section {
+                        writer.write_all(&self.fetch(section.uri(), section.byte_range())?)?;
+                    }
+                }
+                writer.write_all(&data)?;
+                next += 1;
+                if let Some(progress) = &self.progress {
+                    body::report(progress, next as u64, Some(total));
+                }
+            }
+        }
+        if next < segments.len() {
+            return Err(HttpError::Other(format!(
+                "Segment `{}` was not downloaded",
+                segments[next].uri()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Helper method, download the key of a segment unless it is known already.
+    fn fetch_key(
+        &self,
+        segment: &Segment,
+        keys: &mut HashMap<String, Aes128>,
+    ) -> Result<(), HttpError> {
+        let Some(key) = segment.key() else {
+            return Ok(());
+        };
+        if key.method() != &KeyMethod::Aes128 {
+            return Err(HttpError::Other(format!(
+                "Unsupported encryption method {:?} for segment `{}`",
+                key.method(),
+                segment.uri()
+            )));
+        }
+        let uri = key
+            .uri()
+            .ok_or_else(|| HttpError::Other("AES-128 key without URI".to_owned()))?;
+        if !keys.contains_key(uri) {
+            let bytes = self.fetch(uri, None)?;
+            let bytes = <[u8; 16]>::try_from(bytes.as_slice()).map_err(|_| {
+                HttpError::Other(format!(
+                    "Expected a 16 byte AES-128 key at `{uri}`, got {} bytes",
+                    bytes.len()
+                ))
+            })?;
+            keys.insert(uri.to_owned(), Aes128::new(&bytes));
+        }
+        Ok(())
+    }
+
+    /// Helper method, download a segment and decrypt it if needed.
+    fn fetch_segment(
+        &self,
+        segment: &Segment,
+        keys: &HashMap<String, Aes128>,
+    ) -> Result<Vec<u8>, HttpError> {
+        let data = self.fetch(segment.uri(), segment.byte_range())?;
+        let Some(key) = segment.key() else {
+            return Ok(data);
+        };
+        // Keys are fetched before their segments
+        let cipher = key
+            .uri()
+            .and_then(|uri| keys.get(uri))
+            .ok_or_else(|| HttpError::Other("Missing AES-128 key".to_owned()))?;
+        // Without an explicit IV the sequence number of the segment is used
+        let iv = key
+            .iv()
+            .unwrap_or_else(|| u128::from(segment.sequence()).to_be_bytes());
+        cipher.decrypt_cbc(&iv, &data)
+    }
+
+    /// Helper method, download a resource or part of it, trying again after a delay when it
+    /// fails for a reason that may not last.
+    fn fetch(&self, url: &str, range: Option<&Range<u64>>) -> Result<Vec<u8>, HttpError> {
+        let mut retry = 0;
+        loop {
+            let result = self.fetch_once(url, range);
+            let again = retry + 1 < self.retry.attempts()
+                && match &result {
+                    Ok(_) => false,
+                    Err(HttpError::BadResponse(status, _)) => self.retry.retries_status(*status),
+                    Err(error) => self.retry.retries_error(error),
+                };
+            if !again {
+                return result;
+            }
+            std::thread::sleep(self.retry.delay(retry));
+            retry += 1;
+        }
+    }
+
+    /// Helper method, download a resource or part of it.
+    fn fetch_once(&self, url: &str, range: Option<&Range<u64>>) -> Result<Vec<u8>, HttpError> {
+        let mut request = self.client.get(url)?;
+        if let Some(range) = range {
+            request = request.range(range.clone())?;
+        }
+        let response = request.send()?;
+        let status = response.status_code();
+        let content_range = response.header_value(H_CONTENT_RANGE);
+        let data = response.data().to_vec();
+        match (status, range) {
+            (200, None) => Ok(data),
+            // The server must send the bytes that were asked for
+            (206, Some(range)) => {
+                let sent = content_range
+                    .and_then(|value| multipart::parse_content_range(&value))
+                    .map(|(start, end, _)| start..end.saturating_add(1));
+                if sent.as_ref() != Some(range) || data.len() as u64 != range.end - range.start {
+                    return Err(HttpError::BadResponse(
+                        status,
+                        format!("Resource `{url}` was sent with a range other than the segment's"),
+                    ));
+                }
+                Ok(data)
+            }
+            // A server ignoring the range sends the whole resource
+            (200, Some(range)) => data
+                .get(range.start as usize..range.end as usize)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| {
+                    HttpError::BadResponse(
+                        status,
+                        format!("Resource `{url}` is shorter than the segment range"),
+                    )
+                }),
+            _ => Err(HttpError::BadResponse(
+                status,
+                format!("Failed to download `{url}`"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::error::HttpError;
+use crate::mime::split_list;
+use crate::redirect::resolve_location;
+
+/// An HLS playlist (RFC 8216), either listing the variants of a stream or its media segments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Playlist {
+    Master(MasterPlaylist),
+    Media(MediaPlaylist),
+}
+
+impl Playlist {
+    /// Parse an M3U8 playlist, resolving the URIs it holds against the URL it came from.
+    /// # Arguments
+    /// `text`  Content of the playlist.
+    /// `url`   URL of the playlist, relative URIs are kept as they are when empty.
+    ///
+    /// # Errors
+    /// When the text is not an M3U8 playlist or one of its tags is invalid.
+    pub fn parse(text: &str, url: &str) -> Result<Playlist, HttpError> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some("#EXTM3U") {
+            return Err(invalid("missing #EXTM3U header"));
+        }
+        let mut variants = Vec::new();
+        let mut media = MediaPlaylist::default();
+        // Tags applying to the next URI
+        let mut stream_info = None;
+        let mut duration = None;
+        let mut byte_range: Option<(u64, Option<u64>)> = None;
+        // Tags applying to every following segment
+        let mut key = None;
+        let mut init_section = None;
+        let mut next_offset = 0;
+        for line in lines {
+            let Some(tag) = line.strip_prefix('#') else {
+                let uri = resolve(url, line);
+                if let Some(variant) = stream_info.take() {
+                    variants.push(Variant { uri, ..variant });
+                    continue;
+                }
+                let (duration, title) = duration
+                    .take()
+                    .ok_or_else(|| invalid(&format!("segment `{line}` without #EXTINF")))?;
+                let byte_range = match byte_range.take() {
+                    Some((length, offset)) => {
+                        let start = offset.unwrap_or(next_offset);
+                        next_offset = start
+                            .checked_add(length)
+                            .ok_or_else(|| invalid(&format!("segment `{line}` out of range")))?;
+                        Some(start..next_offset)
+                    }
+                    None => None,
+                };
+                let sequence = media
+                    .media_sequence
+                    .checked_add(media.segments.len() as u64)
+                    .ok_or_else(|| invalid("#EXT-X-MEDIA-SEQUENCE out of range"))?;
+                media.segments.push(Segment {
+                    uri,
+                    duration,
+                    title,
+                    byte_range,
+                    key: key.clone(),
+                    init_section: init_section.clone(),
+                    sequence,
+                });
+                continue;
+            };
+            let (name, value) = tag.split_once(':').unwrap_or((tag, ""));
+            match name {
+                "EXT-X-STREAM-INF" => {
+                    let attributes = attributes(value);
+                    let bandwidth = attribute(&attributes, "BANDWIDTH")
+                        .and_then(|bandwidth| bandwidth.parse().ok())
+                        .ok_or_else(|| invalid("#EXT-X-STREAM-INF without BANDWIDTH"))?;
+                    let resolution = attribute(&attributes, "RESOLUTION").and_then(|resolution| {
+                        let (width, height) = resolution.split_once(['x', 'X'])?;
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    });
+                    stream_info = Some(Variant {
+                        uri: String::new(),
+                        bandwidth,
+                        resolution,
+                        codecs: attribute(&attributes, "CODECS").map(str::to_owned),
+                    });
+                }
+                "EXTINF" => {
+                    let (seconds, title) = value.split_once(',').unwrap_or((value, ""));
+                    // Negative, infinite or too large durations are invalid
+                    let seconds = seconds
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                        .ok_or_else(|| invalid(&format!("invalid #EXTINF `{value}`")))?;
+                    let title = Some(title.trim())
+                        .filter(|title| !title.is_empty())
+                        .map(str::to_owned);
+                    duration = Some((seconds, title));
+                }
+                "EXT-X-BYTERANGE" => {
+                    byte_range =
+                        Some(parse_byte_range(value).ok_or_else(|| {
+                            invalid(&format!("invalid #EXT-X-BYTERANGE `{value}`"))
+                        })?);
+                }
+                "EXT-X-TARGETDURATION" => {
+                    let seconds = value.trim().parse().map_err(|_| {
+                        invalid(&format!("invalid #EXT-X-TARGETDURATION `{value}`"))
+                    })?;
+                    media.target_duration = Duration::from_secs(seconds);
+                }
+                "EXT-X-MEDIA-SEQUENCE" => {
+                    media.media_sequence = value.trim().parse().map_err(|_| {
+                        invalid(&format!("invalid #EXT-X-MEDIA-SEQUENCE `{value}`"))
+                    })?;
+                }
+                "EXT-X-KEY" => key = Key::parse(value, url)?,
+                "EXT-X-MAP" => {
+                    let attributes = attributes(value);
+                    let uri = attribute(&attributes, "URI")
+                        .ok_or_else(|| invalid("#EXT-X-MAP without URI"))?;
+                    let byte_range = match attribute(&attributes, "BYTERANGE") {
+                        Some(range) => {
+                            let (length, offset) = parse_byte_range(range)
+                                .ok_or_else(|| invalid(&format!("invalid BYTERANGE `{range}`")))?;
+                            let offset = offset.unwrap_or_default();
+                            let end = offset
+                                .checked_add(length)
+                                .ok_or_else(|| invalid(&format!("invalid BYTERANGE `{range}`")))?;
+                            Some(offset..end)
+                        }
+                        None => None,
+                    };
+                    init_section = Some(InitSection {
+                        uri: resolve(url, uri),
+                        byte_range,
+                    });
+                }
+                "EXT-X-ENDLIST" => media.ended = true,
+                // Comments and unknown tags are ignored
+                _ => (),
+            }
+        }
+        if variants.is_empty() {
+            Ok(Playlist::Media(media))
+        } else {
+            Ok(Playlist::Master(MasterPlaylist { variants }))
+        }
+    }
+}
+
+/// A playlist listing the variants of a stream, e.g in different resolutions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterPlaylist {
+    variants: Vec<Variant>,
+}
+
+impl MasterPlaylist {
+    /// The variants of the stream, in the order they are listed.
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    /// The variant with the highest bandwidth not above `max_bandwidth`, if any, or the one
+    /// with the lowest bandwidth when they are all above it.
+    /// # Arguments
+    /// `max_bandwidth` Highest bandwidth accepted in bits per second.
+    pub fn best_variant(&self, max_bandwidth: Option<u64>) -> Option<&Variant> {
+        let max_bandwidth = max_bandwidth.unwrap_or(u64::MAX);
+        self.variants
+            .iter()
+            .filter(|variant| variant.bandwidth <= max_bandwidth)
+            .max_by_key(|variant| variant.bandwidth)
+            .or_else(|| self.variants.iter().min_by_key(|variant| variant.bandwidth))
+    }
+}
+
+/// A variant of a stream, listed by a [`MasterPlaylist`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    uri: String,
+    bandwidth: u64,
+    resolution: Option<(u32, u32)>,
+    codecs: Option<String>,
+}
+
+impl Variant {
+    /// URL of the media playlist of this variant.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Peak bandwidth of this variant in bits per second.
+    pub fn bandwidth(&self) -> u64 {
+        self.bandwidth
+    }
+
+    /// Width and height of the video of this variant, if any.
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        self.resolution
+    }
+
+    /// Codecs of this variant, e.g `avc1.4d401f,mp4a.40.2`.
+    pub fn codecs(&self) -> Option<&str> {
+        self.codecs.as_deref()
+    }
+}
+
+/// A playlist listing the media segments of a stream.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaPlaylist {
+    target_duration: Duration,
+    media_sequence: u64,
+    segments: Vec<Segment>,
+    ended: bool,
+}
+
+impl MediaPlaylist {
+    /// Longest duration of a segment.
+    pub fn target_duration(&self) -> Duration {
+        self.target_duration
+    }
+
+    /// Sequence number of the first segment.
+    pub fn media_sequence(&self) -> u64 {
+        self.media_sequence
+    }
+
+    /// The segments of the stream, in playing order.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Whether the playlist is complete, `false` for live streams still adding segments.
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Total duration of the segments.
+    pub fn duration(&self) -> Duration {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+}
+
+/// A media segment, listed by a [`MediaPlaylist`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    uri: String,
+    duration: Duration,
+    title: Option<String>,
+    byte_range: Option<Range<u64>>,
+    key: Option<Key>,
+    init_section: Option<InitSection>,
+    sequence: u64,
+}
+
+impl Segment {
+    /// URL of the resource holding this segment.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Duration of this segment.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Title of this segment, if any.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Bytes of the resource holding this segment, when it is not the whole resource.
+    pub fn byte_range(&self) -> Option<&Range<u64>> {
+        self.byte_range.as_ref()
+    }
+
+    /// Key this segment is encrypted with, if any.
+    pub fn key(&self) -> Option<&Key> {
+        self.key.as_ref()
+    }
+
+    /// Media initialization section needed to play this segment, e.g for fragmented MP4.
+    pub fn init_section(&self) -> Option<&InitSection> {
+        self.init_section.as_ref()
+    }
+
+    /// Media sequence number of this segment.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// The media initialization section of segments, from an `#EXT-X-MAP` tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitSection {
+    uri: String,
+    byte_range: Option<Range<u64>>,
+}
+
+impl InitSection {
+    /// URL of the resource holding the initialization section.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Bytes of the resource holding the initialization section, when it is not the whole resource.
+    pub fn byte_range(&self) -> Option<&Range<u64>> {
+        self.byte_range.as_ref()
+    }
+}
+
+/// Method used to encrypt segments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyMethod {
+    /// Whole segments encrypted with AES-128 in CBC mode, decrypted by [`super::HlsDownloader`].
+    Aes128,
+    /// Samples encrypted inside the media, left to the player.
+    SampleAes,
+    Other(String),
+}
+
+/// The key segments are encrypted with, from an `#EXT-X-KEY` tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    method: KeyMethod,
+    uri: Option<String>,
+    iv: Option<[u8; 16]>,
+}
+
+impl Key {
+    /// Helper method, parse the attributes of an `#EXT-X-KEY` tag, `None` for `METHOD=NONE`.
+    fn parse(value: &str, url: &str) -> Result<Option<Key>, HttpError> {
+        let attributes = attributes(value);
+        let method = match attribute(&attributes, "METHOD") {
+            Some("NONE") => return Ok(None),
+            Some("AES-128") => KeyMethod::Aes128,
+            Some("SAMPLE-AES") => KeyMethod::SampleAes,
+            Some(method) => KeyMethod::Other(method.to_owned()),
+            None => return Err(invalid("#EXT-X-KEY without METHOD")),
+        };
+        let iv = match attribute(&attributes, "IV") {
+            Some(iv) => Some(parse_iv(iv).ok_or_else(|| invalid(&format!("invalid IV `{iv}`")))?),
+            None => None,
+        };
+        Ok(Some(Key {
+            method,
+            uri: attribute(&attributes, "URI").map(|uri| resolve(url, uri)),
+            iv,
+        }))
+    }
+
+    /// Method the segments are encrypted with.
+    pub fn method(&self) -> &KeyMethod {
+        &self.method
+    }
+
+    /// URL of the key.
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    /// Initialization vector, when not derived from the sequence number of the segment.
+    pub fn iv(&self) -> Option<[u8; 16]> {
+        self.iv
+    }
+}
+
+/// Helper method, the error of an invalid playlist.
+fn invalid(reason: &str) -> HttpError {
+    HttpError::Other(format!("Invalid M3U8 playlist: {reason}"))
+}
+
+/// Helper method, resolve a URI of a playlist against the URL of the playlist.
+fn resolve(url: &str, uri: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return uri.to_owned();
+    };
+    let (address, path) = rest
+        .find('/')
+        .map_or((rest, "/"), |index| rest.split_at(index));
+    resolve_location(scheme, address, path, uri)
+}
+
+/// Helper method, split an attribute list such as `BANDWIDTH=1280000,CODECS="avc1,mp4a"`.
+fn attributes(value: &str) -> Vec<(&str, &str)> {
+    split_list(value)
+        .into_iter()
+        .filter_map(|attribute| {
+            let (name, value) = attribute.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            Some((name.trim(), value))
+        })
+        .collect()
+}
+
+/// Helper method, the value of an attribute of an attribute list.
+fn attribute<'a>(attributes: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(attribute, _)| *attribute == name)
+        .map(|(_, value)| *value)
+}
+
+/// Helper method, parse a byte range such as `1024@2048` into its length and offset.
+fn parse_byte_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (length, offset) = match value.trim().split_once('@') {
+        Some((length, offset)) => (length, Some(offset.parse().ok()?)),
+        None => (value.trim(), None),
+    };
+    Some((length.parse().ok()?, offset))
+}
+
+/// Helper method, parse a hexadecimal initialization vector, e.g
+/// `0x00000000000000000000000000000001`.
+fn parse_iv(value: &str) -> Option<[u8; 16]> {
+    let hex = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))?;
+    if hex.len() != 32 {
+        return None;
+    }
+    let mut iv = [0u8; 16];
+    for (index, byte) in iv.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+    Some(iv)
+}
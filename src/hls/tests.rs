@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use crate::client::Client;
+use crate::error::HttpError;
+use crate::hls::aes::Aes128;
+use crate::hls::{HlsDownloader, KeyMethod, Playlist};
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn parse_master_playlist() {
+    let text = "#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,mp4a.40.2\"
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=5120000,RESOLUTION=1920x1080
+https://cdn.example.com/high/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2560000
+/mid/index.m3u8
+";
+    let Playlist::Master(master) =
+        Playlist::parse(text, "https://example.com/stream/master.m3u8?token=1").unwrap()
+    else {
+        panic!("expected a master playlist");
+    };
+    let uris = master
+        .variants()
+        .iter()
+        .map(|variant| variant.uri())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        uris,
+        [
+            "https://example.com/stream/low/index.m3u8",
+            "https://cdn.example.com/high/index.m3u8",
+            "https://example.com/mid/index.m3u8",
+        ]
+    );
+    let low = &master.variants()[0];
+    assert_eq!(low.resolution(), Some((640, 360)));
+    assert_eq!(low.codecs(), Some("avc1.4d401e,mp4a.40.2"));
+    assert_eq!(master.best_variant(None).unwrap().bandwidth(), 5_120_000);
+    assert_eq!(
+        master.best_variant(Some(3_000_000)).unwrap().bandwidth(),
+        2_560_000
+    );
+    assert_eq!(master.best_variant(Some(1)).unwrap().bandwidth(), 1_280_000);
+}
+
+#[test]
+fn parse_media_playlist() {
+    let text = "#EXTM3U
+#EXT-X-TARGETDURATION:10
+#EXT-X-MEDIA-SEQUENCE:7
+#EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"
+#EXTINF:9.009,First
+#EXT-X-BYTERANGE:1000@720
+media.mp4
+#EXT-X-KEY:METHOD=AES-128,URI=\"https://keys.example.com/k1\",IV=0x000102030405060708090a0b0c0d0e0f
+#EXTINF:9.009,
+#EXT-X-BYTERANGE:500
+media.mp4
+#EXT-X-KEY:METHOD=NONE
+#EXTINF:3.5,
+last.ts
+#EXT-X-ENDLIST
+";
+    let Playlist::Media(media) =
+        Playlist::parse(text, "https://example.com/vod/index.m3u8").unwrap()
+    else {
+        panic!("expected a media playlist");
+    };
+    assert_eq!(media.target_duration(), Duration::from_secs(10));
+    assert!(media.is_ended());
+    assert_eq!(media.duration(), Duration::from_secs_f64(21.518));
+    let segments = media.segments();
+    assert_eq!(segments.len(), 3);
+
+    assert_eq!(segments[0].uri(), "https://example.com/vod/media.mp4");
+    assert_eq!(segments[0].title(), Some("First"));
+    assert_eq!(segments[0].byte_range(), Some(&(720..1720)));
+    assert_eq!(segments[0].sequence(), 7);
+    assert!(segments[0].key().is_none());
+    let init = segments[0].init_section().unwrap();
+    assert_eq!(init.uri(), "https://example.com/vod/init.mp4");
+    assert_eq!(init.byte_range(), Some(&(0..720)));
+
+    // The byte range continues where the previous one ended
+    assert_eq!(segments[1].byte_range(), Some(&(1720..2220)));
+    assert_eq!(segments[1].title(), None);
+    let key = segments[1].key().unwrap();
+    assert_eq!(key.method(), &KeyMethod::Aes128);
+    assert_eq!(key.uri(), Some("https://keys.example.com/k1"));
+    assert_eq!(key.iv().unwrap()[15], 0x0f);
+
+    assert_eq!(segments[2].uri(), "https://example.com/vod/last.ts");
+    assert_eq!(segments[2].sequence(), 9);
+    assert!(segments[2].key().is_none());
+    assert!(segments[2].byte_range().is_none());
+}
+
+#[test]
+fn invalid_playlists() {
+    assert!(Playlist::parse("segment.ts", "").is_err());
+    assert!(Playlist::parse("#EXTM3U\nsegment.ts", "").is_err());
+    assert!(Playlist::parse("#EXTM3U\n#EXTINF:abc,\nsegment.ts", "").is_err());
+    assert!(Playlist::parse("#EXTM3U\n#EXT-X-STREAM-INF:RESOLUTION=1x1\na.m3u8", "").is_err());
+    // Values from a remote playlist overflowing durations or offsets
+    assert!(Playlist::parse("#EXTM3U\n#EXTINF:1e20,\na.ts", "").is_err());
+    let huge_range = "#EXTM3U\n#EXTINF:1,\n#EXT-X-BYTERANGE:18446744073709551615@1\na.ts";
+    assert!(Playlist::parse(huge_range, "").is_err());
+    let huge_map = "#EXTM3U\n#EXT-X-MAP:URI=\"i.mp4\",BYTERANGE=\"2@18446744073709551615\"";
+    assert!(Playlist::parse(huge_map, "").is_err());
+    let huge_sequence =
+        "#EXTM3U\n#EXT-X-MEDIA-SEQUENCE:18446744073709551615\n#EXTINF:1,\na.ts\n#EXTINF:1,\nb.ts";
+    assert!(Playlist::parse(huge_sequence, "").is_err());
+    // Relative URIs are kept without the URL of the playlist
+    let Playlist::Media(media) = Playlist::parse("#EXTM3U\n#EXTINF:1,\na.ts", "").unwrap() else {
+        panic!("expected a media playlist");
+    };
+    assert_eq!(media.segments()[0].uri(), "a.ts");
+}
+
+#[test]
+fn aes_128_block() {
+    // FIPS 197, appendix C.1
+    let key = core::array::from_fn(|index| index as u8);
+    let mut block = [
+        0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5,
+        0x5a,
+    ];
+    Aes128::new(&key).decrypt_block(&mut block);
+    assert_eq!(
+        block,
+        [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff
+        ]
+    );
+}
+
+#[test]
+fn aes_128_cbc() {
+    let key = core::array::from_fn(|index| index as u8);
+    let iv = core::array::from_fn(|index| 0xf0 - 0x10 * index as u8);
+    let encrypted = [
+        0xca, 0x36, 0xe4, 0x95, 0xdb, 0x31, 0xbc, 0xac, 0x08, 0x8e, 0x98, 0x8e, 0x36, 0x82, 0x31,
+        0x4b, 0x24, 0x98, 0x53, 0x37, 0xcc, 0x61, 0xe4, 0x95, 0xf5, 0xcd, 0xa3, 0x8f, 0x15, 0xf0,
+        0x0b, 0xfc, 0x9f, 0x7d, 0xa6, 0x06, 0xb4, 0xbd, 0x6a, 0x80, 0xd9, 0x0c, 0x17, 0x72, 0x2f,
+        0x0e, 0x43, 0x4b,
+    ];
+    let cipher = Aes128::new(&key);
+    assert_eq!(
+        cipher.decrypt_cbc(&iv, &encrypted).unwrap(),
+        b"Segment data that is longer than one block!"
+    );
+    assert!(cipher.decrypt_cbc(&iv, &encrypted[..47]).is_err());
+    assert!(Aes128::new(&[0; 16]).decrypt_cbc(&iv, &encrypted).is_err());
+}
+
+#[test]
+fn download_segments() {
+    let server = TestServer::start().unwrap();
+    let playlist = "#EXTM3U
+#EXTINF:1,
+a.ts
+#EXTINF:1,
+b.ts
+#EXTINF:1,
+#EXT-X-BYTERANGE:4@2
+c.ts
+#EXT-X-ENDLIST
+";
+    server.route("GET", "/index.m3u8", TestResponse::text(200, playlist));
+    server.route(
+        "GET",
+        "/a.ts",
+        TestResponse::text(200, "aa").delay(Duration::from_millis(200)),
+    );
+    server.route("GET", "/b.ts", TestResponse::new(503));
+    server.route("GET", "/b.ts", TestResponse::text(200, "bb"));
+    server.route(
+        "GET",
+        "/c.ts",
+        TestResponse::text(206, "cccc").header("Content-Range", "bytes 2-5/10"),
+    );
+    let downloader = HlsDownloader::new(Client::builder().no_proxy().build())
+        .backoff(Duration::from_millis(1), Duration::from_millis(1));
+    let mut written = Vec::new();
+    downloader
+        .download(&server.url("/index.m3u8"), &mut written)
+        .unwrap();
+    // Written in playing order, though the first segment arrives last
+    assert_eq!(written, b"aabbcccc");
+    let requests = server.requests();
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|request| request.path() == "/b.ts")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn failed_segments() {
+    let server = TestServer::start().unwrap();
+    server.route(
+        "GET",
+        "/missing.m3u8",
+        TestResponse::text(200, "#EXTM3U\n#EXTINF:1,\nmissing.ts\n#EXT-X-ENDLIST"),
+    );
+    server.route("GET", "/missing.ts", TestResponse::new(404));
+    let ranged = "#EXTM3U\n#EXTINF:1,\n#EXT-X-BYTERANGE:4@2\nc.ts\n#EXT-X-ENDLIST";
+    server.route("GET", "/ranged.m3u8", TestResponse::text(200, ranged));
+    server.route(
+        "GET",
+        "/c.ts",
+        TestResponse::text(206, "cccc").header("Content-Range", "bytes 0-3/10"),
+    );
+    let downloader = HlsDownloader::new(Client::builder().no_proxy().build())
+        .backoff(Duration::from_millis(1), Duration::from_millis(1));
+    let result = downloader.download(&server.url("/missing.m3u8"), &mut Vec::new());
+    assert!(matches!(result, Err(HttpError::BadResponse(404, _))));
+    // Not found for good, so not asked again
+    let requests = server.requests();
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|request| request.path() == "/missing.ts")
+            .count(),
+        1
+    );
+    let result = downloader.download(&server.url("/ranged.m3u8"), &mut Vec::new());
+    assert!(matches!(result, Err(HttpError::BadResponse(206, _))));
+}
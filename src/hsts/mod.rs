@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+/// Longest time a host is remembered, longer `max-age` values are shortened to it.
+const MAX_AGE: Duration = Duration::from_secs(2 * 365 * 24 * 60 * 60);
+
+/// A host that asked to be contacted over HTTPS only.
+#[derive(Debug, Clone)]
+struct KnownHost {
+    expires: Instant,
+    include_subdomains: bool,
+}
+
+/// Hosts known to require HTTPS, learned from `Strict-Transport-Security` headers (RFC 6797).
+///
+/// Plain `http://` requests to a known host, or to a subdomain of a known host that included
+/// them, are sent to `https://` instead.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::builder().hsts(true).build();
+///     client.get("https://example.com/")?.send()?;
+///     // Sent to `https://example.com/login` if the server sent `Strict-Transport-Security`
+///     client.get("http://example.com/login")?.send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct HstsStore {
+    hosts: Mutex<HashMap<String, KnownHost>>,
+}
+
+impl HstsStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require HTTPS for the given host, e.g to preload well known hosts.
+    /// # Arguments
+    /// `host`  Host requiring HTTPS.
+    /// `max_age`   How long the requirement lasts, at most two years.
+    /// `include_subdomains`    Whether every subdomain of `host` requires HTTPS as well.
+    pub fn insert(&self, host: &str, max_age: Duration, include_subdomains: bool) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            hosts.insert(
+                host.to_ascii_lowercase(),
+                KnownHost {
+                    expires: Instant::now() + max_age.min(MAX_AGE),
+                    include_subdomains,
+                },
+            );
+        }
+    }
+
+    /// Whether requests to the given host must use HTTPS.
+    pub fn is_known(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        let Ok(hosts) = self.hosts.lock() else {
+            return false;
+        };
+        let now = Instant::now();
+        if hosts.get(&host).is_some_and(|known| known.expires > now) {
+            return true;
+        }
+        // Superdomains of the host, e.g `example.com` for `www.example.com`
+        host.match_indices('.').any(|(index, _)| {
+            hosts
+                .get(&host[index + 1..])
+                .is_some_and(|known| known.include_subdomains && known.expires > now)
+        })
+    }
+
+    /// Forget every known host.
+    pub fn clear(&self) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            hosts.clear();
+        }
+    }
+
+    /// Update the store with a `Strict-Transport-Security` header received over HTTPS.
+    pub(crate) fn record(&self, host: &str, header: &str) {
+        if host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok() {
+            return;
+        }
+        let mut max_age = None;
+        let mut include_subdomains = false;
+        for directive in header.split(';') {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim().trim_matches('"')),
+                None => (directive.trim(), ""),
+            };
+            if name.eq_ignore_ascii_case("max-age") {
+                max_age = value.parse::<u64>().ok();
+            } else if name.eq_ignore_ascii_case("includeSubDomains") {
+                include_subdomains = true;
+            }
+        }
+        match max_age {
+            Some(0) => {
+                if let Ok(mut hosts) = self.hosts.lock() {
+                    hosts.remove(&host.to_ascii_lowercase());
+                }
+            }
+            Some(max_age) => self.insert(host, Duration::from_secs(max_age), include_subdomains),
+            None => (),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use crate::hsts::HstsStore;
+
+#[test]
+fn record_sts_headers() {
+    let store = HstsStore::new();
+    store.record("example.com", "max-age=31536000; includeSubDomains");
+    store.record("other.com", "max-age=\"600\"");
+    store.record("127.0.0.1", "max-age=600");
+    store.record("ignored.com", "includeSubDomains");
+    store.record("forever.com", "max-age=18446744073709551615");
+    store.insert("preloaded.com", Duration::MAX, false);
+    assert!(store.is_known("EXAMPLE.com"));
+    assert!(store.is_known("www.example.com"));
+    assert!(store.is_known("other.com"));
+    assert!(!store.is_known("www.other.com"));
+    assert!(!store.is_known("127.0.0.1"));
+    assert!(!store.is_known("ignored.com"));
+    assert!(store.is_known("forever.com"));
+    assert!(store.is_known("preloaded.com"));
+
+    store.record("example.com", "max-age=0");
+    assert!(!store.is_known("example.com"));
+}
+
+#[test]
+fn expired_hosts() {
+    let store = HstsStore::new();
+    store.insert("example.com", Duration::ZERO, true);
+    assert!(!store.is_known("example.com"));
+    assert!(!store.is_known("www.example.com"));
+}
@@ -48,6 +48,13 @@ impl JsonValue {
             _ => None,
         }
     }
+    /// Parse this JSON Object as a `String` is possible.
+    pub fn string(&self) -> Option<&String> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
     /// Parse this JSON Object as a `Vec<JsonValue` is possible.
     pub fn array(&self) -> Option<&Vec<JsonValue>> {
         match self {
@@ -141,50 +148,58 @@ impl Display for JsonValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             JsonValue::Null => write!(f, "null"),
+            // JSON has no representation for NaN or infinity
+            JsonValue::Float(v) if !v.is_finite() => write!(f, "null"),
             JsonValue::Float(v) => write!(f, "{}", v),
             JsonValue::Integer(v) => write!(f, "{}", v),
             JsonValue::Boolean(v) => write!(f, "{}", v),
-            JsonValue::String(v) => write!(f, "\"{}\"", v),
+            JsonValue::String(v) => write_json_string(f, v),
             JsonValue::Array(vec) => {
                 write!(f, "[")?;
-                for i in 0..vec.len().saturating_sub(1) {
-                    write!(f, "{},", vec[i])?;
-                }
-                if vec.len() > 1 {
-                    write!(f, "{}", vec[vec.len().saturating_sub(1)])?;
-                }
-                write!(f, "]")
-            }
-            JsonValue::Object(hash_map) => {
-                write!(f, "{}", "{")?;
-                let last = hash_map.inner.len().saturating_sub(1);
-                for (index, (key, value)) in hash_map.inner.iter().enumerate() {
-                    write!(f, "\"{}\": {}", key, value)?;
-                    if index < last {
+                for (index, value) in vec.iter().enumerate() {
+                    if index > 0 {
                         write!(f, ",")?;
                     }
+                    write!(f, "{}", value)?;
                 }
-                write!(f, "{}", "}")?;
-                Ok(())
+                write!(f, "]")
             }
+            JsonValue::Object(json_obj) => write!(f, "{}", json_obj),
         }
     }
 }
 
 impl Display for JsonObj {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", "{")?;
-        let last = self.inner.len() - 1;
+        write!(f, "{{")?;
         for (index, (key, value)) in self.inner.iter().enumerate() {
-            write!(f, "\"{}\": {}", key, value)?;
-            if index < last {
+            if index > 0 {
                 write!(f, ",")?;
             }
+            write_json_string(f, key)?;
+            write!(f, ": {}", value)?;
         }
-        write!(f, "{}", "}")
+        write!(f, "}}")
     }
 }
 
+/// Helper method, write a quoted JSON string escaping the characters that require it.
+fn write_json_string(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
 /// Conversion implementations
 impl From<&String> for JsonValue {
     fn from(value: &String) -> Self {
@@ -258,3 +273,31 @@ where
         JsonValue::Array(vec)
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for JsonValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeMap, SerializeSeq};
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Float(v) => serializer.serialize_f64(*v),
+            JsonValue::Integer(v) => serializer.serialize_i64(*v as i64),
+            JsonValue::Boolean(v) => serializer.serialize_bool(*v),
+            JsonValue::String(v) => serializer.serialize_str(v),
+            JsonValue::Array(vec) => {
+                let mut seq = serializer.serialize_seq(Some(vec.len()))?;
+                for value in vec {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            JsonValue::Object(json_obj) => {
+                let mut map = serializer.serialize_map(Some(json_obj.inner.len()))?;
+                for (key, value) in json_obj.inner.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
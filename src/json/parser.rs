@@ -50,30 +50,61 @@ impl JsonParser {
         let mut result = String::new();
         data.next(); // skip quote
         loop {
-            let c = data.peek();
-            match c {
+            match data.next() {
                 Some('\\') => match data.next() {
                     Some('"') => result.push('"'),
                     Some('\\') => result.push('\\'),
                     Some('/') => result.push('/'),
-                    Some('t') => result.push('/'),
-                    Some('f') => result.push('\u{008}'),
-                    Some('b') => result.push('\u{00C}'),
+                    Some('b') => result.push('\u{0008}'),
+                    Some('f') => result.push('\u{000C}'),
                     Some('n') => result.push('\n'),
+                    Some('r') => result.push('\r'),
+                    Some('t') => result.push('\t'),
+                    Some('u') => result.push(Self::parse_unicode_escape(data)?),
                     Some(other) => {
                         return Err(format!("Invalid escape sequence in data `\\{other}` "))
                     }
-                    _ => return Err(format!("Incomplete escape sequence in Json Object.")),
+                    None => return Err(format!("Incomplete escape sequence in Json Object.")),
                 },
                 Some('"') => break,
-                Some(other) => result.push(*other),
-                _ => return Err(format!("Incomplete String value found `{result}`")),
+                Some(other) => result.push(other),
+                None => return Err(format!("Incomplete String value found `{result}`")),
             };
-            data.next(); // move to the next location
         }
-        data.next();
         Ok(JsonValue::String(result))
     }
+    /// Helper method, the character of a `\uXXXX` escape whose `\u` was read, joining
+    /// surrogate pairs. Unpaired surrogates become U+FFFD, unless followed by another escape.
+    fn parse_unicode_escape<I>(data: &mut Peekable<I>) -> Result<char, String>
+    where
+        I: Iterator<Item = char>,
+    {
+        let code_unit = |data: &mut Peekable<I>| {
+            let digits = data.take(4).collect::<String>();
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .filter(|_| digits.len() == 4)
+                .ok_or_else(|| format!("Invalid unicode escape sequence `\\u{digits}`"))
+        };
+        let high = code_unit(data)?;
+        let code_point = match high {
+            0xD800..=0xDBFF => {
+                if data.peek() != Some(&'\\') {
+                    return Ok(char::REPLACEMENT_CHARACTER);
+                }
+                data.next();
+                if data.next() != Some('u') {
+                    return Err("Unpaired surrogate in unicode escape sequence".to_string());
+                }
+                match code_unit(data)? {
+                    low @ 0xDC00..=0xDFFF => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                    _ => return Ok(char::REPLACEMENT_CHARACTER),
+                }
+            }
+            code_point => code_point,
+        };
+        Ok(char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
     fn parse_number<I>(data: &mut Peekable<I>) -> Result<JsonValue, String>
     where
         I: Iterator<Item = char>,
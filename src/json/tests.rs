@@ -60,3 +60,28 @@ fn invalid_json() {
     let json = JsonParser::parse_json(&json_text);
     assert!(json.is_err());
 }
+
+#[test]
+fn display_valid_json() {
+    let json = JsonParser::parse_json(r#"{"quote": "say \"hi\"\n", "list": [1], "empty": {}}"#)
+        .expect("Fail parsing JSON");
+    let reparsed = JsonParser::parse_json(&json.to_string()).expect("Display output must parse");
+    assert_eq!(json, reparsed);
+    assert_eq!(
+        JsonValue::Array(vec![JsonValue::Integer(1)]).to_string(),
+        "[1]"
+    );
+    assert_eq!(JsonValue::Float(f64::NAN).to_string(), "null");
+}
+
+#[test]
+fn string_escapes() {
+    let json = JsonParser::parse_json(r#"["a\"b\\c\/d", "\t\r\n\b\f", "é😀", "\ud83d"]"#)
+        .expect("Fail parsing JSON");
+    assert_eq!(json[0].string().unwrap(), "a\"b\\c/d");
+    assert_eq!(json[1].string().unwrap(), "\t\r\n\u{8}\u{c}");
+    assert_eq!(json[2].string().unwrap(), "é😀");
+    assert_eq!(json[3].string().unwrap(), "\u{fffd}");
+    assert!(JsonParser::parse_json(r#""\x""#).is_err());
+    assert!(JsonParser::parse_json(r#""\u12""#).is_err());
+}
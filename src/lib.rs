@@ -26,13 +26,99 @@
 //! }
 //! ```
 //!
+#[cfg(any(test, feature = "test-util"))]
+pub mod assertions;
+
+#[allow(dead_code)]
+pub mod auth;
+mod body;
+#[allow(dead_code)]
+pub mod breaker;
+#[allow(dead_code)]
+pub mod cache;
+#[allow(dead_code)]
+pub mod cancel;
 #[allow(dead_code)]
 pub mod client;
+mod coalesce;
 mod config;
+mod connection;
+#[allow(dead_code)]
+pub mod cookie;
+mod date;
+#[allow(dead_code)]
+pub mod download;
+#[allow(dead_code)]
+pub mod duplex;
+#[allow(dead_code)]
+pub mod encoding;
 #[allow(dead_code)]
 pub mod error;
+#[allow(dead_code)]
+pub mod extensions;
+#[allow(dead_code)]
+pub mod fault;
+#[allow(dead_code)]
+pub mod har;
+#[allow(dead_code)]
+pub mod hash;
+#[allow(dead_code)]
+pub mod hls;
+#[allow(dead_code)]
+pub mod hsts;
+pub mod tls;
 
 #[allow(dead_code)]
 pub mod json;
+#[cfg(feature = "log")]
+mod logging;
+
+#[allow(dead_code)]
+pub mod metrics;
+
+#[allow(dead_code)]
+pub mod mime;
+
+#[allow(dead_code)]
+pub mod multipart;
+
+#[allow(dead_code)]
+pub mod oauth;
+
+#[allow(dead_code)]
+pub mod poll;
+
+#[allow(dead_code)]
+pub mod proxy;
+
+#[allow(dead_code)]
+pub mod redirect;
+
+#[allow(dead_code)]
+pub mod response;
+
+#[allow(dead_code)]
+pub mod retry;
+
+#[allow(dead_code)]
+pub mod s3;
+
+#[allow(dead_code)]
+pub mod server_timing;
+
+#[allow(dead_code)]
+pub mod structured;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_server;
+
+#[allow(dead_code)]
+pub mod trace_context;
+
+#[allow(dead_code)]
+pub mod tus;
+
+#[allow(dead_code)]
+pub mod vcr;
 
 pub use http_parse::*;
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use crate::client::{H_AUTHORIZATION, H_COOKIE, H_PROXY_AUTHORIZATION, H_REQUEST_ID, H_SET_COOKIE};
+
+#[cfg(test)]
+mod tests;
+
+/// Headers carrying credentials, whose values are never logged.
+const SENSITIVE_HEADERS: [&str; 4] = [
+    H_AUTHORIZATION,
+    H_PROXY_AUTHORIZATION,
+    H_COOKIE,
+    H_SET_COOKIE,
+];
+
+/// Logged in place of the value of a sensitive header.
+const REDACTED: &str = "[redacted]";
+
+/// The value of a header as it can be logged, hiding credentials.
+pub(crate) fn redacted<'a>(name: &str, value: &'a str) -> &'a str {
+    if SENSITIVE_HEADERS
+        .iter()
+        .any(|sensitive| sensitive.eq_ignore_ascii_case(name))
+    {
+        REDACTED
+    } else {
+        value
+    }
+}
+
+/// The status line and the header fields of a response head.
+pub(crate) fn head_fields(head: &[u8]) -> (String, Vec<(String, String)>) {
+    let text = String::from_utf8_lossy(head);
+    let mut lines = text.split("\r\n");
+    let status_line = lines.next().unwrap_or_default().to_owned();
+    let fields = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+    (status_line, fields)
+}
+
+/// Log a request about to be sent: its request line and `X-Request-Id` at `debug` level and
+/// its headers at `trace` level, as `curl -v` shows them.
+/// # Arguments
+/// `method`    Method of the request, e.g `GET`.
+/// `url`       URL of the request.
+/// `headers`   Header fields of the request.
+pub(crate) fn request<'a, I>(method: &str, url: &str, headers: I)
+where
+    I: IntoIterator<Item = (&'a str, String)>,
+{
+    if !log::log_enabled!(log::Level::Debug) {
+        return;
+    }
+    let headers = headers.into_iter().collect::<Vec<(&str, String)>>();
+    match headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(H_REQUEST_ID))
+    {
+        Some((_, id)) => log::debug!("> {method} {url} (request id {id})"),
+        None => log::debug!("> {method} {url}"),
+    }
+    for (name, value) in headers {
+        log::trace!("> {name}: {}", redacted(name, &value));
+    }
+}
+
+/// Log a response head once received: its status line and the time taken at `debug`
+/// level, its headers at `trace` level.
+/// # Arguments
+/// `url`       URL of the request.
+/// `head`      Head of the response in wire format.
+/// `elapsed`   Time since the request was sent.
+pub(crate) fn response(url: &str, head: &[u8], elapsed: Duration) {
+    if !log::log_enabled!(log::Level::Debug) {
+        return;
+    }
+    let (status_line, fields) = head_fields(head);
+    log::debug!("< {status_line} from {url} in {}ms", elapsed.as_millis());
+    for (name, value) in fields {
+        log::trace!("< {name}: {}", redacted(&name, &value));
+    }
+}
@@ -0,0 +1,27 @@
+use crate::logging::{head_fields, redacted};
+
+#[test]
+fn credentials_redacted() {
+    assert_eq!(redacted("authorization", "Bearer secret"), "[redacted]");
+    assert_eq!(
+        redacted("Proxy-Authorization", "Basic c2VjcmV0"),
+        "[redacted]"
+    );
+    assert_eq!(redacted("Cookie", "session=secret"), "[redacted]");
+    assert_eq!(redacted("SET-COOKIE", "session=secret"), "[redacted]");
+    assert_eq!(redacted("Accept", "text/html"), "text/html");
+}
+
+#[test]
+fn response_head_fields() {
+    let (status_line, fields) =
+        head_fields(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nSet-Cookie: a=b\r\n\r\n");
+    assert_eq!(status_line, "HTTP/1.1 200 OK");
+    assert_eq!(
+        fields,
+        vec![
+            ("Content-Type".to_owned(), "text/plain".to_owned()),
+            ("Set-Cookie".to_owned(), "a=b".to_owned()),
+        ]
+    );
+}
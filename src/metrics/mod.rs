@@ -0,0 +1,227 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// Upper bounds of the buckets of the latency histogram, the last bucket holding the rest.
+const LATENCY_BOUNDS: [Duration; 11] = [
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(25),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+/// Counters updated by the requests of a client, see [`crate::client::Client::metrics`].
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    requests: AtomicU64,
+    /// Responses received by status class, `1xx` to `5xx`.
+    responses: [AtomicU64; 5],
+    failures: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    connections_opened: AtomicU64,
+    latencies: [AtomicU64; LATENCY_BOUNDS.len() + 1],
+    /// Sum of the latencies, in microseconds.
+    latency_total: AtomicU64,
+}
+
+impl Metrics {
+    /// Count a response received after `latency`, unless its status is outside `100`-`599`.
+    pub(crate) fn record_response(&self, status: usize, latency: Duration) {
+        // The latency of a status outside every class would skew the mean of the others
+        let Some(counter) = (1..=5)
+            .position(|class| status / 100 == class)
+            .map(|index| &self.responses[index])
+        else {
+            return;
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        let bucket = LATENCY_BOUNDS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BOUNDS.len());
+        self.latencies[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_total
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Count a request sent, and whether it failed before its response was read in full.
+    pub(crate) fn record_request(&self, failed: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count bytes written to a connection other than through [`Counted`].
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count a connection opened for a request.
+    pub(crate) fn record_connection(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The current values of the counters.
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            requests: load(&self.requests),
+            responses: self.responses.each_ref().map(load),
+            failures: load(&self.failures),
+            bytes_sent: load(&self.bytes_sent),
+            bytes_received: load(&self.bytes_received),
+            connections_opened: load(&self.connections_opened),
+            latencies: self.latencies.each_ref().map(load).to_vec(),
+            latency_total: Duration::from_micros(load(&self.latency_total)),
+        }
+    }
+}
+
+/// The metrics of a client at some point in time, see [`crate::client::Client::metrics`].
+///
+/// Every request sent counts, including redirects, retries and authentication challenges,
+/// as well as responses replayed from a HAR file. Responses served from the cache or shared
+/// with identical concurrent requests are not counted, nor are requests sent with
+/// [`crate::client::ClientRequest::send_duplex`] or
+/// [`crate::client::ClientRequest::send_nonblocking`].
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::new();
+///     client.get("https://example.com")?.send()?;
+///     let metrics = client.metrics();
+///     println!(
+///         "{} requests, {} server errors, {} bytes received, mean latency {:?}",
+///         metrics.requests(),
+///         metrics.responses(5),
+///         metrics.bytes_received(),
+///         metrics.mean_latency()
+///     );
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    requests: u64,
+    responses: [u64; 5],
+    failures: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    connections_opened: u64,
+    latencies: Vec<u64>,
+    latency_total: Duration,
+}
+
+impl MetricsSnapshot {
+    /// The number of requests sent, answered or failed.
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// The number of responses of the given status class, e.g `4` for `4xx` responses.
+    /// # Arguments
+    /// `class` First digit of the status codes, from `1` to `5`.
+    pub fn responses(&self, class: u16) -> u64 {
+        match class {
+            1..=5 => self.responses[class as usize - 1],
+            _ => 0,
+        }
+    }
+
+    /// The number of requests that failed before their response was read in full, e.g
+    /// because the connection failed or timed out. A response body streamed to the caller
+    /// is not read by the client, so failures while reading it are not counted.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    /// The number of bytes written to connections, heads and bodies.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// The number of bytes read from connections, heads and bodies.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// The number of connections opened.
+    pub fn connections_opened(&self) -> u64 {
+        self.connections_opened
+    }
+
+    /// The number of responses by time taken to receive their head, each bucket with its
+    /// upper bound, `None` for the last one.
+    pub fn latency_histogram(&self) -> Vec<(Option<Duration>, u64)> {
+        LATENCY_BOUNDS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .zip(self.latencies.iter().copied())
+            .collect()
+    }
+
+    /// The mean time taken to receive the head of a response, `None` before any response.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let responses = self.responses.iter().sum::<u64>();
+        (responses > 0)
+            .then(|| Duration::from_secs_f64(self.latency_total.as_secs_f64() / responses as f64))
+    }
+}
+
+/// A connection counting the bytes read and written through it in the metrics of a client.
+pub(crate) struct Counted<S> {
+    stream: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S> Counted<S> {
+    pub(crate) fn new(stream: S, metrics: Arc<Metrics>) -> Self {
+        Counted { stream, metrics }
+    }
+}
+
+impl<S: Read> Read for Counted<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.stream.read(buf)?;
+        self.metrics
+            .bytes_received
+            .fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Counted<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.stream.write(buf)?;
+        self.metrics.record_sent(written);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl<S: AsRef<TcpStream>> AsRef<TcpStream> for Counted<S> {
+    fn as_ref(&self) -> &TcpStream {
+        self.stream.as_ref()
+    }
+}
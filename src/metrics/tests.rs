@@ -0,0 +1,62 @@
+use std::io::{Cursor, Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::metrics::{Counted, Metrics, MetricsSnapshot};
+
+#[test]
+fn responses_and_latencies_counted() {
+    let metrics = Metrics::default();
+    metrics.record_response(200, Duration::from_millis(3));
+    metrics.record_response(204, Duration::from_millis(40));
+    metrics.record_response(503, Duration::from_secs(30));
+    // Neither counted nor part of the mean latency
+    metrics.record_response(999, Duration::from_secs(300));
+    metrics.record_request(false);
+    metrics.record_request(false);
+    metrics.record_request(false);
+    metrics.record_request(true);
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.requests(), 4);
+    assert_eq!(snapshot.responses(2), 2);
+    assert_eq!(snapshot.responses(5), 1);
+    assert_eq!(snapshot.responses(4), 0);
+    assert_eq!(snapshot.responses(9), 0);
+    assert_eq!(snapshot.failures(), 1);
+    let histogram = snapshot.latency_histogram();
+    assert_eq!(histogram.len(), 12);
+    assert_eq!(histogram[0], (Some(Duration::from_millis(5)), 1));
+    assert_eq!(histogram[3], (Some(Duration::from_millis(50)), 1));
+    assert_eq!(histogram[11], (None, 1));
+    assert_eq!(
+        snapshot.mean_latency(),
+        Some(Duration::from_nanos(10_014_333_333))
+    );
+}
+
+#[test]
+fn bytes_counted() {
+    let metrics = Arc::new(Metrics::default());
+    let mut counted = Counted::new(Cursor::new(b"hello".to_vec()), metrics.clone());
+    counted.read_to_end(&mut Vec::new()).unwrap();
+    let mut counted = Counted::new(Vec::new(), metrics.clone());
+    counted.write_all(b"abc").unwrap();
+    metrics.record_sent(10);
+    metrics.record_connection();
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.bytes_received(), 5);
+    assert_eq!(snapshot.bytes_sent(), 13);
+    assert_eq!(snapshot.connections_opened(), 1);
+    assert_eq!(snapshot.mean_latency(), None);
+}
+
+#[test]
+fn mean_latency_of_many_responses() {
+    // More responses than fit in a `u32`
+    let snapshot = MetricsSnapshot {
+        responses: [0, 5_000_000_000, 0, 0, 0],
+        latency_total: Duration::from_secs(10_000_000),
+        ..MetricsSnapshot::default()
+    };
+    assert_eq!(snapshot.mean_latency(), Some(Duration::from_millis(2)));
+}
@@ -0,0 +1,255 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::encoding::{decode_text, percent_decode};
+use crate::error::HttpError;
+
+#[cfg(test)]
+mod tests;
+
+/// A media type such as `application/json` or `text/html; charset=utf-8` (RFC 9110 section 8.3.1).
+///
+/// Type, subtype and parameter names are case-insensitive and kept in lowercase.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, mime::Mime};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::new();
+///     client
+///         .post("https://example.com/notes")?
+///         .content_type(Mime::TEXT_PLAIN.with_param("charset", "utf-8"))
+///         .accept(Mime::JSON)
+///         .body(b"Remember the milk")
+///         .send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mime {
+    essence: Cow<'static, str>,
+    params: Vec<(String, String)>,
+}
+
+impl Mime {
+    /// `*/*`, any media type.
+    pub const ANY: Mime = Mime::from_static("*/*");
+    /// `application/json`
+    pub const JSON: Mime = Mime::from_static("application/json");
+    /// `application/xml`
+    pub const XML: Mime = Mime::from_static("application/xml");
+    /// `application/x-www-form-urlencoded`
+    pub const FORM_URLENCODED: Mime = Mime::from_static("application/x-www-form-urlencoded");
+    /// `multipart/form-data`
+    pub const MULTIPART_FORM_DATA: Mime = Mime::from_static("multipart/form-data");
+    /// `application/octet-stream`, arbitrary binary data.
+    pub const OCTET_STREAM: Mime = Mime::from_static("application/octet-stream");
+    /// `text/plain`
+    pub const TEXT_PLAIN: Mime = Mime::from_static("text/plain");
+    /// `text/html`
+    pub const TEXT_HTML: Mime = Mime::from_static("text/html");
+
+    /// Helper method, a media type without parameters from a lowercase `type/subtype`.
+    const fn from_static(essence: &'static str) -> Mime {
+        Mime {
+            essence: Cow::Borrowed(essence),
+            params: Vec::new(),
+        }
+    }
+
+    /// Parse a media type, e.g the value of a `Content-Type` header.
+    /// # Arguments
+    /// `value` Media type such as `text/html; charset="utf-8"`.
+    ///
+    /// # Errors
+    /// When the value is not a valid media type.
+    pub fn parse(value: &str) -> Result<Mime, HttpError> {
+        let invalid = || HttpError::Other(format!("Invalid media type `{value}`"));
+        let (essence, rest) = value.split_once(';').unwrap_or((value, ""));
+        let essence = essence.trim();
+        match essence.split_once('/') {
+            Some((kind, subtype)) if is_token(kind) && is_token(subtype) => (),
+            _ => return Err(invalid()),
+        }
+        let mime = Mime {
+            essence: Cow::Owned(essence.to_ascii_lowercase()),
+            params: Vec::new(),
+        };
+        let params = parse_params(rest).ok_or_else(invalid)?;
+        Ok(params
+            .into_iter()
+            .fold(mime, |mime, (name, value)| mime.with_param(&name, &value)))
+    }
+
+    /// The type and subtype, e.g `text/html`, without parameters.
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// The value of a parameter, e.g `charset`.
+    /// # Arguments
+    /// `name`  Name of the parameter, case-insensitive.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// This media type with a parameter set, replacing the previous value if any.
+    /// # Arguments
+    /// `name`  Name of the parameter, e.g `charset`.
+    /// `value` Value of the parameter, e.g `utf-8`.
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        self.params.retain(|(param, _)| *param != name);
+        self.params.push((name, value.to_owned()));
+        self
+    }
+}
+
+impl Display for Mime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.essence)?;
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {name}={value}")?;
+            } else {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {name}=\"{escaped}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Mime {
+    type Err = HttpError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Mime::parse(value)
+    }
+}
+
+/// Whether a value is a token, such as a method or parameter name (RFC 9110 section 5.6.2).
+pub(crate) fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Parse the `; name=value` parameters of a header such as `Content-Type`, values are unquoted.
+///
+/// Returns `None` when the parameters are malformed.
+pub(crate) fn parse_params(mut rest: &str) -> Option<Vec<(String, String)>> {
+    let mut params = Vec::new();
+    while !rest.trim().is_empty() {
+        let (name, value) = rest.split_once('=')?;
+        let name = name.trim_start_matches([' ', '\t', ';']).trim();
+        if !is_token(name) {
+            return None;
+        }
+        let value = value.trim_start();
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => unquote(quoted)?,
+            None => {
+                let (value, remaining) = value.split_once(';').unwrap_or((value, ""));
+                (value.trim().to_owned(), remaining)
+            }
+        };
+        params.push((name.to_owned(), value));
+        rest = remaining;
+    }
+    Some(params)
+}
+
+/// Split a comma-separated header value into its elements, ignoring commas in quoted strings.
+///
+/// Elements are trimmed, empty elements are skipped.
+pub(crate) fn split_list(value: &str) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                elements.push(value[start..index].trim());
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    elements.push(value[start..].trim());
+    elements.retain(|element| !element.is_empty());
+    elements
+}
+
+/// The file name suggested by a `Content-Disposition` header (RFC 6266), without any directory.
+///
+/// The extended `filename*` parameter, e.g `UTF-8''na%C3%AFve.txt`, is preferred over `filename`.
+pub(crate) fn disposition_filename(value: &str) -> Option<String> {
+    let (_, rest) = value.split_once(';')?;
+    let params = parse_params(rest)?;
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+    let extended = param("filename*").and_then(|value| {
+        let mut parts = value.splitn(3, '\'');
+        let (charset, _language, encoded) = (parts.next()?, parts.next()?, parts.next()?);
+        Some(decode_text(&percent_decode(encoded), Some(charset)))
+    });
+    let name = extended.or_else(|| param("filename").map(str::to_owned))?;
+    safe_filename(&name)
+}
+
+/// The file name at the end of a URL path, e.g `report.pdf` for `/files/report.pdf?v=2`.
+pub(crate) fn url_filename(path: &str) -> Option<String> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segment = path.rsplit('/').next().unwrap_or_default();
+    safe_filename(&String::from_utf8_lossy(&percent_decode(segment)))
+}
+
+/// A file name that cannot leave the directory it is saved to, `None` if nothing is left or
+/// it holds a `:`.
+pub(crate) fn safe_filename(name: &str) -> Option<String> {
+    // A drive prefix, e.g `C:evil`, leaves the directory and a stream hides the data
+    if name.contains(':') {
+        return None;
+    }
+    // Never let a server choose the directory a file is saved to
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name = name.replace(char::is_control, "");
+    match name.trim() {
+        "" | "." | ".." => None,
+        name => Some(name.to_owned()),
+    }
+}
+
+/// Helper method, split a quoted string, after its opening quote, from what follows it.
+fn unquote(quoted: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => {
+                let rest = quoted[index + 1..].trim_start();
+                let rest = match rest.strip_prefix(';') {
+                    Some(rest) => rest,
+                    None if rest.is_empty() => rest,
+                    None => return None,
+                };
+                return Some((value, rest));
+            }
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+    None
+}
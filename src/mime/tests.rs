@@ -0,0 +1,83 @@
+use crate::mime::{disposition_filename, split_list, url_filename, Mime};
+
+#[test]
+fn parse_media_types() {
+    let mime = Mime::parse("Text/HTML; Charset=UTF-8").unwrap();
+    assert_eq!(mime.essence(), "text/html");
+    assert_eq!(mime.param("charset"), Some("UTF-8"));
+    assert_eq!(mime.to_string(), "text/html; charset=UTF-8");
+
+    let mime = Mime::parse("multipart/form-data; boundary=\"a;b \\\"c\\\"\"; x=1").unwrap();
+    assert_eq!(mime.param("boundary"), Some("a;b \"c\""));
+    assert_eq!(mime.param("x"), Some("1"));
+    assert_eq!(Mime::parse(&mime.to_string()).unwrap(), mime);
+
+    assert_eq!("application/json".parse::<Mime>().unwrap(), Mime::JSON);
+    assert!(Mime::parse("json").is_err());
+    assert!(Mime::parse("text/ html").is_err());
+    assert!(Mime::parse("text/plain; charset").is_err());
+    assert!(Mime::parse("text/plain; charset=\"utf-8").is_err());
+}
+
+#[test]
+fn media_type_params() {
+    let mime = Mime::TEXT_PLAIN
+        .with_param("charset", "utf-8")
+        .with_param("Charset", "ascii")
+        .with_param("format", "flowed text");
+    assert_eq!(
+        mime.to_string(),
+        "text/plain; charset=ascii; format=\"flowed text\""
+    );
+}
+
+#[test]
+fn disposition_filenames() {
+    let filename = |value| disposition_filename(value);
+    assert_eq!(
+        filename("attachment; filename=\"report 2024.pdf\""),
+        Some("report 2024.pdf".to_owned())
+    );
+    assert_eq!(
+        filename("attachment; filename=fallback.txt; filename*=UTF-8''na%C3%AFve%20file.txt"),
+        Some("naïve file.txt".to_owned())
+    );
+    assert_eq!(
+        filename("attachment; filename*=iso-8859-1'en'caf%E9.txt"),
+        Some("café.txt".to_owned())
+    );
+    assert_eq!(
+        filename("attachment; filename=\"../../etc/passwd\""),
+        Some("passwd".to_owned())
+    );
+    assert_eq!(filename("attachment; filename=\"..\""), None);
+    assert_eq!(filename("attachment; filename=\"C:evil.exe\""), None);
+    assert_eq!(filename("attachment; filename=\"C:\\\\x.exe\""), None);
+    assert_eq!(filename("attachment; filename=\"file.txt:stream\""), None);
+    assert_eq!(filename("inline"), None);
+}
+
+#[test]
+fn url_filenames() {
+    assert_eq!(
+        url_filename("/files/My%20Report.pdf?v=2#page=3"),
+        Some("My Report.pdf".to_owned())
+    );
+    assert_eq!(
+        url_filename("/files/..%2F..%2Fetc%2Fpasswd"),
+        Some("passwd".to_owned())
+    );
+    assert_eq!(url_filename("/a%0Ab"), Some("ab".to_owned()));
+    assert_eq!(url_filename("/files/"), None);
+    assert_eq!(url_filename("/.."), None);
+    assert_eq!(url_filename("/D%3Aevil.exe"), None);
+}
+
+#[test]
+fn split_header_lists() {
+    assert_eq!(
+        split_list("a, b;x=\"1,2\" ,, c=\"\\\",\""),
+        ["a", "b;x=\"1,2\"", "c=\"\\\",\""]
+    );
+    assert!(split_list(" , ").is_empty());
+}
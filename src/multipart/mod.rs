@@ -0,0 +1,391 @@
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::body::BodyStream;
+use crate::error::HttpError;
+
+#[cfg(test)]
+mod tests;
+
+/// Content of a part, kept in memory or read from a file while the request is sent.
+enum Content {
+    Bytes(Vec<u8>),
+    File(File, u64),
+}
+
+/// A single part of a [`Form`].
+pub struct Part {
+    content: Content,
+    file_name: Option<String>,
+    mime_type: Option<String>,
+}
+
+impl Part {
+    /// A text part.
+    pub fn text(value: &str) -> Part {
+        Part::bytes(value.as_bytes().to_vec())
+    }
+
+    /// A binary part.
+    pub fn bytes(data: Vec<u8>) -> Part {
+        Part {
+            content: Content::Bytes(data),
+            file_name: None,
+            mime_type: None,
+        }
+    }
+
+    /// A part streamed from a file when the request is sent.
+    ///
+    /// The file name and MIME type are taken from the path, they can be changed with
+    /// [`Part::file_name`] and [`Part::mime_type`].
+    /// # Arguments
+    /// `path`  Path of the file being uploaded.
+    pub fn file<P: AsRef<Path>>(path: P) -> Result<Part, HttpError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        let mime_type = mime_type(path);
+        Ok(Part {
+            content: Content::File(file, length),
+            file_name,
+            mime_type: Some(mime_type.to_owned()),
+        })
+    }
+
+    /// Set the file name reported for this part.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_owned());
+        self
+    }
+
+    /// Set the `Content-Type` of this part.
+    pub fn mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_type = Some(mime_type.to_owned());
+        self
+    }
+
+    /// Helper method, the headers starting this part.
+    fn head(&self, boundary: &str, name: &str) -> String {
+        let mut head = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"",
+            escape(name)
+        );
+        if let Some(file_name) = &self.file_name {
+            head.push_str(&format!("; filename=\"{}\"", escape(file_name)));
+        }
+        head.push_str("\r\n");
+        if let Some(mime_type) = &self.mime_type {
+            head.push_str(&format!("Content-Type: {mime_type}\r\n"));
+        }
+        head.push_str("\r\n");
+        head
+    }
+}
+
+/// A `multipart/form-data` request body.
+///
+/// File parts are streamed from disk while the request is sent, without loading them in memory.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, multipart::Form};
+/// fn main() -> Result<(), HttpError> {
+///     let form = Form::new()
+///         .text("title", "Holidays")
+///         .file("photo", "beach.jpg")?;
+///     Client::new()
+///         .post("https://example.com/upload")?
+///         .multipart(form)
+///         .send()?;
+///     Ok(())
+/// }
+/// ```
+pub struct Form {
+    boundary: String,
+    parts: Vec<(String, Part)>,
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Form {
+            boundary: boundary(),
+            parts: Vec::new(),
+        }
+    }
+}
+
+impl Form {
+    /// Create an empty form.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a text field.
+    /// # Arguments
+    /// `name`  Name of the field.
+    /// `value` Value of the field.
+    pub fn text(self, name: &str, value: &str) -> Self {
+        self.part(name, Part::text(value))
+    }
+
+    /// Add a file field, streamed from disk when the request is sent.
+    /// # Arguments
+    /// `name`  Name of the field.
+    /// `path`  Path of the file being uploaded.
+    pub fn file<P: AsRef<Path>>(self, name: &str, path: P) -> Result<Self, HttpError> {
+        Ok(self.part(name, Part::file(path)?))
+    }
+
+    /// Add a field with the given content.
+    /// # Arguments
+    /// `name`  Name of the field.
+    /// `part`  Content of the field.
+    pub fn part(mut self, name: &str, part: Part) -> Self {
+        self.parts.push((name.to_owned(), part));
+        self
+    }
+
+    /// The boundary separating the parts of this form.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// The `Content-Type` of this form, including its boundary.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Helper method, the encoded form as a body read while the request is sent.
+    pub(crate) fn into_stream(self) -> BodyStream {
+        let mut length = 0;
+        let mut reader: Box<dyn Read + Send> = Box::new(std::io::empty());
+        for (name, part) in self.parts {
+            let head = part.head(&self.boundary, &name).into_bytes();
+            length += head.len() as u64 + 2;
+            let content: Box<dyn Read + Send> = match part.content {
+                Content::Bytes(data) => {
+                    length += data.len() as u64;
+                    Box::new(Cursor::new(data))
+                }
+                Content::File(file, size) => {
+                    length += size;
+                    Box::new(file.take(size))
+                }
+            };
+            reader = Box::new(
+                reader
+                    .chain(Cursor::new(head))
+                    .chain(content)
+                    .chain(&b"\r\n"[..]),
+            );
+        }
+        let tail = format!("--{}--\r\n", self.boundary).into_bytes();
+        length += tail.len() as u64;
+        BodyStream::new(reader.chain(Cursor::new(tail)), Some(length))
+    }
+}
+
+/// A range of bytes of a resource, sent in a `206 Partial Content` response.
+///
+/// See [`crate::response::Response::byte_ranges`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteRange {
+    start: u64,
+    end: u64,
+    total: Option<u64>,
+    content_type: Option<String>,
+    data: Vec<u8>,
+}
+
+impl ByteRange {
+    pub(crate) fn new(
+        start: u64,
+        end: u64,
+        total: Option<u64>,
+        content_type: Option<String>,
+        data: Vec<u8>,
+    ) -> ByteRange {
+        ByteRange {
+            start,
+            end,
+            total,
+            content_type,
+            data,
+        }
+    }
+
+    /// Offset of the first byte of this range in the resource.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Offset of the last byte of this range in the resource, inclusive.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Size of the whole resource, if the server announced it.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// The `Content-Type` of the resource, as sent with a part of a `multipart/byteranges` body.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// The bytes of this range.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the bytes of this range.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Parse a `Content-Range` header value such as `bytes 0-499/1234` or `bytes 0-499/*` into
+/// the first byte, the last byte and the size of the resource if known.
+pub(crate) fn parse_content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.trim().split_once('-')?;
+    let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    (start <= end && !matches!(total, Some(total) if end >= total)).then_some((start, end, total))
+}
+
+/// Parse the size of the resource sent in the `Content-Range` header of a `416` response,
+/// e.g `bytes */1234`.
+pub(crate) fn parse_unsatisfied_range(value: &str) -> Option<u64> {
+    value.trim().strip_prefix("bytes */")?.trim().parse().ok()
+}
+
+/// Parse the parts of a `multipart/byteranges` body, in the order they were sent.
+/// # Arguments
+/// `body`      The whole response body.
+/// `boundary`  Boundary of the parts, from the `Content-Type` of the response.
+pub(crate) fn parse_byteranges(body: &[u8], boundary: &str) -> Result<Vec<ByteRange>, HttpError> {
+    let invalid =
+        |reason: &str| HttpError::Other(format!("Invalid multipart/byteranges body: {reason}"));
+    let delimiter = format!("\r\n--{boundary}").into_bytes();
+    // The first delimiter may start the body, without a line break before it
+    let mut rest = match body.strip_prefix(&delimiter[2..]) {
+        Some(rest) => rest,
+        None => {
+            let start = find(body, &delimiter).ok_or_else(|| invalid("missing boundary"))?;
+            &body[start + delimiter.len()..]
+        }
+    };
+    let mut parts = Vec::new();
+    loop {
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        // The rest of the delimiter line is padding
+        let line_end = find(rest, b"\r\n").ok_or_else(|| invalid("truncated part"))?;
+        rest = &rest[line_end + 2..];
+        let head_end = if rest.starts_with(b"\r\n") {
+            0
+        } else {
+            find(rest, b"\r\n\r\n")
+                .map(|end| end + 2)
+                .ok_or_else(|| invalid("truncated part headers"))?
+        };
+        let head = String::from_utf8_lossy(&rest[..head_end]);
+        let header = |wanted: &str| {
+            head.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case(wanted)
+                    .then(|| value.trim().to_owned())
+            })
+        };
+        let (start, end, total) = header("Content-Range")
+            .and_then(|value| parse_content_range(&value))
+            .ok_or_else(|| invalid("part without a valid Content-Range"))?;
+        let content_type = header("Content-Type");
+        rest = &rest[head_end + 2..];
+        let data_end = find(rest, &delimiter).ok_or_else(|| invalid("missing closing boundary"))?;
+        let length = end
+            .checked_sub(start)
+            .and_then(|length| length.checked_add(1))
+            .ok_or_else(|| invalid("part with an invalid Content-Range"))?;
+        if data_end as u64 != length {
+            return Err(invalid("part length does not match its Content-Range"));
+        }
+        parts.push(ByteRange {
+            start,
+            end,
+            total,
+            content_type,
+            data: rest[..data_end].to_vec(),
+        });
+        rest = &rest[data_end + delimiter.len()..];
+    }
+}
+
+/// Helper method, the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Helper method, a random boundary unlikely to appear in any part.
+fn boundary() -> String {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    format!("------------------------{:016x}", hasher.finish())
+}
+
+/// Helper method, escape a field or file name for a `Content-Disposition` header.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\r', '\n'], " ")
+}
+
+/// The MIME type of a file, guessed from its extension.
+pub(crate) fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "ts" => "video/mp2t",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use crate::multipart::{
+    mime_type, parse_byteranges, parse_content_range, parse_unsatisfied_range, Form, Part,
+};
+
+#[test]
+fn encode_form() {
+    let mut form = Form::new().text("title", "Holidays").part(
+        "photo",
+        Part::bytes(vec![1, 2, 3])
+            .file_name("a\"b.jpg")
+            .mime_type("image/jpeg"),
+    );
+    form.boundary = "XyZ".into();
+    assert_eq!(form.content_type(), "multipart/form-data; boundary=XyZ");
+
+    let mut body = Vec::new();
+    let mut stream = form.into_stream();
+    let length = stream.length();
+    stream.write_to(&mut body, None).unwrap();
+    let mut expected =
+        b"--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nHolidays\r\n\
+--XyZ\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"a\\\"b.jpg\"\r\n\
+Content-Type: image/jpeg\r\n\r\n"
+            .to_vec();
+    expected.extend([1, 2, 3]);
+    expected.extend(b"\r\n--XyZ--\r\n");
+    assert_eq!(body, expected);
+    assert_eq!(length, Some(expected.len() as u64));
+    assert!(stream.write_to(&mut body, None).is_err());
+}
+
+#[test]
+fn guess_mime_types() {
+    assert_eq!(mime_type(Path::new("photo.JPG")), "image/jpeg");
+    assert_eq!(mime_type(Path::new("data.json")), "application/json");
+    assert_eq!(mime_type(Path::new("archive")), "application/octet-stream");
+}
+
+#[test]
+fn content_ranges() {
+    assert_eq!(
+        parse_content_range("bytes 0-499/1234"),
+        Some((0, 499, Some(1234)))
+    );
+    assert_eq!(
+        parse_content_range("bytes 500-999/*"),
+        Some((500, 999, None))
+    );
+    assert_eq!(parse_content_range("bytes 500-499/1234"), None);
+    assert_eq!(parse_content_range("bytes 0-1234/1234"), None);
+    assert_eq!(parse_content_range("bytes */1234"), None);
+    assert_eq!(parse_unsatisfied_range("bytes */1234"), Some(1234));
+    assert_eq!(parse_unsatisfied_range("bytes 0-1/2"), None);
+}
+
+#[test]
+fn parse_multipart_byteranges() {
+    let body = b"--THIS\r\n\
+Content-Type: text/plain\r\n\
+Content-Range: bytes 0-4/12\r\n\
+\r\n\
+hello\r\n\
+--THIS  \r\n\
+Content-Range: bytes 6-11/12\r\n\
+\r\n\
+world\n\r\n\
+--THIS--\r\n";
+    let parts = parse_byteranges(body, "THIS").unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!((parts[0].start(), parts[0].end()), (0, 4));
+    assert_eq!(parts[0].total(), Some(12));
+    assert_eq!(parts[0].content_type(), Some("text/plain"));
+    assert_eq!(parts[0].data(), b"hello");
+    assert_eq!(parts[1].content_type(), None);
+    assert_eq!(parts[1].data(), b"world\n");
+
+    assert!(parse_byteranges(b"--THIS\r\n\r\nhello\r\n--THIS--", "THIS").is_err());
+    let wrong_length = b"--THIS\r\nContent-Range: bytes 0-9/12\r\n\r\nhello\r\n--THIS--";
+    assert!(parse_byteranges(wrong_length, "THIS").is_err());
+    let unterminated = b"--THIS\r\nContent-Range: bytes 0-4/12\r\n\r\nhello";
+    assert!(parse_byteranges(unterminated, "THIS").is_err());
+    // A range as long as the largest length overflows it
+    let huge = b"--THIS\r\nContent-Range: bytes 0-18446744073709551615/*\r\n\r\nhello\r\n--THIS--";
+    assert!(parse_byteranges(huge, "THIS").is_err());
+}
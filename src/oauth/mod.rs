@@ -0,0 +1,230 @@
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use http_parse::StatusCode;
+
+use crate::client::Client;
+use crate::error::HttpError;
+use crate::json::{JsonParser, JsonValue};
+
+#[cfg(test)]
+mod tests;
+
+/// Tokens are refreshed this long before they expire, to allow for clock skew and latency.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// OAuth2 tokens obtained from the authorization server.
+#[derive(Default)]
+struct TokenState {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_at: Option<Instant>,
+}
+
+impl TokenState {
+    /// Whether the access token can still be used.
+    fn is_valid(&self) -> bool {
+        self.access_token.is_some()
+            && self
+                .expires_at
+                .is_none_or(|expires_at| Instant::now() + EXPIRY_MARGIN < expires_at)
+    }
+}
+
+/// OAuth2 bearer token management.
+///
+/// Requests of a client configured with [`crate::client::ClientBuilder::oauth2`] carry the
+/// current access token. The token is refreshed when it expires or when a request is rejected
+/// with status 401, using the refresh token grant when a refresh token is known and the client
+/// credentials grant otherwise. Refreshes are serialized, threads needing a token while a refresh
+/// is in progress wait for its result instead of starting their own.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, oauth::OAuth2};
+/// fn main() -> Result<(), HttpError> {
+///     let oauth = OAuth2::client_credentials("https://auth.example.com/token", "my-app", "secret")
+///         .scope("read write");
+///     let client = Client::builder().oauth2(oauth).build();
+///     let user = client.get("https://api.example.com/me")?.json_response()?;
+///     println!("{user}");
+///     Ok(())
+/// }
+/// ```
+pub struct OAuth2 {
+    token_url: String,
+    client_id: String,
+    client_secret: Option<String>,
+    scope: Option<String>,
+    client: Client,
+    state: Mutex<TokenState>,
+}
+
+impl OAuth2 {
+    /// Obtain tokens with the client credentials grant.
+    /// # Arguments
+    /// `token_url` Token endpoint of the authorization server.
+    /// `client_id` Identifier of this application.
+    /// `client_secret` Secret of this application.
+    pub fn client_credentials(token_url: &str, client_id: &str, client_secret: &str) -> OAuth2 {
+        OAuth2 {
+            token_url: token_url.to_owned(),
+            client_id: client_id.to_owned(),
+            client_secret: Some(client_secret.to_owned()),
+            scope: None,
+            client: Client::new(),
+            state: Mutex::new(TokenState::default()),
+        }
+    }
+
+    /// Obtain tokens with the refresh token grant.
+    /// # Arguments
+    /// `token_url` Token endpoint of the authorization server.
+    /// `client_id` Identifier of this application.
+    /// `refresh_token` Refresh token previously issued to this application.
+    pub fn refresh_token(token_url: &str, client_id: &str, refresh_token: &str) -> OAuth2 {
+        OAuth2 {
+            token_url: token_url.to_owned(),
+            client_id: client_id.to_owned(),
+            client_secret: None,
+            scope: None,
+            client: Client::new(),
+            state: Mutex::new(TokenState {
+                refresh_token: Some(refresh_token.to_owned()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Authenticate this application at the token endpoint with the given secret.
+    pub fn client_secret(mut self, secret: &str) -> Self {
+        self.client_secret = Some(secret.to_owned());
+        self
+    }
+
+    /// Request the given space separated scopes.
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.to_owned());
+        self
+    }
+
+    /// Start with an access token obtained elsewhere.
+    /// # Arguments
+    /// `token` The access token.
+    /// `expires_in`    Remaining lifetime of the token, if known.
+    pub fn access_token(self, token: &str, expires_in: Option<Duration>) -> Self {
+        if let Ok(mut state) = self.state.lock() {
+            state.access_token = Some(token.to_owned());
+            state.expires_at = expires_in.map(|expires_in| Instant::now() + expires_in);
+        }
+        self
+    }
+
+    /// Use the given client to talk to the token endpoint.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// A valid access token, refreshed first when the current one expired.
+    pub fn token(&self) -> Result<String, HttpError> {
+        let mut state = self.lock()?;
+        if !state.is_valid() {
+            self.refresh(&mut state)?;
+        }
+        state
+            .access_token
+            .clone()
+            .ok_or_else(|| HttpError::Other("No OAuth2 access token available".into()))
+    }
+
+    /// A new access token after `rejected` was refused by a server.
+    ///
+    /// If another thread already replaced the rejected token, its replacement is returned.
+    pub(crate) fn token_after_rejection(&self, rejected: &str) -> Result<String, HttpError> {
+        let mut state = self.lock()?;
+        if state.access_token.as_deref() == Some(rejected) || !state.is_valid() {
+            self.refresh(&mut state)?;
+        }
+        state
+            .access_token
+            .clone()
+            .ok_or_else(|| HttpError::Other("No OAuth2 access token available".into()))
+    }
+
+    /// Helper method, lock the token state.
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, TokenState>, HttpError> {
+        self.state
+            .lock()
+            .map_err(|_| HttpError::Other("OAuth2 token state poisoned".into()))
+    }
+
+    /// Helper method, request new tokens from the token endpoint.
+    fn refresh(&self, state: &mut TokenState) -> Result<(), HttpError> {
+        let mut request = self.client.post(&self.token_url)?;
+        request = match &state.refresh_token {
+            Some(refresh_token) => request
+                .form_data("grant_type", "refresh_token")
+                .form_data("refresh_token", refresh_token),
+            None if self.client_secret.is_some() => {
+                request.form_data("grant_type", "client_credentials")
+            }
+            None => {
+                return Err(HttpError::Other(
+                    "OAuth2 refresh needs a refresh token or a client secret".into(),
+                ))
+            }
+        };
+        if let Some(scope) = &self.scope {
+            request = request.form_data("scope", scope);
+        }
+        request = match &self.client_secret {
+            Some(secret) => request.basic_auth(&self.client_id, Some(secret)),
+            None => request.form_data("client_id", &self.client_id),
+        };
+
+        let response = request.send()?;
+        let body = String::from_utf8_lossy(response.data());
+        if response.status_code() != StatusCode::OK {
+            // Error responses are not always JSON, e.g when a proxy refused the request
+            let json = JsonParser::parse_json(&body).ok();
+            let field = |name| json.as_ref()?.get(name).and_then(JsonValue::string);
+            let reason = match (field("error"), field("error_description")) {
+                (Some(error), Some(description)) => format!("{error}: {description}"),
+                (Some(error), None) => error.to_owned(),
+                _ => response.status_msg().to_owned(),
+            };
+            return Err(HttpError::BadResponse(
+                response.status_code(),
+                format!("OAuth2 token request failed: {reason}"),
+            ));
+        }
+        let json = JsonParser::parse_json(&body).map_err(HttpError::Other)?;
+        let access_token = json
+            .get("access_token")
+            .and_then(JsonValue::string)
+            .ok_or_else(|| HttpError::Other("Token response without `access_token`".into()))?;
+        state.access_token = Some(access_token.to_owned());
+        state.expires_at = json
+            .get("expires_in")
+            .and_then(JsonValue::integer)
+            .and_then(|seconds| {
+                Instant::now().checked_add(Duration::from_secs((*seconds).max(0) as u64))
+            });
+        if let Some(refresh_token) = json.get("refresh_token").and_then(JsonValue::string) {
+            state.refresh_token = Some(refresh_token.to_owned());
+        }
+        Ok(())
+    }
+}
+
+impl Debug for OAuth2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuth2")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
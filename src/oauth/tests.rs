@@ -0,0 +1,106 @@
+use crate::client::Client;
+use crate::error::HttpError;
+use crate::oauth::OAuth2;
+use crate::test_server::{TestResponse, TestServer};
+
+/// A client for the token endpoint of a test server.
+fn client() -> Client {
+    Client::builder().no_proxy().build()
+}
+
+#[test]
+fn client_credentials_grant() {
+    let server = TestServer::start().unwrap();
+    let token = r#"{"access_token": "a1", "token_type": "Bearer", "expires_in": 3600}"#;
+    server.route("POST", "/token", TestResponse::text(200, token));
+    let oauth = OAuth2::client_credentials(&server.url("/token"), "app", "secret")
+        .scope("read")
+        .http_client(client());
+    assert_eq!(oauth.token().unwrap(), "a1");
+    // Still valid, not requested again
+    assert_eq!(oauth.token().unwrap(), "a1");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let body = String::from_utf8_lossy(&requests[0].body);
+    assert!(body.contains("grant_type=client_credentials"));
+    assert!(body.contains("scope=read"));
+    assert!(requests[0]
+        .header("Authorization")
+        .is_some_and(|value| value.starts_with("Basic ")));
+}
+
+#[test]
+fn refresh_token_grant() {
+    let server = TestServer::start().unwrap();
+    let first = r#"{"access_token": "a1", "refresh_token": "r2", "expires_in": 3600}"#;
+    server.route("POST", "/token", TestResponse::text(200, first));
+    server.route(
+        "POST",
+        "/token",
+        TestResponse::text(200, r#"{"access_token": "a2"}"#),
+    );
+    let oauth = OAuth2::refresh_token(&server.url("/token"), "app", "r1").http_client(client());
+    assert_eq!(oauth.token().unwrap(), "a1");
+    assert_eq!(oauth.token_after_rejection("a1").unwrap(), "a2");
+    // Already replaced by another thread
+    assert_eq!(oauth.token_after_rejection("a1").unwrap(), "a2");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    let body = |index: usize| String::from_utf8_lossy(&requests[index].body).to_string();
+    assert!(body(0).contains("grant_type=refresh_token"));
+    assert!(body(0).contains("refresh_token=r1"));
+    assert!(body(0).contains("client_id=app"));
+    // The rotated refresh token is used
+    assert!(body(1).contains("refresh_token=r2"));
+}
+
+#[test]
+fn token_errors() {
+    let server = TestServer::start().unwrap();
+    let rejected = r#"{"error": "invalid_grant", "error_description": "Token revoked"}"#;
+    server.route("POST", "/revoked", TestResponse::text(400, rejected));
+    server.route(
+        "POST",
+        "/minimal",
+        TestResponse::text(401, r#"{"error": "invalid_client"}"#),
+    );
+    server.route(
+        "POST",
+        "/proxy",
+        TestResponse::text(502, "<html>Bad Gateway</html>"),
+    );
+    server.route(
+        "POST",
+        "/empty",
+        TestResponse::text(200, r#"{"token_type": "Bearer"}"#),
+    );
+    let token = |path: &str| {
+        OAuth2::client_credentials(&server.url(path), "app", "secret")
+            .http_client(client())
+            .token()
+    };
+
+    match token("/revoked") {
+        Err(HttpError::BadResponse(400, message)) => {
+            assert_eq!(
+                message,
+                "OAuth2 token request failed: invalid_grant: Token revoked"
+            )
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    match token("/minimal") {
+        Err(HttpError::BadResponse(401, message)) => {
+            assert_eq!(message, "OAuth2 token request failed: invalid_client")
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    // Not a JSON error, the status is reported
+    assert!(matches!(
+        token("/proxy"),
+        Err(HttpError::BadResponse(502, _))
+    ));
+    assert!(matches!(token("/empty"), Err(HttpError::Other(_))));
+}
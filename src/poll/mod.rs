@@ -0,0 +1,385 @@
+use std::io::{Cursor, ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+use http_parse::{HttpParser, HttpResponse, HttpUrl, H_CONTENT_LENGTH, H_TRANSFER_ENCODING};
+use rustls::pki_types::ServerName;
+use rustls::ClientConnection;
+
+use crate::body::{ChunkedDecoder, Framing};
+use crate::config::Config;
+use crate::connection::{self, Connection};
+use crate::error::HttpError;
+use crate::response::Response;
+
+#[cfg(test)]
+mod tests;
+
+/// Bytes read from the socket at once.
+const READ_SIZE: usize = 16 * 1024;
+
+/// A decoded response body and its trailer fields.
+type DecodedBody = (Vec<u8>, Vec<(String, String)>);
+
+/// The length of a parsed response head, the head and the framing of its body.
+type ParsedHead = (usize, HttpResponse, Framing);
+
+/// A request driven without blocking, from the event loop of the caller, see
+/// [`crate::client::ClientRequest::send_nonblocking`].
+///
+/// The socket is non-blocking: register it with a readiness API such as `poll`, `epoll` or
+/// `mio` and call [`PendingResponse::poll`] whenever it is readable, or writable while
+/// [`PendingResponse::wants_write`] says so, until the response is complete. No thread is
+/// started and no call waits for the network once the request is returned. Resolving the
+/// host and opening the TCP connection do block though, for up to the connect timeout of the
+/// client, as they happen before the request is returned. Only the TLS handshake, the request
+/// and the response are driven without blocking.
+///
+/// This is a low level API: redirects, retries, authentication challenges, cookies and the
+/// cache are left to the caller, and proxies are not supported.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let mut pending = Client::new()
+///         .get("https://example.com/status")?
+///         .send_nonblocking()?;
+///     // Register `pending.socket()` with the event loop, then on every readiness event:
+///     let response = loop {
+///         if let Some(response) = pending.poll()? {
+///             break response;
+///         }
+///         // ...wait for the next event instead of spinning
+///     };
+///     println!("{}", response.status_code());
+///     Ok(())
+/// }
+/// ```
+pub struct PendingResponse {
+    socket: TcpStream,
+    tls: Option<Box<ClientConnection>>,
+    url: String,
+    method: String,
+    /// Bytes of a plain request not written yet.
+    unsent: Vec<u8>,
+    /// Bytes of the response received so far, after the interim responses.
+    received: Vec<u8>,
+    /// Bytes of `received` already searched for the end of the head.
+    scanned: usize,
+    /// The head of the response, once received.
+    head: Option<ParsedHead>,
+    /// Decodes a chunked body as it is received, the bytes decoded are dropped from
+    /// `received`.
+    chunked: ChunkedDecoder,
+    /// The decoded body and its trailers, once all of it was received.
+    complete: Option<DecodedBody>,
+    /// Whether the server closed the connection.
+    closed: bool,
+    max_header_size: usize,
+    max_body_size: Option<u64>,
+}
+
+impl PendingResponse {
+    /// Open a connection for the given request and make it non-blocking.
+    ///
+    /// Resolving the host and connecting block, the socket is non-blocking afterwards.
+    /// # Arguments
+    /// `method`    Method of the request, e.g `GET`.
+    /// `request`   The whole request in wire format.
+    pub(crate) fn start(
+        config: &Config,
+        url: &HttpUrl,
+        method: String,
+        request: Vec<u8>,
+    ) -> Result<Self, HttpError> {
+        if config
+            .proxy
+            .as_ref()
+            .is_some_and(|proxy| proxy.server_for(url).is_some())
+        {
+            return Err(HttpError::Other(
+                "Requests sent through a proxy cannot be sent without blocking".to_owned(),
+            ));
+        }
+        let socket = Connection::connect(config, config.resolved(url.address().to_string()), None)?;
+        socket.set_nonblocking(true)?;
+        let mut pending = PendingResponse {
+            socket,
+            tls: None,
+            url: url.to_string(),
+            method,
+            unsent: Vec::new(),
+            received: Vec::new(),
+            scanned: 0,
+            head: None,
+            chunked: ChunkedDecoder::default(),
+            complete: None,
+            closed: false,
+            max_header_size: config.max_header_size,
+            max_body_size: config.max_body_size,
+        };
+        if url.scheme().eq_ignore_ascii_case("https") {
+            let name = config.tls_server_name(url.host()).to_owned();
+            let server_name =
+                ServerName::try_from(name).map_err(|_e| HttpError::InvalidUrl(url.to_string()))?;
+            let mut tls = ClientConnection::new(config.tls_settings()?, server_name)
+                .map_err(|e| HttpError::ConnectionError(e.to_string()))?;
+            // Buffered until the handshake completes
+            tls.writer().write_all(&request)?;
+            pending.tls = Some(Box::new(tls));
+        } else {
+            pending.unsent = request;
+        }
+        Ok(pending)
+    }
+
+    /// The socket of the request, to register with an event loop. It must not be read or
+    /// written directly.
+    pub fn socket(&self) -> &TcpStream {
+        &self.socket
+    }
+
+    /// Whether the request has bytes to write, so the socket must be polled for writability
+    /// as well as readability.
+    pub fn wants_write(&self) -> bool {
+        match &self.tls {
+            Some(tls) => tls.wants_write(),
+            None => !self.unsent.is_empty(),
+        }
+    }
+
+    /// Make as much progress as possible without blocking, returning the response once it
+    /// is complete.
+    ///
+    /// # Errors
+    /// When the connection fails, or the response is invalid or larger than allowed.
+    pub fn poll(&mut self) -> Result<Option<Response>, HttpError> {
+        self.flush()?;
+        self.fill()?;
+        // Reading may have produced TLS records to send, e.g during the handshake
+        self.flush()?;
+        self.parse()
+    }
+
+    /// Helper method, write what can be written of the request.
+    fn flush(&mut self) -> Result<(), HttpError> {
+        match &mut self.tls {
+            Some(tls) => {
+                while tls.wants_write() {
+                    match tls.write_tls(&mut self.socket) {
+                        Ok(_) => (),
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+            None => {
+                while !self.unsent.is_empty() {
+                    match self.socket.write(&self.unsent) {
+                        Ok(0) => {
+                            return Err(HttpError::ConnectionError(
+                                "Connection closed while writing the request".to_owned(),
+                            ))
+                        }
+                        Ok(written) => {
+                            self.unsent.drain(..written);
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper method, read what the server sent so far, until the response is complete.
+    ///
+    /// The response is checked after every read, so a response larger than allowed fails
+    /// before it is buffered.
+    fn fill(&mut self) -> Result<(), HttpError> {
+        let mut buf = [0u8; READ_SIZE];
+        while !self.closed && self.complete.is_none() {
+            let read = match &mut self.tls {
+                Some(tls) => match tls.read_tls(&mut self.socket) {
+                    Ok(0) => 0,
+                    Ok(_) => {
+                        tls.process_new_packets()
+                            .map_err(|e| HttpError::ConnectionError(e.to_string()))?;
+                        Self::drain_plaintext(tls, &mut self.received, &mut self.closed)?;
+                        self.advance()?;
+                        continue;
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                    Err(e) => return Err(e.into()),
+                },
+                None => match self.socket.read(&mut buf) {
+                    Ok(read) => read,
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                    Err(e) => return Err(e.into()),
+                },
+            };
+            if read == 0 {
+                self.closed = true;
+            }
+            self.received.extend_from_slice(&buf[..read]);
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    /// Helper method, move the decrypted bytes of a TLS connection to `received`.
+    fn drain_plaintext(
+        tls: &mut ClientConnection,
+        received: &mut Vec<u8>,
+        closed: &mut bool,
+    ) -> Result<(), HttpError> {
+        let mut buf = [0u8; READ_SIZE];
+        loop {
+            match tls.reader().read(&mut buf) {
+                // The server sent `close_notify`
+                Ok(0) => {
+                    *closed = true;
+                    return Ok(());
+                }
+                Ok(read) => received.extend_from_slice(&buf[..read]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Helper method, the response once all of it was received.
+    ///
+    /// The head is searched for in the bytes received since the last call only, and parsed
+    /// once, so receiving a response in many small reads is not quadratic.
+    fn parse(&mut self) -> Result<Option<Response>, HttpError> {
+        self.advance()?;
+        let (Some((end, response, framing)), Some((data, trailers))) =
+            (&self.head, self.complete.take())
+        else {
+            return Ok(None);
+        };
+        let head = &self.received[..*end];
+        let message = connection::decoded_message(head, *framing, &data);
+        let response = match framing {
+            Framing::Empty => response.clone(),
+            _ => HttpParser::from_reader(&mut Cursor::new(&message)).response()?,
+        };
+        Ok(Some(
+            Response::new(response, head.to_vec(), self.url.clone(), Vec::new())
+                .with_peer_addr(self.socket.peer_addr().ok())
+                .with_trailers(trailers),
+        ))
+    }
+
+    /// Helper method, parse what was received since the last call, the head of the response
+    /// and then its body.
+    fn advance(&mut self) -> Result<(), HttpError> {
+        self.parse_head()?;
+        let Some((end, _, framing)) = &self.head else {
+            return Ok(());
+        };
+        if self.complete.is_none() {
+            self.complete = self.body(*framing, *end)?;
+        }
+        Ok(())
+    }
+
+    /// Helper method, parse the head of the final response once it was received, skipping
+    /// interim responses such as `100 Continue`.
+    fn parse_head(&mut self) -> Result<(), HttpError> {
+        while self.head.is_none() {
+            let end = head_end(&self.received, self.scanned);
+            // The end of the head may start in the last bytes searched
+            self.scanned = self.received.len().saturating_sub(3);
+            let Some(end) = end else {
+                if self.received.len() > self.max_header_size {
+                    return Err(HttpError::TooLarge(format!(
+                        "response head exceeds {} bytes",
+                        self.max_header_size
+                    )));
+                }
+                if self.closed {
+                    return Err(HttpError::ConnectionError(
+                        "Connection closed while reading response head".to_owned(),
+                    ));
+                }
+                return Ok(());
+            };
+            let response = HttpParser::from_reader(&mut Cursor::new(&self.received[..end]))
+                .response_head_only()?;
+            let status = response.status_code();
+            if (100..200).contains(&status) && status != 101 {
+                self.received.drain(..end);
+                self.scanned = 0;
+                continue;
+            }
+            let header = |name: &str| {
+                response
+                    .header(name)
+                    .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+            };
+            let framing = Framing::of(
+                &self.method,
+                status,
+                header(H_TRANSFER_ENCODING).as_deref(),
+                header(H_CONTENT_LENGTH).as_deref(),
+            )?;
+            self.head = Some((end, response, framing));
+        }
+        Ok(())
+    }
+
+    /// Helper method, the decoded body and its trailers once all of it was received.
+    ///
+    /// A chunked body is decoded as it is received, every byte once.
+    /// # Arguments
+    /// `framing`   Framing of the body.
+    /// `start`     Position of the body in the bytes received.
+    fn body(&mut self, framing: Framing, start: usize) -> Result<Option<DecodedBody>, HttpError> {
+        let too_large = |limit| HttpError::TooLarge(format!("response body exceeds {limit} bytes"));
+        let limit = self.max_body_size.unwrap_or(u64::MAX);
+        let body = &self.received[start..];
+        match framing {
+            Framing::Empty => Ok(Some((Vec::new(), Vec::new()))),
+            Framing::Sized(length) if length > limit => Err(too_large(limit)),
+            Framing::Sized(length) if body.len() as u64 >= length => {
+                Ok(Some((body[..length as usize].to_vec(), Vec::new())))
+            }
+            Framing::Sized(length) if self.closed => {
+                Err(HttpError::IncompleteBody(body.len() as u64, Some(length)))
+            }
+            Framing::Close if body.len() as u64 > limit => Err(too_large(limit)),
+            Framing::Close if self.closed => Ok(Some((body.to_vec(), Vec::new()))),
+            Framing::Chunked => {
+                let used = self.chunked.decode(body)?;
+                self.received.drain(start..start + used);
+                let decoded = self.chunked.data().len() as u64;
+                if decoded > limit {
+                    return Err(too_large(limit));
+                }
+                if self.chunked.is_done() {
+                    return Ok(Some(self.chunked.take()));
+                }
+                if self.closed {
+                    return Err(HttpError::IncompleteBody(decoded, None));
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The length of the head of a response, once it was received.
+/// # Arguments
+/// `received`  Bytes of the response received so far.
+/// `from`      Bytes already searched without finding the end of the head.
+pub(crate) fn head_end(received: &[u8], from: usize) -> Option<usize> {
+    let position = received
+        .get(from..)?
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")?;
+    Some(from + position + 4)
+}
@@ -0,0 +1,153 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::body::{ChunkedDecoder, Framing};
+use crate::client::Client;
+use crate::error::HttpError;
+use crate::poll::{head_end, PendingResponse};
+
+/// A plain request on a non-blocking connection, with the server end of the connection.
+fn pending(method: &str) -> (PendingResponse, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    socket.set_nonblocking(true).unwrap();
+    let pending = PendingResponse {
+        socket,
+        tls: None,
+        url: "http://localhost/".to_owned(),
+        method: method.to_owned(),
+        unsent: b"GET / HTTP/1.1\r\n\r\n".to_vec(),
+        received: Vec::new(),
+        scanned: 0,
+        head: None,
+        chunked: ChunkedDecoder::default(),
+        complete: None,
+        closed: false,
+        max_header_size: 8192,
+        max_body_size: Some(64),
+    };
+    (pending, server)
+}
+
+#[test]
+fn connection_driven_without_blocking() {
+    let (mut pending, mut server) = pending("GET");
+    assert!(pending.wants_write());
+    pending.flush().unwrap();
+    assert!(!pending.wants_write());
+    // Nothing to read yet
+    pending.fill().unwrap();
+    assert!(pending.received.is_empty());
+
+    let mut request = [0u8; 18];
+    server.read_exact(&mut request).unwrap();
+    assert_eq!(&request, b"GET / HTTP/1.1\r\n\r\n");
+    server.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+    drop(server);
+    while !pending.closed {
+        pending.fill().unwrap();
+    }
+    assert_eq!(pending.received, b"HTTP/1.1 200 OK\r\n\r\n");
+}
+
+#[test]
+fn body_completion() {
+    let (mut pending, _server) = pending("GET");
+    let mut body = |framing, data: &[u8]| {
+        pending.received = data.to_vec();
+        pending.body(framing, 0)
+    };
+
+    assert!(body(Framing::Sized(10), b"hello").unwrap().is_none());
+    assert!(body(Framing::Close, b"hello").unwrap().is_none());
+    assert!(matches!(
+        body(Framing::Sized(100), b""),
+        Err(HttpError::TooLarge(_))
+    ));
+    // The limit applies while a body of unknown length is received
+    assert!(matches!(
+        body(Framing::Close, &[b'a'; 65]),
+        Err(HttpError::TooLarge(_))
+    ));
+
+    pending.closed = true;
+    let mut body = |framing, data: &[u8]| {
+        pending.received = data.to_vec();
+        pending.body(framing, 0)
+    };
+    assert_eq!(body(Framing::Close, b"hello").unwrap().unwrap().0, b"hello");
+    assert!(matches!(
+        body(Framing::Sized(10), b"hello"),
+        Err(HttpError::IncompleteBody(5, Some(10)))
+    ));
+}
+
+#[test]
+fn chunked_body_decoded_incrementally() {
+    let (mut large, _large) = pending("GET");
+    let (mut truncated, _truncated) = pending("GET");
+    let (mut pending, _server) = pending("GET");
+    pending.received = b"5\r\nhel".to_vec();
+    assert!(pending.body(Framing::Chunked, 0).unwrap().is_none());
+    // Decoded bytes are not decoded again
+    assert_eq!(pending.received, b"");
+    pending.received.extend_from_slice(b"lo\r\n0\r\nExpi");
+    assert!(pending.body(Framing::Chunked, 0).unwrap().is_none());
+    assert_eq!(pending.received, b"Expi");
+    pending.received.extend_from_slice(b"res: never\r\n\r\n");
+    let (data, trailers) = pending.body(Framing::Chunked, 0).unwrap().unwrap();
+    assert_eq!(data, b"hello");
+    assert_eq!(trailers, [("Expires".to_owned(), "never".to_owned())]);
+
+    // The limit applies to the body decoded so far
+    large.received = b"41\r\n".to_vec();
+    large.received.extend_from_slice(&[b'a'; 65]);
+    assert!(matches!(
+        large.body(Framing::Chunked, 0),
+        Err(HttpError::TooLarge(_))
+    ));
+    truncated.received = b"5\r\nhel".to_vec();
+    truncated.closed = true;
+    assert!(matches!(
+        truncated.body(Framing::Chunked, 0),
+        Err(HttpError::IncompleteBody(3, None))
+    ));
+}
+
+#[test]
+fn response_heads() {
+    assert_eq!(head_end(b"HTTP/1.1 200 OK\r\n", 0), None);
+    assert_eq!(head_end(b"HTTP/1.1 200 OK\r\n\r\nbody", 0), Some(19));
+    assert_eq!(head_end(b"HTTP/1.1 200 OK\r\n\r\nbody", 15), Some(19));
+    assert_eq!(head_end(b"HTTP/1.1 200 OK\r\n", 40), None);
+}
+
+#[test]
+fn response_parsed_incrementally() {
+    let (mut pending, _server) = pending("GET");
+    let response =
+        b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+    let mut parsed = None;
+    for byte in response {
+        assert!(parsed.is_none());
+        pending.received.push(*byte);
+        parsed = pending.parse().unwrap();
+        // Only the last bytes are searched again for the end of the head
+        assert!(pending.head.is_some() || pending.scanned + 3 >= pending.received.len());
+    }
+    let parsed = parsed.unwrap();
+    assert_eq!(parsed.status_code(), 200);
+    assert_eq!(parsed.text(), "hello");
+    assert_eq!(pending.head.as_ref().unwrap().0, 38);
+}
+
+#[test]
+fn plain_urls_refused() {
+    let client = Client::builder().https_only(true).build();
+    let result = client
+        .get("http://127.0.0.1:9/")
+        .unwrap()
+        .send_nonblocking();
+    assert!(matches!(result, Err(HttpError::InvalidUrl(_))));
+}
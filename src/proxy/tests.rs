@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use crate::proxy::{Proxy, ProxyServer};
+
+#[test]
+fn parse_proxy_address() {
+    let proxy = Proxy::all("http://proxy.local:3128/").expect("Fail parsing proxy");
+    assert_eq!(
+        proxy.http,
+        Some(ProxyServer {
+            host: "proxy.local".into(),
+            port: 3128,
+            authorization: None,
+        })
+    );
+    assert_eq!(proxy.http, proxy.https);
+
+    let proxy = Proxy::all("http://Aladdin:open sesame@proxy.local:3128").unwrap();
+    let server = proxy.https.unwrap();
+    assert_eq!(server.address(), "proxy.local:3128");
+    assert_eq!(
+        server.authorization.as_deref(),
+        Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+    );
+
+    let proxy = Proxy::http("proxy.local").expect("Fail parsing proxy");
+    assert_eq!(proxy.http.unwrap().port, 80);
+    assert!(proxy.https.is_none());
+
+    assert!(Proxy::all("proxy.local:port").is_err());
+
+    let ipv6 = |address| Proxy::all(address).map(|proxy| proxy.http.unwrap().address());
+    assert_eq!(ipv6("http://[::1]:3128").unwrap(), "[::1]:3128");
+    assert_eq!(ipv6("[2001:db8::1]").unwrap(), "[2001:db8::1]:80");
+    assert!(ipv6("[::1").is_err());
+    assert!(ipv6("[::1]3128").is_err());
+    assert!(ipv6("[::1]:port").is_err());
+}
+
+#[test]
+fn no_proxy_matching() {
+    let proxy = Proxy::all("proxy:8080")
+        .unwrap()
+        .no_proxy("localhost; .internal,*.corp.com, example.org,<local>");
+    assert!(proxy.bypass("localhost"));
+    assert!(proxy.bypass("intranet"));
+    assert!(proxy.bypass("wiki.internal"));
+    assert!(proxy.bypass("internal"));
+    assert!(proxy.bypass("mail.corp.com"));
+    assert!(proxy.bypass("api.example.org"));
+    assert!(proxy.bypass("EXAMPLE.org"));
+    assert!(!proxy.bypass("notexample.org"));
+    assert!(!proxy.bypass("example.com"));
+}
+
+#[test]
+fn windows_registry_settings() {
+    let output = r"
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Internet Settings
+    ProxyEnable    REG_DWORD    0x1
+    ProxyServer    REG_SZ    http=web.corp:8080;https=secure.corp:8443
+    ProxyOverride    REG_SZ    <local>;*.corp
+";
+    let proxy = Proxy::from_registry(output).expect("Proxy should be enabled");
+    assert_eq!(proxy.http.as_ref().unwrap().address(), "web.corp:8080");
+    assert_eq!(proxy.https.as_ref().unwrap().address(), "secure.corp:8443");
+    assert!(proxy.bypass("wiki.corp"));
+    assert!(proxy.bypass("printer"));
+
+    let disabled = output.replace("0x1", "0x0");
+    assert!(Proxy::from_registry(&disabled).is_none());
+}
+
+#[test]
+fn macos_scutil_settings() {
+    let output = "<dictionary> {
+  ExceptionsList : <array> {
+    0 : *.local
+    1 : 169.254/16
+  }
+  HTTPEnable : 1
+  HTTPPort : 3128
+  HTTPProxy : proxy.corp
+  HTTPSEnable : 0
+}";
+    let proxy = Proxy::from_scutil(output).expect("Proxy should be enabled");
+    assert_eq!(proxy.http.as_ref().unwrap().address(), "proxy.corp:3128");
+    assert!(proxy.https.is_none());
+    assert!(proxy.bypass("printer.local"));
+    assert!(!proxy.bypass("example.com"));
+}
+
+#[test]
+fn auto_config_urls() {
+    let registry = r"
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Internet Settings
+    ProxyEnable    REG_DWORD    0x0
+    AutoConfigURL    REG_SZ    http://wpad.corp/proxy.pac
+";
+    assert_eq!(
+        Proxy::auto_config_from_registry(registry).as_deref(),
+        Some("http://wpad.corp/proxy.pac")
+    );
+    assert!(Proxy::from_registry(registry).is_none());
+    assert!(Proxy::auto_config_from_registry("    ProxyEnable    REG_DWORD    0x1").is_none());
+
+    let scutil = "<dictionary> {
+  HTTPEnable : 0
+  ProxyAutoConfigEnable : 1
+  ProxyAutoConfigURLString : http://wpad.corp/proxy.pac
+}";
+    assert_eq!(
+        Proxy::auto_config_from_scutil(scutil).as_deref(),
+        Some("http://wpad.corp/proxy.pac")
+    );
+    let disabled = scutil.replace("ProxyAutoConfigEnable : 1", "ProxyAutoConfigEnable : 0");
+    assert!(Proxy::auto_config_from_scutil(&disabled).is_none());
+}
+
+#[test]
+fn environment_settings() {
+    let from = |vars: &[(&str, &str)]| {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        Proxy::from_vars(|name| vars.get(name).cloned())
+    };
+    let proxy = from(&[
+        ("HTTP_PROXY", "http://web.corp:8080"),
+        ("https_proxy", "secure.corp:8443"),
+        ("NO_PROXY", "localhost"),
+    ])
+    .unwrap();
+    assert_eq!(proxy.http.as_ref().unwrap().address(), "web.corp:8080");
+    assert_eq!(proxy.https.as_ref().unwrap().address(), "secure.corp:8443");
+    assert!(proxy.bypass("localhost"));
+    assert!(from(&[("HTTP_PROXY", " ")]).is_none());
+
+    // Under CGI `HTTP_PROXY` comes from the `Proxy` header of the request being served
+    let cgi = [("REQUEST_METHOD", "GET"), ("HTTP_PROXY", "attacker:80")];
+    assert!(from(&cgi).is_none());
+    let proxy = from(&[("REQUEST_METHOD", "GET"), ("http_proxy", "web.corp:8080")]).unwrap();
+    assert_eq!(proxy.http.unwrap().address(), "web.corp:8080");
+}
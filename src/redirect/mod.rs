@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use http_parse::{HttpUrl, H_CONTENT_LENGTH, H_CONTENT_TYPE, H_TRANSFER_ENCODING};
+
+#[cfg(test)]
+mod tests;
+
+/// Headers describing a request body, dropped when a redirect turns the request into a `GET`.
+pub(crate) const BODY_HEADERS: [&str; 5] = [
+    H_CONTENT_TYPE,
+    H_CONTENT_LENGTH,
+    H_TRANSFER_ENCODING,
+    "Content-Digest",
+    "Trailer",
+];
+
+/// A redirect followed while sending a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    url: String,
+    status: usize,
+}
+
+impl Redirect {
+    pub(crate) fn new(url: String, status: usize) -> Redirect {
+        Redirect { url, status }
+    }
+
+    /// The URL that answered with the redirect.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Status code of the redirect, e.g `301`.
+    pub fn status(&self) -> usize {
+        self.status
+    }
+}
+
+/// A permanent redirect remembered by a [`RedirectCache`].
+#[derive(Debug, Clone)]
+struct CachedRedirect {
+    status: usize,
+    location: String,
+    expires: Instant,
+}
+
+/// Permanent redirects (`301` and `308`) remembered per URL.
+///
+/// Requests to a URL known to be permanently redirected are sent straight to its new location,
+/// saving a round trip. The skipped redirect is still reported by
+/// [`crate::response::Response::redirects`].
+#[derive(Debug)]
+pub struct RedirectCache {
+    redirects: Mutex<HashMap<String, CachedRedirect>>,
+    ttl: Duration,
+}
+
+impl RedirectCache {
+    /// Create an empty cache remembering redirects for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        RedirectCache {
+            redirects: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Number of redirects remembered.
+    pub fn len(&self) -> usize {
+        self.redirects
+            .lock()
+            .map(|redirects| redirects.len())
+            .unwrap_or_default()
+    }
+
+    /// Whether no redirect is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every redirect.
+    pub fn clear(&self) {
+        if let Ok(mut redirects) = self.redirects.lock() {
+            redirects.clear();
+        }
+    }
+
+    /// The status and location of the permanent redirect remembered for a URL.
+    pub(crate) fn get(&self, url: &str) -> Option<(usize, String)> {
+        let mut redirects = self.redirects.lock().ok()?;
+        let redirect = redirects.get(url)?;
+        if redirect.expires <= Instant::now() {
+            redirects.remove(url);
+            return None;
+        }
+        Some((redirect.status, redirect.location.clone()))
+    }
+
+    /// Remember a permanent redirect from `url` to `location`.
+    pub(crate) fn insert(&self, url: &str, status: usize, location: &str) {
+        if let Ok(mut redirects) = self.redirects.lock() {
+            redirects.retain(|_, redirect| redirect.expires > Instant::now());
+            redirects.insert(
+                url.to_owned(),
+                CachedRedirect {
+                    status,
+                    location: location.to_owned(),
+                    expires: Instant::now() + self.ttl,
+                },
+            );
+        }
+    }
+}
+
+/// Whether the given status code asks the client to follow a `Location` header.
+pub(crate) fn is_redirect(status: usize) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Whether following a redirect with the given status turns the request into a body-less `GET`.
+///
+/// A `303 See Other` is always followed with `GET` (`HEAD` stays `HEAD`), `301` and `302`
+/// turn `POST` into `GET` as browsers do. `307` and `308` keep the method and body.
+pub(crate) fn changes_to_get(status: usize, method: &str) -> bool {
+    match status {
+        301 | 302 => method == "POST",
+        303 => !matches!(method, "GET" | "HEAD"),
+        _ => false,
+    }
+}
+
+/// Whether two URLs have the same origin, i.e the same scheme, host and port, so the
+/// credentials sent to one can be sent to the other.
+pub(crate) fn same_origin(previous: &HttpUrl, next: &HttpUrl) -> bool {
+    previous.scheme().eq_ignore_ascii_case(next.scheme())
+        && previous
+            .address()
+            .to_string()
+            .eq_ignore_ascii_case(&next.address().to_string())
+}
+
+/// Resolve a `Location` header value against the URL of the request that received it.
+///
+/// # Arguments
+/// `scheme`    Scheme of the request URL, e.g `https`
+/// `address`   Host and port of the request URL, e.g `example.com:443`
+/// `path`  Path of the request URL, including the query.
+/// `location`  Value of the `Location` header.
+pub(crate) fn resolve_location(scheme: &str, address: &str, path: &str, location: &str) -> String {
+    let location = location.trim();
+    if let Some((location_scheme, _)) = location.split_once("://") {
+        if !location_scheme.is_empty()
+            && location_scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            return location.to_owned();
+        }
+    }
+    if location.starts_with("//") {
+        return format!("{scheme}:{location}");
+    }
+    let path = path.split('#').next().unwrap_or_default();
+    let target = if location.starts_with('/') {
+        location.to_owned()
+    } else if location.starts_with('?') {
+        let path = path.split('?').next().unwrap_or_default();
+        format!("{path}{location}")
+    } else {
+        let path = path.split('?').next().unwrap_or_default();
+        let directory = path.rfind('/').map_or("/", |index| &path[..=index]);
+        format!("{directory}{location}")
+    };
+    format!("{scheme}://{address}{}", remove_dot_segments(&target))
+}
+
+/// Helper method, remove `.` and `..` segments from a path as described by RFC 3986.
+fn remove_dot_segments(target: &str) -> String {
+    let (path, query) = match target.find(['?', '#']) {
+        Some(index) => target.split_at(index),
+        None => (target, ""),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in path.split('/').skip(1) {
+        trailing_slash = matches!(segment, "." | ".." | "");
+        match segment {
+            "." => (),
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    if trailing_slash && segments.last().is_some_and(|last| !last.is_empty()) {
+        segments.push("");
+    }
+    format!("/{}{query}", segments.join("/"))
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use http_parse::HttpUrl;
+
+use crate::auth::Credentials;
+use crate::client::Client;
+use crate::oauth::OAuth2;
+use crate::redirect::{changes_to_get, is_redirect, resolve_location, same_origin, RedirectCache};
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn redirect_statuses() {
+    assert!([301, 302, 303, 307, 308].into_iter().all(is_redirect));
+    assert!(![200, 300, 304, 305, 400].into_iter().any(is_redirect));
+}
+
+#[test]
+fn origins_compared() {
+    let url = |url: &str| HttpUrl::try_from(url).unwrap();
+    let previous = url("https://example.com/a");
+    assert!(same_origin(&previous, &url("https://EXAMPLE.com/b?c=1")));
+    assert!(same_origin(&previous, &url("https://example.com:443/b")));
+    assert!(!same_origin(&previous, &url("http://example.com/a")));
+    assert!(!same_origin(&previous, &url("https://example.com:8443/a")));
+    assert!(!same_origin(&previous, &url("https://api.example.com/a")));
+}
+
+#[test]
+fn resolve_locations() {
+    let resolve = |location| resolve_location("https", "example.com:443", "/a/b/c?q=1", location);
+    assert_eq!(resolve("http://other.com/x"), "http://other.com/x");
+    assert_eq!(resolve("//cdn.com/x"), "https://cdn.com/x");
+    assert_eq!(resolve("/x?y=2"), "https://example.com:443/x?y=2");
+    assert_eq!(resolve("d"), "https://example.com:443/a/b/d");
+    assert_eq!(resolve("../d"), "https://example.com:443/a/d");
+    assert_eq!(resolve("./"), "https://example.com:443/a/b/");
+    assert_eq!(resolve("?q=2"), "https://example.com:443/a/b/c?q=2");
+    assert_eq!(resolve("../../../d"), "https://example.com:443/d");
+}
+
+#[test]
+fn redirect_methods() {
+    for status in [301, 302, 303] {
+        assert!(changes_to_get(status, "POST"));
+        assert!(!changes_to_get(status, "GET"));
+        assert!(!changes_to_get(status, "HEAD"));
+    }
+    assert!(changes_to_get(303, "PUT"));
+    assert!(!changes_to_get(301, "PUT"));
+    for status in [307, 308] {
+        assert!(!changes_to_get(status, "POST"));
+        assert!(!changes_to_get(status, "PUT"));
+    }
+    assert!(changes_to_get(303, "DELETE"));
+    assert!(changes_to_get(303, "PROPFIND"));
+    assert!(!changes_to_get(302, "DELETE"));
+}
+
+#[test]
+fn remember_permanent_redirects() {
+    let cache = RedirectCache::new(Duration::from_secs(60));
+    cache.insert("http://a/old", 301, "http://a/new");
+    assert_eq!(
+        cache.get("http://a/old"),
+        Some((301, "http://a/new".into()))
+    );
+    assert_eq!(cache.get("http://a/new"), None);
+    cache.clear();
+    assert!(cache.is_empty());
+
+    let expired = RedirectCache::new(Duration::ZERO);
+    expired.insert("http://a/old", 308, "http://a/new");
+    assert_eq!(expired.get("http://a/old"), None);
+}
+
+#[test]
+fn client_credentials_dropped_across_origins() {
+    let origin = TestServer::start().unwrap();
+    let other = TestServer::start().unwrap();
+    origin.route(
+        "GET",
+        "/a",
+        TestResponse::new(302).header("Location", &other.url("/b")),
+    );
+    origin.route("GET", "/c", TestResponse::new(302).header("Location", "/d"));
+    origin.route("GET", "/d", TestResponse::text(200, "same"));
+    other.route("GET", "/b", TestResponse::text(200, "other"));
+    let client = Client::builder().no_proxy().bearer_auth("secret").build();
+    let response = client.get(&origin.url("/a")).unwrap().send().unwrap();
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        origin.requests()[0].header("authorization"),
+        Some("Bearer secret")
+    );
+    assert_eq!(other.requests()[0].header("authorization"), None);
+    client.get(&origin.url("/c")).unwrap().send().unwrap();
+    assert_eq!(
+        origin.requests()[2].header("authorization"),
+        Some("Bearer secret")
+    );
+}
+
+#[test]
+fn tokens_not_sent_across_origins() {
+    let tokens = TestServer::start().unwrap();
+    let origin = TestServer::start().unwrap();
+    let other = TestServer::start().unwrap();
+    let token = r#"{"access_token": "a1", "expires_in": 3600}"#;
+    tokens.route("POST", "/token", TestResponse::text(200, token));
+    origin.route(
+        "GET",
+        "/a",
+        TestResponse::new(302).header("Location", &other.url("/b")),
+    );
+    let challenge = TestResponse::new(401).header("WWW-Authenticate", "Basic realm=\"b\"");
+    other.route("GET", "/b", challenge);
+    let oauth = OAuth2::client_credentials(&tokens.url("/token"), "app", "secret")
+        .http_client(Client::builder().no_proxy().build());
+    let client = Client::builder()
+        .no_proxy()
+        .oauth2(oauth)
+        .credential_provider(Credentials::basic("user", Some("pass")))
+        .build();
+    let response = client.get(&origin.url("/a")).unwrap().send().unwrap();
+    assert_eq!(
+        origin.requests()[0].header("authorization"),
+        Some("Bearer a1")
+    );
+    // Neither the token nor an answer to the challenge reach the other origin
+    assert_eq!(response.status_code(), 401);
+    let received = other.requests();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].header("authorization"), None);
+}
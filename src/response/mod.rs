@@ -0,0 +1,441 @@
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::ops::Deref;
+
+use http_parse::{HttpResponse, HttpUrl, H_CONTENT_LENGTH, H_CONTENT_RANGE, H_CONTENT_TYPE};
+
+use crate::body::ResponseBody;
+use crate::client::H_SET_COOKIE;
+use crate::connection::head_values;
+use crate::cookie::Cookie;
+use crate::encoding::decode_text;
+use crate::error::HttpError;
+use crate::extensions::Extensions;
+use crate::json::parser::JsonParser;
+use crate::json::JsonValue;
+use crate::mime::{disposition_filename, Mime};
+use crate::multipart::{self, ByteRange};
+use crate::redirect::Redirect;
+use crate::server_timing::ServerTiming;
+use crate::structured::{self, Item, Member};
+
+#[cfg(test)]
+mod tests;
+
+const H_CONTENT_DISPOSITION: &str = "Content-Disposition";
+const H_SERVER_TIMING: &str = "Server-Timing";
+
+/// The version of the HTTP protocol a response was received with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Version {
+    /// `HTTP/1.0`
+    Http10,
+    /// `HTTP/1.1`
+    Http11,
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Version::Http10 => write!(f, "HTTP/1.0"),
+            Version::Http11 => write!(f, "HTTP/1.1"),
+        }
+    }
+}
+
+/// A response to a request sent by a [`crate::client::Client`].
+///
+/// Dereferences to the underlying [`HttpResponse`] for access to the status, headers and body.
+/// The body can also be read through the [`Read`] implementation, which is the only way to
+/// get the body of responses to [`crate::client::ClientRequest::send_streaming`].
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let response = Client::new().get("http://example.com/old")?.send()?;
+///     for redirect in response.redirects() {
+///         println!("{} {}", redirect.status(), redirect.url());
+///     }
+///     println!("{} {}", response.status_code(), response.url());
+///     Ok(())
+/// }
+/// ```
+pub struct Response {
+    inner: HttpResponse,
+    /// The head of the response as received, before parsing.
+    head: Vec<u8>,
+    url: String,
+    redirects: Vec<Redirect>,
+    peer_addr: Option<SocketAddr>,
+    /// The `X-Request-Id` of the request, if any.
+    request_id: Option<String>,
+    /// Trailer fields of a buffered body.
+    trailers: Vec<(String, String)>,
+    extensions: Extensions,
+    /// The body left on the connection of a streamed response.
+    body: Option<ResponseBody>,
+    /// Bytes of a buffered body already read through [`Read`].
+    position: usize,
+}
+
+impl Response {
+    pub(crate) fn new(
+        inner: HttpResponse,
+        head: Vec<u8>,
+        url: String,
+        redirects: Vec<Redirect>,
+    ) -> Response {
+        Response {
+            inner,
+            head,
+            url,
+            redirects,
+            peer_addr: None,
+            request_id: None,
+            trailers: Vec::new(),
+            extensions: Extensions::new(),
+            body: None,
+            position: 0,
+        }
+    }
+
+    /// This response with its body read from the connection instead of being buffered.
+    pub(crate) fn streamed(mut self, body: ResponseBody) -> Response {
+        self.body = Some(body);
+        self
+    }
+
+    /// This response as received from the given address.
+    pub(crate) fn with_peer_addr(mut self, peer_addr: Option<SocketAddr>) -> Response {
+        self.peer_addr = peer_addr;
+        self
+    }
+
+    /// This response as the answer to the request with the given `X-Request-Id`.
+    pub(crate) fn with_request_id(mut self, request_id: Option<String>) -> Response {
+        self.request_id = request_id;
+        self
+    }
+
+    /// This response with the trailer fields received after its buffered body.
+    pub(crate) fn with_trailers(mut self, trailers: Vec<(String, String)>) -> Response {
+        self.trailers = trailers;
+        self
+    }
+
+    /// Whether the body of this response is read from the connection instead of being
+    /// buffered, in which case [`HttpResponse::data`] is empty.
+    pub fn is_streamed(&self) -> bool {
+        self.body.is_some()
+    }
+
+    /// The URL the response was received from, after following every redirect.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The redirects followed to get this response, in order.
+    pub fn redirects(&self) -> &[Redirect] {
+        &self.redirects
+    }
+
+    /// The version of the protocol the server answered with, from the status line.
+    ///
+    /// `None` when the status line names a version this client does not speak.
+    pub fn version(&self) -> Option<Version> {
+        match self.head.get(..9)? {
+            b"HTTP/1.0 " => Some(Version::Http10),
+            b"HTTP/1.1 " => Some(Version::Http11),
+            _ => None,
+        }
+    }
+
+    /// The socket address this response was received from, after resolving the host name.
+    ///
+    /// This is the address of the proxy when one is used, and `None` for responses served
+    /// from the cache.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// The `X-Request-Id` sent with the request this response answers, to find it in the
+    /// logs of the server, see [`crate::client::ClientBuilder::request_ids`].
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// The extensions of the request this response answers.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// The extensions of the request this response answers, for modification.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// The media type of the body, from the `Content-Type` header.
+    pub fn content_type(&self) -> Option<Mime> {
+        Mime::parse(&self.header_value(H_CONTENT_TYPE)?).ok()
+    }
+
+    /// The length of the body announced by the server, from the `Content-Length` header.
+    pub fn content_length(&self) -> Option<u64> {
+        self.header_value(H_CONTENT_LENGTH)?.trim().parse().ok()
+    }
+
+    /// The file name suggested by the `Content-Disposition` header, without any directory.
+    pub fn filename(&self) -> Option<String> {
+        disposition_filename(&self.header_value(H_CONTENT_DISPOSITION)?)
+    }
+
+    /// The ranges of the resource sent in this `206 Partial Content` response, in the order
+    /// they were sent.
+    ///
+    /// A response to a request for several ranges holds them in a `multipart/byteranges`
+    /// body, which is split in its parts. Otherwise the whole body is the single range
+    /// described by the `Content-Range` header.
+    ///
+    /// # Errors
+    /// When the status is not `206`, or when the body or its `Content-Range` are invalid.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let response = Client::new()
+    ///         .get("https://example.com/data.bin")?
+    ///         .header("Range", "bytes=0-99,500-599")
+    ///         .send()?;
+    ///     for range in response.byte_ranges()? {
+    ///         println!("{}-{}: {} bytes", range.start(), range.end(), range.data().len());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn byte_ranges(&self) -> Result<Vec<ByteRange>, HttpError> {
+        if self.status_code() != 206 {
+            return Err(HttpError::BadResponse(
+                self.status_code(),
+                format!(
+                    "Expected a `206 Partial Content` response, got `{}`",
+                    self.status_msg()
+                ),
+            ));
+        }
+        if let Some(boundary) = self.byteranges_boundary() {
+            return multipart::parse_byteranges(self.inner.data(), &boundary);
+        }
+        let (start, end, total) = self
+            .header_value(H_CONTENT_RANGE)
+            .and_then(|value| multipart::parse_content_range(&value))
+            .ok_or_else(|| {
+                HttpError::BadResponse(
+                    self.status_code(),
+                    format!("Missing or invalid `{H_CONTENT_RANGE}` header"),
+                )
+            })?;
+        Ok(vec![ByteRange::new(
+            start,
+            end,
+            total,
+            self.header_value(H_CONTENT_TYPE),
+            self.inner.data().to_vec(),
+        )])
+    }
+
+    /// The boundary of a `multipart/byteranges` body, when this response has one.
+    pub(crate) fn byteranges_boundary(&self) -> Option<String> {
+        self.content_type()
+            .filter(|mime| mime.essence() == "multipart/byteranges")
+            .and_then(|mime| mime.param("boundary").map(str::to_owned))
+    }
+
+    /// Every value of the header with the given name, in the order they were received.
+    ///
+    /// Unlike [`HttpResponse::header`] this includes headers sent more than once.
+    /// # Arguments
+    /// `name`  Name of the header, case-insensitive.
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        head_values(&self.head, name)
+    }
+
+    /// The cookies set by this response through its `Set-Cookie` headers.
+    ///
+    /// Available whether or not the client keeps a cookie jar. Cookies the jar would reject,
+    /// e.g because their `Domain` does not cover the URL of the response, are left out.
+    ///
+    /// # Example:
+    /// ``` no_run
+    /// use http_client::{client::Client, error::HttpError};
+    /// fn main() -> Result<(), HttpError> {
+    ///     let response = Client::new().get("https://example.com/login")?.send()?;
+    ///     for cookie in response.cookies() {
+    ///         println!("{}={} for {}{}", cookie.name(), cookie.value(), cookie.domain(), cookie.path());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn cookies(&self) -> Vec<Cookie> {
+        let Ok(url) = HttpUrl::try_from(self.url.as_str()) else {
+            return Vec::new();
+        };
+        self.header_values(H_SET_COOKIE)
+            .iter()
+            .filter_map(|header| Cookie::parse(header, url.host(), url.path()))
+            .collect()
+    }
+
+    /// The metrics reported by the server in its `Server-Timing` headers and trailers.
+    pub fn server_timing(&self) -> Vec<ServerTiming> {
+        let trailers = self
+            .trailers()
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(H_SERVER_TIMING))
+            .map(|(_, value)| value.clone());
+        self.header_values(H_SERVER_TIMING)
+            .into_iter()
+            .chain(trailers)
+            .flat_map(|value| ServerTiming::parse(&value))
+            .collect()
+    }
+
+    /// The value of a Structured Field header (RFC 8941) holding an item.
+    ///
+    /// `None` when the header is missing or invalid.
+    /// # Arguments
+    /// `name`  Name of the header, e.g `Sec-CH-UA-Mobile`.
+    pub fn structured_item(&self, name: &str) -> Option<Item> {
+        structured::parse_item(&self.header_values(name).join(", ")).ok()
+    }
+
+    /// The value of a Structured Field header (RFC 8941) holding a list, every header with
+    /// the given name combined.
+    ///
+    /// `None` when the header is missing or invalid.
+    /// # Arguments
+    /// `name`  Name of the header, e.g `Cache-Status`.
+    pub fn structured_list(&self, name: &str) -> Option<Vec<Member>> {
+        let values = self.header_values(name);
+        if values.is_empty() {
+            return None;
+        }
+        structured::parse_list(&values.join(", ")).ok()
+    }
+
+    /// The value of a Structured Field header (RFC 8941) holding a dictionary, every header
+    /// with the given name combined.
+    ///
+    /// `None` when the header is missing or invalid.
+    /// # Arguments
+    /// `name`  Name of the header, e.g `Priority`.
+    pub fn structured_dictionary(&self, name: &str) -> Option<Vec<(String, Member)>> {
+        let values = self.header_values(name);
+        if values.is_empty() {
+            return None;
+        }
+        structured::parse_dictionary(&values.join(", ")).ok()
+    }
+
+    /// The trailer fields sent after a chunked body, e.g `grpc-status` or a checksum, as
+    /// `(name, value)` pairs in the order they were received.
+    ///
+    /// For a streamed response they are only available once the body is fully read.
+    pub fn trailers(&self) -> &[(String, String)] {
+        match &self.body {
+            Some(body) => body.trailers(),
+            None => &self.trailers,
+        }
+    }
+
+    /// Helper method, the value of the first header with the given name.
+    pub(crate) fn header_value(&self, name: &str) -> Option<String> {
+        self.inner
+            .header(name)
+            .map(|header| header.value::<String>().unwrap()) // save to unwrap, a str can always turn into String
+    }
+
+    /// The body of this response as text, decoded with the charset of its `Content-Type`.
+    ///
+    /// Bodies without a charset are decoded as UTF-8, sequences that cannot be decoded are
+    /// replaced with `U+FFFD`.
+    pub fn text(&self) -> String {
+        let content_type = self.content_type();
+        let charset = content_type.as_ref().and_then(|mime| mime.param("charset"));
+        decode_text(self.inner.data(), charset)
+    }
+
+    /// Parse the body of this response as JSON.
+    ///
+    /// # Errors
+    /// When the `Content-Type` of the response is not JSON, e.g `application/json` or
+    /// `application/problem+json`, or when the body is not valid JSON.
+    pub fn json(&self) -> Result<JsonValue, HttpError> {
+        self.expect_json()?;
+        JsonParser::parse_json(&String::from_utf8_lossy(self.inner.data()))
+            .map_err(HttpError::Other)
+    }
+
+    /// Deserialize the body of this response from JSON into any `serde::Deserialize` type.
+    ///
+    /// # Errors
+    /// When the `Content-Type` of the response is not JSON, or when the body does not match `T`.
+    #[cfg(feature = "serde")]
+    pub fn json_as<T: serde::de::DeserializeOwned>(&self) -> Result<T, HttpError> {
+        self.expect_json()?;
+        serde_json::from_slice(self.inner.data()).map_err(|e| HttpError::Other(e.to_string()))
+    }
+
+    /// Helper method, fail unless the `Content-Type` of the response, if any, is JSON.
+    fn expect_json(&self) -> Result<(), HttpError> {
+        if let Some(content_type) = self.header_value(H_CONTENT_TYPE) {
+            let is_json = Mime::parse(&content_type).is_ok_and(|mime| {
+                mime.essence() == "application/json" || mime.essence().ends_with("+json")
+            });
+            if !is_json {
+                return Err(HttpError::Other(format!(
+                    "Expected a JSON response, got `{content_type}`"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the rest of the body to `writer`, returning the number of bytes copied.
+    ///
+    /// Streamed bodies are copied as they are received, using a small buffer.
+    /// # Arguments
+    /// `writer`    Destination of the body, e.g a file.
+    pub fn copy_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> Result<u64, HttpError> {
+        Ok(std::io::copy(self, writer)?)
+    }
+
+    /// The underlying [`HttpResponse`].
+    pub fn into_inner(self) -> HttpResponse {
+        self.inner
+    }
+}
+
+impl Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(body) = &mut self.body {
+            return body.read(buf);
+        }
+        let remaining = &self.inner.data()[self.position..];
+        let read = remaining.len().min(buf.len());
+        buf[..read].copy_from_slice(&remaining[..read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+impl Deref for Response {
+    type Target = HttpResponse;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
@@ -0,0 +1,127 @@
+use std::io::{Cursor, Read};
+
+use http_parse::HttpParser;
+
+use crate::client::Client;
+use crate::error::HttpError;
+use crate::json::JsonValue;
+use crate::response::{Response, Version};
+use crate::test_server::{TestResponse, TestServer};
+
+/// A buffered response parsed from its wire format.
+fn response(raw: &str) -> Response {
+    let inner = HttpParser::from_reader(&mut Cursor::new(raw.as_bytes()))
+        .response()
+        .unwrap();
+    let head = raw.split_once("\r\n\r\n").unwrap().0;
+    let head = format!("{head}\r\n\r\n").into_bytes();
+    Response::new(inner, head, "http://example.com/".to_owned(), Vec::new())
+}
+
+/// A response with the given `Content-Type` and body.
+fn typed(content_type: &str, body: &str) -> Response {
+    response(&format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    ))
+}
+
+#[test]
+fn json_bodies() {
+    let body = r#"{"id": 1}"#;
+    for content_type in [
+        "application/json",
+        "application/json; charset=utf-8",
+        "application/problem+json",
+    ] {
+        assert!(matches!(
+            typed(content_type, body).json(),
+            Ok(JsonValue::Object(_))
+        ));
+    }
+    // Responses without a type are parsed as well
+    let untyped = format!("HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n{body}");
+    assert!(response(&untyped).json().is_ok());
+
+    assert!(matches!(
+        typed("text/html", body).json(),
+        Err(HttpError::Other(_))
+    ));
+    assert!(matches!(
+        typed("application/json", "{\"id\":").json(),
+        Err(HttpError::Other(_))
+    ));
+}
+
+#[test]
+fn body_copied() {
+    let mut response = typed("text/plain", "hello world");
+    let mut start = [0; 6];
+    response.read_exact(&mut start).unwrap();
+    assert_eq!(&start, b"hello ");
+    // Only the rest of the body is copied
+    let mut rest = Vec::new();
+    assert_eq!(response.copy_to(&mut rest).unwrap(), 5);
+    assert_eq!(rest, b"world");
+    assert_eq!(response.copy_to(&mut rest).unwrap(), 0);
+
+    // Writers that cannot take the body fail the copy
+    let mut response = typed("text/plain", "hello world");
+    let mut full = [0; 4];
+    assert!(response.copy_to(&mut &mut full[..]).is_err());
+}
+
+#[test]
+fn every_cookie_parsed() {
+    let response = response(
+        "HTTP/1.1 200 OK\r\n\
+        Set-Cookie: session=abc; Path=/; Secure; HttpOnly\r\n\
+        Set-Cookie: theme=dark; Domain=example.com\r\n\
+        Set-Cookie: invalid\r\n\
+        Content-Length: 0\r\n\r\n",
+    );
+    let cookies = response.cookies();
+    assert_eq!(cookies.len(), 2);
+    assert_eq!((cookies[0].name(), cookies[0].value()), ("session", "abc"));
+    assert!(cookies[0].secure() && cookies[0].http_only());
+    assert_eq!((cookies[1].name(), cookies[1].value()), ("theme", "dark"));
+    assert_eq!(cookies[1].domain(), "example.com");
+}
+
+#[test]
+fn peer_address() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::text(200, "ok"));
+    let client = Client::builder().no_proxy().build();
+    let response = client.get(&server.url("/")).unwrap().send().unwrap();
+    assert_eq!(response.peer_addr(), Some(server.address()));
+    // Nothing was received from the network
+    assert_eq!(typed("text/plain", "ok").peer_addr(), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn typed_json_bodies() {
+    use std::collections::HashMap;
+
+    let body = r#"{"ids": [1, 2, 3]}"#;
+    let ids: HashMap<String, Vec<u32>> = typed("application/json", body).json_as().unwrap();
+    assert_eq!(ids["ids"], [1, 2, 3]);
+
+    let wrong_type = typed("text/plain", body).json_as::<HashMap<String, Vec<u32>>>();
+    assert!(matches!(wrong_type, Err(HttpError::Other(_))));
+    let wrong_shape = typed("application/json", body).json_as::<Vec<u32>>();
+    assert!(matches!(wrong_shape, Err(HttpError::Other(_))));
+}
+
+#[test]
+fn protocol_versions() {
+    let version = |status_line: &str| {
+        response(&format!("{status_line}\r\nContent-Length: 0\r\n\r\n")).version()
+    };
+    assert_eq!(version("HTTP/1.1 200 OK"), Some(Version::Http11));
+    assert_eq!(version("HTTP/1.0 200 OK"), Some(Version::Http10));
+    assert_eq!(version("HTTP/2 200 OK"), None);
+    assert_eq!(Version::Http10.to_string(), "HTTP/1.0");
+    assert_eq!(Version::Http11.to_string(), "HTTP/1.1");
+}
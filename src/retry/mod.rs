@@ -0,0 +1,273 @@
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::date::parse_http_date;
+use crate::error::HttpError;
+use crate::hash::hex;
+
+#[cfg(test)]
+mod tests;
+
+/// Attempts made to send a request, unless configured otherwise.
+const DEFAULT_MAX_ATTEMPTS: usize = 3;
+
+/// Delay before the first retry, unless configured otherwise.
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Longest delay between two attempts, unless configured otherwise.
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Longest wait asked by a `Retry-After` header that is honored, unless configured otherwise.
+const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Statuses retried unless configured otherwise, rate limiting and server errors that are
+/// usually temporary.
+const DEFAULT_STATUSES: [usize; 5] = [429, 500, 502, 503, 504];
+
+/// When and how often a failed request is sent again, see
+/// [`crate::client::ClientBuilder::retry`].
+///
+/// By default a request is attempted 3 times when the connection fails, times out or the
+/// server answers `429`, `500`, `502`, `503` or `504`. The delay between attempts doubles
+/// from 100 ms up to 10 s, a random part of it being waited so that clients failing together
+/// do not retry together. A `429` or `503` response with a `Retry-After` header is retried
+/// after the time it asks for instead, up to a minute. Only idempotent methods and requests
+/// with an `Idempotency-Key` are retried, as a request failing part way may still have been
+/// processed, see [`crate::client::ClientBuilder::idempotency_keys`]. A request whose
+/// streamed body was sent is never retried.
+///
+/// # Example:
+/// ``` no_run
+/// use std::time::Duration;
+/// use http_client::{client::Client, error::HttpError, retry::RetryPolicy};
+/// fn main() -> Result<(), HttpError> {
+///     let client = Client::builder()
+///         .retry(
+///             RetryPolicy::new()
+///                 .max_attempts(5)
+///                 .backoff(Duration::from_millis(200), Duration::from_secs(30)),
+///         )
+///         .build();
+///     client.get("https://example.com/flaky")?.send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_retry_after: Duration,
+    jitter: bool,
+    statuses: Vec<usize>,
+    errors: bool,
+    non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+            jitter: true,
+            statuses: DEFAULT_STATUSES.to_vec(),
+            errors: true,
+            non_idempotent: false,
+        }
+    }
+
+    /// Send a request at most the given number of times, including the first one.
+    /// # Arguments
+    /// `attempts`  Total number of attempts, at least one.
+    pub fn max_attempts(mut self, attempts: usize) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait `base` before the first retry, doubling the delay after every attempt up to `cap`.
+    /// # Arguments
+    /// `base`  Delay before the first retry.
+    /// `cap`   Longest delay between two attempts.
+    pub fn backoff(mut self, base: Duration, cap: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = cap;
+        self
+    }
+
+    /// Wait at most `cap` when a `429` or `503` response asks for a longer delay with its
+    /// `Retry-After` header.
+    /// # Arguments
+    /// `cap`   Longest delay waited before a retry asked for by the server.
+    pub fn max_retry_after(mut self, cap: Duration) -> Self {
+        self.max_retry_after = cap;
+        self
+    }
+
+    /// Whether a random part of the delay is waited instead of the whole delay.
+    /// # Arguments
+    /// `enabled`   Whether to randomize delays.
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// Retry responses with the given statuses, instead of `429`, `500`, `502`, `503` and `504`.
+    /// # Arguments
+    /// `statuses`  Statuses sent again, e.g `&[503]`.
+    pub fn statuses(mut self, statuses: &[usize]) -> Self {
+        self.statuses = statuses.to_vec();
+        self
+    }
+
+    /// Whether requests failing without a response, e.g refused connections, timeouts or
+    /// truncated bodies, are retried.
+    /// # Arguments
+    /// `enabled`   Whether to retry failed connections.
+    pub fn on_errors(mut self, enabled: bool) -> Self {
+        self.errors = enabled;
+        self
+    }
+
+    /// Whether requests with a method that is not idempotent, e.g `POST`, are retried too.
+    ///
+    /// Such a request may be processed twice, when the server received it before failing.
+    /// # Arguments
+    /// `enabled`   Whether to retry every method.
+    pub fn non_idempotent(mut self, enabled: bool) -> Self {
+        self.non_idempotent = enabled;
+        self
+    }
+
+    /// The total number of attempts allowed.
+    pub(crate) fn attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Whether requests with the given method may be retried.
+    pub(crate) fn allows(&self, method: &str) -> bool {
+        self.non_idempotent || is_idempotent(method)
+    }
+
+    /// Whether a response with the given status is retried.
+    pub(crate) fn retries_status(&self, status: usize) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    /// Whether a request failing with the given error is retried.
+    pub(crate) fn retries_error(&self, error: &HttpError) -> bool {
+        self.errors && is_transient(error)
+    }
+
+    /// The delay before the given retry, counted from zero, without jitter.
+    pub(crate) fn backoff_delay(&self, retry: usize) -> Duration {
+        let factor = 1u32.checked_shl(retry.min(31) as u32).unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// The time to wait before the given retry of a response with the given status and
+    /// `Retry-After` header, if any.
+    pub(crate) fn response_delay(
+        &self,
+        retry: usize,
+        status: usize,
+        retry_after: Option<&str>,
+    ) -> Duration {
+        let asked = retry_after
+            .filter(|_| status == 429 || status == 503)
+            .and_then(|value| parse_retry_after(value, SystemTime::now()));
+        match asked {
+            Some(asked) => asked.min(self.max_retry_after),
+            None => self.delay(retry),
+        }
+    }
+
+    /// The time to wait before the given retry, counted from zero.
+    pub(crate) fn delay(&self, retry: usize) -> Duration {
+        let delay = self.backoff_delay(retry);
+        match self.jitter {
+            true => delay.mul_f64(random_fraction()),
+            false => delay,
+        }
+    }
+}
+
+/// The delay asked for by a `Retry-After` header, a number of seconds or an HTTP date
+/// (RFC 9110 section 10.2.3). A date in the past asks for no delay.
+pub(crate) fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Whether sending a request with the given method several times has the same effect as
+/// sending it once (RFC 9110 section 9.2.2).
+pub(crate) fn is_idempotent(method: &str) -> bool {
+    ["GET", "HEAD", "OPTIONS", "TRACE", "PUT", "DELETE"]
+        .iter()
+        .any(|idempotent| method.eq_ignore_ascii_case(idempotent))
+}
+
+/// Whether an error may not happen again when the request is sent again, e.g a dropped
+/// connection.
+///
+/// Failures to write a download to its destination and checksum mismatches are reported as
+/// [`HttpError::Destination`] and [`HttpError::ChecksumMismatch`], so they are not retried.
+pub(crate) fn is_transient(error: &HttpError) -> bool {
+    // Reading and writing errors of the connection are reported as `Other`
+    matches!(
+        error,
+        HttpError::ConnectionError(_) | HttpError::Other(_) | HttpError::IncompleteBody(..)
+    )
+}
+
+/// A new `Idempotency-Key` value, a random UUID as a structured field string.
+pub(crate) fn idempotency_key() -> String {
+    format!("\"{}\"", uuid_v4())
+}
+
+/// A new random UUID (version 4) in its hyphenated form.
+pub(crate) fn uuid_v4() -> String {
+    let mut bytes = [random_u64().to_be_bytes(), random_u64().to_be_bytes()].concat();
+    // Version 4, variant of RFC 9562
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Helper method, a random number between zero and one.
+pub(crate) fn random_fraction() -> f64 {
+    // The 53 bits a double holds exactly
+    (random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Helper method, a random number, each call hashing with new random keys.
+pub(crate) fn random_u64() -> u64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    hasher.finish()
+}
@@ -0,0 +1,265 @@
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::client::Client;
+use crate::config::Config;
+use crate::error::HttpError;
+use crate::retry::{
+    idempotency_key, is_idempotent, is_transient, parse_retry_after, uuid_v4, RetryPolicy,
+};
+use crate::test_server::{TestResponse, TestServer};
+
+#[test]
+fn exponential_backoff() {
+    let policy = RetryPolicy::new()
+        .backoff(Duration::from_millis(100), Duration::from_secs(1))
+        .jitter(false);
+    assert_eq!(policy.delay(0), Duration::from_millis(100));
+    assert_eq!(policy.delay(1), Duration::from_millis(200));
+    assert_eq!(policy.delay(3), Duration::from_millis(800));
+    assert_eq!(policy.delay(4), Duration::from_secs(1));
+    assert_eq!(policy.delay(100), Duration::from_secs(1));
+
+    let policy = policy.jitter(true);
+    for retry in 0..5 {
+        assert!(policy.delay(retry) <= policy.backoff_delay(retry));
+    }
+}
+
+#[test]
+fn retried_failures() {
+    let policy = RetryPolicy::new();
+    assert!(policy.retries_status(503));
+    assert!(policy.retries_status(429));
+    assert!(!policy.retries_status(501));
+    assert!(!policy.retries_status(404));
+    assert!(policy.retries_error(&HttpError::ConnectionError("refused".to_owned())));
+    assert!(policy.retries_error(&HttpError::IncompleteBody(5, Some(10))));
+    assert!(!policy.retries_error(&HttpError::InvalidUrl("url".to_owned())));
+    assert!(!policy
+        .clone()
+        .on_errors(false)
+        .retries_error(&HttpError::ConnectionError("refused".to_owned())));
+
+    assert!(policy.allows("GET") && policy.allows("put") && !policy.allows("POST"));
+    assert!(policy.non_idempotent(true).allows("POST"));
+    assert!(is_idempotent("DELETE") && !is_idempotent("PATCH"));
+}
+
+#[test]
+fn retry_after() {
+    let now = UNIX_EPOCH + Duration::from_secs(784_111_777);
+    assert_eq!(
+        parse_retry_after("120", now),
+        Some(Duration::from_secs(120))
+    );
+    assert_eq!(
+        parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now),
+        Some(Duration::from_secs(30))
+    );
+    assert_eq!(
+        parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon", now), None);
+
+    let policy = RetryPolicy::new()
+        .jitter(false)
+        .max_retry_after(Duration::from_secs(30));
+    assert_eq!(
+        policy.response_delay(0, 503, Some("5")),
+        Duration::from_secs(5)
+    );
+    assert_eq!(
+        policy.response_delay(0, 429, Some("3600")),
+        Duration::from_secs(30)
+    );
+    // Only honored for `429` and `503`
+    assert_eq!(
+        policy.response_delay(0, 500, Some("5")),
+        Duration::from_millis(100)
+    );
+    assert_eq!(
+        policy.response_delay(1, 503, None),
+        Duration::from_millis(200)
+    );
+}
+
+#[test]
+fn idempotency_keys() {
+    let key = idempotency_key();
+    assert_eq!(key.len(), 38);
+    let uuid = key.trim_matches('"');
+    let groups = uuid.split('-').map(str::len).collect::<Vec<_>>();
+    assert_eq!(groups, [8, 4, 4, 4, 12]);
+    assert!(uuid[14..].starts_with('4'));
+    assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+    assert_ne!(key, idempotency_key());
+}
+
+#[test]
+fn transient_errors() {
+    assert!(is_transient(&HttpError::ConnectionError(
+        "reset".to_owned()
+    )));
+    assert!(is_transient(&HttpError::Other("broken pipe".to_owned())));
+    assert!(is_transient(&HttpError::IncompleteBody(10, Some(20))));
+    assert!(!is_transient(&HttpError::Destination(
+        "disk full".to_owned()
+    )));
+    assert!(!is_transient(&HttpError::ChecksumMismatch(
+        "sha-256".to_owned()
+    )));
+    assert!(!is_transient(&HttpError::BadResponse(
+        404,
+        "Not Found".to_owned()
+    )));
+    assert!(!is_transient(&HttpError::Cancelled));
+}
+
+#[test]
+fn deadline_bounds_attempts() {
+    let mut config = Config::default();
+    config.read_timeout = Some(Duration::from_secs(30));
+    let bounded = config.bounded(Duration::from_secs(2));
+    assert_eq!(bounded.read_timeout, Some(Duration::from_secs(2)));
+    assert_eq!(bounded.connect_timeout, Some(Duration::from_secs(2)));
+    // Built once for every attempt
+    let tls = bounded.tls_settings().unwrap();
+    assert!(Arc::ptr_eq(&tls, &config.tls_settings().unwrap()));
+    let again = config.bounded(Duration::from_secs(1));
+    assert!(Arc::ptr_eq(&tls, &again.tls_settings().unwrap()));
+}
+
+#[test]
+fn deadline_exceeded() {
+    let server = TestServer::start().unwrap();
+    let slow = TestResponse::text(200, "slow").delay(Duration::from_secs(2));
+    server.route("GET", "/slow", slow);
+    let client = Client::builder()
+        .no_proxy()
+        .deadline(Duration::from_millis(100))
+        .build();
+    let started = Instant::now();
+    let result = client.get(&server.url("/slow")).unwrap().send();
+    assert!(matches!(result, Err(HttpError::DeadlineExceeded)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn no_retry_past_deadline() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/busy", TestResponse::new(503));
+    let policy = RetryPolicy::new()
+        .max_attempts(5)
+        .backoff(Duration::from_secs(1), Duration::from_secs(1))
+        .jitter(false);
+    let client = Client::builder()
+        .no_proxy()
+        .retry(policy)
+        .deadline(Duration::from_millis(300))
+        .build();
+    let started = Instant::now();
+    let response = client.get(&server.url("/busy")).unwrap().send().unwrap();
+    // The last response is kept rather than waiting past the deadline
+    assert_eq!(response.status_code(), 503);
+    assert!(started.elapsed() < Duration::from_millis(300));
+    assert_eq!(server.requests().len(), 1);
+}
+
+/// Whether `id` is a hyphenated UUID of version 4 and the variant of RFC 9562.
+fn is_uuid_v4(id: &str) -> bool {
+    let groups = id.split('-').map(str::len).collect::<Vec<_>>();
+    groups == [8, 4, 4, 4, 12]
+        && id
+            .chars()
+            .all(|c| c == '-' || c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+        && id[14..15] == *"4"
+        && matches!(&id[19..20], "8" | "9" | "a" | "b")
+}
+
+#[test]
+fn random_uuids() {
+    let first = uuid_v4();
+    assert!(is_uuid_v4(&first), "{first}");
+    assert!((0..100)
+        .map(|_| uuid_v4())
+        .all(|id| is_uuid_v4(&id) && id != first));
+}
+
+#[test]
+fn request_id_appended_to_errors() {
+    let with_id = |error: HttpError| error.with_request_id("abc").to_string();
+    assert_eq!(
+        with_id(HttpError::BadResponse(500, "Failed".into())),
+        "500: Failed (request id abc)"
+    );
+    assert_eq!(
+        with_id(HttpError::Other("Reset".into())),
+        "Reset (request id abc)"
+    );
+    assert_eq!(
+        with_id(HttpError::ConnectionError("Refused".into())),
+        "Connection error: `Refused (request id abc)`"
+    );
+    assert_eq!(
+        with_id(HttpError::TooLarge("1 MB".into())),
+        "Response too large: 1 MB (request id abc)"
+    );
+    assert!(matches!(
+        HttpError::Cancelled.with_request_id("abc"),
+        HttpError::Cancelled
+    ));
+}
+
+#[test]
+fn request_ids_sent_and_reported() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/", TestResponse::new(204));
+    let client = Client::builder().no_proxy().request_ids(true).build();
+    let response = client.get(&server.url("/")).unwrap().send().unwrap();
+    let id = response.request_id().unwrap().to_owned();
+    assert!(is_uuid_v4(&id), "{id}");
+
+    // Set by the caller, kept as it is
+    let response = client
+        .get(&server.url("/"))
+        .unwrap()
+        .header("X-Request-Id", "caller-42")
+        .send()
+        .unwrap();
+    assert_eq!(response.request_id(), Some("caller-42"));
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("X-Request-Id"), Some(id.as_str()));
+    assert_eq!(requests[1].header("X-Request-Id"), Some("caller-42"));
+
+    let response = Client::builder()
+        .no_proxy()
+        .build()
+        .get(&server.url("/"))
+        .unwrap()
+        .send()
+        .unwrap();
+    assert_eq!(response.request_id(), None);
+}
+
+#[test]
+fn request_id_of_failed_request() {
+    // Nothing listens on the port once the listener is dropped
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let error = Client::builder()
+        .no_proxy()
+        .request_id_generator(|| "op-7".to_owned())
+        .build()
+        .get(&format!("http://{address}/"))
+        .unwrap()
+        .send()
+        .err()
+        .unwrap();
+    assert!(error.to_string().ends_with("(request id op-7)"), "{error}");
+}
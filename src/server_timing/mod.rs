@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::mime::{is_token, parse_params, split_list};
+
+#[cfg(test)]
+mod tests;
+
+/// A metric reported by the server in a `Server-Timing` header (W3C Server Timing).
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// fn main() -> Result<(), HttpError> {
+///     let response = Client::new().get("https://example.com")?.send()?;
+///     for metric in response.server_timing() {
+///         println!("{}: {:?} {}", metric.name(), metric.duration(), metric.description().unwrap_or_default());
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerTiming {
+    name: String,
+    duration: Option<Duration>,
+    description: Option<String>,
+}
+
+impl ServerTiming {
+    /// Parse every metric of a `Server-Timing` header value, skipping malformed ones.
+    /// # Arguments
+    /// `value` Header value such as `db;dur=53.2, cache;desc="Cache Read";dur=23`.
+    pub fn parse(value: &str) -> Vec<ServerTiming> {
+        split_list(value)
+            .into_iter()
+            .filter_map(Self::parse_metric)
+            .collect()
+    }
+
+    /// Helper method, parse a single metric of a `Server-Timing` header.
+    fn parse_metric(metric: &str) -> Option<ServerTiming> {
+        let (name, rest) = metric.split_once(';').unwrap_or((metric, ""));
+        let name = name.trim();
+        if !is_token(name) {
+            return None;
+        }
+        let params = parse_params(rest)?;
+        // Only the first occurrence of a parameter counts
+        let param = |wanted: &str| {
+            params
+                .iter()
+                .find(|(param, _)| param.eq_ignore_ascii_case(wanted))
+                .map(|(_, value)| value.as_str())
+        };
+        let duration = param("dur")
+            .and_then(|millis| millis.parse::<f64>().ok())
+            // Negative, infinite or too large durations are dropped
+            .and_then(|millis| Duration::try_from_secs_f64(millis / 1000.0).ok());
+        Some(ServerTiming {
+            name: name.to_owned(),
+            duration,
+            description: param("desc").map(str::to_owned),
+        })
+    }
+
+    /// Name of the metric, e.g `db`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Duration of the metric, sent in milliseconds as the `dur` parameter.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Human-readable description of the metric, sent as the `desc` parameter.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
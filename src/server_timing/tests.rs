@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use crate::server_timing::ServerTiming;
+
+#[test]
+fn parse_metrics() {
+    let metrics = ServerTiming::parse(
+        "db;dur=53.5, cache;desc=\"Cache, Read\";dur=23, miss, cpu;dur=-1;DUR=2, bad name;dur=1, huge;dur=1e300, nan;dur=NaN",
+    );
+    let summary = metrics
+        .iter()
+        .map(|metric| (metric.name(), metric.duration(), metric.description()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            ("db", Some(Duration::from_micros(53_500)), None),
+            (
+                "cache",
+                Some(Duration::from_millis(23)),
+                Some("Cache, Read")
+            ),
+            ("miss", None, None),
+            ("cpu", None, None),
+            ("huge", None, None),
+            ("nan", None, None),
+        ]
+    );
+}
+
+#[test]
+fn skip_malformed_params() {
+    assert!(ServerTiming::parse("db;dur").is_empty());
+    assert!(ServerTiming::parse("db;desc=\"open").is_empty());
+    assert!(ServerTiming::parse("").is_empty());
+}
@@ -0,0 +1,420 @@
+use std::fmt::Display;
+
+use crate::encoding::{base64_decode, base64_encode};
+use crate::error::HttpError;
+
+#[cfg(test)]
+mod tests;
+
+/// Parameters of an item or inner list, in order, with unique keys.
+pub type Parameters = Vec<(String, BareItem)>;
+
+/// A value of a Structured Field (RFC 8941), as used by headers such as `Priority` or
+/// `Cache-Status`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BareItem {
+    /// An integer of at most 15 digits.
+    Integer(i64),
+    /// A decimal number with at most 3 fractional digits.
+    Decimal(f64),
+    /// A string of printable ASCII characters.
+    String(String),
+    /// A token such as `text/html` or `*`.
+    Token(String),
+    /// Binary data, sent in base64.
+    ByteSequence(Vec<u8>),
+    /// A boolean, `?1` or `?0`.
+    Boolean(bool),
+}
+
+/// A value with its parameters, e.g `u=1;incremental`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    value: BareItem,
+    params: Parameters,
+}
+
+/// A member of a list or dictionary, either an item or a list of items in parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Member {
+    Item(Item),
+    InnerList(Vec<Item>, Parameters),
+}
+
+impl Item {
+    /// An item without parameters.
+    pub fn new(value: BareItem) -> Item {
+        Item {
+            value,
+            params: Vec::new(),
+        }
+    }
+
+    /// This item with a parameter set, replacing the previous value if any.
+    /// # Arguments
+    /// `key`   Key of the parameter, lowercase letters, digits, `_`, `-`, `.` and `*`.
+    /// `value` Value of the parameter.
+    pub fn with_param(mut self, key: &str, value: BareItem) -> Item {
+        set_param(&mut self.params, key.to_owned(), value);
+        self
+    }
+
+    /// The value of the item.
+    pub fn value(&self) -> &BareItem {
+        &self.value
+    }
+
+    /// The parameters of the item.
+    pub fn params(&self) -> &[(String, BareItem)] {
+        &self.params
+    }
+
+    /// The value of a parameter.
+    /// # Arguments
+    /// `key`   Key of the parameter.
+    pub fn param(&self, key: &str) -> Option<&BareItem> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == key)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Parse a header holding a single item, e.g `Sec-CH-UA-Mobile: ?1`.
+/// # Arguments
+/// `value` Value of the header, every line joined with `, `.
+pub fn parse_item(value: &str) -> Result<Item, HttpError> {
+    Parser::parse(value, |parser| parser.item())
+}
+
+/// Parse a header holding a list, e.g `Cache-Status`.
+/// # Arguments
+/// `value` Value of the header, every line joined with `, `.
+pub fn parse_list(value: &str) -> Result<Vec<Member>, HttpError> {
+    Parser::parse(value, |parser| parser.members(|parser| parser.member()))
+}
+
+/// Parse a header holding a dictionary, e.g `Priority` or `RateLimit`.
+///
+/// When a key is repeated its last value is kept, at the position of the first.
+/// # Arguments
+/// `value` Value of the header, every line joined with `, `.
+pub fn parse_dictionary(value: &str) -> Result<Vec<(String, Member)>, HttpError> {
+    Parser::parse(value, |parser| {
+        let entries = parser.members(|parser| {
+            let key = parser.key()?;
+            let member = if parser.eat(b'=') {
+                parser.member()?
+            } else {
+                Member::Item(Item {
+                    value: BareItem::Boolean(true),
+                    params: parser.params()?,
+                })
+            };
+            Some((key, member))
+        })?;
+        let mut dictionary: Vec<(String, Member)> = Vec::with_capacity(entries.len());
+        for (key, member) in entries {
+            match dictionary.iter_mut().find(|(existing, _)| *existing == key) {
+                Some(entry) => entry.1 = member,
+                None => dictionary.push((key, member)),
+            }
+        }
+        Some(dictionary)
+    })
+}
+
+/// Serialize a list, e.g to send it as a header value.
+pub fn serialize_list(list: &[Member]) -> String {
+    list.iter()
+        .map(Member::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Serialize a dictionary, e.g to send it as a header value.
+pub fn serialize_dictionary(dictionary: &[(String, Member)]) -> String {
+    dictionary
+        .iter()
+        .map(|(key, member)| match member {
+            Member::Item(Item {
+                value: BareItem::Boolean(true),
+                params,
+            }) => format!("{key}{}", DisplayParams(params)),
+            member => format!("{key}={member}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for BareItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BareItem::Integer(value) => write!(f, "{value}"),
+            BareItem::Decimal(value) => {
+                let decimal = format!("{value:.3}");
+                // At least one fractional digit is kept
+                let decimal = decimal.trim_end_matches('0');
+                match decimal.strip_suffix('.') {
+                    Some(integer) => write!(f, "{integer}.0"),
+                    None => write!(f, "{decimal}"),
+                }
+            }
+            BareItem::String(value) => {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "\"{escaped}\"")
+            }
+            BareItem::Token(value) => write!(f, "{value}"),
+            BareItem::ByteSequence(value) => write!(f, ":{}:", base64_encode(value)),
+            BareItem::Boolean(value) => write!(f, "?{}", u8::from(*value)),
+        }
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, DisplayParams(&self.params))
+    }
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Member::Item(item) => write!(f, "{item}"),
+            Member::InnerList(items, params) => {
+                let items = items
+                    .iter()
+                    .map(Item::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                write!(f, "({items}){}", DisplayParams(params))
+            }
+        }
+    }
+}
+
+/// Helper struct, serializes parameters, omitting the value of `true` booleans.
+struct DisplayParams<'a>(&'a [(String, BareItem)]);
+
+impl Display for DisplayParams<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in self.0 {
+            match value {
+                BareItem::Boolean(true) => write!(f, ";{key}")?,
+                value => write!(f, ";{key}={value}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Helper method, set a parameter, replacing the previous value if any.
+fn set_param(params: &mut Parameters, key: String, value: BareItem) {
+    match params.iter_mut().find(|(existing, _)| *existing == key) {
+        Some(param) => param.1 = value,
+        None => params.push((key, value)),
+    }
+}
+
+/// Parser following the algorithms of RFC 8941 section 4.2, `None` means the field is invalid.
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Helper method, parse a whole field value, surrounding spaces are ignored.
+    fn parse<T, F: FnOnce(&mut Parser<'a>) -> Option<T>>(
+        value: &'a str,
+        parse: F,
+    ) -> Result<T, HttpError> {
+        let mut parser = Parser {
+            input: value.as_bytes(),
+            position: 0,
+        };
+        parser.skip_spaces();
+        parse(&mut parser)
+            .filter(|_| {
+                parser.skip_spaces();
+                parser.peek().is_none()
+            })
+            .ok_or_else(|| HttpError::Other(format!("Invalid structured field `{value}`")))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    /// Consume the next byte if it is `byte`.
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.eat(b' ') {}
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.eat(b' ') || self.eat(b'\t') {}
+    }
+
+    /// Consume bytes while they match `accept`, returning them.
+    fn take_while<F: Fn(u8) -> bool>(&mut self, accept: F) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(&accept) {
+            self.position += 1;
+        }
+        // Only ASCII bytes are ever accepted
+        std::str::from_utf8(&self.input[start..self.position]).unwrap_or_default()
+    }
+
+    /// Comma-separated members of a list or dictionary.
+    fn members<T, F: Fn(&mut Parser<'a>) -> Option<T>>(&mut self, member: F) -> Option<Vec<T>> {
+        let mut members = Vec::new();
+        while self.peek().is_some() {
+            members.push(member(self)?);
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                break;
+            }
+            if !self.eat(b',') {
+                return None;
+            }
+            self.skip_whitespace();
+            // A trailing comma is invalid
+            self.peek()?;
+        }
+        Some(members)
+    }
+
+    fn member(&mut self) -> Option<Member> {
+        if !self.eat(b'(') {
+            return self.item().map(Member::Item);
+        }
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.eat(b')') {
+                return Some(Member::InnerList(items, self.params()?));
+            }
+            items.push(self.item()?);
+            if !matches!(self.peek()?, b' ' | b')') {
+                return None;
+            }
+        }
+    }
+
+    fn item(&mut self) -> Option<Item> {
+        let value = self.bare_item()?;
+        Some(Item {
+            value,
+            params: self.params()?,
+        })
+    }
+
+    fn params(&mut self) -> Option<Parameters> {
+        let mut params = Vec::new();
+        while self.eat(b';') {
+            self.skip_spaces();
+            let key = self.key()?;
+            let value = if self.eat(b'=') {
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            set_param(&mut params, key, value);
+        }
+        Some(params)
+    }
+
+    fn key(&mut self) -> Option<String> {
+        if !self
+            .peek()
+            .is_some_and(|b| b.is_ascii_lowercase() || b == b'*')
+        {
+            return None;
+        }
+        let key = self
+            .take_while(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-.*".contains(&b));
+        Some(key.to_owned())
+    }
+
+    fn bare_item(&mut self) -> Option<BareItem> {
+        match self.peek()? {
+            b'-' | b'0'..=b'9' => self.number(),
+            b'"' => self.string(),
+            b':' => self.byte_sequence(),
+            b'?' => {
+                self.position += 1;
+                match self.peek()? {
+                    b'0' | b'1' => {
+                        self.position += 1;
+                        Some(BareItem::Boolean(self.input[self.position - 1] == b'1'))
+                    }
+                    _ => None,
+                }
+            }
+            b if b.is_ascii_alphabetic() || b == b'*' => {
+                let token = self
+                    .take_while(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~:/".contains(&b));
+                Some(BareItem::Token(token.to_owned()))
+            }
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Option<BareItem> {
+        let negative = self.eat(b'-');
+        let number = self.take_while(|b| b.is_ascii_digit() || b == b'.');
+        let sign = if negative { -1 } else { 1 };
+        match number.split_once('.') {
+            None if (1..=15).contains(&number.len()) => {
+                Some(BareItem::Integer(sign * number.parse::<i64>().ok()?))
+            }
+            Some((integer, fraction))
+                if (1..=12).contains(&integer.len())
+                    && (1..=3).contains(&fraction.len())
+                    && !fraction.contains('.') =>
+            {
+                Some(BareItem::Decimal(sign as f64 * number.parse::<f64>().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    fn string(&mut self) -> Option<BareItem> {
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.position += 1;
+                    return Some(BareItem::String(value));
+                }
+                b'\\' => {
+                    self.position += 1;
+                    match self.peek()? {
+                        c @ (b'"' | b'\\') => value.push(c as char),
+                        _ => return None,
+                    }
+                }
+                c @ 0x20..=0x7E => value.push(c as char),
+                _ => return None,
+            }
+            self.position += 1;
+        }
+    }
+
+    fn byte_sequence(&mut self) -> Option<BareItem> {
+        self.position += 1;
+        let encoded = self.take_while(|b| b.is_ascii_alphanumeric() || b"+/=".contains(&b));
+        if !self.eat(b':') {
+            return None;
+        }
+        base64_decode(encoded).ok().map(BareItem::ByteSequence)
+    }
+}
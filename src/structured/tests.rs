@@ -0,0 +1,102 @@
+use crate::structured::{
+    parse_dictionary, parse_item, parse_list, serialize_dictionary, serialize_list, BareItem, Item,
+    Member,
+};
+
+#[test]
+fn parse_items() {
+    let item = parse_item("  \"say \\\"hi\\\"\";lang=en;formal  ").unwrap();
+    assert_eq!(item.value(), &BareItem::String("say \"hi\"".to_owned()));
+    assert_eq!(item.param("lang"), Some(&BareItem::Token("en".to_owned())));
+    assert_eq!(item.param("formal"), Some(&BareItem::Boolean(true)));
+
+    assert_eq!(parse_item("-42").unwrap().value(), &BareItem::Integer(-42));
+    assert_eq!(parse_item("4.5").unwrap().value(), &BareItem::Decimal(4.5));
+    assert_eq!(parse_item("?0").unwrap().value(), &BareItem::Boolean(false));
+    assert_eq!(
+        parse_item(":aGVsbG8=:").unwrap().value(),
+        &BareItem::ByteSequence(b"hello".to_vec())
+    );
+    assert_eq!(
+        parse_item("*/*").unwrap().value(),
+        &BareItem::Token("*/*".to_owned())
+    );
+
+    for invalid in [
+        "",
+        "1234567890123456",
+        "1.2345",
+        "1.",
+        "\"open",
+        "\"tab\t\"",
+        "?2",
+        "a, b",
+        "a;Key=1",
+        ":aGVsbG8=",
+    ] {
+        assert!(parse_item(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn parse_lists() {
+    let list = parse_list("sugar, (tea \"milk\");cup=2,   ()").unwrap();
+    assert_eq!(
+        list,
+        [
+            Member::Item(Item::new(BareItem::Token("sugar".to_owned()))),
+            Member::InnerList(
+                vec![
+                    Item::new(BareItem::Token("tea".to_owned())),
+                    Item::new(BareItem::String("milk".to_owned())),
+                ],
+                vec![("cup".to_owned(), BareItem::Integer(2))]
+            ),
+            Member::InnerList(Vec::new(), Vec::new()),
+        ]
+    );
+    assert_eq!(parse_list("").unwrap(), []);
+    assert!(parse_list("a,").is_err());
+    assert!(parse_list("(a b").is_err());
+    assert!(parse_list("(a,b)").is_err());
+}
+
+#[test]
+fn parse_dictionaries() {
+    let dictionary = parse_dictionary("u=1, i, u=3;x, j=?0").unwrap();
+    assert_eq!(
+        dictionary,
+        [
+            (
+                "u".to_owned(),
+                Member::Item(
+                    Item::new(BareItem::Integer(3)).with_param("x", BareItem::Boolean(true))
+                )
+            ),
+            (
+                "i".to_owned(),
+                Member::Item(Item::new(BareItem::Boolean(true)))
+            ),
+            (
+                "j".to_owned(),
+                Member::Item(Item::new(BareItem::Boolean(false)))
+            ),
+        ]
+    );
+    assert!(parse_dictionary("U=1").is_err());
+}
+
+#[test]
+fn serialize_fields() {
+    let value = "a=1.5, b;x=\"q\\\\\", c=(1 2);y=:AAE=:, d=?0";
+    assert_eq!(
+        serialize_dictionary(&parse_dictionary(value).unwrap()),
+        value
+    );
+    assert_eq!(Item::new(BareItem::Decimal(2.0)).to_string(), "2.0");
+    assert_eq!(Item::new(BareItem::Decimal(0.12345)).to_string(), "0.123");
+    assert_eq!(
+        serialize_list(&parse_list("a;q=?1, (b c)").unwrap()),
+        "a;q, (b c)"
+    );
+}
@@ -0,0 +1,413 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// Largest request head accepted by the server.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// A response served by a [`TestServer`].
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Option<Duration>,
+}
+
+impl TestResponse {
+    /// A response with the given status and no body.
+    /// # Arguments
+    /// `status`    Status code, e.g `200`.
+    pub fn new(status: u16) -> Self {
+        TestResponse {
+            status,
+            reason: reason_phrase(status).to_owned(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            delay: None,
+        }
+    }
+
+    /// A response with the given status and text body, sent as `text/plain`.
+    /// # Arguments
+    /// `status`    Status code, e.g `200`.
+    /// `body`      Body of the response.
+    pub fn text(status: u16, body: &str) -> Self {
+        Self::new(status)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(body.as_bytes())
+    }
+
+    /// Add a header to the response.
+    /// # Arguments
+    /// `name`  Name of the header.
+    /// `value` Value of the header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Set the body of the response, sent with its `Content-Length`.
+    /// # Arguments
+    /// `body`  Body of the response.
+    pub fn body(mut self, body: &[u8]) -> Self {
+        self.body = body.to_vec();
+        self
+    }
+
+    /// Wait before sending the response, e.g to test timeouts.
+    /// # Arguments
+    /// `delay` Time waited once the request was received.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Helper method, the response in wire format.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!("HTTP/1.1 {} {}\r\n", self.status, self.reason).into_bytes();
+        for (name, value) in &self.headers {
+            bytes.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        let has_length = self
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("Content-Length"));
+        if !has_length {
+            bytes.extend_from_slice(format!("Content-Length: {}\r\n", self.body.len()).as_bytes());
+        }
+        bytes.extend_from_slice(b"Connection: close\r\n\r\n");
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// A request received by a [`TestServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedRequest {
+    /// Method of the request, e.g `GET`.
+    pub method: String,
+    /// Target of the request, its path and query, e.g `/search?q=rust`.
+    pub target: String,
+    /// Header fields of the request, in the order they were received.
+    pub headers: Vec<(String, String)>,
+    /// Body of the request, without its transfer framing.
+    pub body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    /// The path of the request, without its query.
+    pub fn path(&self) -> &str {
+        self.target
+            .split_once('?')
+            .map_or(&self.target, |(path, _)| path)
+    }
+
+    /// The value of the first header with the given name, case insensitive.
+    /// # Arguments
+    /// `name`  Name of the header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A route of a [`TestServer`] and the responses it serves.
+#[derive(Debug)]
+struct Route {
+    method: String,
+    path: String,
+    responses: Vec<TestResponse>,
+    served: usize,
+}
+
+/// The state shared by a server and the threads handling its connections.
+#[derive(Debug, Default)]
+struct State {
+    routes: Mutex<Vec<Route>>,
+    requests: Mutex<Vec<ReceivedRequest>>,
+    stopped: AtomicBool,
+}
+
+/// A minimal HTTP/1.1 server listening on an ephemeral port of the loopback interface, to
+/// test code sending requests over real sockets.
+///
+/// Each route answers a method and path with the responses given to
+/// [`TestServer::route`], in order, the last one being served again once every response
+/// was. Requests to other routes are answered with `404 Not Found`. Every request received
+/// is kept for inspection, see [`TestServer::requests`]. Each connection serves a single
+/// request, and the server stops when dropped.
+///
+/// Only available with the `test-util` feature.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError};
+/// use http_client::test_server::{TestResponse, TestServer};
+/// fn main() -> Result<(), HttpError> {
+///     let server = TestServer::start()?;
+///     server.route("POST", "/users", TestResponse::text(201, "created"));
+///     let response = Client::new()
+///         .post(&server.url("/users"))?
+///         .body(b"{\"name\": \"ana\"}")
+///         .send()?;
+///     assert_eq!(response.status_code(), 201);
+///     assert_eq!(server.requests()[0].body, b"{\"name\": \"ana\"}");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TestServer {
+    address: SocketAddr,
+    state: Arc<State>,
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    /// Start a server on an ephemeral port of `127.0.0.1`.
+    ///
+    /// # Errors
+    /// When no port can be bound.
+    pub fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(State::default());
+        let shared = state.clone();
+        let acceptor = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if shared.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    // Failures only concern the client that caused them
+                    let _ = Self::serve(stream, &shared);
+                });
+            }
+        });
+        Ok(TestServer {
+            address,
+            state,
+            acceptor: Some(acceptor),
+        })
+    }
+
+    /// Answer requests with the given method and path with `response`, after the responses
+    /// already given for them.
+    /// # Arguments
+    /// `method`    Method of the requests, e.g `GET`.
+    /// `path`      Path of the requests, without a query, e.g `/users`.
+    /// `response`  Response served.
+    pub fn route(&self, method: &str, path: &str, response: TestResponse) {
+        let Ok(mut routes) = self.state.routes.lock() else {
+            return;
+        };
+        match routes
+            .iter_mut()
+            .find(|route| route.method.eq_ignore_ascii_case(method) && route.path == path)
+        {
+            Some(route) => route.responses.push(response),
+            None => routes.push(Route {
+                method: method.to_owned(),
+                path: path.to_owned(),
+                responses: vec![response],
+                served: 0,
+            }),
+        }
+    }
+
+    /// The address the server listens on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The URL of the given path on this server, e.g `http://127.0.0.1:41234/users`.
+    /// # Arguments
+    /// `path`  Path and query, starting with `/`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.address)
+    }
+
+    /// The requests received so far, in the order they were received.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state
+            .requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+
+    /// Helper method, read a request from a connection and answer it.
+    fn serve(stream: TcpStream, state: &State) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let Some(request) = Self::read_request(&mut reader, &mut writer)? else {
+            return Ok(());
+        };
+        let response = state.routes.lock().ok().and_then(|mut routes| {
+            let route = routes.iter_mut().find(|route| {
+                route.method.eq_ignore_ascii_case(&request.method) && route.path == request.path()
+            })?;
+            let response = route.responses[route.served.min(route.responses.len() - 1)].clone();
+            route.served += 1;
+            Some(response)
+        });
+        if let Ok(mut requests) = state.requests.lock() {
+            requests.push(request);
+        }
+        let response = response.unwrap_or_else(|| TestResponse::text(404, "Not Found"));
+        if let Some(delay) = response.delay {
+            thread::sleep(delay);
+        }
+        writer.write_all(&response.to_bytes())?;
+        writer.flush()
+    }
+
+    /// Helper method, read the head and body of a request, `None` when the connection
+    /// closed before a request was received.
+    fn read_request<R: BufRead>(
+        reader: &mut R,
+        writer: &mut impl Write,
+    ) -> std::io::Result<Option<ReceivedRequest>> {
+        let invalid = |reason: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, reason);
+        let mut lines = Vec::new();
+        let mut size = 0;
+        loop {
+            let mut line = String::new();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                if lines.is_empty() {
+                    return Ok(None);
+                }
+                return Err(invalid("connection closed while reading the request head"));
+            }
+            size += read;
+            if size > MAX_HEAD_SIZE {
+                return Err(invalid("request head too large"));
+            }
+            let line = line.trim_end_matches(['\r', '\n']).to_owned();
+            if line.is_empty() {
+                break;
+            }
+            lines.push(line);
+        }
+        let request_line = lines
+            .first()
+            .ok_or_else(|| invalid("missing request line"))?;
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(invalid("invalid request line"));
+        };
+        let headers = lines[1..]
+            .iter()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+            .collect::<Vec<(String, String)>>();
+        let mut request = ReceivedRequest {
+            method: method.to_owned(),
+            target: target.to_owned(),
+            headers,
+            body: Vec::new(),
+        };
+        if request
+            .header("Expect")
+            .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+        {
+            writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+            writer.flush()?;
+        }
+        let chunked = request
+            .header("Transfer-Encoding")
+            .is_some_and(|coding| coding.to_ascii_lowercase().contains("chunked"));
+        if chunked {
+            request.body = Self::read_chunked(reader)?;
+        } else if let Some(length) = request.header("Content-Length") {
+            let length = length
+                .parse::<u64>()
+                .map_err(|_| invalid("invalid Content-Length"))?;
+            reader.take(length).read_to_end(&mut request.body)?;
+        }
+        Ok(Some(request))
+    }
+
+    /// Helper method, read a chunked body, ignoring chunk extensions and trailers.
+    fn read_chunked<R: BufRead>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+        let invalid = |reason: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, reason);
+        let mut body = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = u64::from_str_radix(size, 16).map_err(|_| invalid("invalid chunk size"))?;
+            if size == 0 {
+                break;
+            }
+            reader.take(size).read_to_end(&mut body)?;
+            line.clear();
+            reader.read_line(&mut line)?;
+        }
+        // Trailer fields, up to the empty line ending the body
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                return Ok(body);
+            }
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.state.stopped.store(true, Ordering::SeqCst);
+        // Wake up the thread waiting for connections
+        let _ = TcpStream::connect(self.address);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+    }
+}
+
+/// Helper method, the reason phrase of common status codes.
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        412 => "Precondition Failed",
+        416 => "Range Not Satisfiable",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Unknown",
+    }
+}
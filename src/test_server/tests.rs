@@ -0,0 +1,57 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+use crate::test_server::{TestResponse, TestServer};
+
+/// Send a raw request to the server and return its raw response.
+fn exchange(server: &TestServer, request: &[u8]) -> String {
+    let mut stream = TcpStream::connect(server.address()).unwrap();
+    stream.write_all(request).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn routes_answered_in_order() {
+    let server = TestServer::start().unwrap();
+    server.route("GET", "/status", TestResponse::new(503));
+    server.route(
+        "GET",
+        "/status",
+        TestResponse::text(200, "up").header("X-Node", "a"),
+    );
+    let request = b"GET /status?verbose=1 HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let first = exchange(&server, request);
+    assert!(first.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    for _ in 0..2 {
+        let next = exchange(&server, request);
+        assert!(next.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(next.contains("X-Node: a\r\n"));
+        assert!(next.ends_with("Content-Length: 2\r\nConnection: close\r\n\r\nup"));
+    }
+    let missing = exchange(&server, b"DELETE /status HTTP/1.1\r\n\r\n");
+    assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert_eq!(server.requests().len(), 4);
+    assert_eq!(server.requests()[0].path(), "/status");
+    assert_eq!(server.requests()[0].target, "/status?verbose=1");
+}
+
+#[test]
+fn request_bodies_received() {
+    let server = TestServer::start().unwrap();
+    server.route("POST", "/upload", TestResponse::new(204));
+    let sized = exchange(
+        &server,
+        b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\nhello",
+    );
+    assert!(sized.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 204 No Content\r\n"));
+    exchange(
+        &server,
+        b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3;ext\r\nabc\r\n2\r\nde\r\n0\r\nDigest: x\r\n\r\n",
+    );
+    let requests = server.requests();
+    assert_eq!(requests[0].body, b"hello");
+    assert_eq!(requests[0].header("content-length"), Some("5"));
+    assert_eq!(requests[1].body, b"abcde");
+}
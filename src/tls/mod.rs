@@ -0,0 +1,151 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, Error, SignatureScheme};
+
+#[cfg(test)]
+mod tests;
+
+/// A function deciding whether a server certificate chain is trusted.
+///
+/// It receives the DER encoded chain, starting with the server's own certificate,
+/// and the name of the server being contacted.
+pub type VerifyFn = dyn Fn(&[&[u8]], &str) -> bool + Send + Sync;
+
+/// How certificate revocation is checked against the configured revocation lists (CRLs).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RevocationPolicy {
+    /// Revocation is not checked.
+    #[default]
+    Ignore,
+    /// Revoked certificates are rejected, certificates whose status cannot be determined
+    /// from the given CRLs are accepted.
+    SoftFail,
+    /// Revoked certificates and certificates whose status cannot be determined are rejected,
+    /// expired CRLs are treated as errors. At least one CRL must be configured.
+    HardFail,
+}
+
+/// A certificate verifier that accepts any certificate chain for any server name.
+///
+/// Handshake signatures are still checked, so the server must own the key of the
+/// certificate it presents, but nothing proves that certificate is trustworthy.
+#[derive(Debug)]
+pub(crate) struct NoCertificateVerification {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl NoCertificateVerification {
+    pub(crate) fn new() -> Self {
+        NoCertificateVerification {
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// A certificate verifier delegating the trust decision to a user provided function.
+///
+/// Handshake signatures are checked as usual.
+pub(crate) struct CallbackVerification {
+    callback: Arc<VerifyFn>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl CallbackVerification {
+    pub(crate) fn new(callback: Arc<VerifyFn>) -> Self {
+        CallbackVerification {
+            callback,
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl Debug for CallbackVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackVerification")
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerCertVerifier for CallbackVerification {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let chain = std::iter::once(end_entity)
+            .chain(intermediates)
+            .map(|certificate| certificate.as_ref())
+            .collect::<Vec<&[u8]>>();
+        if (self.callback)(&chain, &server_name.to_str()) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,111 @@
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use rustls::client::danger::ServerCertVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+
+use crate::client::Client;
+use crate::config::Config;
+use crate::error::HttpError;
+use crate::tls::{CallbackVerification, NoCertificateVerification, RevocationPolicy, VerifyFn};
+
+/// A certificate that is not even valid DER.
+fn garbage() -> CertificateDer<'static> {
+    CertificateDer::from(b"not a certificate".to_vec())
+}
+
+#[test]
+fn hard_fail_needs_crls() {
+    let settings = |policy| {
+        let mut config = Config::default();
+        config.tls_revocation = policy;
+        config.tls_settings()
+    };
+    assert!(settings(RevocationPolicy::Ignore).is_ok());
+    assert!(settings(RevocationPolicy::SoftFail).is_ok());
+    assert!(matches!(
+        settings(RevocationPolicy::HardFail),
+        Err(HttpError::ConnectionError(_))
+    ));
+}
+
+#[test]
+fn hard_fail_without_crls_refuses_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}/", listener.local_addr().unwrap());
+    let result = Client::builder()
+        .no_proxy()
+        .tls_revocation_policy(RevocationPolicy::HardFail)
+        .build()
+        .get(&url)
+        .unwrap()
+        .send()
+        .err();
+    match result {
+        Some(HttpError::ConnectionError(message)) => assert!(message.contains("CRL")),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn invalid_certificates_accepted() {
+    let verifier = NoCertificateVerification::new();
+    let name = ServerName::try_from("expired.example.com").unwrap();
+    assert!(verifier
+        .verify_server_cert(&garbage(), &[garbage()], &name, &[], UnixTime::now())
+        .is_ok());
+    // Handshake signatures are still checked
+    assert!(!verifier.supported_verify_schemes().is_empty());
+
+    let settings = |policy| {
+        let mut config = Config::default();
+        config.danger_accept_invalid_certs = true;
+        config.tls_revocation = policy;
+        config.tls_settings()
+    };
+    assert!(settings(RevocationPolicy::Ignore).is_ok());
+    // Nothing would be verified, the revocation policy cannot apply
+    assert!(matches!(
+        settings(RevocationPolicy::SoftFail),
+        Err(HttpError::ConnectionError(_))
+    ));
+    // Neither can it with a verifier of the caller
+    let mut config = Config::default();
+    config.tls_verifier = Some(Arc::new(NoCertificateVerification::new()));
+    config.tls_revocation = RevocationPolicy::HardFail;
+    assert!(config.tls_settings().is_err());
+}
+
+#[test]
+fn callback_decides_trust() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&seen);
+    let callback: Arc<VerifyFn> = Arc::new(move |chain: &[&[u8]], name: &str| {
+        recorded.lock().unwrap().push((
+            chain.iter().map(|c| c.to_vec()).collect::<Vec<_>>(),
+            name.to_owned(),
+        ));
+        name == "trusted.example.com"
+    });
+    let verifier = CallbackVerification::new(callback);
+    let leaf = CertificateDer::from(b"leaf".to_vec());
+    let intermediates = [CertificateDer::from(b"intermediate".to_vec())];
+    let verify = |name: &'static str| {
+        let name = ServerName::try_from(name).unwrap();
+        verifier.verify_server_cert(&leaf, &intermediates, &name, &[], UnixTime::now())
+    };
+
+    assert!(verify("trusted.example.com").is_ok());
+    assert!(matches!(
+        verify("other.example.com"),
+        Err(rustls::Error::InvalidCertificate(
+            rustls::CertificateError::ApplicationVerificationFailure
+        ))
+    ));
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    // The chain starts with the server's own certificate
+    assert_eq!(seen[0].0, vec![b"leaf".to_vec(), b"intermediate".to_vec()]);
+    assert_eq!(seen[0].1, "trusted.example.com");
+    assert_eq!(seen[1].1, "other.example.com");
+}
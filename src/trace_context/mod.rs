@@ -0,0 +1,181 @@
+use std::fmt::Display;
+
+use crate::hash::hex;
+use crate::retry::random_u64;
+
+#[cfg(test)]
+mod tests;
+
+/// Version of the W3C Trace Context format produced.
+const VERSION: &str = "00";
+
+/// Flag of a trace recorded by its caller.
+const SAMPLED: u8 = 0x01;
+
+/// A W3C Trace Context `traceparent` value, identifying the trace a request belongs to and
+/// the operation that sent it, see [`crate::client::ClientBuilder::trace_context`].
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, trace_context::TraceParent};
+/// fn main() -> Result<(), HttpError> {
+///     // As received by a server handling a request
+///     let incoming = TraceParent::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+///         .expect("valid traceparent");
+///     Client::new()
+///         .get("https://example.com/inventory")?
+///         .trace_parent(&incoming)
+///         .send()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    trace_id: String,
+    parent_id: String,
+    flags: u8,
+}
+
+impl TraceParent {
+    /// Start a new sampled trace with random identifiers.
+    pub fn new() -> Self {
+        TraceParent {
+            trace_id: random_id(2),
+            parent_id: random_id(1),
+            flags: SAMPLED,
+        }
+    }
+
+    /// Parse a `traceparent` header, `None` when it is invalid.
+    ///
+    /// Versions above `00` are accepted as long as they start with the fields of version `00`.
+    /// # Arguments
+    /// `header`    Value of the header.
+    pub fn parse(header: &str) -> Option<Self> {
+        let mut fields = header.trim().split('-');
+        let version = fields.next()?;
+        let trace_id = fields.next()?;
+        let parent_id = fields.next()?;
+        let flags = fields.next()?;
+        let is_hex = |field: &str, length: usize| {
+            field.len() == length
+                && field
+                    .bytes()
+                    .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        };
+        let is_zero = |field: &str| field.bytes().all(|byte| byte == b'0');
+        if !is_hex(version, 2)
+            || version == "ff"
+            || (version == VERSION && fields.next().is_some())
+            || !is_hex(trace_id, 32)
+            || is_zero(trace_id)
+            || !is_hex(parent_id, 16)
+            || is_zero(parent_id)
+            || !is_hex(flags, 2)
+        {
+            return None;
+        }
+        Some(TraceParent {
+            trace_id: trace_id.to_owned(),
+            parent_id: parent_id.to_owned(),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        })
+    }
+
+    /// The value identifying an operation of the same trace, to send to the next service.
+    pub fn child(&self) -> Self {
+        TraceParent {
+            trace_id: self.trace_id.clone(),
+            parent_id: random_id(1),
+            flags: self.flags,
+        }
+    }
+
+    /// Identifier of the whole trace, 32 hexadecimal digits.
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// Identifier of the operation that sent the request, 16 hexadecimal digits.
+    pub fn parent_id(&self) -> &str {
+        &self.parent_id
+    }
+
+    /// Whether the caller records the trace.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & SAMPLED != 0
+    }
+}
+
+impl Default for TraceParent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for TraceParent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{VERSION}-{}-{}-{:02x}",
+            self.trace_id, self.parent_id, self.flags
+        )
+    }
+}
+
+/// Helper method, a random identifier of `words` 64 bits words in hexadecimal, never all
+/// zeros as the format forbids it.
+fn random_id(words: usize) -> String {
+    loop {
+        let bytes = (0..words)
+            .flat_map(|_| random_u64().to_be_bytes())
+            .collect::<Vec<u8>>();
+        if bytes.iter().any(|&byte| byte != 0) {
+            return hex(&bytes);
+        }
+    }
+}
+
+/// Run `send` in a `tracing` span named `http_request`, recording the status of the
+/// response, or the error, and the time it took.
+/// # Arguments
+/// `method`    Method of the request, e.g `GET`.
+/// `host`      Host the request is sent to.
+/// `path`      Path of the request.
+/// `trace_id`  Identifier of the W3C trace the request belongs to, if any.
+/// `request_id`    The `X-Request-Id` of the request, if any.
+#[cfg(feature = "tracing")]
+pub(crate) fn in_span<F>(
+    method: &str,
+    host: &str,
+    path: &str,
+    trace_id: Option<&str>,
+    request_id: Option<&str>,
+    send: F,
+) -> Result<crate::response::Response, crate::error::HttpError>
+where
+    F: FnOnce() -> Result<crate::response::Response, crate::error::HttpError>,
+{
+    use tracing::field;
+
+    let span = tracing::info_span!(
+        "http_request",
+        method,
+        host,
+        path,
+        trace_id,
+        request_id,
+        status = field::Empty,
+        duration_ms = field::Empty,
+        error = field::Empty,
+    );
+    let _entered = span.enter();
+    let started = std::time::Instant::now();
+    let result = send();
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    match &result {
+        Ok(response) => span.record("status", response.status_code()),
+        Err(error) => span.record("error", field::display(error)),
+    };
+    result
+}
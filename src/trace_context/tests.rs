@@ -0,0 +1,44 @@
+use crate::trace_context::TraceParent;
+
+#[test]
+fn parse_and_format() {
+    let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    let parent = TraceParent::parse(header).unwrap();
+    assert_eq!(parent.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(parent.parent_id(), "00f067aa0ba902b7");
+    assert!(parent.is_sampled());
+    assert_eq!(parent.to_string(), header);
+}
+
+#[test]
+fn invalid_values_rejected() {
+    for header in [
+        "",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+    ] {
+        assert_eq!(TraceParent::parse(header), None, "{header}");
+    }
+    // Later versions may add fields
+    assert!(
+        TraceParent::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra")
+            .is_some()
+    );
+}
+
+#[test]
+fn child_continues_trace() {
+    let parent = TraceParent::new();
+    assert_eq!(
+        TraceParent::parse(&parent.to_string()),
+        Some(parent.clone())
+    );
+    let child = parent.child();
+    assert_eq!(child.trace_id(), parent.trace_id());
+    assert_ne!(child.parent_id(), parent.parent_id());
+    assert_eq!(child.parent_id().len(), 16);
+}
@@ -0,0 +1,315 @@
+use std::cmp::min;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use http_parse::HttpUrl;
+
+use crate::body::{self, ProgressFn};
+use crate::client::Client;
+use crate::encoding::base64_encode;
+use crate::error::HttpError;
+use crate::hash::Sha256;
+use crate::redirect::resolve_location;
+use crate::response::Response;
+
+#[cfg(test)]
+mod tests;
+
+/// Version of the tus protocol spoken by [`TusClient`].
+const TUS_VERSION: &str = "1.0.0";
+
+/// Bytes sent by each `PATCH` request, unless configured otherwise.
+const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Attempts made to send a chunk again after it failed, unless configured otherwise.
+const DEFAULT_RETRIES: usize = 3;
+
+const H_TUS_RESUMABLE: &str = "Tus-Resumable";
+const H_UPLOAD_LENGTH: &str = "Upload-Length";
+const H_UPLOAD_OFFSET: &str = "Upload-Offset";
+const H_UPLOAD_METADATA: &str = "Upload-Metadata";
+const H_UPLOAD_CHECKSUM: &str = "Upload-Checksum";
+const MIME_OFFSET_STREAM: &str = "application/offset+octet-stream";
+
+/// A client of the tus resumable upload protocol (<https://tus.io>), with the creation and
+/// checksum extensions.
+///
+/// An upload is created once, its URL can be stored to resume it later, even after the
+/// program restarted: [`TusClient::upload`] asks the server how much it received and sends
+/// the rest.
+///
+/// # Example:
+/// ``` no_run
+/// use http_client::{client::Client, error::HttpError, tus::TusClient};
+/// fn main() -> Result<(), HttpError> {
+///     let tus = TusClient::new(Client::new()).checksums(true);
+///     let length = std::fs::metadata("video.mp4")?.len();
+///     let upload_url = tus.create("https://example.com/files/", length, &[("filename", "video.mp4")])?;
+///     // Keep `upload_url` to resume the upload if it is interrupted
+///     tus.upload(&upload_url, &mut std::fs::File::open("video.mp4")?)?;
+///     Ok(())
+/// }
+/// ```
+pub struct TusClient {
+    client: Client,
+    chunk_size: usize,
+    checksums: bool,
+    retries: usize,
+    progress: Option<ProgressFn>,
+}
+
+impl TusClient {
+    /// Create a tus client sending its requests with the given client.
+    pub fn new(client: Client) -> Self {
+        TusClient {
+            client,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            checksums: false,
+            retries: DEFAULT_RETRIES,
+            progress: None,
+        }
+    }
+
+    /// Send uploads in chunks of the given size, one `PATCH` request each.
+    /// # Arguments
+    /// `size`  Bytes sent by each request, at least one.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// Send the SHA-256 digest of every chunk as `Upload-Checksum` so the server discards
+    /// corrupted chunks, the server must support the checksum extension.
+    /// # Arguments
+    /// `enabled`   Whether to send checksums.
+    pub fn checksums(mut self, enabled: bool) -> Self {
+        self.checksums = enabled;
+        self
+    }
+
+    /// Resume the upload up to `retries` times in a row when sending a chunk fails.
+    /// # Arguments
+    /// `retries`   Attempts made after a failure, before giving up.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Report the progress of uploads.
+    ///
+    /// `callback` is called with the number of bytes the server holds and the size of the
+    /// upload after every chunk.
+    /// # Arguments
+    /// `callback`  Called with the bytes uploaded and the total, e.g to draw a progress bar.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        self.progress = Some(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    /// Create an upload and return its URL.
+    /// # Arguments
+    /// `endpoint`  URL uploads are created at.
+    /// `length`    Size of the upload in bytes.
+    /// `metadata`  Names and values describing the upload, e.g `filename`.
+    ///
+    /// # Errors
+    /// When a metadata name is empty or contains spaces or commas, or the server refuses the upload.
+    pub fn create(
+        &self,
+        endpoint: &str,
+        length: u64,
+        metadata: &[(&str, &str)],
+    ) -> Result<String, HttpError> {
+        let mut request = self
+            .client
+            .post(endpoint)?
+            .header(H_TUS_RESUMABLE, TUS_VERSION)
+            .header(H_UPLOAD_LENGTH, length);
+        if !metadata.is_empty() {
+            request = request.header(H_UPLOAD_METADATA, encode_metadata(metadata)?);
+        }
+        let response = expect_status(request.send()?, 201)?;
+        let location = response.header_value("Location").ok_or_else(|| {
+            HttpError::BadResponse(201, "Upload created without a `Location`".to_owned())
+        })?;
+        let endpoint =
+            HttpUrl::try_from(response.url()).map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+        Ok(resolve_location(
+            endpoint.scheme(),
+            &endpoint.address().to_string(),
+            endpoint.path(),
+            &location,
+        ))
+    }
+
+    /// The number of bytes of an upload the server received.
+    /// # Arguments
+    /// `upload_url`    URL of the upload, as returned by [`TusClient::create`].
+    pub fn offset(&self, upload_url: &str) -> Result<u64, HttpError> {
+        let response = self
+            .client
+            .head(upload_url)?
+            .header(H_TUS_RESUMABLE, TUS_VERSION)
+            .header("Cache-Control", "no-store")
+            .send()?;
+        let response = match response.status_code() {
+            204 => response,
+            _ => expect_status(response, 200)?,
+        };
+        upload_offset(&response)
+    }
+
+    /// Send the part of the upload the server does not hold yet, read from `source`.
+    ///
+    /// `source` must provide the same bytes every time the upload is resumed, from its start.
+    /// # Arguments
+    /// `upload_url`    URL of the upload, as returned by [`TusClient::create`].
+    /// `source`        Content of the upload.
+    pub fn upload<R: Read + Seek>(
+        &self,
+        upload_url: &str,
+        source: &mut R,
+    ) -> Result<(), HttpError> {
+        let length = source.seek(SeekFrom::End(0))?;
+        let mut offset = self.offset(upload_url)?;
+        let mut failures = 0;
+        let mut chunk = Vec::new();
+        while offset < length {
+            source.seek(SeekFrom::Start(offset))?;
+            chunk.clear();
+            let size = min(self.chunk_size as u64, length - offset);
+            source.by_ref().take(size).read_to_end(&mut chunk)?;
+            if chunk.len() as u64 != size {
+                return Err(HttpError::Other(format!(
+                    "Upload source ended at byte {}, expected {length} bytes",
+                    offset + chunk.len() as u64
+                )));
+            }
+            match self.send_chunk(upload_url, offset, &chunk) {
+                Ok(next) => {
+                    offset = next;
+                    failures = 0;
+                    if let Some(progress) = &self.progress {
+                        body::report(progress, offset, Some(length));
+                    }
+                }
+                // The server may have kept part of the chunk
+                Err(_) if failures < self.retries => {
+                    failures += 1;
+                    offset = self.offset(upload_url)?;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Create an upload for the file at `path`, named after the file, send it and return the
+    /// URL of the upload.
+    /// # Arguments
+    /// `endpoint`  URL uploads are created at.
+    /// `path`      Path of the file being uploaded.
+    pub fn upload_file<P: AsRef<Path>>(
+        &self,
+        endpoint: &str,
+        path: P,
+    ) -> Result<String, HttpError> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let upload_url = self.create(endpoint, length, &[("filename", &name)])?;
+        self.upload(&upload_url, &mut file)?;
+        Ok(upload_url)
+    }
+
+    /// Helper method, send a chunk at the given offset and return the new offset.
+    fn send_chunk(&self, upload_url: &str, offset: u64, chunk: &[u8]) -> Result<u64, HttpError> {
+        let mut request = self
+            .client
+            .patch(upload_url)?
+            .header(H_TUS_RESUMABLE, TUS_VERSION)
+            .header(H_UPLOAD_OFFSET, offset)
+            .header("Content-Type", MIME_OFFSET_STREAM)
+            .body(chunk);
+        if self.checksums {
+            request = request.header(H_UPLOAD_CHECKSUM, checksum_header(chunk));
+        }
+        let response = expect_status(request.send()?, 204)?;
+        let next = upload_offset(&response)?;
+        if next <= offset {
+            return Err(HttpError::BadResponse(
+                204,
+                format!("Upload offset did not advance from {offset}"),
+            ));
+        }
+        Ok(next)
+    }
+}
+
+/// Encode the `Upload-Metadata` header, names followed by their Base64 encoded value.
+pub(crate) fn encode_metadata(metadata: &[(&str, &str)]) -> Result<String, HttpError> {
+    let pairs = metadata
+        .iter()
+        .map(|(name, value)| {
+            if name.is_empty() || name.contains([' ', ',']) {
+                return Err(HttpError::Other(format!(
+                    "Invalid upload metadata name `{name}`"
+                )));
+            }
+            Ok(match value.is_empty() {
+                true => name.to_string(),
+                false => format!("{name} {}", base64_encode(value.as_bytes())),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(pairs.join(","))
+}
+
+/// The `Upload-Checksum` header of a chunk.
+pub(crate) fn checksum_header(chunk: &[u8]) -> String {
+    format!("sha256 {}", base64_encode(&Sha256::digest(chunk)))
+}
+
+/// Helper method, fail unless the response has the expected status.
+fn expect_status(response: Response, expected: usize) -> Result<Response, HttpError> {
+    match response.status_code() {
+        status if status == expected => Ok(response),
+        412 => Err(HttpError::BadResponse(
+            412,
+            format!(
+                "Server does not support tus {TUS_VERSION}, it supports `{}`",
+                response.header_value("Tus-Version").unwrap_or_default()
+            ),
+        )),
+        460 => Err(HttpError::BadResponse(
+            460,
+            "Upload checksum mismatch".to_owned(),
+        )),
+        status => Err(HttpError::BadResponse(
+            status,
+            response.status_msg().to_owned(),
+        )),
+    }
+}
+
+/// Helper method, the `Upload-Offset` of a response.
+fn upload_offset(response: &Response) -> Result<u64, HttpError> {
+    response
+        .header_value(H_UPLOAD_OFFSET)
+        .and_then(|offset| offset.trim().parse().ok())
+        .ok_or_else(|| {
+            HttpError::BadResponse(
+                response.status_code(),
+                format!("Missing or invalid `{H_UPLOAD_OFFSET}` header"),
+            )
+        })
+}
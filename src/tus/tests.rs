@@ -0,0 +1,25 @@
+use crate::tus::{checksum_header, encode_metadata};
+
+#[test]
+fn upload_metadata() {
+    assert_eq!(
+        encode_metadata(&[
+            ("filename", "world_domination_plan.pdf"),
+            ("is_confidential", "")
+        ])
+        .unwrap(),
+        "filename d29ybGRfZG9taW5hdGlvbl9wbGFuLnBkZg==,is_confidential"
+    );
+    assert_eq!(encode_metadata(&[]).unwrap(), "");
+    assert!(encode_metadata(&[("file name", "a")]).is_err());
+    assert!(encode_metadata(&[("a,b", "a")]).is_err());
+    assert!(encode_metadata(&[("", "a")]).is_err());
+}
+
+#[test]
+fn upload_checksum() {
+    assert_eq!(
+        checksum_header(b"abc"),
+        "sha256 ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+    );
+}
@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::client::{H_AUTHORIZATION, H_COOKIE, H_PROXY_AUTHORIZATION, H_SET_COOKIE};
+use crate::connection;
+use crate::date::format_iso8601;
+use crate::encoding::base64_encode;
+use crate::error::HttpError;
+use crate::har::{HarReplay, ENCODED_FIELD};
+use crate::json::{JsonObj, JsonValue};
+
+#[cfg(test)]
+mod tests;
+
+/// Headers whose values are never written to a cassette, unless configured otherwise.
+const DEFAULT_FILTERED_HEADERS: [&str; 4] = [
+    H_AUTHORIZATION,
+    H_PROXY_AUTHORIZATION,
+    H_COOKIE,
+    H_SET_COOKIE,
+];
+
+/// Written to a cassette in place of the value of a filtered header.
+const FILTERED: &str = "[filtered]";
+
+/// Whether a cassette records interactions or replays them.
+#[derive(Debug)]
+enum Mode {
+    /// The interactions recorded so far, as HAR entries.
+    Record(Mutex<Vec<JsonValue>>),
+    Replay(HarReplay),
+}
+
+/// Records the requests sent and the responses received to a file on the first run, and
+/// replays them from that file on the next runs instead of using the network, see
+/// [`crate::client::ClientBuilder::cassette`].
+///
+/// Cassettes are HAR files: while recording, the file is rewritten after every response so
+/// nothing is lost if the program stops, and it is replayed as described by [`HarReplay`].
+/// Delete the file to record it again. The values of credential headers such as
+/// `Authorization` or `Cookie` are replaced with `[filtered]` before they are written, see
+/// [`Cassette::filter_header`].
+///
+/// While recording, the body of a streamed response is read in full before it is returned.
+///
+/// # Example:
+/// ``` no_run
+/// use std::sync::Arc;
+/// use http_client::{client::Client, error::HttpError, vcr::Cassette};
+/// fn main() -> Result<(), HttpError> {
+///     let cassette = Cassette::open("tests/cassettes/users.har")?.filter_header("X-Api-Key");
+///     let client = Client::builder().cassette(Arc::new(cassette)).build();
+///     // From the network the first time, from the cassette afterwards
+///     let response = client
+///         .get("https://api.example.com/users")?
+///         .header("X-Api-Key", "secret")
+///         .send()?;
+///     assert_eq!(response.status_code(), 200);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    /// Lowercase names of the headers whose values are not written.
+    filtered_headers: Vec<String>,
+}
+
+impl Cassette {
+    /// Replay the cassette at `path` if it exists, otherwise record one there.
+    /// # Arguments
+    /// `path`  Location of the cassette.
+    ///
+    /// # Errors
+    /// When the cassette exists but cannot be read or is not a valid HAR file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, HttpError> {
+        let path = path.as_ref().to_path_buf();
+        let mode = if path.exists() {
+            Mode::Replay(HarReplay::from_file(&path)?)
+        } else {
+            Mode::Record(Mutex::new(Vec::new()))
+        };
+        Ok(Cassette {
+            path,
+            mode,
+            filtered_headers: DEFAULT_FILTERED_HEADERS
+                .iter()
+                .map(|name| name.to_ascii_lowercase())
+                .collect(),
+        })
+    }
+
+    /// Write `[filtered]` instead of the value of the given header, in requests and responses,
+    /// e.g for an API key.
+    /// # Arguments
+    /// `name`  Name of the header, case insensitive.
+    pub fn filter_header(mut self, name: &str) -> Self {
+        self.filtered_headers.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Only replay a response to requests with the same value for the given header as the
+    /// recorded request, see [`HarReplay::match_header`]. Has no effect while recording.
+    /// # Arguments
+    /// `name`  Name of the header, case insensitive.
+    pub fn match_header(mut self, name: &str) -> Self {
+        if let Mode::Replay(replay) = self.mode {
+            self.mode = Mode::Replay(replay.match_header(name));
+        }
+        self
+    }
+
+    /// Whether the cassette records interactions rather than replaying them.
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Record(_))
+    }
+
+    /// The recorded interactions to answer requests with, when replaying.
+    pub(crate) fn replay(&self) -> Option<&HarReplay> {
+        match &self.mode {
+            Mode::Replay(replay) => Some(replay),
+            Mode::Record(_) => None,
+        }
+    }
+
+    /// Add an interaction to the cassette and write it, nothing is done when replaying.
+    /// # Arguments
+    /// `method`    Method of the request, e.g `GET`.
+    /// `url`       URL of the request.
+    /// `headers`   Header fields of the request.
+    /// `head`      Head of the response in wire format.
+    /// `body`      Body of the response, without its transfer framing.
+    /// `elapsed`   Time taken by the exchange.
+    ///
+    /// # Errors
+    /// When the cassette cannot be written.
+    pub(crate) fn record(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+        head: &[u8],
+        body: &[u8],
+        elapsed: Duration,
+    ) -> Result<(), HttpError> {
+        let Mode::Record(entries) = &self.mode else {
+            return Ok(());
+        };
+        let status_line = String::from_utf8_lossy(head)
+            .split("\r\n")
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        let mut parts = status_line.splitn(3, ' ').skip(1);
+        let status = parts
+            .next()
+            .and_then(|status| status.parse::<isize>().ok())
+            .unwrap_or_default();
+        let reason = parts.next().unwrap_or_default();
+        let response_headers = String::from_utf8_lossy(head)
+            .split("\r\n")
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+            .collect::<Vec<(String, String)>>();
+        let content_type = connection::head_values(head, "Content-Type")
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let mut request = JsonObj::new();
+        request.insert("method", method);
+        request.insert("url", url);
+        request.insert("httpVersion", "HTTP/1.1");
+        request.insert("headers", self.headers_json(headers));
+        request.insert("queryString", JsonValue::Array(Vec::new()));
+        request.insert("cookies", JsonValue::Array(Vec::new()));
+        request.insert("headersSize", -1);
+        request.insert("bodySize", -1);
+
+        let mut content = JsonObj::new();
+        content.insert("size", body.len() as isize);
+        content.insert("mimeType", content_type.as_str());
+        // The body is recorded as received, e.g compressed
+        if response_headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("Content-Encoding") && !value.eq_ignore_ascii_case("identity")
+        }) {
+            content.insert(ENCODED_FIELD, true);
+        }
+        match std::str::from_utf8(body) {
+            Ok(text) => {
+                content.insert("text", text);
+            }
+            Err(_) => {
+                content.insert("text", base64_encode(body).as_str());
+                content.insert("encoding", "base64");
+            }
+        }
+        let mut response = JsonObj::new();
+        response.insert("status", status);
+        response.insert("statusText", reason);
+        response.insert("httpVersion", "HTTP/1.1");
+        response.insert("headers", self.headers_json(&response_headers));
+        response.insert("cookies", JsonValue::Array(Vec::new()));
+        response.insert("content", content);
+        response.insert("redirectURL", "");
+        response.insert("headersSize", head.len() as isize);
+        response.insert("bodySize", body.len() as isize);
+
+        let time = elapsed.as_millis() as isize;
+        let mut timings = JsonObj::new();
+        timings.insert("send", 0);
+        timings.insert("wait", time);
+        timings.insert("receive", 0);
+        let mut entry = JsonObj::new();
+        entry.insert(
+            "startedDateTime",
+            format_iso8601(SystemTime::now() - elapsed).as_str(),
+        );
+        entry.insert("time", time);
+        entry.insert("request", request);
+        entry.insert("response", response);
+        entry.insert("cache", JsonObj::new());
+        entry.insert("timings", timings);
+
+        let mut entries = entries
+            .lock()
+            .map_err(|e| HttpError::Other(e.to_string()))?;
+        entries.push(JsonValue::Object(entry));
+        std::fs::write(&self.path, Self::document(&entries).to_string())?;
+        Ok(())
+    }
+
+    /// Helper method, the HAR `headers` array of the given fields, filtered.
+    fn headers_json(&self, headers: &[(String, String)]) -> JsonValue {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                let filtered = self
+                    .filtered_headers
+                    .iter()
+                    .any(|filtered| filtered.eq_ignore_ascii_case(name));
+                let mut header = JsonObj::new();
+                header.insert("name", name.as_str());
+                header.insert("value", if filtered { FILTERED } else { value.as_str() });
+                JsonValue::Object(header)
+            })
+            .collect();
+        JsonValue::Array(headers)
+    }
+
+    /// Helper method, the HAR document holding the given entries.
+    fn document(entries: &[JsonValue]) -> JsonValue {
+        let mut creator = JsonObj::new();
+        creator.insert("name", env!("CARGO_PKG_NAME"));
+        creator.insert("version", env!("CARGO_PKG_VERSION"));
+        let mut log = JsonObj::new();
+        log.insert("version", "1.2");
+        log.insert("creator", creator);
+        log.insert("entries", JsonValue::Array(entries.to_vec()));
+        let mut document = JsonObj::new();
+        document.insert("log", log);
+        JsonValue::Object(document)
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use crate::vcr::Cassette;
+
+#[test]
+fn recorded_then_replayed() {
+    let path = std::env::temp_dir().join(format!("vcr-{}.har", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let cassette = Cassette::open(&path).unwrap().filter_header("x-api-key");
+    assert!(cassette.is_recording());
+    assert!(cassette.replay().is_none());
+    let headers = [
+        ("Authorization".to_owned(), "Bearer secret".to_owned()),
+        ("X-Api-Key".to_owned(), "secret".to_owned()),
+        ("Accept".to_owned(), "text/plain".to_owned()),
+    ];
+    cassette
+        .record(
+            "GET",
+            "https://example.com/hello",
+            &headers,
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nSet-Cookie: id=secret\r\n\r\n",
+            b"hello \"world\"\n",
+            Duration::from_millis(12),
+        )
+        .unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(!written.contains("secret"));
+    assert!(written.contains("[filtered]"));
+
+    let cassette = Cassette::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(!cassette.is_recording());
+    let response = cassette
+        .replay()
+        .unwrap()
+        .respond("GET", "https://example.com/hello", &[])
+        .unwrap();
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: text/plain\r\n"));
+    assert!(response.ends_with("Content-Length: 14\r\n\r\nhello \"world\"\n"));
+}
+
+#[test]
+fn binary_bodies_recorded() {
+    let path = std::env::temp_dir().join(format!("vcr-binary-{}.har", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let cassette = Cassette::open(&path).unwrap();
+    let body = [0u8, 159, 146, 150, 255];
+    cassette
+        .record(
+            "POST",
+            "http://example.com/upload",
+            &[],
+            b"HTTP/1.1 201 Created\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n",
+            &body,
+            Duration::ZERO,
+        )
+        .unwrap();
+    let cassette = Cassette::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let response = cassette
+        .replay()
+        .unwrap()
+        .respond("POST", "http://example.com/upload", &[])
+        .unwrap();
+    // The body is served with its content coding, without its transfer coding
+    let head = b"HTTP/1.1 201 Created\r\nContent-Encoding: gzip\r\nContent-Length: 5\r\n\r\n";
+    assert_eq!(response, [head.as_slice(), &body].concat());
+}